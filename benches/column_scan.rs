@@ -0,0 +1,80 @@
+// =============================================================================
+// BENCH — Scan en colonnes vs scan par lignes
+// =============================================================================
+//
+// Une seule entité Employee (salary, emp_name) ; la requête garde 1 % des
+// lignes sur un filtre de salaire. Avec `Instance::with_columns`, le filtre
+// parcourt la colonne salary au lieu d'une HashMap par ligne.
+//
+//   cargo bench --bench column_scan
+//
+// =============================================================================
+
+use std::collections::HashMap;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use catrust::core::eval::eval_query;
+use catrust::core::instance::Instance;
+use catrust::core::query::{CompOp, CqlQuery};
+use catrust::core::schema::Schema;
+use catrust::core::typeside::{BaseType, Value};
+
+const EMPLOYEES: i64 = 100_000;
+
+fn employee_schema() -> Schema {
+    let mut schema = Schema::new("BenchScan");
+    schema.add_node("Employee")
+          .add_attribute("emp_name", "Employee", BaseType::String)
+          .add_attribute("salary", "Employee", BaseType::Integer);
+    schema
+}
+
+fn employee_instance(schema: &Schema) -> Instance {
+    let mut inst = Instance::new("BenchData", schema);
+    for i in 0..EMPLOYEES {
+        inst.insert("Employee", HashMap::from([
+            ("emp_name".to_string(), Value::String(format!("Emp{}", i))),
+            ("salary".to_string(), Value::Integer(i)),
+        ]), HashMap::new());
+    }
+    inst
+}
+
+fn top_salaries_query(schema: &Schema) -> CqlQuery {
+    let mut query = CqlQuery::new("TopSalaries", "BenchScan");
+    query.add_block(
+        CqlQuery::select()
+            .from("e", "Employee")
+            .where_cmp("e", &["salary"], CompOp::Gte, Value::Integer(EMPLOYEES - EMPLOYEES / 100))
+            .bind("name", "e", &[], "emp_name")
+            .build()
+            .unwrap(),
+        schema,
+    ).unwrap();
+    query
+}
+
+fn bench_scan(c: &mut Criterion) {
+    let schema = employee_schema();
+    let rows = employee_instance(&schema);
+    let columns = rows.clone().with_columns();
+    let query = top_salaries_query(&schema);
+
+    let by_rows = eval_query(&query, &rows, &schema).unwrap();
+    let by_columns = eval_query(&query, &columns, &schema).unwrap();
+    assert_eq!(by_rows.rows_returned, by_columns.rows_returned);
+
+    let mut group = c.benchmark_group("salary_scan");
+    group.bench_function("row_store", |b| {
+        b.iter(|| eval_query(black_box(&query), &rows, &schema).unwrap())
+    });
+    group.bench_function("column_store", |b| {
+        b.iter(|| eval_query(black_box(&query), &columns, &schema).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_scan);
+criterion_main!(benches);
@@ -0,0 +1,111 @@
+// =============================================================================
+// BENCH — Gain de l'élimination de JOINs sur l'évaluateur in-memory
+// =============================================================================
+//
+// Schéma : Employee --department--> Department --manager--> Employee
+//          Employee --direct_mgr--> Employee
+//          avec l'équation  department.manager = direct_mgr
+//
+// La requête filtre sur e.department.manager.emp_name (2 FK par employé) ;
+// après optimisation elle suit e.direct_mgr.emp_name (1 FK).
+//
+//   cargo bench --bench eval_optimizer
+//
+// =============================================================================
+
+use std::collections::HashMap;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use catrust::core::eval::{eval_query, eval_query_optimized};
+use catrust::core::instance::Instance;
+use catrust::core::query::CqlQuery;
+use catrust::core::schema::{Path, Schema};
+use catrust::core::typeside::{BaseType, Value};
+
+const DEPARTMENTS: usize = 50;
+const EMPLOYEES: usize = 5_000;
+
+fn company_schema() -> Schema {
+    let mut schema = Schema::new("BenchCompany");
+    schema.add_node("Employee")
+          .add_node("Department")
+          .add_fk("department", "Employee", "Department")
+          .add_fk("manager", "Department", "Employee")
+          .add_fk("direct_mgr", "Employee", "Employee")
+          .add_attribute("emp_name", "Employee", BaseType::String)
+          .add_attribute("dept_name", "Department", BaseType::String)
+          .add_path_equation(
+              Path::new("Employee", vec!["department", "manager"]),
+              Path::new("Employee", vec!["direct_mgr"]),
+          );
+    schema
+}
+
+/// Le i-ème département est dirigé par le i-ème employé ; chaque employé a
+/// pour `direct_mgr` le manager de son département (l'équation est vraie).
+fn company_instance(schema: &Schema) -> Instance {
+    let mut inst = Instance::new("BenchData", schema);
+    let name = |i: usize| HashMap::from([("emp_name".to_string(), Value::String(format!("Emp{}", i)))]);
+
+    let depts: Vec<_> = (0..DEPARTMENTS)
+        .map(|d| inst.insert("Department",
+            HashMap::from([("dept_name".to_string(), Value::String(format!("Dept{}", d)))]),
+            HashMap::new(),
+        ))
+        .collect();
+    let managers: Vec<_> = (0..DEPARTMENTS)
+        .map(|d| inst.insert("Employee", name(d), HashMap::from([("department".to_string(), depts[d])])))
+        .collect();
+    for d in 0..DEPARTMENTS {
+        inst.set_fk("Department", depts[d], "manager", managers[d], schema).unwrap();
+        inst.set_fk("Employee", managers[d], "direct_mgr", managers[d], schema).unwrap();
+    }
+    for i in DEPARTMENTS..EMPLOYEES {
+        let d = i % DEPARTMENTS;
+        inst.insert("Employee", name(i), HashMap::from([
+            ("department".to_string(), depts[d]),
+            ("direct_mgr".to_string(), managers[d]),
+        ]));
+    }
+    inst
+}
+
+fn long_path_query(schema: &Schema) -> CqlQuery {
+    let mut query = CqlQuery::new("ManagedByEmp0", "BenchCompany");
+    query.add_block(
+        CqlQuery::select()
+            .from("e", "Employee")
+            .where_eq("e", &["department", "manager", "emp_name"], Value::String("Emp0".into()))
+            .bind("name", "e", &[], "emp_name")
+            .build()
+            .unwrap(),
+        schema,
+    ).unwrap();
+    query
+}
+
+fn bench_optimizer(c: &mut Criterion) {
+    let schema = company_schema();
+    let inst = company_instance(&schema);
+    let query = long_path_query(&schema);
+
+    let plain = eval_query(&query, &inst, &schema).unwrap();
+    let optimized = eval_query_optimized(&query, &inst, &schema).unwrap();
+    assert_eq!(plain.rows_returned, optimized.rows_returned);
+    // Le raccourci suit moins de FK : c'est ce que mesure le benchmark
+    assert!(optimized.fk_hops < plain.fk_hops);
+
+    let mut group = c.benchmark_group("long_path");
+    group.bench_function("eval_query", |b| {
+        b.iter(|| eval_query(black_box(&query), &inst, &schema).unwrap())
+    });
+    group.bench_function("eval_query_optimized", |b| {
+        b.iter(|| eval_query_optimized(black_box(&query), &inst, &schema).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_optimizer);
+criterion_main!(benches);
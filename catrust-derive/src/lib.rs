@@ -0,0 +1,102 @@
+// =============================================================================
+// CATRUST-DERIVE — #[derive(CatrustEntity)]
+// =============================================================================
+//
+// Déclare une entité de schéma à partir d'une struct Rust :
+//
+//   #[derive(CatrustEntity)]
+//   struct Employee {
+//       emp_name: String,          → attribut emp_name : Employee → String
+//       salary: i64,               → attribut salary : Employee → Integer
+//       works_in: Fk<Department>,  → FK works_in : Employee → Department
+//   }
+//
+// Le type de chaque champ est vérifié à la compilation : un `Fk<T>` exige
+// que T soit lui-même une entité, tout autre type doit implémenter
+// `AttributeType` (String, i64, f64, bool, Vec<_>...).
+//
+// Le code généré vise `::catrust::core::entity` : la crate catrust doit
+// être une dépendance directe.
+//
+// =============================================================================
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericArgument, PathArguments, Type};
+
+/// Implémente `catrust::core::entity::CatrustEntity` pour une struct à
+/// champs nommés : le nœud porte le nom de la struct, chaque champ devient
+/// un attribut ou une FK (`Fk<Cible>`).
+#[proc_macro_derive(CatrustEntity)]
+pub fn derive_catrust_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let name = ident.to_string();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(
+                ident, "CatrustEntity : struct à champs nommés attendue",
+            )),
+        },
+        _ => return Err(syn::Error::new_spanned(
+            ident, "CatrustEntity : seules les structs sont des entités",
+        )),
+    };
+
+    let edges = fields.iter().map(|field| {
+        let edge = field.ident.as_ref().map(|i| i.to_string()).unwrap_or_default();
+        let ty = &field.ty;
+        match fk_target(ty) {
+            Some(target) => quote! {
+                schema.add_fk(
+                    #edge,
+                    <Self as ::catrust::core::entity::CatrustEntity>::NAME,
+                    <#target as ::catrust::core::entity::CatrustEntity>::NAME,
+                );
+            },
+            None => quote! {
+                schema.add_attribute(
+                    #edge,
+                    <Self as ::catrust::core::entity::CatrustEntity>::NAME,
+                    <#ty as ::catrust::core::entity::AttributeType>::base_type(),
+                );
+            },
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::catrust::core::entity::CatrustEntity for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+
+            fn register_edges(schema: &mut ::catrust::core::schema::Schema) {
+                #(#edges)*
+            }
+        }
+    })
+}
+
+/// Cible `T` d'un champ de type `Fk<T>` (quel que soit le chemin : `Fk`,
+/// `entity::Fk`...), `None` pour un attribut.
+fn fk_target(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let last = path.path.segments.last()?;
+    if last.ident != "Fk" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &last.arguments else { return None };
+    match args.args.first()? {
+        GenericArgument::Type(target) if args.args.len() == 1 => Some(target),
+        _ => None,
+    }
+}
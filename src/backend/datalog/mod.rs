@@ -0,0 +1,331 @@
+// =============================================================================
+// BACKEND DATALOG — Export de faits Soufflé à partir des catégories
+// =============================================================================
+//
+// Datalog est la logique des relations : une instance CQL s'y écrit
+// directement comme une base de faits, ce qui permet de faire de
+// l'inférence (Soufflé) sur les données catégoriques.
+//
+//   - Nœud CQL (entité) → relation `Employee(id, attr1, attr2...)`
+//   - FK CQL → relation binaire `works_in(id, cible)`
+//   - Attribut CQL → colonne de la relation de l'entité
+//   - Équation de chemins → règle qui dérive ses violations
+//
+// Une équation `p = q` devient une relation `eq_violation_i(id)` : elle est
+// vide si et seulement si l'instance respecte l'équation.
+//
+// =============================================================================
+
+use crate::core::schema::{Schema, Edge, Path};
+use crate::core::instance::{Instance, EntityData, RowId};
+use crate::core::mapping::Mapping;
+use crate::core::typeside::{BaseType, Value};
+use crate::backend::{Backend, Statement};
+
+/// Backend Datalog — génère des déclarations, faits et règles Soufflé
+pub struct DatalogBackend;
+
+impl Default for DatalogBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DatalogBackend {
+    pub fn new() -> Self {
+        DatalogBackend
+    }
+}
+
+/// Attributs d'une entité, triés : c'est l'ordre des colonnes de sa relation
+/// (après l'identifiant).
+fn entity_attributes<'a>(schema: &'a Schema, entity: &str) -> Vec<(&'a String, &'a BaseType)> {
+    let mut attrs: Vec<(&String, &BaseType)> = schema.attributes_of(entity).into_iter()
+        .filter_map(|e| match e {
+            Edge::Attribute { name, target, .. } => Some((name, target)),
+            _ => None,
+        })
+        .collect();
+    attrs.sort_by_key(|(name, _)| *name);
+    attrs
+}
+
+/// Type Soufflé d'un type de base (pas de booléen : 0/1)
+fn type_to_datalog(ty: &BaseType) -> &'static str {
+    match ty {
+        // Pas de liste en Datalog plat : la liste est écrite comme un symbole
+        BaseType::String | BaseType::Custom(_) | BaseType::Enum { .. } | BaseType::Array(_) => "symbol",
+        BaseType::Integer | BaseType::Boolean => "number",
+        BaseType::Float => "float",
+    }
+}
+
+/// Convertit une Value en constante Soufflé. `None` pour NULL, qui n'a pas
+/// d'équivalent en Datalog.
+fn value_to_datalog(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))),
+        Value::Integer(i) => Some(format!("{}", i)),
+        Value::Float(f) => Some(format!("{:?}", f)),
+        Value::Boolean(b) => Some(if *b { "1".into() } else { "0".into() }),
+        Value::Array(_) => value_to_datalog(&Value::String(value.to_string())),
+        Value::Ref { row, .. } => Some(format!("{}", row)),
+        Value::Null => None,
+    }
+}
+
+/// Attributs NULL (ou absents) d'une ligne : la ligne n'a pas de fait
+fn null_attributes<'a>(entity: &str, schema: &'a Schema, entity_data: &EntityData, row_id: RowId) -> Vec<&'a str> {
+    entity_attributes(schema, entity).into_iter()
+        .filter(|(attr, _)| entity_data.get_attr(row_id, attr).and_then(value_to_datalog).is_none())
+        .map(|(attr, _)| attr.as_str())
+        .collect()
+}
+
+/// Fait d'une ligne d'entité : `Employee(1, "Alice", 90000).`
+fn entity_fact(entity: &str, schema: &Schema, entity_data: &EntityData, row_id: RowId) -> String {
+    let mut args = vec![format!("{}", row_id)];
+    args.extend(entity_attributes(schema, entity).into_iter()
+        .filter_map(|(attr, _)| entity_data.get_attr(row_id, attr).and_then(value_to_datalog)));
+    let nulls = null_attributes(entity, schema, entity_data, row_id);
+    if !nulls.is_empty() {
+        return format!(
+            "// {}({}) : NULL non représentable ({})",
+            entity, row_id, nulls.join(", ")
+        );
+    }
+    format!("{}({}).", entity, args.join(", "))
+}
+
+/// Atome de la relation d'une entité : `Employee(x, _, v)`, avec la variable
+/// `id` en première position et, optionnellement, `value` à la place d'un
+/// attribut.
+fn entity_atom(schema: &Schema, entity: &str, id: &str, value: Option<(&str, &str)>) -> String {
+    let mut args = vec![id.to_string()];
+    for (attr, _) in entity_attributes(schema, entity) {
+        match value {
+            Some((a, var)) if a == attr => args.push(var.to_string()),
+            _ => args.push("_".into()),
+        }
+    }
+    format!("{}({})", entity, args.join(", "))
+}
+
+/// Traduit la navigation d'un chemin en atomes, à partir de la variable `x`.
+/// Retourne les atomes et la variable d'arrivée (`prefix1`, `prefix2`...).
+/// `None` si une arête n'existe pas dans le schéma.
+fn path_atoms(schema: &Schema, path: &Path, prefix: &str) -> Option<(Vec<String>, String)> {
+    let mut atoms = Vec::new();
+    let mut var = "x".to_string();
+    let mut node = path.start.clone();
+
+    for (i, edge_name) in path.edges.iter().enumerate() {
+        let next = format!("{}{}", prefix, i + 1);
+        match schema.edges.get(edge_name)? {
+            Edge::ForeignKey { name, target, .. } => {
+                atoms.push(format!("{}({}, {})", name, var, next));
+                node = target.clone();
+            }
+            Edge::Attribute { name, .. } => {
+                atoms.push(entity_atom(schema, &node, &var, Some((name, &next))));
+            }
+        }
+        var = next;
+    }
+
+    Some((atoms, var))
+}
+
+impl Backend for DatalogBackend {
+    fn deploy_schema(&self, schema: &Schema) -> Vec<Statement> {
+        let mut stmts = Vec::new();
+
+        // Une relation par entité : id, puis les attributs
+        for entity in schema.topological_order() {
+            let cols: Vec<String> = std::iter::once("id:number".to_string())
+                .chain(entity_attributes(schema, &entity).into_iter()
+                    .map(|(name, ty)| format!("{}:{}", name, type_to_datalog(ty))))
+                .collect();
+            stmts.push(Statement::Datalog(format!(".decl {}({})", entity, cols.join(", "))));
+        }
+
+        // Une relation binaire par FK
+        let mut fks: Vec<&str> = schema.foreign_keys().into_iter().map(|e| e.name()).collect();
+        fks.sort();
+        for fk in fks {
+            stmts.push(Statement::Datalog(format!(".decl {}(id:number, target:number)", fk)));
+        }
+
+        // Équations de chemins : règle qui dérive les lignes en violation
+        for (i, eq) in schema.path_equations.iter().enumerate() {
+            let (Some((lhs_atoms, lhs_var)), Some((rhs_atoms, rhs_var))) =
+                (path_atoms(schema, &eq.lhs, "l"), path_atoms(schema, &eq.rhs, "r"))
+            else {
+                stmts.push(Statement::Datalog(format!("// Équation {} ignorée : arête inconnue ({})", i, eq)));
+                continue;
+            };
+
+            let relation = format!("eq_violation_{}", i);
+            let body: Vec<String> = std::iter::once(entity_atom(schema, &eq.lhs.start, "x", None))
+                .chain(lhs_atoms)
+                .chain(rhs_atoms)
+                .chain(std::iter::once(format!("{} != {}", lhs_var, rhs_var)))
+                .collect();
+
+            stmts.push(Statement::Datalog(format!("// Équation {} : {}", i, eq)));
+            stmts.push(Statement::Datalog(format!(".decl {}(id:number)", relation)));
+            stmts.push(Statement::Datalog(format!("{}(x) :- {}.", relation, body.join(", "))));
+            stmts.push(Statement::Datalog(format!(".output {}", relation)));
+        }
+
+        stmts
+    }
+
+    fn export_instance_iter<'a>(
+        &'a self,
+        schema: &'a Schema,
+        instance: &'a Instance,
+    ) -> Box<dyn Iterator<Item = Statement> + 'a> {
+        let rows = move || {
+            schema.topological_order().into_iter().filter_map(move |entity| {
+                instance.data.get(&entity).map(|entity_data| {
+                    let mut row_ids = entity_data.row_ids();
+                    row_ids.sort();
+                    (entity, entity_data, row_ids)
+                })
+            })
+        };
+
+        // Faits des entités, puis faits des FK
+        let entities = rows().flat_map(move |(entity, entity_data, row_ids)| {
+            row_ids.into_iter().map(move |row_id| {
+                Statement::Datalog(entity_fact(&entity, schema, entity_data, row_id))
+            })
+        });
+
+        // Une ligne sans fait (attribut NULL) n'a pas non plus de FK, ni
+        // sortantes ni entrantes : elles désigneraient un identifiant absent
+        // de la relation de l'entité.
+        let has_fact = move |entity: &str, row_id: RowId| {
+            instance.data.get(entity)
+                .is_some_and(|d| null_attributes(entity, schema, d, row_id).is_empty())
+        };
+        let fks = rows().flat_map(move |(entity, entity_data, row_ids)| {
+            row_ids.into_iter().flat_map(move |row_id| {
+                let mut fks = entity_data.row_fks(row_id);
+                fks.sort();
+                let entity = entity.clone();
+                fks.into_iter().map(move |(fk, target_id)| {
+                    let target = match schema.edges.get(fk) {
+                        Some(Edge::ForeignKey { target, .. }) => target.as_str(),
+                        _ => "",
+                    };
+                    if has_fact(&entity, row_id) && has_fact(target, target_id) {
+                        Statement::Datalog(format!("{}({}, {}).", fk, row_id, target_id))
+                    } else {
+                        Statement::Datalog(format!("// {}({}, {}) : ligne sans fait (NULL)", fk, row_id, target_id))
+                    }
+                })
+            })
+        });
+
+        Box::new(entities.chain(fks))
+    }
+
+    fn generate_delta(&self, _mapping: &Mapping, _source: &Schema, _target: &Schema) -> Vec<Statement> {
+        // TODO: Générer les règles de Δ (une règle par relation cible)
+        vec![Statement::Datalog("// TODO: Delta migration Datalog".into())]
+    }
+
+    fn generate_sigma(&self, _mapping: &Mapping, _source: &Schema, _target: &Schema) -> Vec<Statement> {
+        // TODO: Générer les règles de Σ
+        vec![Statement::Datalog("// TODO: Sigma migration Datalog".into())]
+    }
+
+    fn name(&self) -> &str {
+        "Datalog"
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn company_schema() -> Schema {
+        let mut s = Schema::new("Company");
+        s.add_node("Employee")
+         .add_node("Department")
+         .add_fk("works_in", "Employee", "Department")
+         .add_fk("manager", "Department", "Employee")
+         .add_fk("direct_mgr", "Employee", "Employee")
+         .add_attribute("emp_name", "Employee", BaseType::String)
+         .add_attribute("salary", "Employee", BaseType::Integer)
+         .add_attribute("dept_name", "Department", BaseType::String)
+         .add_path_equation(
+             Path::new("Employee", vec!["works_in", "manager"]),
+             Path::new("Employee", vec!["direct_mgr"]),
+         );
+        s
+    }
+
+    #[test]
+    fn test_datalog_facts() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        let d = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Engineering".into()))]),
+            HashMap::new(),
+        );
+        let alice = inst.insert("Employee",
+            HashMap::from([
+                ("emp_name".into(), Value::String("Alice".into())),
+                ("salary".into(), Value::Integer(90000)),
+            ]),
+            HashMap::from([("works_in".into(), d)]),
+        );
+        inst.set_fk("Employee", alice, "direct_mgr", alice, &schema).unwrap();
+        inst.set_fk("Department", d, "manager", alice, &schema).unwrap();
+
+        let backend = DatalogBackend::new();
+        let decls: Vec<String> = backend.deploy_schema(&schema).iter().map(|s| s.to_string()).collect();
+        assert!(decls.contains(&".decl Employee(id:number, emp_name:symbol, salary:number)".to_string()));
+        assert!(decls.contains(&".decl works_in(id:number, target:number)".to_string()));
+        assert!(decls.contains(
+            &"eq_violation_0(x) :- Employee(x, _, _), works_in(x, l1), manager(l1, l2), direct_mgr(x, r1), l2 != r1.".to_string()
+        ));
+
+        let facts: Vec<String> = backend.export_instance(&schema, &inst).iter().map(|s| s.to_string()).collect();
+        assert!(facts.contains(&format!("Employee({}, \"Alice\", 90000).", alice)));
+        assert!(facts.contains(&format!("Department({}, \"Engineering\").", d)));
+        assert!(facts.contains(&format!("works_in({}, {}).", alice, d)));
+        println!("=== Datalog ===\n{}\n{}", decls.join("\n"), facts.join("\n"));
+    }
+
+    #[test]
+    fn test_datalog_null_row_has_no_fk_facts() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        let d = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Engineering".into()))]),
+            HashMap::new(),
+        );
+        // Bob n'a pas de salaire : pas de fait Employee, donc pas de FK
+        let bob = inst.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String("Bob".into()))]),
+            HashMap::from([("works_in".into(), d)]),
+        );
+        inst.set_fk("Department", d, "manager", bob, &schema).unwrap();
+
+        let facts: Vec<String> = DatalogBackend::new().export_instance(&schema, &inst).iter().map(|s| s.to_string()).collect();
+        assert!(facts.contains(&format!("// Employee({}) : NULL non représentable (salary)", bob)), "{:?}", facts);
+        assert!(facts.contains(&format!("Department({}, \"Engineering\").", d)), "{:?}", facts);
+        // Ni la FK sortante de Bob, ni celle qui le vise
+        assert!(!facts.iter().any(|f| f.starts_with("works_in(") || f.starts_with("manager(")), "{:?}", facts);
+        assert!(facts.contains(&format!("// works_in({}, {}) : ligne sans fait (NULL)", bob, d)), "{:?}", facts);
+        assert!(facts.contains(&format!("// manager({}, {}) : ligne sans fait (NULL)", d, bob)), "{:?}", facts);
+    }
+}
@@ -0,0 +1,159 @@
+// =============================================================================
+// EXECUTOR — Exécution des statements produits par un backend
+// =============================================================================
+//
+// Les backends ne font que GÉNÉRER des commandes (`Vec<Statement>`).
+// Un Executor les EXÉCUTE : contre une vraie base (pilote derrière une
+// feature), ou à blanc pour tester la chaîne deploy → export de bout en
+// bout sans base de données.
+//
+//   let mut exec = RecordingExecutor::new();
+//   exec.execute_all(&backend.deploy_schema(&schema))?;
+//   exec.execute_all(&backend.export_instance(&schema, &instance))?;
+//
+// Le cœur catégorique n'en dépend pas : c'est un simple consommateur
+// de `Statement`.
+//
+// =============================================================================
+
+use super::{Backend, Statement};
+use crate::core::instance::Instance;
+use crate::core::schema::Schema;
+
+/// Exécute des statements sur une cible (base de données ou simulation).
+pub trait Executor {
+    /// Exécute un statement ; retourne le nombre de lignes affectées.
+    fn execute(&mut self, stmt: &Statement) -> Result<u64, String>;
+
+    /// Exécute les statements dans l'ordre et s'arrête à la première
+    /// erreur. Retourne le total des lignes affectées.
+    fn execute_all(&mut self, stmts: &[Statement]) -> Result<u64, String> {
+        let mut total = 0;
+        for stmt in stmts {
+            total += self.execute(stmt)?;
+        }
+        Ok(total)
+    }
+}
+
+/// Déploie le schéma puis charge l'instance (entités dans l'ordre
+/// topologique, comme `export_instance`) via `executor`. Les statements
+/// d'export sont générés au fil de l'eau. Retourne les lignes affectées.
+pub fn deploy_and_load<B, E>(backend: &B, schema: &Schema, instance: &Instance, executor: &mut E) -> Result<u64, String>
+where
+    B: Backend + ?Sized,
+    E: Executor + ?Sized,
+{
+    let mut total = executor.execute_all(&backend.deploy_schema(schema))?;
+    for stmt in backend.export_instance_iter(schema, instance) {
+        total += executor.execute(&stmt)?;
+    }
+    Ok(total)
+}
+
+/// Lignes qu'affecterait un statement, exécuté à blanc : 1 pour une
+/// commande de données (INSERT / UPDATE / DELETE, CREATE / MERGE d'un nœud
+/// Cypher, fait Datalog), 0 pour la structure (DDL, contraintes, règles).
+///
+/// Les backends émettent une ligne par commande de données, d'où le 1.
+pub fn estimated_affected_rows(stmt: &Statement) -> u64 {
+    let starts_with = |text: &str, keywords: &[&str]| {
+        let head = text.trim_start().to_uppercase();
+        keywords.iter().any(|k| head.starts_with(k))
+    };
+    let is_data = match stmt {
+        Statement::Sql(sql) => starts_with(sql, &["INSERT", "UPDATE", "DELETE"]),
+        Statement::Cypher(cypher) => {
+            starts_with(cypher, &["CREATE", "MERGE", "MATCH", "UNWIND"])
+                && !starts_with(cypher, &["CREATE CONSTRAINT", "CREATE INDEX"])
+        }
+        Statement::Datalog(line) => {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('.') && !line.starts_with("//") && !line.contains(":-")
+        }
+    };
+    u64::from(is_data)
+}
+
+/// Executor qui ne fait rien : chaque statement « réussit » avec
+/// `estimated_affected_rows` lignes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullExecutor;
+
+impl Executor for NullExecutor {
+    fn execute(&mut self, stmt: &Statement) -> Result<u64, String> {
+        Ok(estimated_affected_rows(stmt))
+    }
+}
+
+/// Executor à blanc qui garde chaque statement exécuté, dans l'ordre,
+/// avec le nombre de lignes annoncé (`estimated_affected_rows`).
+#[derive(Debug, Clone, Default)]
+pub struct RecordingExecutor {
+    /// Statements exécutés et lignes affectées
+    pub recorded: Vec<(Statement, u64)>,
+}
+
+impl RecordingExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Statements exécutés, dans l'ordre
+    pub fn statements(&self) -> impl Iterator<Item = &Statement> {
+        self.recorded.iter().map(|(stmt, _)| stmt)
+    }
+
+    /// Total des lignes affectées
+    pub fn affected_rows(&self) -> u64 {
+        self.recorded.iter().map(|(_, rows)| rows).sum()
+    }
+}
+
+impl Executor for RecordingExecutor {
+    fn execute(&mut self, stmt: &Statement) -> Result<u64, String> {
+        let rows = estimated_affected_rows(stmt);
+        self.recorded.push((stmt.clone(), rows));
+        Ok(rows)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::sql::{PostgresDialect, SqlBackend};
+    use crate::core::typeside::BaseType;
+    use crate::{fks, row};
+
+    #[test]
+    fn test_recording_executor_runs_deploy_and_export() {
+        let mut schema = Schema::new("Company");
+        schema.add_node("Employee")
+              .add_node("Department")
+              .add_fk("works_in", "Employee", "Department")
+              .add_attribute("emp_name", "Employee", BaseType::String)
+              .add_attribute("dept_name", "Department", BaseType::String);
+
+        let mut inst = Instance::new("Data", &schema);
+        let d1 = inst.insert("Department", row!{dept_name: "Engineering"}, fks!{});
+        inst.insert("Employee", row!{emp_name: "Alice"}, fks!{works_in: d1});
+        inst.insert("Employee", row!{emp_name: "Bob"}, fks!{works_in: d1});
+
+        let backend = SqlBackend::new(PostgresDialect);
+        let ddl = backend.deploy_schema(&schema);
+        let dml = backend.export_instance(&schema, &inst);
+
+        let mut exec = RecordingExecutor::new();
+        assert_eq!(exec.execute_all(&ddl).unwrap(), 0);
+        assert_eq!(exec.execute_all(&dml).unwrap(), 3);
+        assert_eq!(exec.recorded.len(), ddl.len() + dml.len());
+        assert_eq!(exec.affected_rows(), 3);
+        assert!(exec.statements().last().unwrap().to_string().starts_with("INSERT INTO \"Employee\""));
+
+        assert_eq!(NullExecutor.execute_all(&dml).unwrap(), 3);
+        assert_eq!(deploy_and_load(&backend, &schema, &inst, &mut NullExecutor).unwrap(), 3);
+    }
+}
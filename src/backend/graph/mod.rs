@@ -12,39 +12,235 @@
 //
 // =============================================================================
 
+use std::sync::Arc;
 use crate::core::schema::{Schema, Edge};
-use crate::core::instance::Instance;
+use crate::core::instance::{Instance, EntityData};
 use crate::core::mapping::Mapping;
 use crate::core::typeside::Value;
-use crate::backend::{Backend, Statement};
+use crate::backend::{Backend, Statement, IdStrategy};
+
+/// Stratégie de nommage des types de relation à partir des noms de FK
+#[derive(Clone, Default)]
+pub enum RelNaming {
+    /// `works_in` / `worksIn` → `WORKS_IN` (convention Neo4j)
+    #[default]
+    UpperSnake,
+    /// Le nom de la FK tel quel
+    Verbatim,
+    /// Fonction fournie par l'utilisateur
+    Custom(Arc<dyn Fn(&str) -> String + Send + Sync>),
+}
+
+impl RelNaming {
+    /// Type de relation Neo4j d'une FK
+    pub fn apply(&self, fk_name: &str) -> String {
+        match self {
+            RelNaming::UpperSnake => {
+                let mut out = String::new();
+                let mut prev_lower = false;
+                for c in fk_name.chars() {
+                    if c.is_uppercase() && prev_lower {
+                        out.push('_');
+                    }
+                    prev_lower = c.is_lowercase() || c.is_ascii_digit();
+                    out.extend(c.to_uppercase());
+                }
+                out
+            }
+            RelNaming::Verbatim => fk_name.to_string(),
+            RelNaming::Custom(f) => f(fk_name),
+        }
+    }
+}
+
+impl std::fmt::Debug for RelNaming {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RelNaming::UpperSnake => write!(f, "UpperSnake"),
+            RelNaming::Verbatim => write!(f, "Verbatim"),
+            RelNaming::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
 
 /// Backend Neo4j — génère du Cypher
-pub struct Neo4jBackend;
+pub struct Neo4jBackend {
+    /// Propriété d'identifiant de chaque nœud (par défaut `catrust_id`)
+    pub id_strategy: IdStrategy,
+    /// Nommage des types de relation (par défaut `WORKS_IN`)
+    pub rel_naming: RelNaming,
+    /// Taille des lots `UNWIND` de l'export (`None` = un `CREATE` par ligne)
+    pub batch_size: Option<usize>,
+}
+
+impl Default for Neo4jBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Neo4jBackend {
     pub fn new() -> Self {
-        Neo4jBackend
+        Neo4jBackend {
+            id_strategy: IdStrategy::default(),
+            rel_naming: RelNaming::default(),
+            batch_size: None,
+        }
+    }
+
+    /// Remplace la stratégie d'identifiant
+    pub fn with_id_strategy(mut self, id_strategy: IdStrategy) -> Self {
+        self.id_strategy = id_strategy;
+        self
+    }
+
+    /// Remplace la stratégie de nommage des relations
+    pub fn with_rel_naming(mut self, rel_naming: RelNaming) -> Self {
+        self.rel_naming = rel_naming;
+        self
+    }
+
+    /// Exporte par lots : un `UNWIND` par groupe d'au plus `batch_size`
+    /// nœuds d'un même label (ou relations d'une même FK). Une taille
+    /// nulle est ramenée à 1.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size.max(1));
+        self
+    }
+
+    /// Type de relation Neo4j d'une FK, selon `rel_naming`
+    pub fn rel_type(&self, fk_name: &str) -> String {
+        self.rel_naming.apply(fk_name)
+    }
+
+    /// Nom de la contrainte d'unicité de l'identifiant d'un label
+    fn constraint_name(&self, label: &str) -> String {
+        format!("{}_{}_unique", label, self.id_strategy.column)
     }
 
     /// Génère le Cypher pour créer un nœud avec ses propriétés
     fn create_node_cypher(
+        &self,
         entity_name: &str,
         row_id: u64,
         attrs: &std::collections::HashMap<String, Value>,
     ) -> String {
-        let props: Vec<String> = std::iter::once(format!("catrust_id: {}", row_id))
-            .chain(attrs.iter().map(|(k, v)| format!("{}: {}", k, value_to_cypher(v))))
+        let props: Vec<String> = std::iter::once(format!("{}: {}", cypher_ident(&self.id_strategy.column), row_id))
+            .chain(attrs.iter().map(|(k, v)| format!("{}: {}", cypher_ident(k), value_to_cypher(v))))
             .collect();
 
         format!(
             "CREATE (:{} {{ {} }});",
-            entity_name,
+            cypher_ident(entity_name),
             props.join(", ")
         )
     }
 
+    /// Génère un `UNWIND` créant un lot de nœuds d'un même label.
+    /// Les lignes sont embarquées en liste littérale :
+    /// `{id: 1, props: {...}}`, les propriétés étant posées par `SET n += row.props`.
+    fn create_nodes_batch_cypher(
+        &self,
+        entity_name: &str,
+        entity_data: &EntityData,
+        row_ids: &[u64],
+    ) -> String {
+        let rows: Vec<String> = row_ids.iter().map(|row_id| {
+            let mut attrs = entity_data.row_attrs(*row_id);
+            attrs.sort_by(|a, b| a.0.cmp(b.0));
+            let props: Vec<String> = attrs.iter()
+                .map(|(k, v)| format!("{}: {}", cypher_ident(k), value_to_cypher(v)))
+                .collect();
+            format!("{{id: {}, props: {{{}}}}}", row_id, props.join(", "))
+        }).collect();
+
+        format!(
+            "UNWIND [{}] AS row CREATE (n:{} {{ {}: row.id }}) SET n += row.props;",
+            rows.join(", "),
+            cypher_ident(entity_name),
+            cypher_ident(&self.id_strategy.column),
+        )
+    }
+
+    /// Génère un `UNWIND` créant un lot de relations d'une même FK
+    fn create_relationships_batch_cypher(
+        &self,
+        source_entity: &str,
+        rel_name: &str,
+        target_entity: &str,
+        pairs: &[(u64, u64)],
+    ) -> String {
+        let rows: Vec<String> = pairs.iter()
+            .map(|(from, to)| format!("{{from: {}, to: {}}}", from, to))
+            .collect();
+        format!(
+            "UNWIND [{}] AS row MATCH (a:{} {{ {id}: row.from }}), (b:{} {{ {id}: row.to }}) CREATE (a)-[:{}]->(b);",
+            rows.join(", "),
+            cypher_ident(source_entity),
+            cypher_ident(target_entity),
+            cypher_ident(&self.rel_type(rel_name)),
+            id = cypher_ident(&self.id_strategy.column),
+        )
+    }
+
+    /// Export par lots `UNWIND` : nœuds label par label, puis relations FK par FK
+    fn export_batched<'a>(
+        &'a self,
+        schema: &'a Schema,
+        instance: &'a Instance,
+        batch_size: usize,
+    ) -> Box<dyn Iterator<Item = Statement> + 'a> {
+        let order = schema.topological_order();
+
+        let rows = move || {
+            order.clone().into_iter().filter_map(move |entity_name| {
+                instance.data.get(&entity_name).map(|entity_data| {
+                    let mut row_ids = entity_data.row_ids();
+                    row_ids.sort();
+                    (entity_name, entity_data, row_ids)
+                })
+            })
+        };
+
+        // Phase 1 : les nœuds, par lots d'un même label
+        let nodes = rows().flat_map(move |(entity_name, entity_data, row_ids)| {
+            row_ids.chunks(batch_size)
+                .map(|chunk| Statement::Cypher(
+                    self.create_nodes_batch_cypher(&entity_name, entity_data, chunk)
+                ))
+                .collect::<Vec<_>>()
+        });
+
+        // Phase 2 : les relations, par lots d'une même FK
+        let relationships = rows().flat_map(move |(entity_name, entity_data, row_ids)| {
+            let mut fks: Vec<(&String, &String)> = schema.edges.values()
+                .filter_map(|e| match e {
+                    Edge::ForeignKey { name, source, target, .. } if *source == entity_name => {
+                        Some((name, target))
+                    }
+                    _ => None,
+                })
+                .collect();
+            fks.sort();
+            fks.into_iter().flat_map(|(fk_name, target)| {
+                let pairs: Vec<(u64, u64)> = row_ids.iter()
+                    .filter_map(|row_id| entity_data.get_fk(*row_id, fk_name)
+                        .map(|target_id| (*row_id, target_id)))
+                    .collect();
+                pairs.chunks(batch_size)
+                    .map(|chunk| Statement::Cypher(self.create_relationships_batch_cypher(
+                        &entity_name, fk_name, target, chunk,
+                    )))
+                    .collect::<Vec<_>>()
+            }).collect::<Vec<_>>()
+        });
+
+        Box::new(nodes.chain(relationships))
+    }
+
     /// Génère le Cypher pour créer une relation entre deux nœuds
     fn create_relationship_cypher(
+        &self,
         source_entity: &str,
         source_id: u64,
         rel_name: &str,
@@ -52,88 +248,202 @@ impl Neo4jBackend {
         target_id: u64,
     ) -> String {
         format!(
-            "MATCH (a:{} {{ catrust_id: {} }}), (b:{} {{ catrust_id: {} }}) CREATE (a)-[:{}]->(b);",
-            source_entity, source_id,
-            target_entity, target_id,
-            rel_name.to_uppercase(),
+            "MATCH (a:{} {{ {id}: {} }}), (b:{} {{ {id}: {} }}) CREATE (a)-[:{}]->(b);",
+            cypher_ident(source_entity), source_id,
+            cypher_ident(target_entity), target_id,
+            cypher_ident(&self.rel_type(rel_name)),
+            id = cypher_ident(&self.id_strategy.column),
         )
     }
 }
 
+/// Nom de l'index Neo4j d'une propriété
+fn index_name(label: &str, property: &str) -> String {
+    format!("{}_{}_index", label, property)
+}
+
+/// Identifiant Cypher (label, type de relation, propriété, nom d'index) :
+/// tel quel s'il est simple, sinon entre backticks (les backticks internes
+/// doublés)
+fn cypher_ident(name: &str) -> String {
+    let simple = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if simple {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "``"))
+    }
+}
+
+/// Littéral de chaîne Cypher entre apostrophes : backslash, guillemets et
+/// caractères de contrôle sont échappés (`\n`, `\t`, `\uXXXX`...), le reste
+/// de l'Unicode est gardé tel quel.
+///
+/// Le crate n'a pas d'exécuteur Cypher : les valeurs sont écrites en
+/// littéraux. Un exécuteur devrait plutôt les passer en paramètres.
+fn cypher_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('\'');
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+    out
+}
+
 /// Convertit une Value en littéral Cypher
 fn value_to_cypher(value: &Value) -> String {
     match value {
-        Value::String(s) => format!("'{}'", s.replace('\'', "\\'")),
+        Value::String(s) => cypher_string(s),
         Value::Integer(i) => format!("{}", i),
+        // Pas de littéral Cypher pour NaN et ±∞ : conversion de leur nom
+        Value::Float(f) if f.is_nan() => "toFloat('NaN')".into(),
+        Value::Float(f) if f.is_infinite() => {
+            format!("toFloat('{}Infinity')", if *f < 0.0 { "-" } else { "" })
+        }
         Value::Float(f) => format!("{}", f),
         Value::Boolean(b) => if *b { "true".into() } else { "false".into() },
+        // Propriété liste
+        Value::Array(values) => {
+            let items: Vec<String> = values.iter().map(value_to_cypher).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Ref { row, .. } => format!("{}", row),
         Value::Null => "null".into(),
     }
 }
 
 impl Backend for Neo4jBackend {
     fn deploy_schema(&self, schema: &Schema) -> Vec<Statement> {
+        let mut labels: Vec<&String> = schema.nodes.keys().collect();
+        labels.sort();
         let mut stmts = Vec::new();
 
         // Créer des contraintes d'unicité pour chaque label
-        for entity_name in schema.nodes.keys() {
+        // (nommées, pour pouvoir les supprimer dans teardown_schema)
+        for entity_name in labels {
             stmts.push(Statement::Cypher(format!(
-                "CREATE CONSTRAINT IF NOT EXISTS FOR (n:{}) REQUIRE n.catrust_id IS UNIQUE;",
-                entity_name
+                "CREATE CONSTRAINT {} IF NOT EXISTS FOR (n:{}) REQUIRE n.{} IS UNIQUE;",
+                cypher_ident(&self.constraint_name(entity_name)),
+                cypher_ident(entity_name),
+                cypher_ident(&self.id_strategy.column),
             )));
         }
 
         // Créer des index sur les propriétés fréquemment utilisées
-        for edge in schema.edges.values() {
-            if let Edge::Attribute { name, source, .. } = edge {
-                stmts.push(Statement::Cypher(format!(
-                    "CREATE INDEX IF NOT EXISTS FOR (n:{}) ON (n.{});",
-                    source, name
-                )));
-            }
+        let mut attrs: Vec<(&String, &String)> = schema.edges.values()
+            .filter_map(|e| match e {
+                Edge::Attribute { name, source, .. } => Some((source, name)),
+                _ => None,
+            })
+            .collect();
+        attrs.sort();
+        for (source, name) in attrs {
+            stmts.push(Statement::Cypher(format!(
+                "CREATE INDEX {} IF NOT EXISTS FOR (n:{}) ON (n.{});",
+                cypher_ident(&index_name(source, name)), cypher_ident(source), cypher_ident(name)
+            )));
         }
 
         stmts
     }
 
-    fn export_instance(&self, schema: &Schema, instance: &Instance) -> Vec<Statement> {
+    fn teardown_schema(&self, schema: &Schema) -> Vec<Statement> {
+        let mut labels: Vec<&String> = schema.nodes.keys().collect();
+        labels.sort();
         let mut stmts = Vec::new();
 
-        // Phase 1 : Créer tous les nœuds
-        for (entity_name, entity_data) in &instance.data {
-            for row_id in entity_data.row_ids() {
-                let attrs = entity_data.attribute_values
-                    .get(&row_id)
-                    .cloned()
-                    .unwrap_or_default();
-                stmts.push(Statement::Cypher(
-                    Neo4jBackend::create_node_cypher(entity_name, row_id, &attrs)
-                ));
-            }
+        // D'abord les données : DETACH DELETE supprime aussi les relations
+        for label in &labels {
+            stmts.push(Statement::Cypher(format!("MATCH (n:{}) DETACH DELETE n;", cypher_ident(label))));
         }
 
-        // Phase 2 : Créer toutes les relations (FK)
-        for (entity_name, entity_data) in &instance.data {
-            for row_id in entity_data.row_ids() {
-                if let Some(fks) = entity_data.fk_values.get(&row_id) {
-                    for (fk_name, target_id) in fks {
-                        // Trouver l'entité cible
-                        if let Some(Edge::ForeignKey { target, .. }) = schema.edges.get(fk_name) {
-                            stmts.push(Statement::Cypher(
-                                Neo4jBackend::create_relationship_cypher(
-                                    entity_name, row_id,
-                                    fk_name, target, *target_id,
-                                )
-                            ));
-                        }
-                    }
-                }
-            }
+        // Puis la structure : index et contraintes
+        let mut attrs: Vec<(&String, &String)> = schema.edges.values()
+            .filter_map(|e| match e {
+                Edge::Attribute { name, source, .. } => Some((source, name)),
+                _ => None,
+            })
+            .collect();
+        attrs.sort();
+        for (source, name) in attrs {
+            stmts.push(Statement::Cypher(format!(
+                "DROP INDEX {} IF EXISTS;", cypher_ident(&index_name(source, name))
+            )));
+        }
+        for label in labels {
+            stmts.push(Statement::Cypher(format!(
+                "DROP CONSTRAINT {} IF EXISTS;", cypher_ident(&self.constraint_name(label))
+            )));
         }
 
         stmts
     }
 
+    fn export_instance_iter<'a>(
+        &'a self,
+        schema: &'a Schema,
+        instance: &'a Instance,
+    ) -> Box<dyn Iterator<Item = Statement> + 'a> {
+        if let Some(batch_size) = self.batch_size {
+            return self.export_batched(schema, instance, batch_size);
+        }
+        let order = schema.topological_order();
+
+        // (entité, données, lignes triées) dans l'ordre topologique
+        let rows = move || {
+            order.clone().into_iter().filter_map(move |entity_name| {
+                instance.data.get(&entity_name).map(|entity_data| {
+                    let mut row_ids = entity_data.row_ids();
+                    row_ids.sort();
+                    (entity_name, entity_data, row_ids)
+                })
+            })
+        };
+
+        // Phase 1 : Créer tous les nœuds
+        let nodes = rows().flat_map(move |(entity_name, entity_data, row_ids)| {
+            row_ids.into_iter().map(move |row_id| {
+                let attrs = entity_data.row(row_id).map(|row| row.attrs).unwrap_or_default();
+                Statement::Cypher(self.create_node_cypher(&entity_name, row_id, &attrs))
+            })
+        });
+
+        // Phase 2 : Créer toutes les relations (FK)
+        let relationships = rows().flat_map(move |(entity_name, entity_data, row_ids)| {
+            row_ids.into_iter().flat_map(move |row_id| {
+                let mut fks = entity_data.row_fks(row_id);
+                fks.sort();
+                let entity_name = entity_name.clone();
+                fks.into_iter().filter_map(move |(fk_name, target_id)| {
+                    // Trouver l'entité cible
+                    match schema.edges.get(fk_name) {
+                        Some(Edge::ForeignKey { target, .. }) => Some(Statement::Cypher(
+                            self.create_relationship_cypher(
+                                &entity_name, row_id,
+                                fk_name, target, target_id,
+                            )
+                        )),
+                        _ => None,
+                    }
+                })
+            })
+        });
+
+        Box::new(nodes.chain(relationships))
+    }
+
     fn generate_delta(&self, _mapping: &Mapping, _source: &Schema, _target: &Schema) -> Vec<Statement> {
         // TODO: Générer les MATCH ... RETURN pour Δ
         vec![Statement::Cypher("// TODO: Delta migration Cypher".into())]
@@ -179,6 +489,15 @@ mod tests {
         let cypher = stmts.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n");
         assert!(cypher.contains("CREATE CONSTRAINT"));
         println!("=== Neo4j Schema ===\n{}", cypher);
+
+        // Ordre stable : contraintes par label, index par (label, attribut)
+        let lines: Vec<String> = stmts.iter().map(|s| s.to_string()).collect();
+        assert_eq!(lines, vec![
+            "CREATE CONSTRAINT Department_catrust_id_unique IF NOT EXISTS FOR (n:Department) REQUIRE n.catrust_id IS UNIQUE;",
+            "CREATE CONSTRAINT Employee_catrust_id_unique IF NOT EXISTS FOR (n:Employee) REQUIRE n.catrust_id IS UNIQUE;",
+            "CREATE INDEX Department_dept_name_index IF NOT EXISTS FOR (n:Department) ON (n.dept_name);",
+            "CREATE INDEX Employee_emp_name_index IF NOT EXISTS FOR (n:Employee) ON (n.emp_name);",
+        ]);
     }
 
     #[test]
@@ -203,4 +522,214 @@ mod tests {
         assert!(cypher.contains("WORKS_IN"));
         println!("=== Neo4j Instance ===\n{}", cypher);
     }
+
+    #[test]
+    fn test_neo4j_rel_naming() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        let d1 = inst.insert("Department", HashMap::new(), HashMap::new());
+        inst.insert("Employee", HashMap::new(), HashMap::from([("works_in".into(), d1)]));
+
+        let cypher = |backend: &Neo4jBackend| backend.export_instance(&schema, &inst).iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let verbatim = Neo4jBackend::new().with_rel_naming(RelNaming::Verbatim);
+        assert!(cypher(&verbatim).contains("CREATE (a)-[:works_in]->(b)"));
+        assert!(!cypher(&verbatim).contains("WORKS_IN"));
+
+        let custom = Neo4jBackend::new()
+            .with_rel_naming(RelNaming::Custom(Arc::new(|fk| format!("HAS_{}", fk.to_uppercase()))));
+        assert!(cypher(&custom).contains("-[:HAS_WORKS_IN]->"));
+
+        assert_eq!(RelNaming::UpperSnake.apply("worksIn"), "WORKS_IN");
+    }
+
+    #[test]
+    fn test_neo4j_teardown() {
+        let schema = company_schema();
+        let backend = Neo4jBackend::new();
+        let cypher: Vec<String> = backend.teardown_schema(&schema).iter().map(|s| s.to_string()).collect();
+
+        let first_drop = cypher.iter().position(|c| c.starts_with("DROP")).unwrap();
+        let last_delete = cypher.iter().rposition(|c| c.contains("DETACH DELETE")).unwrap();
+        assert!(last_delete < first_drop, "Les relations doivent être détachées avant le DROP");
+        assert!(cypher.contains(&"MATCH (n:Employee) DETACH DELETE n;".to_string()));
+        assert!(cypher.contains(&"DROP CONSTRAINT Employee_catrust_id_unique IF EXISTS;".to_string()));
+
+        // Les noms supprimés sont ceux créés par deploy_schema
+        let deploy = backend.deploy_schema(&schema).iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n");
+        assert!(deploy.contains("CREATE CONSTRAINT Employee_catrust_id_unique IF NOT EXISTS"));
+        assert!(deploy.contains("CREATE INDEX Employee_emp_name_index IF NOT EXISTS"));
+    }
+
+    #[test]
+    fn test_neo4j_unwind_batches() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        for d in 0..3 {
+            let dept = inst.insert("Department",
+                HashMap::from([("dept_name".into(), Value::String(format!("D{}", d)))]),
+                HashMap::new(),
+            );
+            for e in 0..10 {
+                inst.insert("Employee",
+                    HashMap::from([("emp_name".into(), Value::String(format!("E{}_{}", d, e)))]),
+                    HashMap::from([("works_in".into(), dept)]),
+                );
+            }
+        }
+
+        let per_row = Neo4jBackend::new().export_instance(&schema, &inst);
+        assert_eq!(per_row.len(), 3 + 30 + 30);
+
+        let batched = Neo4jBackend::new().with_batch_size(25).export_instance(&schema, &inst);
+        let cypher: Vec<String> = batched.iter().map(|s| s.to_string()).collect();
+        // Department : 1 lot ; Employee : 2 lots ; WORKS_IN : 2 lots
+        assert_eq!(cypher.len(), 5);
+        assert!(cypher.iter().all(|c| c.starts_with("UNWIND [")));
+        assert!(cypher[0].contains("CREATE (n:Department { catrust_id: row.id }) SET n += row.props;"));
+        assert!(cypher[0].contains("props: {dept_name: 'D0'}"));
+        assert!(cypher[3].contains(
+            "MATCH (a:Employee { catrust_id: row.from }), (b:Department { catrust_id: row.to }) CREATE (a)-[:WORKS_IN]->(b);"
+        ));
+
+        // Une taille nulle est ramenée à 1 : un lot par ligne, sans panique
+        let unit = Neo4jBackend::new().with_batch_size(0);
+        assert_eq!(unit.batch_size, Some(1));
+        assert_eq!(unit.export_instance(&schema, &inst).len(), 3 + 30 + 30);
+    }
+
+    #[test]
+    fn test_neo4j_global_ids() {
+        use crate::core::instance::IdPolicy;
+
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema).with_id_policy(IdPolicy::Global);
+        let d1 = inst.insert("Department", HashMap::new(), HashMap::new());
+        let d2 = inst.insert("Department", HashMap::new(), HashMap::new());
+        let e1 = inst.insert("Employee", HashMap::new(), HashMap::from([("works_in".into(), d2)]));
+
+        let cypher: Vec<String> = Neo4jBackend::new().export_instance(&schema, &inst).iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut ids: Vec<&str> = cypher.iter()
+            .filter_map(|c| c.strip_prefix("CREATE (:"))
+            .filter_map(|c| c.split("catrust_id: ").nth(1))
+            .map(|c| c.trim_end_matches(" });"))
+            .collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 3, "Aucun catrust_id partagé entre Employee et Department");
+
+        assert_ne!(e1, d1);
+        let rel = cypher.iter().find(|c| c.contains("WORKS_IN")).unwrap();
+        assert!(rel.contains(&format!("(a:Employee {{ catrust_id: {} }})", e1)), "{}", rel);
+        assert!(rel.contains(&format!("(b:Department {{ catrust_id: {} }})", d2)), "{}", rel);
+    }
+
+    #[test]
+    fn test_neo4j_custom_id_property() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        let d1 = inst.insert("Department", HashMap::new(), HashMap::new());
+        inst.insert("Employee", HashMap::new(), HashMap::from([("works_in".into(), d1)]));
+
+        let backend = Neo4jBackend::new().with_id_strategy(IdStrategy::new("_id"));
+        let cypher = backend.deploy_schema(&schema).iter()
+            .chain(backend.export_instance(&schema, &inst).iter())
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert!(cypher.contains("REQUIRE n._id IS UNIQUE"));
+        assert!(cypher.contains("(a:Employee { _id: "));
+        assert!(!cypher.contains("catrust_id"));
+    }
+
+    /// Littéraux de chaîne d'une commande Cypher, décodés. Échoue si un
+    /// littéral contient un saut de ligne brut, une séquence d'échappement
+    /// inconnue ou n'est pas refermé (de même pour un identifiant `...`).
+    fn cypher_literals(cypher: &str) -> Vec<String> {
+        let mut literals = Vec::new();
+        let mut chars = cypher.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' | '"' => {
+                    let mut literal = String::new();
+                    loop {
+                        match chars.next().expect("littéral non refermé") {
+                            '\\' => literal.push(match chars.next().expect("échappement tronqué") {
+                                'n' => '\n',
+                                't' => '\t',
+                                'r' => '\r',
+                                'b' => '\u{8}',
+                                'f' => '\u{c}',
+                                'u' => {
+                                    let hex: String = chars.by_ref().take(4).collect();
+                                    char::from_u32(u32::from_str_radix(&hex, 16).unwrap()).unwrap()
+                                }
+                                e @ ('\\' | '\'' | '"') => e,
+                                e => panic!("échappement inconnu \\{}", e),
+                            }),
+                            '\n' | '\r' => panic!("saut de ligne brut dans un littéral"),
+                            q if q == c => break,
+                            other => literal.push(other),
+                        }
+                    }
+                    literals.push(literal);
+                }
+                '`' => {
+                    // `` dans un identifiant : backtick échappé
+                    loop {
+                        match chars.next().expect("identifiant non refermé") {
+                            '`' if chars.as_str().starts_with('`') => { chars.next(); }
+                            '`' => break,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        literals
+    }
+
+    #[test]
+    fn test_neo4j_escaping() {
+        let mut schema = Schema::new("Company");
+        schema.add_node("Employee")
+            .add_node("Key Account")
+            .add_fk("managed by", "Key Account", "Employee")
+            .add_attribute("emp_name", "Employee", BaseType::String)
+            .add_attribute("account name", "Key Account", BaseType::String);
+        let name = "O'Brien\n\"JR\" \\ \u{1}";
+        let mut inst = Instance::new("Data", &schema);
+        let e = inst.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String(name.into()))]),
+            HashMap::new(),
+        );
+        inst.insert("Key Account",
+            HashMap::from([("account name".into(), Value::String("Ünïcode ✓".into()))]),
+            HashMap::from([("managed by".into(), e)]),
+        );
+
+        for backend in [Neo4jBackend::new(), Neo4jBackend::new().with_batch_size(10)] {
+            let stmts: Vec<String> = backend.deploy_schema(&schema).iter()
+                .chain(&backend.export_instance(&schema, &inst))
+                .map(|s| s.to_string())
+                .collect();
+            let cypher = stmts.join("\n");
+            // Une commande par ligne, chaque littéral se relit à l'identique
+            assert_eq!(cypher.lines().count(), stmts.len(), "{}", cypher);
+            let literals: Vec<String> = stmts.iter().flat_map(|s| cypher_literals(s)).collect();
+            assert!(literals.contains(&name.to_string()), "{:?}", literals);
+            assert!(literals.contains(&"Ünïcode ✓".to_string()), "{:?}", literals);
+            assert!(cypher.contains("(:`Key Account` {") || cypher.contains("(n:`Key Account` {"), "{}", cypher);
+            assert!(cypher.contains("`account name`: "), "{}", cypher);
+            assert!(cypher.contains("[:`MANAGED BY`]"), "{}", cypher);
+        }
+        assert_eq!(cypher_ident("a`b"), "`a``b`");
+    }
 }
@@ -7,6 +7,7 @@
 //   - PostgreSQL (SQL)
 //   - Snowflake (SQL avec dialecte spécifique)
 //   - Neo4j (Cypher)
+//   - Soufflé (faits Datalog)
 //   - DuckDB, SQLite, etc.
 //
 // Le cœur catégorique (module core) ne connaît JAMAIS les backends.
@@ -16,6 +17,10 @@
 
 pub mod sql;
 pub mod graph;
+pub mod datalog;
+pub mod executor;
+#[cfg(feature = "postgres")]
+pub mod postgres;
 
 use crate::core::schema::Schema;
 use crate::core::instance::Instance;
@@ -29,6 +34,8 @@ pub enum Statement {
     Sql(String),
     /// Commande Cypher (Neo4j)
     Cypher(String),
+    /// Déclaration, fait ou règle Datalog (Soufflé)
+    Datalog(String),
 }
 
 impl std::fmt::Display for Statement {
@@ -36,10 +43,74 @@ impl std::fmt::Display for Statement {
         match self {
             Statement::Sql(s) => write!(f, "{}", s),
             Statement::Cypher(s) => write!(f, "{}", s),
+            Statement::Datalog(s) => write!(f, "{}", s),
         }
     }
 }
 
+/// Mode de génération de la clé technique dans la base cible.
+///
+/// Dans les deux cas, les RowId de l'instance sont écrits explicitement
+/// par `export_instance` (les FK en dépendent) ; le mode ne change que le
+/// type de la colonne, c'est-à-dire le comportement pour les lignes insérées
+/// hors de Catrust.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdGeneration {
+    /// Auto-incrément du moteur quand il existe (BIGSERIAL, AUTOINCREMENT...)
+    AutoIncrement,
+    /// Clé toujours fournie par le client (pas de séquence)
+    Explicit,
+}
+
+/// Stratégie d'identifiant : nom, type et génération de la clé technique
+/// que chaque table / nœud reçoit (par défaut `catrust_id`).
+#[derive(Debug, Clone)]
+pub struct IdStrategy {
+    /// Nom de la colonne (SQL) ou de la propriété (Neo4j)
+    pub column: String,
+    /// Type SQL explicite de la clé (ex: "UUID PRIMARY KEY").
+    /// `None` = type déduit du dialecte et du mode de génération.
+    pub key_type: Option<String>,
+    /// Type SQL explicite des colonnes de FK. `None` = type du dialecte.
+    pub reference_type: Option<String>,
+    /// Mode de génération
+    pub generation: IdGeneration,
+}
+
+impl Default for IdStrategy {
+    fn default() -> Self {
+        IdStrategy {
+            column: "catrust_id".to_string(),
+            key_type: None,
+            reference_type: None,
+            generation: IdGeneration::AutoIncrement,
+        }
+    }
+}
+
+impl IdStrategy {
+    /// Stratégie par défaut avec un autre nom de colonne
+    pub fn new(column: &str) -> Self {
+        IdStrategy {
+            column: column.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Impose les types SQL de la clé et des colonnes de FK (ex: UUID)
+    pub fn with_types(mut self, key_type: &str, reference_type: &str) -> Self {
+        self.key_type = Some(key_type.to_string());
+        self.reference_type = Some(reference_type.to_string());
+        self
+    }
+
+    /// Change le mode de génération
+    pub fn with_generation(mut self, generation: IdGeneration) -> Self {
+        self.generation = generation;
+        self
+    }
+}
+
 /// Trait abstrait pour tous les backends de base de données.
 ///
 /// Chaque backend implémente ce trait pour traduire les concepts
@@ -53,10 +124,29 @@ pub trait Backend {
     /// En Cypher : CREATE CONSTRAINT, CREATE INDEX...
     fn deploy_schema(&self, schema: &Schema) -> Vec<Statement>;
 
-    /// Génère les commandes DML pour insérer les données d'une instance.
+    /// Génère paresseusement les commandes DML pour insérer les données
+    /// d'une instance, entités dans l'ordre topologique du schéma (les cibles
+    /// de FK d'abord). Rien n'est matérialisé : adapté aux grosses instances.
     /// En SQL : INSERT INTO...
     /// En Cypher : CREATE (n:Label {props})...
-    fn export_instance(&self, schema: &Schema, instance: &Instance) -> Vec<Statement>;
+    fn export_instance_iter<'a>(
+        &'a self,
+        schema: &'a Schema,
+        instance: &'a Instance,
+    ) -> Box<dyn Iterator<Item = Statement> + 'a>;
+
+    /// Génère les commandes DML pour insérer les données d'une instance.
+    /// Collecte simplement `export_instance_iter`.
+    fn export_instance(&self, schema: &Schema, instance: &Instance) -> Vec<Statement> {
+        self.export_instance_iter(schema, instance).collect()
+    }
+
+    /// Génère les commandes pour supprimer la structure du schéma
+    /// (et les données qu'elle contient). Inverse de `deploy_schema`.
+    /// Par défaut : rien à faire.
+    fn teardown_schema(&self, _schema: &Schema) -> Vec<Statement> {
+        Vec::new()
+    }
 
     /// Génère les commandes pour effectuer une migration Δ.
     /// En SQL : CREATE TABLE ... AS SELECT ... JOIN ...
@@ -0,0 +1,147 @@
+// =============================================================================
+// POSTGRES — Executor sur une vraie base PostgreSQL (feature `postgres`)
+// =============================================================================
+//
+// `PostgresExecutor` exécute les `Statement::Sql` produits par
+// `SqlBackend<PostgresDialect>` via tokio-postgres. Le trait `Executor` est
+// synchrone : l'executor garde son propre runtime tokio (mono-thread) et
+// attend chaque requête.
+//
+//   let mut exec = PostgresExecutor::connect("host=localhost user=postgres")?;
+//   deploy_and_load(&SqlBackend::new(PostgresDialect), &schema, &instance, &mut exec)?;
+//
+// =============================================================================
+
+use tokio::runtime::Runtime;
+use tokio_postgres::{Client, NoTls, SimpleQueryMessage};
+
+use super::Statement;
+use super::executor::Executor;
+
+/// Message d'une erreur tokio-postgres, avec celui du serveur s'il y en a un
+fn describe(e: &tokio_postgres::Error, sql: &str) -> String {
+    match e.as_db_error() {
+        Some(db) => format!("PostgreSQL : {} ({})", db.message(), sql),
+        None => format!("PostgreSQL : {} ({})", e, sql),
+    }
+}
+
+/// Executor qui envoie chaque statement SQL à PostgreSQL.
+pub struct PostgresExecutor {
+    client: Client,
+    runtime: Runtime,
+}
+
+impl PostgresExecutor {
+    /// Se connecte (sans TLS) avec une chaîne de connexion libpq
+    /// (ex: "host=localhost user=postgres dbname=test").
+    pub fn connect(params: &str) -> Result<Self, String> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Runtime tokio : {}", e))?;
+        let (client, connection) = runtime.block_on(tokio_postgres::connect(params, NoTls))
+            .map_err(|e| format!("Connexion PostgreSQL : {}", e))?;
+        // La connexion avance pendant chaque `block_on` du runtime
+        runtime.spawn(connection);
+        Ok(PostgresExecutor { client, runtime })
+    }
+
+    /// Enveloppe un client déjà connecté ; sa connexion doit tourner sur
+    /// `runtime` (ou sur un runtime qui reste actif).
+    pub fn new(client: Client, runtime: Runtime) -> Self {
+        PostgresExecutor { client, runtime }
+    }
+
+    /// Le client, pour des requêtes hors `Statement`
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Exécute une requête texte et retourne ses lignes, en texte
+    /// (NULL → `None`).
+    pub fn query_text(&mut self, sql: &str) -> Result<Vec<Vec<Option<String>>>, String> {
+        let messages = self.runtime.block_on(self.client.simple_query(sql))
+            .map_err(|e| describe(&e, sql))?;
+        Ok(messages.iter()
+            .filter_map(|m| match m {
+                SimpleQueryMessage::Row(row) => Some(
+                    (0..row.len()).map(|i| row.get(i).map(str::to_string)).collect()
+                ),
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+impl Executor for PostgresExecutor {
+    fn execute(&mut self, stmt: &Statement) -> Result<u64, String> {
+        let Statement::Sql(sql) = stmt else {
+            return Err(format!("PostgresExecutor n'exécute que du SQL : {}", stmt));
+        };
+        let messages = self.runtime.block_on(self.client.simple_query(sql))
+            .map_err(|e| describe(&e, sql))?;
+        Ok(messages.iter()
+            .map(|m| match m {
+                SimpleQueryMessage::CommandComplete(rows) => *rows,
+                _ => 0,
+            })
+            .sum())
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Backend;
+    use crate::backend::executor::deploy_and_load;
+    use crate::backend::sql::{PostgresDialect, SqlBackend};
+    use crate::core::instance::Instance;
+    use crate::core::schema::Schema;
+    use crate::core::typeside::BaseType;
+    use crate::{fks, row};
+
+    /// Aller-retour Company dans une base de test. Lancer avec
+    /// `CATRUST_TEST_POSTGRES` (chaîne de connexion) et `--ignored`.
+    #[test]
+    #[ignore = "demande une base PostgreSQL (CATRUST_TEST_POSTGRES)"]
+    fn test_postgres_roundtrip() {
+        let params = std::env::var("CATRUST_TEST_POSTGRES")
+            .expect("CATRUST_TEST_POSTGRES doit donner la chaîne de connexion de la base de test");
+
+        // Employee est déclaré d'abord : le DDL, trié topologiquement,
+        // crée quand même Department (cible de la FK) avant lui
+        let mut schema = Schema::new("Company");
+        schema.add_node("Employee")
+              .add_node("Department")
+              .add_fk("works_in", "Employee", "Department")
+              .add_attribute("emp_name", "Employee", BaseType::String)
+              .add_attribute("salary", "Employee", BaseType::Integer)
+              .add_attribute("dept_name", "Department", BaseType::String);
+
+        let mut inst = Instance::new("Data", &schema);
+        let d1 = inst.insert("Department", row!{dept_name: "Engineering"}, fks!{});
+        inst.insert("Employee", row!{emp_name: "Alice", salary: 90000}, fks!{works_in: d1});
+        inst.insert("Employee", row!{emp_name: "Bob", salary: 75000}, fks!{works_in: d1});
+
+        let backend = SqlBackend::new(PostgresDialect);
+        let mut exec = PostgresExecutor::connect(&params).unwrap();
+        exec.execute_all(&backend.teardown_schema(&schema)).unwrap();
+
+        assert_eq!(deploy_and_load(&backend, &schema, &inst, &mut exec).unwrap(), 3);
+        let rows = exec.query_text(
+            "SELECT e.\"emp_name\", d.\"dept_name\" FROM \"Employee\" e \
+             JOIN \"Department\" d ON e.\"works_in\" = d.catrust_id ORDER BY 1",
+        ).unwrap();
+        assert_eq!(rows, vec![
+            vec![Some("Alice".to_string()), Some("Engineering".to_string())],
+            vec![Some("Bob".to_string()), Some("Engineering".to_string())],
+        ]);
+
+        assert!(exec.execute(&crate::backend::Statement::Cypher("MATCH (n) RETURN n".into())).is_err());
+        exec.execute_all(&backend.teardown_schema(&schema)).unwrap();
+    }
+}
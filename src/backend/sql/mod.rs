@@ -15,11 +15,13 @@
 
 pub mod planner;
 
+use std::collections::{HashMap, HashSet};
 use crate::core::schema::{Schema, Edge};
-use crate::core::instance::Instance;
-use crate::core::mapping::Mapping;
-use crate::core::typeside::BaseType;
-use crate::backend::{Backend, Statement};
+use crate::core::instance::{Instance, InstanceDiff, EntityData, RowId};
+use crate::core::mapping::{AttrFill, Mapping, EdgeMapping};
+use crate::core::query::StringFn;
+use crate::core::typeside::{BaseType, Value};
+use crate::backend::{Backend, Statement, IdStrategy, IdGeneration};
 
 /// Dialecte SQL — les différences entre les moteurs SQL.
 /// Chaque moteur SQL a ses propres types et syntaxes.
@@ -37,6 +39,124 @@ pub trait SqlDialect {
     fn quote_identifier(&self, name: &str) -> String {
         format!("\"{}\"", name)
     }
+
+    /// Type des colonnes de FK (qui contiennent un catrust_id)
+    fn fk_column_type(&self) -> String {
+        "BIGINT".to_string()
+    }
+
+    /// Le moteur accepte-t-il les clauses REFERENCES ?
+    fn supports_foreign_keys(&self) -> bool {
+        true
+    }
+
+    /// Le moteur génère-t-il lui-même les clés (`auto_id_type` : SERIAL,
+    /// AUTOINCREMENT...) ? Sinon les ID sont toujours fournis par Catrust.
+    fn supports_autoincrement(&self) -> bool {
+        true
+    }
+
+    /// Le moteur sait-il insérer-ou-mettre-à-jour en une commande
+    /// (`ON CONFLICT ... DO UPDATE`, `MERGE`) ?
+    fn supports_upsert(&self) -> bool {
+        false
+    }
+
+    /// Paramètre de requête préparée n° `index` (à partir de 1).
+    /// Par défaut le `?` positionnel (SQLite, MySQL, JDBC...).
+    fn placeholder(&self, _index: usize) -> String {
+        "?".to_string()
+    }
+
+    /// Le moteur applique-t-il les contraintes CHECK ?
+    fn supports_check_constraints(&self) -> bool {
+        true
+    }
+
+    /// Le moteur a-t-il des triggers (dans le dialecte de `deploy_constraints`) ?
+    fn supports_triggers(&self) -> bool {
+        false
+    }
+
+    /// Le moteur a-t-il des colonnes calculées stockées
+    /// (`GENERATED ALWAYS AS (...) STORED`) ?
+    fn supports_generated_columns(&self) -> bool {
+        false
+    }
+
+    /// Le moteur a-t-il des types énumérés (`CREATE TYPE ... AS ENUM`) ?
+    /// Sinon un `BaseType::Enum` devient du texte + `CHECK (col IN (...))`.
+    fn supports_enum_types(&self) -> bool {
+        false
+    }
+
+    /// Σ doit-il créer ses tables par `CREATE TABLE ... AS SELECT` (CTAS)
+    /// plutôt que par `INSERT INTO ... SELECT` ? C'est le cas des moteurs
+    /// fédérés, peu adaptés aux insertions incrémentales.
+    fn supports_ctas(&self) -> bool {
+        false
+    }
+
+    /// Nom complet d'une table créée par CTAS (ex: `catalog.schema."table"`)
+    fn full_table_name(&self, table: &str) -> String {
+        self.quote_identifier(table)
+    }
+
+    /// Appel d'une fonction de chaîne sur l'expression SQL `arg`.
+    /// Par défaut la syntaxe standard (`SUBSTRING(x FROM 2 FOR 3)`).
+    fn string_fn_sql(&self, func: &StringFn, arg: &str) -> String {
+        standard_string_fn(func, arg)
+    }
+
+    /// Littéral d'un flottant. NaN et les infinis n'ont pas de littéral
+    /// SQL : on convertit leur nom, par défaut `CAST('NaN' AS DOUBLE)`.
+    fn float_literal(&self, f: f64) -> String {
+        match non_finite_name(f, "Infinity") {
+            Some(name) => format!("CAST('{}' AS {})", name, self.type_to_sql(&BaseType::Float)),
+            None => format!("{}", f),
+        }
+    }
+
+    /// Littéral d'une liste à partir de ses éléments déjà rendus,
+    /// par défaut `ARRAY[a, b]` (PostgreSQL, Trino)
+    fn array_literal(&self, items: &[String]) -> String {
+        format!("ARRAY[{}]", items.join(", "))
+    }
+}
+
+/// Nom d'un flottant non fini (`None` s'il est fini), l'infini s'écrivant
+/// `infinity` dans le moteur
+fn non_finite_name(f: f64, infinity: &str) -> Option<String> {
+    if f.is_nan() {
+        Some("NaN".to_string())
+    } else if f.is_infinite() {
+        Some(if f > 0.0 { infinity.to_string() } else { format!("-{}", infinity) })
+    } else {
+        None
+    }
+}
+
+/// Fonctions de chaîne en SQL standard
+fn standard_string_fn(func: &StringFn, arg: &str) -> String {
+    match func {
+        StringFn::Upper => format!("UPPER({})", arg),
+        StringFn::Lower => format!("LOWER({})", arg),
+        StringFn::Trim => format!("TRIM({})", arg),
+        StringFn::Substring { start, length: Some(n) } => format!("SUBSTRING({} FROM {} FOR {})", arg, start, n),
+        StringFn::Substring { start, length: None } => format!("SUBSTRING({} FROM {})", arg, start),
+    }
+}
+
+/// Comme `standard_string_fn`, mais `SUBSTR(x, début[, longueur])` pour
+/// les moteurs sans `SUBSTRING ... FROM` (Snowflake, BigQuery). Ces moteurs
+/// lisent un début 0 comme 1 : on décale pour garder la fenêtre standard.
+fn substr_string_fn(func: &StringFn, arg: &str) -> String {
+    match func {
+        StringFn::Substring { start: 0, length: Some(n) } => format!("SUBSTR({}, 1, {})", arg, n.saturating_sub(1)),
+        StringFn::Substring { start, length: Some(n) } => format!("SUBSTR({}, {}, {})", arg, start, n),
+        StringFn::Substring { start, length: None } => format!("SUBSTR({}, {})", arg, start),
+        _ => standard_string_fn(func, arg),
+    }
 }
 
 // ─── PostgreSQL ──────────────────────────────────────────────────────────────
@@ -51,6 +171,8 @@ impl SqlDialect for PostgresDialect {
             BaseType::Float => "DOUBLE PRECISION".to_string(),
             BaseType::Boolean => "BOOLEAN".to_string(),
             BaseType::Custom(name) => name.clone(),
+            BaseType::Enum { name, .. } => self.quote_identifier(name),
+            BaseType::Array(elem) => format!("{}[]", self.type_to_sql(elem)),
         }
     }
 
@@ -61,6 +183,33 @@ impl SqlDialect for PostgresDialect {
     fn dialect_name(&self) -> String {
         "PostgreSQL".to_string()
     }
+
+    fn supports_upsert(&self) -> bool {
+        true
+    }
+
+    fn supports_triggers(&self) -> bool {
+        true
+    }
+
+    fn supports_generated_columns(&self) -> bool {
+        true
+    }
+
+    fn supports_enum_types(&self) -> bool {
+        true
+    }
+
+    fn placeholder(&self, index: usize) -> String {
+        format!("${}", index)
+    }
+
+    fn float_literal(&self, f: f64) -> String {
+        match non_finite_name(f, "Infinity") {
+            Some(name) => format!("'{}'::double precision", name),
+            None => format!("{}", f),
+        }
+    }
 }
 
 // ─── Snowflake ───────────────────────────────────────────────────────────────
@@ -75,6 +224,9 @@ impl SqlDialect for SnowflakeDialect {
             BaseType::Float => "FLOAT".to_string(),
             BaseType::Boolean => "BOOLEAN".to_string(),
             BaseType::Custom(name) => name.clone(),
+            // Pas de type ENUM : texte, contraint par un CHECK si possible
+            BaseType::Enum { .. } => self.type_to_sql(&BaseType::String),
+            BaseType::Array(elem) => format!("ARRAY({})", self.type_to_sql(elem)),
         }
     }
 
@@ -85,6 +237,29 @@ impl SqlDialect for SnowflakeDialect {
     fn dialect_name(&self) -> String {
         "Snowflake".to_string()
     }
+
+    fn supports_upsert(&self) -> bool {
+        true
+    }
+
+    fn supports_check_constraints(&self) -> bool {
+        false
+    }
+
+    fn string_fn_sql(&self, func: &StringFn, arg: &str) -> String {
+        substr_string_fn(func, arg)
+    }
+
+    fn float_literal(&self, f: f64) -> String {
+        match non_finite_name(f, "inf") {
+            Some(name) => format!("CAST('{}' AS {})", name, self.type_to_sql(&BaseType::Float)),
+            None => format!("{}", f),
+        }
+    }
+
+    fn array_literal(&self, items: &[String]) -> String {
+        format!("ARRAY_CONSTRUCT({})", items.join(", "))
+    }
 }
 
 // ─── Trino (ex-Presto) ──────────────────────────────────────────────────────
@@ -129,6 +304,9 @@ impl SqlDialect for TrinoDialect {
             BaseType::Float => "DOUBLE".to_string(),
             BaseType::Boolean => "BOOLEAN".to_string(),
             BaseType::Custom(name) => name.clone(),
+            // Pas de type ENUM : texte, contraint par un CHECK si possible
+            BaseType::Enum { .. } => self.type_to_sql(&BaseType::String),
+            BaseType::Array(elem) => format!("ARRAY({})", self.type_to_sql(elem)),
         }
     }
 
@@ -145,146 +323,1181 @@ impl SqlDialect for TrinoDialect {
     fn quote_identifier(&self, name: &str) -> String {
         format!("\"{}\"", name)
     }
+
+    fn supports_foreign_keys(&self) -> bool {
+        false
+    }
+
+    fn supports_autoincrement(&self) -> bool {
+        false
+    }
+
+    fn supports_check_constraints(&self) -> bool {
+        false
+    }
+
+    fn supports_ctas(&self) -> bool {
+        true
+    }
+
+    fn full_table_name(&self, table: &str) -> String {
+        TrinoDialect::full_table_name(self, table)
+    }
+}
+
+// ─── BigQuery ────────────────────────────────────────────────────────────────
+//
+// Particularités :
+//   - Types propres : STRING, INT64, FLOAT64, BOOL
+//   - Pas d'auto-increment : les ID sont générés côté Catrust (comme Trino)
+//   - Pas de FOREIGN KEY appliquées : les FK sont de simples colonnes INT64
+//   - Quote les identifiants avec des backticks `
+//
+
+pub struct BigQueryDialect;
+
+impl SqlDialect for BigQueryDialect {
+    fn type_to_sql(&self, ty: &BaseType) -> String {
+        match ty {
+            BaseType::String => "STRING".to_string(),
+            BaseType::Integer => "INT64".to_string(),
+            BaseType::Float => "FLOAT64".to_string(),
+            BaseType::Boolean => "BOOL".to_string(),
+            BaseType::Custom(name) => name.clone(),
+            // Pas de type ENUM : texte, contraint par un CHECK si possible
+            BaseType::Enum { .. } => self.type_to_sql(&BaseType::String),
+            BaseType::Array(elem) => format!("ARRAY<{}>", self.type_to_sql(elem)),
+        }
+    }
+
+    fn auto_id_type(&self) -> String {
+        "INT64".to_string()
+    }
+
+    fn dialect_name(&self) -> String {
+        "BigQuery".to_string()
+    }
+
+    fn quote_identifier(&self, name: &str) -> String {
+        format!("`{}`", name)
+    }
+
+    fn fk_column_type(&self) -> String {
+        "INT64".to_string()
+    }
+
+    fn supports_foreign_keys(&self) -> bool {
+        false
+    }
+
+    fn supports_autoincrement(&self) -> bool {
+        false
+    }
+
+    fn supports_upsert(&self) -> bool {
+        true
+    }
+
+    fn supports_check_constraints(&self) -> bool {
+        false
+    }
+
+    fn string_fn_sql(&self, func: &StringFn, arg: &str) -> String {
+        substr_string_fn(func, arg)
+    }
+
+    fn float_literal(&self, f: f64) -> String {
+        match non_finite_name(f, "inf") {
+            Some(name) => format!("CAST('{}' AS {})", name, self.type_to_sql(&BaseType::Float)),
+            None => format!("{}", f),
+        }
+    }
+
+    fn array_literal(&self, items: &[String]) -> String {
+        format!("[{}]", items.join(", "))
+    }
 }
 
 // ─── Backend SQL générique ───────────────────────────────────────────────────
 
+/// Écriture des identifiants (tables, colonnes, types) dans le SQL généré.
+///
+/// PostgreSQL replie les noms non quotés en minuscules (Snowflake en
+/// majuscules) : une table créée sous `"Employee"` ne se requête pas avec
+/// `SELECT * FROM employee`. Les politiques non quotées normalisent la casse
+/// pour que le schéma s'utilise sans guillemets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdentifierPolicy {
+    /// Quotés par le dialecte, casse préservée : `"Employee"`
+    #[default]
+    Quoted,
+    /// Nus, en minuscules : `employee`
+    LowerUnquoted,
+    /// Nus, en majuscules : `EMPLOYEE`
+    UpperUnquoted,
+}
+
+/// Mots réservés du SQL standard et des principaux moteurs : nus, ils
+/// seraient lus comme des mots-clés (`CREATE TABLE user` échoue sous PostgreSQL)
+const RESERVED_WORDS: &[&str] = &[
+    "ALL", "ALTER", "AND", "ANY", "ARRAY", "AS", "ASC", "BETWEEN", "BY", "CASE",
+    "CAST", "CHECK", "COLUMN", "CONSTRAINT", "CREATE", "CROSS", "CURRENT_DATE",
+    "CURRENT_TIME", "CURRENT_TIMESTAMP", "CURRENT_USER", "DEFAULT", "DELETE",
+    "DESC", "DISTINCT", "DROP", "ELSE", "END", "EXCEPT", "EXISTS", "FALSE",
+    "FETCH", "FOR", "FOREIGN", "FROM", "FULL", "GRANT", "GROUP", "HAVING", "IN",
+    "INNER", "INSERT", "INTERSECT", "INTO", "IS", "JOIN", "KEY", "LEFT", "LIKE",
+    "LIMIT", "NATURAL", "NOT", "NULL", "OFFSET", "ON", "OR", "ORDER", "OUTER",
+    "PRIMARY", "REFERENCES", "RIGHT", "ROW", "ROWS", "SELECT", "SESSION_USER",
+    "SET", "SOME", "TABLE", "THEN", "TO", "TRUE", "UNION", "UNIQUE", "UPDATE",
+    "USER", "USING", "VALUES", "WHEN", "WHERE", "WINDOW", "WITH",
+];
+
+impl IdentifierPolicy {
+    /// Écrit `name` selon la politique. Un nom qui ne peut pas rester nu
+    /// (espace, tiret, chiffre en tête, mot réservé...) est quoté après
+    /// normalisation.
+    pub fn render<D: SqlDialect + ?Sized>(self, dialect: &D, name: &str) -> String {
+        let normalized = match self {
+            IdentifierPolicy::Quoted => return dialect.quote_identifier(name),
+            IdentifierPolicy::LowerUnquoted => name.to_lowercase(),
+            IdentifierPolicy::UpperUnquoted => name.to_uppercase(),
+        };
+        let bare = normalized.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && normalized.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !RESERVED_WORDS.contains(&normalized.to_uppercase().as_str());
+        if bare { normalized } else { dialect.quote_identifier(&normalized) }
+    }
+
+    /// Noms distincts que la politique écrit pareil (`hireDate` et
+    /// `hiredate` en `LowerUnquoted`) : (premier nom, second nom, écriture).
+    /// Le moteur les confondrait en une seule table ou colonne.
+    pub fn collisions<'n, D: SqlDialect + ?Sized>(
+        self,
+        dialect: &D,
+        names: impl IntoIterator<Item = &'n str>,
+    ) -> Vec<(String, String, String)> {
+        let mut seen: HashMap<String, &str> = HashMap::new();
+        let mut collisions = Vec::new();
+        for name in names {
+            let rendered = self.render(dialect, name);
+            match seen.get(&rendered) {
+                Some(&first) if first != name => {
+                    collisions.push((first.to_string(), name.to_string(), rendered));
+                }
+                Some(_) => {}
+                None => {
+                    seen.insert(rendered, name);
+                }
+            }
+        }
+        collisions
+    }
+}
+
 /// Backend SQL générique, paramétré par un dialecte.
 pub struct SqlBackend<D: SqlDialect> {
     pub dialect: D,
+    /// Clé technique de chaque table (par défaut `catrust_id`)
+    pub id_strategy: IdStrategy,
+    /// Schéma SQL (namespace) des tables et types : `"hr"."Employee"`.
+    /// `None` : noms nus, résolus par le `search_path` du moteur.
+    pub target_schema: Option<String>,
+    /// Équations d'observation recopiant un attribut de la même ligne en
+    /// colonnes générées plutôt qu'en triggers (cf. `with_generated_columns`)
+    pub generated_columns: bool,
+    /// Écriture des noms de tables, colonnes et types (quotés par défaut)
+    pub identifier_policy: IdentifierPolicy,
 }
 
 impl<D: SqlDialect> SqlBackend<D> {
     pub fn new(dialect: D) -> Self {
-        SqlBackend { dialect }
+        SqlBackend {
+            dialect,
+            id_strategy: IdStrategy::default(),
+            target_schema: None,
+            generated_columns: false,
+            identifier_policy: IdentifierPolicy::default(),
+        }
+    }
+
+    /// Choisit l'écriture des identifiants (cf. `IdentifierPolicy`). À
+    /// passer aussi au planner (`SqlPlanner::with_identifier_policy`) pour
+    /// que ses requêtes nomment les colonnes comme le DDL.
+    pub fn with_identifier_policy(mut self, policy: IdentifierPolicy) -> Self {
+        self.identifier_policy = policy;
+        self
+    }
+
+    /// Matérialise les équations d'observation `E.a = E.b` (deux attributs
+    /// de la même ligne) en colonnes générées : `b` est déclarée
+    /// `GENERATED ALWAYS AS (a) STORED` au lieu d'être vérifiée par trigger.
+    ///
+    /// Une colonne générée ne lit que sa propre ligne : une équation qui
+    /// traverse une FK (`E.fk.a = E.b`) garde son trigger. Sans effet si le
+    /// dialecte n'a pas de colonnes générées (PostgreSQL 12+ en a).
+    pub fn with_generated_columns(mut self) -> Self {
+        self.generated_columns = true;
+        self
     }
 
-    /// Génère le CREATE TABLE pour une entité donnée.
-    fn create_table_sql(&self, entity_name: &str, schema: &Schema) -> String {
-        let quoted = self.dialect.quote_identifier(entity_name);
+    /// Colonnes générées (`with_generated_columns`) : attribut recopié →
+    /// (n° de l'équation, attribut lu sur la même ligne).
+    ///
+    /// Le côté droit de l'équation est la copie. Une copie de copie n'est
+    /// pas générée : PostgreSQL interdit qu'une colonne générée en lise une autre.
+    fn generated_columns<'s>(&self, schema: &'s Schema) -> HashMap<&'s str, (usize, &'s str)> {
+        let mut generated: HashMap<&str, (usize, &str)> = HashMap::new();
+        if !self.generated_columns || !self.dialect.supports_generated_columns() {
+            return generated;
+        }
+        for (i, eq) in schema.path_equations.iter().enumerate() {
+            let same_row = eq.lhs.start == eq.rhs.start && eq.lhs.len() == 1 && eq.rhs.len() == 1;
+            if same_row && eq.is_observation(schema) && eq.lhs.edges[0] != eq.rhs.edges[0] {
+                generated.entry(eq.rhs.edges[0].as_str()).or_insert((i, eq.lhs.edges[0].as_str()));
+            }
+        }
+        let copies: Vec<&str> = generated.keys().copied().collect();
+        generated.retain(|_, (_, read)| !copies.contains(read));
+        generated
+    }
+
+    /// Remplace la stratégie d'identifiant
+    pub fn with_id_strategy(mut self, id_strategy: IdStrategy) -> Self {
+        self.id_strategy = id_strategy;
+        self
+    }
+
+    /// Qualifie les tables et types par le schéma SQL `target_schema`
+    pub fn with_target_schema(mut self, target_schema: &str) -> Self {
+        self.target_schema = Some(target_schema.to_string());
+        self
+    }
+
+    /// Nom d'une table ou d'un type, qualifié par `target_schema`
+    fn qualified_name(&self, name: &str) -> String {
+        qualified_name(&self.dialect, self.identifier_policy, self.target_schema.as_deref(), name)
+    }
+
+    /// Table lue ou créée par un CTAS de Σ : le nom complet du dialecte
+    /// (`catalog.schema."table"` pour Trino), le même des deux côtés du
+    /// `CREATE TABLE ... AS SELECT ... FROM`. Une politique d'identifiants
+    /// ou un schéma cible explicites l'emportent, comme pour les autres
+    /// tables.
+    fn ctas_table_name(&self, name: &str) -> String {
+        if self.identifier_policy == IdentifierPolicy::default() && self.target_schema.is_none() {
+            self.dialect.full_table_name(name)
+        } else {
+            self.qualified_name(name)
+        }
+    }
+
+    /// Colonne de la clé technique selon `identifier_policy`
+    fn id_column(&self) -> String {
+        self.ident(&self.id_strategy.column)
+    }
+
+    /// Identifiant (colonne, schéma SQL) selon `identifier_policy`
+    fn ident(&self, name: &str) -> String {
+        self.identifier_policy.render(&self.dialect, name)
+    }
+
+    /// Vérifie que `identifier_policy` garde distincts les noms du schéma :
+    /// tables et types entre eux, colonnes d'une même table entre elles.
+    /// Une politique non quotée replie la casse, `hireDate` et `hiredate`
+    /// deviendraient une seule colonne.
+    pub fn check_identifiers(&self, schema: &Schema) -> Result<(), Vec<String>> {
+        let policy = self.identifier_policy;
+        let mut errors = Vec::new();
+        let order = schema.topological_order();
+        let types = self.enum_types(schema).into_iter().map(|(name, _)| name.as_str());
+        for (first, second, rendered) in policy.collisions(&self.dialect, order.iter().map(String::as_str).chain(types)) {
+            errors.push(format!("Tables '{}' et '{}' toutes deux écrites {}", first, second, rendered));
+        }
+        for entity in &order {
+            let mut columns = vec![self.id_strategy.column.as_str()];
+            columns.extend(schema.attributes_of(entity).into_iter().map(|e| e.name()));
+            for edge in schema.edges_from(entity) {
+                match edge {
+                    Edge::ForeignKey { columns: fk_columns, .. } if !fk_columns.is_empty() => {
+                        columns.extend(fk_columns.iter().map(|(col, _)| col.as_str()));
+                    }
+                    Edge::ForeignKey { name, .. } => columns.push(name),
+                    Edge::Attribute { .. } => {}
+                }
+            }
+            for (first, second, rendered) in policy.collisions(&self.dialect, columns) {
+                errors.push(format!(
+                    "Table '{}' : colonnes '{}' et '{}' toutes deux écrites {}", entity, first, second, rendered
+                ));
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Type SQL d'une colonne d'attribut (type ENUM qualifié par `target_schema`)
+    fn attribute_type_sql(&self, ty: &BaseType) -> String {
+        match ty {
+            BaseType::Enum { name, .. } if self.dialect.supports_enum_types() => self.qualified_name(name),
+            _ => self.dialect.type_to_sql(ty),
+        }
+    }
+
+    /// Type SQL de la clé technique
+    fn id_column_type(&self) -> String {
+        if let Some(ty) = &self.id_strategy.key_type {
+            return ty.clone();
+        }
+        match self.id_strategy.generation {
+            IdGeneration::AutoIncrement if self.dialect.supports_autoincrement() => {
+                self.dialect.auto_id_type()
+            }
+            // Sans séquence côté moteur, la clé est une colonne comme une autre
+            IdGeneration::AutoIncrement | IdGeneration::Explicit if self.dialect.supports_foreign_keys() => {
+                format!("{} PRIMARY KEY", self.dialect.fk_column_type())
+            }
+            IdGeneration::AutoIncrement | IdGeneration::Explicit => self.dialect.fk_column_type(),
+        }
+    }
+
+    /// Type SQL des colonnes de FK
+    fn reference_column_type(&self) -> String {
+        self.id_strategy
+            .reference_type
+            .clone()
+            .unwrap_or_else(|| self.dialect.fk_column_type())
+    }
+
+    /// Types énumérés du schéma à créer (triés par nom, sans doublon) :
+    /// vide si le dialecte n'a pas de type ENUM.
+    fn enum_types<'a>(&self, schema: &'a Schema) -> Vec<(&'a String, &'a Vec<String>)> {
+        if !self.dialect.supports_enum_types() {
+            return Vec::new();
+        }
+        let mut enums: Vec<(&String, &Vec<String>)> = schema.edges.values()
+            .filter_map(|e| match e {
+                Edge::Attribute { target: BaseType::Enum { name, variants }, .. } => Some((name, variants)),
+                _ => None,
+            })
+            .collect();
+        enums.sort();
+        enums.dedup_by_key(|(name, _)| *name);
+        enums
+    }
+
+    /// Génère le CREATE TABLE pour une entité donnée (contraintes de FK
+    /// comprises, même vers une table pas encore créée).
+    pub fn create_table_sql(&self, entity_name: &str, schema: &Schema) -> String {
+        self.create_table_deferring(entity_name, schema, &HashSet::new()).0
+    }
+
+    /// Comme `create_table_sql`, mais les contraintes de FK vers une table de
+    /// `pending` (pas encore créée : cycle de FK) sont rendues à part, en
+    /// `ALTER TABLE ... ADD FOREIGN KEY`, à exécuter une fois toutes les
+    /// tables créées. Retourne (CREATE TABLE, contraintes différées).
+    fn create_table_deferring(
+        &self,
+        entity_name: &str,
+        schema: &Schema,
+        pending: &HashSet<&str>,
+    ) -> (String, Vec<String>) {
+        let quoted = self.qualified_name(entity_name);
+        let mut deferred = Vec::new();
+        let generated = self.generated_columns(schema);
         let mut columns = vec![
-            format!("  catrust_id {}", self.dialect.auto_id_type()),
+            format!("  {} {}", self.id_column(), self.id_column_type()),
         ];
 
         // Attributs
         for edge in schema.attributes_of(entity_name) {
-            if let Edge::Attribute { name, target, .. } = edge {
-                columns.push(format!(
+            if let Edge::Attribute { name, target, default, .. } = edge {
+                let mut column = format!(
                     "  {} {}",
-                    self.dialect.quote_identifier(name),
-                    self.dialect.type_to_sql(target),
-                ));
+                    self.ident(name),
+                    self.attribute_type_sql(target),
+                );
+                if let Some((_, read)) = generated.get(name.as_str()) {
+                    column.push_str(&format!(
+                        " GENERATED ALWAYS AS ({}) STORED",
+                        self.ident(read),
+                    ));
+                } else if let Some(value) = default {
+                    column.push_str(&format!(" DEFAULT {}", value_to_sql(&self.dialect, &value.0)));
+                }
+                if let BaseType::Enum { variants, .. } = target {
+                    if !self.dialect.supports_enum_types() && self.dialect.supports_check_constraints() {
+                        let values: Vec<String> = variants.iter()
+                            .map(|v| value_to_sql(&self.dialect, &Value::String(v.clone())))
+                            .collect();
+                        column.push_str(&format!(
+                            " CHECK ({} IN ({}))",
+                            self.ident(name),
+                            values.join(", "),
+                        ));
+                    }
+                }
+                columns.push(column);
             }
         }
 
         // FK (colonnes de référence)
+        let mut table_constraints = Vec::new();
         for edge in schema.edges_from(entity_name) {
-            if let Edge::ForeignKey { name, target, .. } = edge {
-                columns.push(format!(
-                    "  {} BIGINT REFERENCES {}(catrust_id)",
-                    self.dialect.quote_identifier(name),
-                    self.dialect.quote_identifier(target),
-                ));
+            if let Edge::ForeignKey { name, target, columns: fk_columns, .. } = edge {
+                if !fk_columns.is_empty() {
+                    // FK composite : une colonne par attribut de la clé métier,
+                    // du type de cet attribut, et une contrainte de table
+                    for (col, attr) in fk_columns {
+                        let ty = match schema.edges.get(attr) {
+                            Some(Edge::Attribute { target: ty, .. }) => self.attribute_type_sql(ty),
+                            _ => self.reference_column_type(),
+                        };
+                        columns.push(format!("  {} {}", self.ident(col), ty));
+                    }
+                    if self.dialect.supports_foreign_keys() {
+                        let (cols, attrs): (Vec<String>, Vec<String>) = fk_columns.iter()
+                            .map(|(col, attr)| (self.ident(col), self.ident(attr)))
+                            .unzip();
+                        let constraint = format!(
+                            "FOREIGN KEY ({}) REFERENCES {} ({})",
+                            cols.join(", "),
+                            self.qualified_name(target),
+                            attrs.join(", "),
+                        );
+                        if pending.contains(target.as_str()) {
+                            deferred.push(format!("ALTER TABLE {} ADD {};", quoted, constraint));
+                        } else {
+                            table_constraints.push(format!("  {}", constraint));
+                        }
+                    }
+                    continue;
+                }
+                let mut column = format!(
+                    "  {} {}",
+                    self.ident(name),
+                    self.reference_column_type(),
+                );
+                if self.dialect.supports_foreign_keys() {
+                    if pending.contains(target.as_str()) {
+                        deferred.push(format!(
+                            "ALTER TABLE {} ADD FOREIGN KEY ({}) REFERENCES {}({});",
+                            quoted,
+                            self.ident(name),
+                            self.qualified_name(target),
+                            self.id_column(),
+                        ));
+                    } else {
+                        column.push_str(&format!(
+                            " REFERENCES {}({})",
+                            self.qualified_name(target),
+                            self.id_column(),
+                        ));
+                    }
+                }
+                columns.push(column);
+            }
+        }
+
+        // Clés métier référencées par des FK composites : elles doivent être uniques
+        if self.dialect.supports_foreign_keys() {
+            let mut keys: Vec<Vec<String>> = schema.fks_targeting(entity_name).into_iter()
+                .filter_map(|e| match e {
+                    Edge::ForeignKey { columns, .. } if !columns.is_empty() => Some(
+                        columns.iter().map(|(_, attr)| self.ident(attr)).collect()
+                    ),
+                    _ => None,
+                })
+                .collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                table_constraints.push(format!("  UNIQUE ({})", key.join(", ")));
+            }
+        }
+        columns.extend(table_constraints);
+
+        (format!("CREATE TABLE {} (\n{}\n);", quoted, columns.join(",\n")), deferred)
+    }
+
+    /// Colonnes (déjà quotées) et valeurs d'une FK pour une ligne.
+    ///
+    /// FK simple : une colonne, le RowId cible. FK composite : une colonne par
+    /// attribut de la clé métier, avec la valeur de cet attribut sur la ligne
+    /// cible (NULL si absente).
+    fn fk_column_values(
+        &self,
+        fk_name: &str,
+        target_id: RowId,
+        schema: &Schema,
+        instance: &Instance,
+    ) -> Vec<(String, Value)> {
+        match schema.edges.get(fk_name) {
+            Some(Edge::ForeignKey { target, columns, .. }) if !columns.is_empty() => {
+                let target_data = instance.data.get(target);
+                columns.iter()
+                    .map(|(col, attr)| {
+                        let value = target_data
+                            .and_then(|d| d.get_attr(target_id, attr))
+                            .cloned()
+                            .unwrap_or(Value::Null);
+                        (self.ident(col), value)
+                    })
+                    .collect()
+            }
+            _ => vec![(self.ident(fk_name), Value::Integer(target_id as i64))],
+        }
+    }
+
+    /// Génère l'INSERT INTO d'une ligne d'une entité.
+    fn insert_row_sql(
+        &self,
+        entity_name: &str,
+        entity_data: &EntityData,
+        row_id: RowId,
+        schema: &Schema,
+        instance: &Instance,
+    ) -> String {
+        let mut col_names = vec![self.id_column()];
+        let mut col_values = vec![format!("{}", row_id)];
+        let generated = self.generated_columns(schema);
+
+        // Attributs (une colonne générée est calculée par le moteur)
+        for (attr_name, value) in entity_data.row_attrs(row_id) {
+            if generated.contains_key(attr_name.as_str()) {
+                continue;
             }
+            col_names.push(self.ident(attr_name));
+            col_values.push(value_to_sql(&self.dialect, value));
         }
 
-        format!("CREATE TABLE {} (\n{}\n);", quoted, columns.join(",\n"))
+        // FK
+        for (fk_name, target_id) in entity_data.row_fks(row_id) {
+            for (col, value) in self.fk_column_values(fk_name, target_id, schema, instance) {
+                col_names.push(col);
+                col_values.push(value_to_sql(&self.dialect, &value));
+            }
+        }
+
+        format!(
+            "INSERT INTO {} ({}) VALUES ({});",
+            self.qualified_name(entity_name),
+            col_names.join(", "),
+            col_values.join(", "),
+        )
     }
 
-    /// Génère les INSERT INTO pour les données d'une entité.
-    fn insert_rows_sql(&self, entity_name: &str, _schema: &Schema, instance: &Instance) -> Vec<String> {
+    /// Comme `export_instance`, mais sans aucun littéral dans le SQL :
+    /// chaque INSERT est retourné avec ses paramètres, dans l'ordre des
+    /// placeholders du dialecte (`$1, $2...` en PostgreSQL, `?` sinon).
+    ///
+    /// Tables dans l'ordre topologique, comme `export_instance` : une ligne
+    /// est insérée après celles qu'elle référence. Colonnes : la clé
+    /// technique, puis les attributs, puis les FK (chacun par ordre alphabétique).
+    pub fn export_instance_prepared(&self, schema: &Schema, instance: &Instance) -> Vec<(String, Vec<Value>)> {
         let mut stmts = Vec::new();
-        
-        if let Some(entity_data) = instance.data.get(entity_name) {
-            for row_id in entity_data.row_ids() {
-                let mut col_names = vec!["catrust_id".to_string()];
-                let mut col_values = vec![format!("{}", row_id)];
-
-                // Attributs
-                if let Some(attrs) = entity_data.attribute_values.get(&row_id) {
-                    for (attr_name, value) in attrs {
-                        col_names.push(self.dialect.quote_identifier(attr_name));
-                        col_values.push(value_to_sql(value));
+        let generated = self.generated_columns(schema);
+
+        for entity_name in &schema.topological_order() {
+            let entity_data = match instance.data.get(entity_name) {
+                Some(d) => d,
+                None => continue,
+            };
+            let mut row_ids = entity_data.row_ids();
+            row_ids.sort();
+
+            for row_id in row_ids {
+                let mut col_names = vec![self.id_column()];
+                let mut params = vec![Value::Integer(row_id as i64)];
+
+                let mut attrs: Vec<(&String, &Value)> = entity_data.row_attrs(row_id).into_iter()
+                    .filter(|(name, _)| !generated.contains_key(name.as_str()))
+                    .collect();
+                attrs.sort_by_key(|(name, _)| *name);
+                for (attr_name, value) in attrs {
+                    col_names.push(self.ident(attr_name));
+                    params.push(value.clone());
+                }
+
+                let mut fks = entity_data.row_fks(row_id);
+                fks.sort();
+                for (fk_name, target_id) in fks {
+                    for (col, value) in self.fk_column_values(fk_name, target_id, schema, instance) {
+                        col_names.push(col);
+                        params.push(value);
                     }
                 }
 
-                // FK
-                if let Some(fks) = entity_data.fk_values.get(&row_id) {
-                    for (fk_name, target_id) in fks {
-                        col_names.push(self.dialect.quote_identifier(fk_name));
-                        col_values.push(format!("{}", target_id));
+                let placeholders: Vec<String> = (1..=params.len())
+                    .map(|i| self.dialect.placeholder(i))
+                    .collect();
+                stmts.push((
+                    format!(
+                        "INSERT INTO {} ({}) VALUES ({});",
+                        self.qualified_name(entity_name),
+                        col_names.join(", "),
+                        placeholders.join(", "),
+                    ),
+                    params,
+                ));
+            }
+        }
+
+        stmts
+    }
+
+    /// Export incrémental : le DML qui applique un `InstanceDiff` à une base
+    /// déjà peuplée (cf. `Instance::diff`).
+    ///
+    /// - `INSERT` pour les lignes ajoutées, tables référencées d'abord
+    /// - `UPDATE ... SET` pour les lignes modifiées, colonnes changées seulement
+    /// - `DELETE` pour les lignes supprimées, tables référençantes d'abord
+    ///
+    /// Les UPDATE passent après les INSERT (une FK peut viser une ligne
+    /// ajoutée) et avant les DELETE (une FK peut quitter une ligne supprimée).
+    /// Une FK composite est résolue en base par sous-requête sur la cible.
+    pub fn export_diff(&self, schema: &Schema, diff: &InstanceDiff) -> Vec<Statement> {
+        let order = schema.topological_order();
+        let mut stmts = Vec::new();
+        let generated = self.generated_columns(schema);
+
+        for entity_name in &order {
+            let Some(entity) = diff.entities.get(entity_name) else { continue };
+            for (row_id, row) in &entity.added {
+                let mut col_names = vec![self.id_column()];
+                let mut col_values = vec![row_id.to_string()];
+
+                let mut names: Vec<&String> = row.attrs.keys()
+                    .filter(|name| !generated.contains_key(name.as_str()))
+                    .collect();
+                names.sort();
+                for attr_name in names {
+                    col_names.push(self.ident(attr_name));
+                    col_values.push(value_to_sql(&self.dialect, &row.attrs[attr_name]));
+                }
+                let mut names: Vec<&String> = row.fks.keys().collect();
+                names.sort();
+                for fk_name in names {
+                    for (col, expr) in self.fk_column_exprs(fk_name, Some(row.fks[fk_name]), schema) {
+                        col_names.push(col);
+                        col_values.push(expr);
                     }
                 }
 
-                stmts.push(format!(
+                stmts.push(Statement::Sql(format!(
                     "INSERT INTO {} ({}) VALUES ({});",
-                    self.dialect.quote_identifier(entity_name),
+                    self.qualified_name(entity_name),
                     col_names.join(", "),
                     col_values.join(", "),
-                ));
+                )));
             }
         }
 
-        stmts
-    }
-}
+        for entity_name in &order {
+            let Some(entity) = diff.entities.get(entity_name) else { continue };
+            for change in &entity.changed {
+                let mut assignments = Vec::new();
 
-/// Convertit une Value en littéral SQL
-fn value_to_sql(value: &crate::core::typeside::Value) -> String {
-    use crate::core::typeside::Value;
-    match value {
-        Value::String(s) => format!("'{}'", s.replace('\'', "''")),
-        Value::Integer(i) => format!("{}", i),
-        Value::Float(f) => format!("{}", f),
-        Value::Boolean(b) => if *b { "TRUE".into() } else { "FALSE".into() },
-        Value::Null => "NULL".into(),
-    }
-}
+                let mut names: Vec<&String> = change.attrs.keys()
+                    .filter(|name| !generated.contains_key(name.as_str()))
+                    .collect();
+                names.sort();
+                for attr_name in names {
+                    assignments.push(format!(
+                        "{} = {}",
+                        self.ident(attr_name),
+                        value_to_sql(&self.dialect, &change.attrs[attr_name].1),
+                    ));
+                }
+                let mut names: Vec<&String> = change.fks.keys().collect();
+                names.sort();
+                for fk_name in names {
+                    for (col, expr) in self.fk_column_exprs(fk_name, change.fks[fk_name].1, schema) {
+                        assignments.push(format!("{} = {}", col, expr));
+                    }
+                }
+                // Seules des colonnes générées ont changé : le moteur s'en charge
+                if assignments.is_empty() {
+                    continue;
+                }
 
-impl<D: SqlDialect> Backend for SqlBackend<D> {
-    fn deploy_schema(&self, schema: &Schema) -> Vec<Statement> {
-        let mut stmts = Vec::new();
+                stmts.push(Statement::Sql(format!(
+                    "UPDATE {} SET {} WHERE {} = {};",
+                    self.qualified_name(entity_name),
+                    assignments.join(", "),
+                    self.id_column(),
+                    change.row,
+                )));
+            }
+        }
 
-        // D'abord les tables sans FK (ordre topologique simplifié)
-        // Phase 1 : CREATE TABLE (sans les REFERENCES pour éviter les dépendances circulaires)
-        for entity_name in schema.nodes.keys() {
-            stmts.push(Statement::Sql(self.create_table_sql(entity_name, schema)));
+        for entity_name in order.iter().rev() {
+            let Some(entity) = diff.entities.get(entity_name) else { continue };
+            for row_id in &entity.removed {
+                stmts.push(Statement::Sql(format!(
+                    "DELETE FROM {} WHERE {} = {};",
+                    self.qualified_name(entity_name),
+                    self.id_column(),
+                    row_id,
+                )));
+            }
         }
 
         stmts
     }
 
-    fn export_instance(&self, schema: &Schema, instance: &Instance) -> Vec<Statement> {
+    /// Colonnes (déjà quotées) et expressions SQL d'une FK, sans instance :
+    /// FK simple → le RowId cible ; FK composite → une sous-requête par
+    /// attribut de la clé métier de la ligne cible. `None` → NULL.
+    fn fk_column_exprs(&self, fk_name: &str, target_id: Option<RowId>, schema: &Schema) -> Vec<(String, String)> {
+        match (schema.edges.get(fk_name), target_id) {
+            (Some(Edge::ForeignKey { target, columns, .. }), Some(id)) if !columns.is_empty() => {
+                columns.iter()
+                    .map(|(col, attr)| (
+                        self.ident(col),
+                        format!(
+                            "(SELECT {} FROM {} WHERE {} = {})",
+                            self.ident(attr),
+                            self.qualified_name(target),
+                            self.id_column(),
+                            id,
+                        ),
+                    ))
+                    .collect()
+            }
+            (Some(Edge::ForeignKey { columns, .. }), None) if !columns.is_empty() => {
+                columns.iter()
+                    .map(|(col, _)| (self.ident(col), "NULL".to_string()))
+                    .collect()
+            }
+            (_, id) => vec![(
+                self.ident(fk_name),
+                id.map_or_else(|| "NULL".to_string(), |id| id.to_string()),
+            )],
+        }
+    }
+
+    /// Génère les contraintes qui font respecter les équations de chemins.
+    ///
+    /// Formes supportées :
+    /// - Les deux côtés sont des chemins de longueur ≤ 1 (FK directe ou
+    ///   identité) : `CHECK` sur la table de départ, ex: `CHECK ("f" = "g")`.
+    /// - Tout autre chemin (FK composées, attribut final) : trigger
+    ///   `AFTER INSERT OR UPDATE` sur la table de départ, qui compare les deux
+    ///   navigations par sous-requêtes (PL/pgSQL). C'est un gabarit : une
+    ///   modification des tables intermédiaires n'est pas revérifiée.
+    ///
+    /// - Avec `with_generated_columns`, une observation entre deux attributs
+    ///   de la même ligne est assurée par la colonne générée du CREATE TABLE :
+    ///   rien à ajouter.
+    ///
+    /// Si le dialecte ne supporte pas la forme requise, l'équation est émise
+    /// en commentaire.
+    pub fn deploy_constraints(&self, schema: &Schema) -> Vec<Statement> {
         let mut stmts = Vec::new();
+        let generated: Vec<usize> = self.generated_columns(schema).values().map(|(i, _)| *i).collect();
+
+        for (i, eq) in schema.path_equations.iter().enumerate() {
+            if generated.contains(&i) {
+                continue;
+            }
+            let table = self.qualified_name(&eq.lhs.start);
+            let direct = eq.lhs.start == eq.rhs.start
+                && [&eq.lhs, &eq.rhs].iter().all(|p| {
+                    p.is_empty()
+                        || (p.len() == 1 && matches!(schema.edges.get(&p.edges[0]), Some(Edge::ForeignKey { .. })))
+                });
 
-        // D'abord les entités sans FK entrantes (pour respecter les REFERENCES)
-        // Ordre simplifié : d'abord toutes les entités qui ne sont cible d'aucune FK
-        // TODO: faire un vrai tri topologique
-        for entity_name in schema.nodes.keys() {
-            for sql in self.insert_rows_sql(entity_name, schema, instance) {
-                stmts.push(Statement::Sql(sql));
+            if direct && self.dialect.supports_check_constraints() {
+                let column = |p: &crate::core::schema::Path| match p.edges.first() {
+                    Some(fk) => self.ident(fk),
+                    None => self.id_column(),
+                };
+                stmts.push(Statement::Sql(format!(
+                    "ALTER TABLE {} ADD CONSTRAINT catrust_eq_{} CHECK ({} = {});",
+                    table, i, column(&eq.lhs), column(&eq.rhs),
+                )));
+            } else if eq.lhs.start == eq.rhs.start && self.dialect.supports_triggers() {
+                let function = format!("catrust_check_eq_{}", i);
+                stmts.push(Statement::Sql(format!(
+                    "CREATE OR REPLACE FUNCTION {function}() RETURNS trigger AS $$\n\
+                     BEGIN\n  \
+                       IF ({lhs}) IS DISTINCT FROM ({rhs}) THEN\n    \
+                         RAISE EXCEPTION 'Équation violée : {eq}';\n  \
+                       END IF;\n  \
+                       RETURN NEW;\n\
+                     END;\n\
+                     $$ LANGUAGE plpgsql;",
+                    function = function,
+                    lhs = self.path_subquery_sql(&eq.lhs, schema),
+                    rhs = self.path_subquery_sql(&eq.rhs, schema),
+                    eq = eq.to_string().replace('\'', "''"),
+                )));
+                stmts.push(Statement::Sql(format!(
+                    "CREATE TRIGGER {}_trg AFTER INSERT OR UPDATE ON {} FOR EACH ROW EXECUTE FUNCTION {}();",
+                    function, table, function,
+                )));
+            } else {
+                stmts.push(Statement::Sql(format!(
+                    "-- Équation non appliquée par {} : {}",
+                    self.dialect.dialect_name(), eq
+                )));
             }
         }
 
         stmts
     }
 
-    fn generate_delta(&self, _mapping: &Mapping, _source: &Schema, _target: &Schema) -> Vec<Statement> {
-        // TODO: Générer les SELECT ... JOIN pour Δ
-        vec![Statement::Sql("-- TODO: Delta migration SQL".into())]
-    }
+    /// Colonnes cible et expressions SELECT qui transfèrent par Σ la FK
+    /// `fk` de `source_node` sur la FK cible `image`.
+    ///
+    /// Σ conserve les clés techniques : une FK simple envoyée sur une FK
+    /// simple recopie sa colonne. Sinon on lit la ligne référencée : une FK
+    /// composite cible reçoit l'image de chaque attribut de sa clé métier,
+    /// une FK simple cible la clé technique. None si un attribut de la clé
+    /// métier cible n'est l'image d'aucun attribut de la cible de `fk`.
+    fn sigma_fk_columns(
+        &self,
+        fk: &str,
+        image: &str,
+        source_node: &str,
+        mapping: &Mapping,
+        source: &Schema,
+        target: &Schema,
+    ) -> Option<Vec<(String, String)>> {
+        let (fk_target, fk_columns) = match source.edges.get(fk) {
+            Some(Edge::ForeignKey { target, columns, .. }) => (target, columns),
+            _ => return None,
+        };
+        let image_columns = match target.edges.get(image) {
+            Some(Edge::ForeignKey { columns, .. }) => columns,
+            _ => return None,
+        };
+        if fk_columns.is_empty() && image_columns.is_empty() {
+            return Some(vec![(self.ident(image), self.ident(fk))]);
+        }
 
-    fn generate_sigma(&self, _mapping: &Mapping, _source: &Schema, _target: &Schema) -> Vec<Statement> {
-        // TODO: Générer les INSERT INTO ... SELECT pour Σ
-        vec![Statement::Sql("-- TODO: Sigma migration SQL".into())]
-    }
+        // Lecture d'une colonne de la ligne référencée ; les colonnes de la
+        // ligne source sont qualifiées par sa table (la sous-requête peut
+        // porter sur la même table)
+        let outer = self.qualified_name(source_node);
+        let condition = if fk_columns.is_empty() {
+            format!("k.{} = {}.{}", self.id_column(), outer, self.ident(fk))
+        } else {
+            fk_columns.iter()
+                .map(|(col, attr)| format!("k.{} = {}.{}", self.ident(attr), outer, self.ident(col)))
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        };
+        let lookup = |column: &str| format!(
+            "(SELECT k.{} FROM {} k WHERE {})",
+            column, self.qualified_name(fk_target), condition,
+        );
 
-    fn name(&self) -> &str {
-        "SQL"
+        if image_columns.is_empty() {
+            return Some(vec![(self.ident(image), lookup(&self.id_column()))]);
+        }
+        image_columns.iter()
+            .map(|(col, key_attr)| {
+                // Attribut de la cible de `fk` envoyé sur `key_attr`
+                let attr = mapping.edge_mapping.iter().find_map(|(name, m)| match m {
+                    EdgeMapping::AttrToPath { fk_path, attr_name }
+                        if fk_path.is_empty()
+                            && attr_name == key_attr
+                            && source.edges.get(name).map(|e| e.source()) == Some(fk_target.as_str()) =>
+                    {
+                        Some(name)
+                    }
+                    _ => None,
+                })?;
+                // Déjà stocké dans une colonne de la FK composite source ?
+                let expr = match fk_columns.iter().find(|(_, a)| a == attr) {
+                    Some((fk_col, _)) => self.ident(fk_col),
+                    None => lookup(&self.ident(attr)),
+                };
+                Some((self.ident(col), expr))
+            })
+            .collect()
     }
-}
 
-// =============================================================================
+    /// Sous-requête qui suit un chemin depuis la ligne `NEW` d'un trigger
+    /// et retourne la colonne d'arrivée (attribut final ou clé technique).
+    fn path_subquery_sql(&self, path: &crate::core::schema::Path, schema: &Schema) -> String {
+        let id = &self.id_column();
+        if path.is_empty() {
+            return format!("NEW.{}", id);
+        }
+
+        let mut joins = String::new();
+        let mut current = "t0".to_string();
+        let mut result = format!("t0.{}", id);
+        for (k, edge_name) in path.edges.iter().enumerate() {
+            match schema.edges.get(edge_name) {
+                Some(Edge::ForeignKey { target, columns, .. }) => {
+                    let alias = format!("t{}", k + 1);
+                    // FK composite : une égalité par colonne de la clé métier
+                    let on: Vec<String> = if columns.is_empty() {
+                        vec![format!("{}.{} = {}.{}", current, self.ident(edge_name), alias, id)]
+                    } else {
+                        columns.iter()
+                            .map(|(col, attr)| format!("{}.{} = {}.{}",
+                                current, self.ident(col), alias, self.ident(attr),
+                            ))
+                            .collect()
+                    };
+                    joins.push_str(&format!(
+                        " JOIN {} {} ON {}",
+                        self.qualified_name(target), alias, on.join(" AND "),
+                    ));
+                    result = format!("{}.{}", alias, id);
+                    current = alias;
+                }
+                _ => {
+                    result = format!("{}.{}", current, self.ident(edge_name));
+                }
+            }
+        }
+        format!(
+            "SELECT {} FROM {} t0{} WHERE t0.{} = NEW.{}",
+            result, self.qualified_name(&path.start), joins, id, id,
+        )
+    }
+}
+
+/// Nom d'une table ou d'un type selon `policy`, préfixé par le schéma SQL
+/// s'il y en a un.
+pub(crate) fn qualified_name<D: SqlDialect + ?Sized>(
+    dialect: &D,
+    policy: IdentifierPolicy,
+    target_schema: Option<&str>,
+    name: &str,
+) -> String {
+    match target_schema {
+        Some(ns) => format!("{}.{}", policy.render(dialect, ns), policy.render(dialect, name)),
+        None => policy.render(dialect, name),
+    }
+}
+
+/// Convertit une Value en littéral SQL du dialecte
+pub(crate) fn value_to_sql<D: SqlDialect + ?Sized>(dialect: &D, value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("'{}'", s.replace('\'', "''")),
+        Value::Integer(i) => format!("{}", i),
+        Value::Float(f) => dialect.float_literal(*f),
+        Value::Boolean(b) => if *b { "TRUE".into() } else { "FALSE".into() },
+        Value::Array(values) => {
+            let items: Vec<String> = values.iter().map(|v| value_to_sql(dialect, v)).collect();
+            dialect.array_literal(&items)
+        }
+        // La clé de la ligne visée, comparable à une colonne de FK
+        Value::Ref { row, .. } => format!("{}", row),
+        Value::Null => "NULL".into(),
+    }
+}
+
+impl<D: SqlDialect> Backend for SqlBackend<D> {
+    fn deploy_schema(&self, schema: &Schema) -> Vec<Statement> {
+        let mut stmts = Vec::new();
+
+        // Noms confondus par identifier_policy : signalés en tête, le moteur
+        // rejettera la table ou la colonne en double
+        if let Err(errors) = self.check_identifiers(schema) {
+            stmts.extend(errors.into_iter().map(|e| Statement::Sql(format!("-- Collision d'identifiants : {}", e))));
+        }
+
+        // Schéma SQL cible (avant tout ce qu'il contient)
+        if let Some(ns) = &self.target_schema {
+            stmts.push(Statement::Sql(format!(
+                "CREATE SCHEMA IF NOT EXISTS {};",
+                self.ident(ns),
+            )));
+        }
+
+        // Types énumérés (avant les tables qui les utilisent)
+        for (name, variants) in self.enum_types(schema) {
+            let values: Vec<String> = variants.iter()
+                .map(|v| value_to_sql(&self.dialect, &Value::String(v.clone())))
+                .collect();
+            stmts.push(Statement::Sql(format!(
+                "CREATE TYPE {} AS ENUM ({});",
+                self.qualified_name(name),
+                values.join(", "),
+            )));
+        }
+
+        // Tables dans l'ordre topologique (une table après celles qu'elle
+        // référence, sinon dans l'ordre de déclaration) et colonnes dans
+        // l'ordre de déclaration : deux déploiements du même schéma donnent
+        // un DDL identique. Les FK d'un cycle sont ajoutées à la fin.
+        let order = schema.topological_order();
+        let mut pending: HashSet<&str> = order.iter().map(|n| n.as_str()).collect();
+        let mut deferred = Vec::new();
+        for entity_name in &order {
+            pending.remove(entity_name.as_str());
+            let (table, constraints) = self.create_table_deferring(entity_name, schema, &pending);
+            stmts.push(Statement::Sql(table));
+            deferred.extend(constraints);
+        }
+        stmts.extend(deferred.into_iter().map(Statement::Sql));
+
+        stmts
+    }
+
+    fn teardown_schema(&self, schema: &Schema) -> Vec<Statement> {
+        // Ordre topologique inversé : les tables qui référencent d'abord
+        let tables = schema.topological_order()
+            .into_iter()
+            .rev()
+            .map(|entity_name| Statement::Sql(format!(
+                "DROP TABLE IF EXISTS {} CASCADE;",
+                self.qualified_name(&entity_name)
+            )));
+
+        // Puis les types énumérés, qui ne sont plus utilisés
+        let types = self.enum_types(schema)
+            .into_iter()
+            .map(|(name, _)| Statement::Sql(format!(
+                "DROP TYPE IF EXISTS {};",
+                self.qualified_name(name)
+            )));
+
+        tables.chain(types).collect()
+    }
+
+    fn export_instance_iter<'a>(
+        &'a self,
+        schema: &'a Schema,
+        instance: &'a Instance,
+    ) -> Box<dyn Iterator<Item = Statement> + 'a> {
+        // Tables référencées d'abord, pour respecter les REFERENCES
+        Box::new(schema.topological_order().into_iter().flat_map(move |entity_name| {
+            let entity_data = instance.data.get(&entity_name);
+            let mut row_ids = entity_data.map(|d| d.row_ids()).unwrap_or_default();
+            row_ids.sort();
+            row_ids.into_iter().filter_map(move |row_id| {
+                entity_data.map(|d| Statement::Sql(self.insert_row_sql(&entity_name, d, row_id, schema, instance)))
+            })
+        }))
+    }
+
+    fn generate_delta(&self, _mapping: &Mapping, _source: &Schema, _target: &Schema) -> Vec<Statement> {
+        // TODO: Générer les SELECT ... JOIN pour Δ
+        vec![Statement::Sql("-- TODO: Delta migration SQL".into())]
+    }
+
+    fn generate_sigma(&self, mapping: &Mapping, source: &Schema, target: &Schema) -> Vec<Statement> {
+        let mut stmts = Vec::new();
+
+        // Un INSERT INTO ... SELECT par nœud source, vers son nœud image.
+        // Comme le Σ en mémoire : attributs directs et FK de longueur 1 seulement.
+        // Si le dialecte préfère le CTAS, le premier nœud source envoyé sur
+        // une table la crée par CREATE TABLE ... AS SELECT.
+        let mut source_nodes: Vec<&String> = source.nodes.keys().collect();
+        source_nodes.sort();
+        let mut created: Vec<&String> = Vec::new();
+
+        for source_node in source_nodes {
+            let target_node = match mapping.node_mapping.get(source_node) {
+                Some(t) => t,
+                None => continue,
+            };
+
+            let mut target_cols = vec![self.id_column()];
+            let mut select_exprs = vec![self.id_column()];
+
+            let mut edge_names: Vec<&String> = mapping.edge_mapping.keys().collect();
+            edge_names.sort();
+
+            for edge_name in edge_names {
+                match (source.edges.get(edge_name), &mapping.edge_mapping[edge_name]) {
+                    (Some(edge), EdgeMapping::AttrToPath { fk_path, attr_name })
+                        if edge.source() == source_node && fk_path.is_empty() =>
+                    {
+                        target_cols.push(self.ident(attr_name));
+                        select_exprs.push(self.ident(edge_name));
+                    }
+                    (Some(edge), EdgeMapping::FkToPath(path))
+                        if edge.source() == source_node && path.len() == 1 =>
+                    {
+                        match self.sigma_fk_columns(edge_name, &path.edges[0], source_node, mapping, source, target) {
+                            Some(columns) => {
+                                for (col, expr) in columns {
+                                    target_cols.push(col);
+                                    select_exprs.push(expr);
+                                }
+                            }
+                            None => stmts.push(Statement::Sql(format!(
+                                "-- FK '{}' non transférée : la clé métier de '{}' n'est pas l'image d'attributs de '{}'",
+                                edge_name, path.edges[0], edge_name,
+                            ))),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            // Attributs remplis par le mapping : attributs du schéma cible portés par ce nœud
+            let mut constants: Vec<(&String, &AttrFill)> = mapping.filled_attrs.iter().collect();
+            constants.sort_by_key(|(attr, _)| *attr);
+            for (attr, fill) in constants {
+                match target.edges.get(attr) {
+                    Some(Edge::Attribute { source: attr_src, .. }) if attr_src == target_node => {}
+                    _ => continue,
+                }
+                match fill {
+                    AttrFill::Constant(value) => {
+                        target_cols.push(self.ident(attr));
+                        select_exprs.push(value_to_sql(&self.dialect, value));
+                    }
+                    AttrFill::SourcePath { fk_path, attr_name } => stmts.push(Statement::Sql(format!(
+                        "-- Attribut '{}' non transféré : lu à travers le chemin source '{}.{}'",
+                        attr, fk_path.join("."), attr_name,
+                    ))),
+                }
+            }
+
+            if self.dialect.supports_ctas() {
+                let table = self.ctas_table_name(target_node);
+                let from = self.ctas_table_name(source_node);
+                if created.contains(&target_node) {
+                    stmts.push(Statement::Sql(format!(
+                        "INSERT INTO {} ({})\nSELECT {}\nFROM {};",
+                        table,
+                        target_cols.join(", "),
+                        select_exprs.join(", "),
+                        from,
+                    )));
+                } else {
+                    created.push(target_node);
+                    let aliased: Vec<String> = select_exprs.iter().zip(&target_cols)
+                        .map(|(expr, col)| if expr == col { expr.clone() } else { format!("{} AS {}", expr, col) })
+                        .collect();
+                    stmts.push(Statement::Sql(format!(
+                        "CREATE TABLE {} AS\nSELECT {}\nFROM {};",
+                        table,
+                        aliased.join(", "),
+                        from,
+                    )));
+                }
+                continue;
+            }
+
+            stmts.push(Statement::Sql(format!(
+                "INSERT INTO {} ({})\nSELECT {}\nFROM {};",
+                self.qualified_name(target_node),
+                target_cols.join(", "),
+                select_exprs.join(", "),
+                self.qualified_name(source_node),
+            )));
+        }
+
+        stmts
+    }
+
+    fn name(&self) -> &str {
+        "SQL"
+    }
+}
+
+// =============================================================================
 // TESTS
 // =============================================================================
 #[cfg(test)]
@@ -318,6 +1531,126 @@ mod tests {
         println!("=== PostgreSQL DDL ===\n{}", sql);
     }
 
+    #[test]
+    fn test_ddl_follows_declaration_order() {
+        let declare = || {
+            let mut s = Schema::new("Company");
+            s.add_node("Employee")
+             .add_node("Department")
+             .add_attribute("salary", "Employee", BaseType::Integer)
+             .add_fk("works_in", "Employee", "Department")
+             .add_attribute("emp_name", "Employee", BaseType::String)
+             .add_attribute("dept_name", "Department", BaseType::String);
+            s
+        };
+        let render = |schema: &Schema| SqlBackend::new(PostgresDialect)
+            .deploy_schema(schema)
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // Deux schémas identiques (HashMap d'ordres différents) : même DDL
+        let ddl = render(&declare());
+        assert_eq!(ddl, render(&declare()));
+
+        // Employee est déclarée d'abord mais référence Department : elle est
+        // créée après. Colonnes dans l'ordre déclaré (attributs, puis FK)
+        assert!(ddl.find("CREATE TABLE \"Department\"").unwrap() < ddl.find("CREATE TABLE \"Employee\"").unwrap());
+        let employee = ddl.split(";").nth(1).unwrap();
+        let position = |col: &str| employee.find(col).unwrap();
+        assert!(position("\"salary\"") < position("\"emp_name\""));
+        assert!(position("\"emp_name\"") < position("\"works_in\""));
+    }
+
+    #[test]
+    fn test_ddl_defers_cyclic_fks() {
+        // Employee → Department → Employee : une des deux FK est ajoutée
+        // après la création des deux tables
+        let mut schema = company_schema();
+        schema.add_fk("manager", "Department", "Employee");
+        let ddl: Vec<String> = SqlBackend::new(PostgresDialect)
+            .deploy_schema(&schema).iter().map(|s| s.to_string()).collect();
+        println!("{}", ddl.join("\n"));
+
+        assert_eq!(ddl.len(), 3);
+        assert!(ddl[0].starts_with("CREATE TABLE \"Employee\""));
+        assert!(ddl[0].contains("  \"works_in\" BIGINT\n"), "{}", ddl[0]);
+        assert!(ddl[1].starts_with("CREATE TABLE \"Department\""));
+        assert!(ddl[1].contains("\"manager\" BIGINT REFERENCES \"Employee\"(\"catrust_id\")"), "{}", ddl[1]);
+        assert_eq!(ddl[2], "ALTER TABLE \"Employee\" ADD FOREIGN KEY (\"works_in\") REFERENCES \"Department\"(\"catrust_id\");");
+    }
+
+    #[test]
+    fn test_lower_unquoted_identifiers() {
+        let mut schema = company_schema();
+        schema.add_attribute("hireDate", "Employee", BaseType::String);
+        let backend = SqlBackend::new(PostgresDialect)
+            .with_identifier_policy(IdentifierPolicy::LowerUnquoted);
+
+        let ddl: Vec<String> = backend.deploy_schema(&schema).iter().map(|s| s.to_string()).collect();
+        let employee = ddl.iter().find(|s| s.starts_with("CREATE TABLE employee")).unwrap();
+        assert!(employee.contains("  emp_name TEXT"), "{}", employee);
+        assert!(employee.contains("  hiredate TEXT"), "{}", employee);
+        assert!(employee.contains("works_in BIGINT REFERENCES department(catrust_id)"), "{}", employee);
+        assert!(ddl.iter().all(|s| !s.contains('"')), "{:?}", ddl);
+
+        // Les INSERT et le planner nomment les colonnes comme le DDL
+        let mut inst = Instance::new("Data", &schema);
+        let d = inst.insert("Department", crate::row! { dept_name: "R&D" }, HashMap::new());
+        inst.insert("Employee", crate::row! { emp_name: "Alice", hireDate: "2020-01-01" }, HashMap::from([("works_in".into(), d)]));
+        let dml = backend.export_instance(&schema, &inst).iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n");
+        assert!(dml.contains("INSERT INTO employee (") && dml.contains("hiredate"), "{}", dml);
+        assert!(!dml.contains('"'), "{}", dml);
+
+        let mut query = crate::core::query::CqlQuery::new("Q", "Company");
+        query.add_block(
+            crate::core::query::CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["works_in", "dept_name"], Value::String("R&D".into()))
+                .bind("hired", "e", &[], "hireDate")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let sql = planner::SqlPlanner::new(&backend.dialect, &schema)
+            .with_identifier_policy(backend.identifier_policy)
+            .plan_query(&query).unwrap()[0]
+            .sql
+            .clone();
+        assert!(sql.contains("e.hiredate AS hired") && sql.contains("FROM employee e"), "{}", sql);
+        assert!(sql.contains("JOIN department"), "{}", sql);
+        assert!(!sql.contains('"'), "{}", sql);
+        assert!(backend.check_identifiers(&schema).is_ok());
+    }
+
+    #[test]
+    fn test_unquoted_identifiers_reserved_and_collisions() {
+        let mut schema = Schema::new("Shop");
+        schema.add_node("User")
+              .add_node("Order")
+              .add_attribute("hireDate", "User", BaseType::String)
+              .add_attribute("hiredate", "User", BaseType::String)
+              .add_fk("placed_by", "Order", "User");
+        let backend = SqlBackend::new(PostgresDialect)
+            .with_identifier_policy(IdentifierPolicy::LowerUnquoted);
+
+        // Mots réservés : quotés même en politique non quotée
+        assert_eq!(backend.ident("order"), "\"order\"");
+        assert_eq!(IdentifierPolicy::UpperUnquoted.render(&PostgresDialect, "user"), "\"USER\"");
+        let ddl: Vec<String> = backend.deploy_schema(&schema).iter().map(|s| s.to_string()).collect();
+        assert!(ddl.iter().any(|s| s.starts_with("CREATE TABLE \"user\" (")), "{:?}", ddl);
+        assert!(ddl.iter().any(|s| s.contains("placed_by BIGINT REFERENCES \"user\"(catrust_id)")), "{:?}", ddl);
+
+        // hireDate et hiredate deviendraient la même colonne
+        let errors = backend.check_identifiers(&schema).unwrap_err();
+        assert_eq!(errors, vec!["Table 'User' : colonnes 'hireDate' et 'hiredate' toutes deux écrites hiredate".to_string()]);
+        assert!(ddl[0].starts_with("-- Collision d'identifiants : Table 'User'"), "{:?}", ddl);
+
+        // Quotés, les noms gardent leur casse : pas de collision
+        assert!(SqlBackend::new(PostgresDialect).check_identifiers(&schema).is_ok());
+    }
+
     #[test]
     fn test_snowflake_ddl() {
         let schema = company_schema();
@@ -354,6 +1687,652 @@ mod tests {
         println!("=== PostgreSQL DML ===\n{}", sql);
     }
 
+    #[test]
+    fn test_non_finite_float_literals() {
+        let literal = |dialect: &dyn SqlDialect, f: f64| value_to_sql(dialect, &Value::Float(f));
+
+        assert_eq!(literal(&PostgresDialect, 1.5), "1.5");
+        assert_eq!(literal(&PostgresDialect, f64::NAN), "'NaN'::double precision");
+        assert_eq!(literal(&PostgresDialect, f64::NEG_INFINITY), "'-Infinity'::double precision");
+        assert_eq!(literal(&TrinoDialect::new("hive", "default"), f64::NAN), "CAST('NaN' AS DOUBLE)");
+        assert_eq!(literal(&TrinoDialect::new("hive", "default"), f64::INFINITY), "CAST('Infinity' AS DOUBLE)");
+        assert_eq!(literal(&SnowflakeDialect, f64::INFINITY), "CAST('inf' AS FLOAT)");
+        assert_eq!(literal(&BigQueryDialect, f64::NAN), "CAST('NaN' AS FLOAT64)");
+
+        // Dans les listes aussi
+        assert_eq!(
+            value_to_sql(&PostgresDialect, &Value::Array(vec![Value::Float(f64::NAN)])),
+            "ARRAY['NaN'::double precision]",
+        );
+    }
+
+    #[test]
+    fn test_export_diff() {
+        let schema = company_schema();
+        let mut old = Instance::new("Data", &schema);
+        let d1 = old.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Engineering".into()))]),
+            HashMap::new(),
+        );
+        let alice = old.insert("Employee",
+            HashMap::from([
+                ("emp_name".into(), Value::String("Alice".into())),
+                ("salary".into(), Value::Integer(80000)),
+            ]),
+            HashMap::from([("works_in".into(), d1)]),
+        );
+
+        let mut new = old.clone();
+        new.set_attr("Employee", alice, "salary", Value::Integer(85000), &schema).unwrap();
+        new.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String("Bob".into()))]),
+            HashMap::from([("works_in".into(), d1)]),
+        );
+
+        let backend = SqlBackend::new(PostgresDialect);
+        let sql: Vec<String> = backend.export_diff(&schema, &old.diff(&new, &schema))
+            .iter().map(|s| s.to_string()).collect();
+        assert_eq!(sql.len(), 2, "{:#?}", sql);
+        assert_eq!(sql[0], "INSERT INTO \"Employee\" (\"catrust_id\", \"emp_name\", \"works_in\") VALUES (2, 'Bob', 1);");
+        assert_eq!(sql[1], "UPDATE \"Employee\" SET \"salary\" = 85000 WHERE \"catrust_id\" = 1;");
+    }
+
+    #[test]
+    fn test_composite_fk() {
+        let mut schema = Schema::new("Offices");
+        schema.add_node("Site")
+              .add_node("Office")
+              .add_attribute("country", "Site", BaseType::String)
+              .add_attribute("code", "Site", BaseType::Integer)
+              .add_composite_fk("located_in", "Office", "Site",
+                  vec![("site_country", "country"), ("site_code", "code")]);
+        assert!(crate::core::validate::validate_schema(&schema).is_ok());
+
+        let backend = SqlBackend::new(PostgresDialect);
+        let office = backend.create_table_sql("Office", &schema);
+        assert!(office.contains("\"site_country\" TEXT"));
+        assert!(office.contains("\"site_code\" INTEGER"));
+        assert!(office.contains(
+            "FOREIGN KEY (\"site_country\", \"site_code\") REFERENCES \"Site\" (\"country\", \"code\")"
+        ));
+        assert!(!office.contains("\"located_in\""));
+        assert!(backend.create_table_sql("Site", &schema).contains("UNIQUE (\"country\", \"code\")"));
+
+        // L'INSERT écrit la clé métier de la ligne cible, pas son RowId
+        let mut inst = Instance::new("Data", &schema);
+        let site = inst.insert("Site",
+            HashMap::from([
+                ("country".into(), Value::String("FR".into())),
+                ("code".into(), Value::Integer(75)),
+            ]),
+            HashMap::new(),
+        );
+        inst.insert("Office", HashMap::new(), HashMap::from([("located_in".into(), site)]));
+        let sql: Vec<String> = backend.export_instance(&schema, &inst).iter().map(|s| s.to_string()).collect();
+        let insert = sql.iter().find(|s| s.contains("INSERT INTO \"Office\"")).unwrap();
+        assert!(insert.contains("\"site_country\""));
+        assert!(insert.contains("'FR'"));
+        assert!(insert.contains("75"));
+        println!("=== FK composite ===\n{}\n{}", office, insert);
+    }
+
+    #[test]
+    fn test_target_schema_qualifies_tables() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        let d = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Engineering".into()))]),
+            HashMap::new(),
+        );
+        inst.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String("Alice".into()))]),
+            HashMap::from([("works_in".into(), d)]),
+        );
+
+        let backend = SqlBackend::new(PostgresDialect).with_target_schema("hr");
+        let stmts: Vec<String> = backend.deploy_schema(&schema).into_iter()
+            .chain(backend.export_instance(&schema, &inst))
+            .chain(backend.teardown_schema(&schema))
+            .map(|s| s.to_string())
+            .collect();
+
+        assert_eq!(stmts[0], "CREATE SCHEMA IF NOT EXISTS \"hr\";");
+        for stmt in &stmts[1..] {
+            let unqualified = stmt
+                .replace("\"hr\".\"Employee\"", "")
+                .replace("\"hr\".\"Department\"", "");
+            assert!(stmt.contains("\"hr\"."), "non qualifié : {}", stmt);
+            assert!(!unqualified.contains("\"Employee\"") && !unqualified.contains("\"Department\""),
+                "table non qualifiée : {}", stmt);
+        }
+
+        // Le planner qualifie aussi les tables de ses JOINs
+        let mut query = crate::core::query::CqlQuery::new("Q", "Company");
+        query.add_block(
+            crate::core::query::CqlQuery::select()
+                .from("e", "Employee")
+                .bind("dept", "e", &["works_in"], "dept_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let plan = &planner::SqlPlanner::new(&PostgresDialect, &schema)
+            .with_target_schema("hr")
+            .plan_query(&query).unwrap()[0];
+        assert!(plan.sql.contains("FROM \"hr\".\"Employee\" e"));
+        assert!(plan.sql.contains("JOIN \"hr\".\"Department\" j1"));
+    }
+
+    #[test]
+    fn test_enum_attribute() {
+        let status = BaseType::Enum {
+            name: "employee_status".into(),
+            variants: vec!["active".into(), "inactive".into()],
+        };
+        let mut schema = Schema::new("HR");
+        schema.add_node("Employee")
+              .add_attribute("status", "Employee", status);
+
+        // PostgreSQL : type ENUM créé avant la table, puis supprimé après
+        let pg = SqlBackend::new(PostgresDialect);
+        let ddl: Vec<String> = pg.deploy_schema(&schema).iter().map(|s| s.to_string()).collect();
+        assert_eq!(ddl[0], "CREATE TYPE \"employee_status\" AS ENUM ('active', 'inactive');");
+        assert!(ddl[1].contains("\"status\" \"employee_status\""));
+        let teardown: Vec<String> = pg.teardown_schema(&schema).iter().map(|s| s.to_string()).collect();
+        assert_eq!(teardown.last().unwrap(), "DROP TYPE IF EXISTS \"employee_status\";");
+
+        // Sans type ENUM : texte + CHECK (si le moteur applique les CHECK)
+        struct CheckOnlyDialect;
+        impl SqlDialect for CheckOnlyDialect {
+            fn type_to_sql(&self, _ty: &BaseType) -> String { "TEXT".into() }
+            fn auto_id_type(&self) -> String { "BIGINT".into() }
+            fn dialect_name(&self) -> String { "CheckOnly".into() }
+        }
+        let ddl = SqlBackend::new(CheckOnlyDialect).create_table_sql("Employee", &schema);
+        assert!(ddl.contains("\"status\" TEXT CHECK (\"status\" IN ('active', 'inactive'))"));
+        assert!(!SqlBackend::new(SnowflakeDialect).create_table_sql("Employee", &schema).contains("CHECK"));
+
+        // Une valeur hors de l'énumération est rejetée par la validation
+        let mut inst = Instance::new("Data", &schema);
+        inst.insert("Employee", HashMap::from([("status".into(), Value::String("active".into()))]), HashMap::new());
+        assert!(crate::core::validate::validate_instance(&inst, &schema).is_ok());
+        inst.insert("Employee", HashMap::from([("status".into(), Value::String("retired".into()))]), HashMap::new());
+        assert!(crate::core::validate::validate_instance(&inst, &schema).is_err());
+    }
+
+    #[test]
+    fn test_array_attribute() {
+        let tags = BaseType::Array(Box::new(BaseType::String));
+        let mut schema = Schema::new("Blog");
+        schema.add_node("Post").add_attribute("tags", "Post", tags);
+
+        assert!(SqlBackend::new(PostgresDialect).create_table_sql("Post", &schema).contains("\"tags\" TEXT[]"));
+        assert!(SqlBackend::new(SnowflakeDialect).create_table_sql("Post", &schema).contains("\"tags\" ARRAY(VARCHAR)"));
+        assert!(SqlBackend::new(TrinoDialect::new("hive", "default")).create_table_sql("Post", &schema).contains("\"tags\" ARRAY(VARCHAR)"));
+        assert!(SqlBackend::new(BigQueryDialect).create_table_sql("Post", &schema).contains("`tags` ARRAY<STRING>"));
+
+        let mut inst = Instance::new("Data", &schema);
+        inst.insert("Post",
+            HashMap::from([("tags".into(), Value::Array(vec![Value::String("cql".into()), Value::String("rust".into())]))]),
+            HashMap::new(),
+        );
+        let sql = SqlBackend::new(PostgresDialect).export_instance(&schema, &inst)[0].to_string();
+        assert!(sql.contains("ARRAY['cql', 'rust']"));
+        // Littéral de liste propre à chaque dialecte
+        let tags = Value::Array(vec![Value::String("cql".into()), Value::Integer(1)]);
+        assert_eq!(value_to_sql(&TrinoDialect::new("hive", "default"), &tags), "ARRAY['cql', 1]");
+        assert_eq!(value_to_sql(&SnowflakeDialect, &tags), "ARRAY_CONSTRUCT('cql', 1)");
+        assert_eq!(value_to_sql(&BigQueryDialect, &tags), "['cql', 1]");
+        let cypher: Vec<String> = crate::backend::graph::Neo4jBackend::new()
+            .export_instance(&schema, &inst).iter().map(|s| s.to_string()).collect();
+        assert!(cypher[0].contains("tags: ['cql', 'rust']"));
+    }
+
+    #[test]
+    fn test_export_instance_iter() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        let d1 = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Engineering".into()))]),
+            HashMap::new(),
+        );
+        for name in ["Alice", "Bob"] {
+            inst.insert("Employee",
+                HashMap::from([("emp_name".into(), Value::String(name.into()))]),
+                HashMap::from([("works_in".into(), d1)]),
+            );
+        }
+
+        let backend = SqlBackend::new(PostgresDialect);
+        let lazy: Vec<String> = backend.export_instance_iter(&schema, &inst).map(|s| s.to_string()).collect();
+        let eager: Vec<String> = backend.export_instance(&schema, &inst).iter().map(|s| s.to_string()).collect();
+        assert_eq!(lazy, eager);
+        assert_eq!(lazy.len(), 3);
+
+        // Ordre topologique : le département avant les employés qui le référencent
+        assert!(lazy[0].starts_with("INSERT INTO \"Department\""));
+
+        let neo4j = crate::backend::graph::Neo4jBackend::new();
+        let lazy: Vec<String> = neo4j.export_instance_iter(&schema, &inst).map(|s| s.to_string()).collect();
+        let eager: Vec<String> = neo4j.export_instance(&schema, &inst).iter().map(|s| s.to_string()).collect();
+        assert_eq!(lazy, eager);
+    }
+
+    fn schema_with_shortcut() -> Schema {
+        let mut s = Schema::new("Company");
+        s.add_node("Employee")
+         .add_node("Department")
+         .add_fk("department", "Employee", "Department")
+         .add_fk("manager", "Department", "Employee")
+         .add_fk("direct_mgr", "Employee", "Employee")
+         .add_fk("backup_mgr", "Employee", "Employee")
+         .add_path_equation(
+             crate::core::schema::Path::new("Employee", vec!["department", "manager"]),
+             crate::core::schema::Path::new("Employee", vec!["direct_mgr"]),
+         )
+         .add_path_equation(
+             crate::core::schema::Path::new("Employee", vec!["backup_mgr"]),
+             crate::core::schema::Path::new("Employee", vec!["direct_mgr"]),
+         );
+        s
+    }
+
+    #[test]
+    fn test_path_equation_constraints() {
+        let schema = schema_with_shortcut();
+        let backend = SqlBackend::new(PostgresDialect);
+        let stmts: Vec<String> = backend.deploy_constraints(&schema).iter().map(|s| s.to_string()).collect();
+
+        // department.manager = direct_mgr → trigger qui suit les deux chemins
+        assert!(stmts[0].contains("CREATE OR REPLACE FUNCTION catrust_check_eq_0()"));
+        assert!(stmts[0].contains(
+            "SELECT t2.\"catrust_id\" FROM \"Employee\" t0 \
+             JOIN \"Department\" t1 ON t0.\"department\" = t1.\"catrust_id\" \
+             JOIN \"Employee\" t2 ON t1.\"manager\" = t2.\"catrust_id\" \
+             WHERE t0.\"catrust_id\" = NEW.\"catrust_id\""
+        ));
+        assert!(stmts[0].contains("t0.\"direct_mgr\" = t1.\"catrust_id\""));
+        assert!(stmts[1].contains("AFTER INSERT OR UPDATE ON \"Employee\""));
+
+        // backup_mgr = direct_mgr → simple CHECK
+        assert_eq!(
+            stmts[2],
+            "ALTER TABLE \"Employee\" ADD CONSTRAINT catrust_eq_1 CHECK (\"backup_mgr\" = \"direct_mgr\");"
+        );
+
+        // Trino : ni CHECK ni trigger
+        let trino = SqlBackend::new(TrinoDialect::new("iceberg", "default"));
+        let stmts = trino.deploy_constraints(&schema);
+        assert!(stmts.iter().all(|s| s.to_string().starts_with("--")));
+        println!("=== Contraintes ===\n{}", backend.deploy_constraints(&schema).iter()
+            .map(|s| s.to_string()).collect::<Vec<_>>().join("\n"));
+    }
+
+    #[test]
+    fn test_generated_column_for_observation() {
+        use crate::core::schema::Path;
+        let mut schema = Schema::new("Company");
+        schema.add_node("Employee")
+              .add_node("Department")
+              .add_fk("department", "Employee", "Department")
+              .add_attribute("emp_name", "Employee", BaseType::String)
+              .add_attribute("display_name", "Employee", BaseType::String)
+              .add_attribute("cached_dept", "Employee", BaseType::String)
+              .add_attribute("dept_name", "Department", BaseType::String)
+              .add_path_equation(
+                  Path::new("Employee", vec!["emp_name"]),
+                  Path::new("Employee", vec!["display_name"]),
+              )
+              .add_path_equation(
+                  Path::new("Employee", vec!["department", "dept_name"]),
+                  Path::new("Employee", vec!["cached_dept"]),
+              );
+        let backend = SqlBackend::new(PostgresDialect).with_generated_columns();
+        let ddl = backend.deploy_schema(&schema).iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n");
+
+        // Même ligne : colonne générée ; à travers une FK : pas légal, trigger
+        assert!(ddl.contains("\"display_name\" TEXT GENERATED ALWAYS AS (\"emp_name\") STORED"), "{}", ddl);
+        assert!(!ddl.contains("\"cached_dept\" TEXT GENERATED"), "{}", ddl);
+        let constraints: Vec<String> = backend.deploy_constraints(&schema).iter().map(|s| s.to_string()).collect();
+        assert_eq!(constraints.len(), 2, "{:?}", constraints);
+        assert!(constraints[0].contains("FUNCTION catrust_check_eq_1()"));
+
+        // Le moteur calcule la colonne : les INSERT ne la fournissent pas
+        let mut inst = Instance::new("Data", &schema);
+        inst.insert("Employee", crate::row! { emp_name: "Alice", display_name: "Alice" }, HashMap::new());
+        let dml = backend.export_instance(&schema, &inst).iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n");
+        assert!(dml.contains("\"emp_name\"") && !dml.contains("display_name"), "{}", dml);
+
+        // Option absente, ou dialecte sans colonnes générées : trigger
+        let ddl = SqlBackend::new(PostgresDialect).deploy_schema(&schema);
+        assert!(ddl.iter().all(|s| !s.to_string().contains("GENERATED")));
+        let snowflake = SqlBackend::new(SnowflakeDialect).with_generated_columns();
+        assert!(snowflake.deploy_schema(&schema).iter().all(|s| !s.to_string().contains("GENERATED")));
+    }
+
+    #[test]
+    fn test_postgres_teardown_order() {
+        let mut schema = company_schema();
+        schema.add_node("Company")
+              .add_fk("belongs_to", "Department", "Company");
+
+        let backend = SqlBackend::new(PostgresDialect);
+        let sql: Vec<String> = backend.teardown_schema(&schema).iter().map(|s| s.to_string()).collect();
+        assert_eq!(sql, vec![
+            "DROP TABLE IF EXISTS \"Employee\" CASCADE;",
+            "DROP TABLE IF EXISTS \"Department\" CASCADE;",
+            "DROP TABLE IF EXISTS \"Company\" CASCADE;",
+        ]);
+    }
+
+    #[test]
+    fn test_bigquery_ddl() {
+        let schema = company_schema();
+        let backend = SqlBackend::new(BigQueryDialect);
+        let stmts = backend.deploy_schema(&schema);
+
+        let sql = stmts.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n");
+        assert!(sql.contains("CREATE TABLE `Employee`"));
+        assert!(sql.contains("`emp_name` STRING"));
+        assert!(sql.contains("`salary` INT64"));
+        assert!(sql.contains("`works_in` INT64"));
+        assert!(!sql.contains("REFERENCES"));
+        assert!(!sql.contains("SERIAL"));
+        assert!(!sql.contains('"'));
+        println!("=== BigQuery DDL ===\n{}", sql);
+    }
+
+    #[test]
+    fn test_bigquery_insert() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+
+        let d1 = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Engineering".into()))]),
+            HashMap::new(),
+        );
+        inst.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String("Alice".into()))]),
+            HashMap::from([("works_in".into(), d1)]),
+        );
+
+        let backend = SqlBackend::new(BigQueryDialect);
+        let stmts = backend.export_instance(&schema, &inst);
+
+        let sql = stmts.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n");
+        assert!(sql.contains("INSERT INTO `Employee`"));
+        assert!(sql.contains("`works_in`"));
+        assert!(!sql.contains('"'));
+        println!("=== BigQuery DML ===\n{}", sql);
+    }
+
+    #[test]
+    fn test_custom_id_column() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        let d1 = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Engineering".into()))]),
+            HashMap::new(),
+        );
+        inst.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String("Alice".into()))]),
+            HashMap::from([("works_in".into(), d1)]),
+        );
+
+        let backend = SqlBackend::new(PostgresDialect).with_id_strategy(IdStrategy::new("_id"));
+        let all: Vec<String> = backend.deploy_schema(&schema).iter()
+            .chain(backend.export_instance(&schema, &inst).iter())
+            .map(|s| s.to_string())
+            .collect();
+        let sql = all.join("\n");
+
+        assert!(sql.contains("\"_id\" BIGSERIAL PRIMARY KEY"));
+        assert!(sql.contains("REFERENCES \"Department\"(\"_id\")"));
+        assert!(sql.contains("INSERT INTO \"Employee\" (\"_id\","));
+        assert!(!sql.contains("catrust_id"));
+
+        // Le planner doit joindre sur la même colonne
+        let planner = planner::SqlPlanner::new(&backend.dialect, &schema)
+            .with_id_strategy(&backend.id_strategy);
+        let mut query = crate::core::query::CqlQuery::new("Q", "Company");
+        query.add_block(crate::core::query::QueryBlock {
+            target_entity: "R".into(),
+            from_vars: HashMap::from([("e".into(), "Employee".into())]),
+            where_clauses: vec![],
+            attribute_bindings: HashMap::from([
+                ("dept".into(), crate::core::query::AttributeBinding {
+                    from_var: "e".into(),
+                    path: vec!["works_in".into()],
+                    attribute: "dept_name".into(),
+                    expr: None,
+                }),
+            ]),
+            fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: crate::core::query::JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(plan.sql.contains("= j1.\"_id\""));
+        assert!(!plan.sql.contains("catrust_id"));
+
+        // Clé explicite de type UUID
+        let uuid = SqlBackend::new(PostgresDialect).with_id_strategy(
+            IdStrategy::new("_id")
+                .with_types("UUID PRIMARY KEY", "UUID")
+                .with_generation(IdGeneration::Explicit),
+        );
+        let ddl = uuid.deploy_schema(&schema).iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n");
+        assert!(ddl.contains("\"_id\" UUID PRIMARY KEY"));
+        assert!(ddl.contains("\"works_in\" UUID REFERENCES"));
+    }
+
+    #[test]
+    fn test_id_column_follows_identifier_policy() {
+        // Clé technique « User » : mot réservé et casse mixte, quotée par la
+        // politique partout où elle est écrite
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        let d1 = inst.insert("Department", HashMap::from([("dept_name".into(), Value::String("R&D".into()))]), HashMap::new());
+        inst.insert("Employee", HashMap::from([("emp_name".into(), Value::String("Alice".into()))]), HashMap::from([("works_in".into(), d1)]));
+
+        let backend = SqlBackend::new(PostgresDialect)
+            .with_id_strategy(IdStrategy::new("User"))
+            .with_identifier_policy(IdentifierPolicy::LowerUnquoted);
+        assert!(backend.check_identifiers(&schema).is_ok());
+        let sql = backend.deploy_schema(&schema).iter()
+            .chain(backend.export_instance(&schema, &inst).iter())
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(sql.contains("\"user\" BIGSERIAL PRIMARY KEY"), "{}", sql);
+        assert!(sql.contains("works_in BIGINT REFERENCES department(\"user\")"), "{}", sql);
+        assert!(sql.contains("INSERT INTO employee (\"user\", emp_name, works_in)"), "{}", sql);
+        assert!(!sql.contains("User") && !sql.contains(" user"), "{}", sql);
+
+        let (prepared, _) = &backend.export_instance_prepared(&schema, &inst)[0];
+        assert!(prepared.starts_with("INSERT INTO department (\"user\", dept_name)"), "{}", prepared);
+
+        let planner = planner::SqlPlanner::new(&backend.dialect, &schema)
+            .with_id_strategy(&backend.id_strategy)
+            .with_identifier_policy(IdentifierPolicy::LowerUnquoted);
+        let mut query = crate::core::query::CqlQuery::new("Q", "Company");
+        query.add_block(
+            crate::core::query::CqlQuery::select()
+                .from("e", "Employee")
+                .bind("dept", "e", &["works_in"], "dept_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(plan.sql.contains("ON e.works_in = j1.\"user\""), "{}", plan.sql);
+    }
+
+    #[test]
+    fn test_sigma_constant_sql() {
+        let mut source = Schema::new("Old");
+        source.add_node("Person")
+              .add_attribute("person_name", "Person", BaseType::String);
+
+        let mut target = Schema::new("New");
+        target.add_node("Employee")
+              .add_attribute("emp_name", "Employee", BaseType::String)
+              .add_attribute("source_system", "Employee", BaseType::String);
+
+        let mut mapping = Mapping::new("M", "Old", "New");
+        mapping.map_node("Person", "Employee")
+               .map_attr_direct("person_name", "emp_name")
+               .map_attr_const("source_system", Value::String("legacy".into()));
+
+        let backend = SqlBackend::new(PostgresDialect);
+        let stmts = backend.generate_sigma(&mapping, &source, &target);
+
+        let sql = stmts.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n");
+        assert!(sql.contains("INSERT INTO \"Employee\" (\"catrust_id\", \"emp_name\", \"source_system\")"));
+        assert!(sql.contains("SELECT \"catrust_id\", \"person_name\", 'legacy'"));
+        assert!(sql.contains("FROM \"Person\""));
+        println!("=== Sigma SQL ===\n{}", sql);
+    }
+
+    #[test]
+    fn test_sigma_composite_fk_sql() {
+        // FK composite (clé métier) → FK simple (clé technique)
+        let mut source = Schema::new("Old");
+        source.add_node("Site")
+              .add_node("Office")
+              .add_attribute("country", "Site", BaseType::String)
+              .add_attribute("code", "Site", BaseType::Integer)
+              .add_composite_fk("located_in", "Office", "Site",
+                  vec![("site_country", "country"), ("site_code", "code")]);
+
+        let mut target = Schema::new("New");
+        target.add_node("Site")
+              .add_node("Office")
+              .add_attribute("country", "Site", BaseType::String)
+              .add_attribute("code", "Site", BaseType::Integer)
+              .add_fk("site", "Office", "Site");
+
+        let mut mapping = Mapping::new("M", "Old", "New");
+        mapping.map_node("Site", "Site")
+               .map_node("Office", "Office")
+               .map_attr_direct("country", "country")
+               .map_attr_direct("code", "code")
+               .map_fk("located_in", crate::core::schema::Path::new("Office", vec!["site"]));
+
+        let backend = SqlBackend::new(PostgresDialect);
+        let sql = backend.generate_sigma(&mapping, &source, &target).iter()
+            .map(|s| s.to_string()).collect::<Vec<_>>().join("\n");
+        println!("=== Sigma FK composite ===\n{}", sql);
+        assert!(sql.contains("INSERT INTO \"Office\" (\"catrust_id\", \"site\")"), "{}", sql);
+        assert!(sql.contains(
+            "(SELECT k.\"catrust_id\" FROM \"Site\" k WHERE k.\"country\" = \"Office\".\"site_country\" AND k.\"code\" = \"Office\".\"site_code\")"
+        ), "{}", sql);
+        assert!(!sql.contains("\"located_in\""), "{}", sql);
+    }
+
+    #[test]
+    fn test_sigma_trino_ctas() {
+        let mut source = Schema::new("Old");
+        source.add_node("Person")
+              .add_attribute("person_name", "Person", BaseType::String);
+
+        let mut target = Schema::new("New");
+        target.add_node("Employee")
+              .add_attribute("emp_name", "Employee", BaseType::String);
+
+        let mut mapping = Mapping::new("M", "Old", "New");
+        mapping.map_node("Person", "Employee")
+               .map_attr_direct("person_name", "emp_name");
+
+        let backend = SqlBackend::new(TrinoDialect::new("iceberg", "default"));
+        let stmts = backend.generate_sigma(&mapping, &source, &target);
+
+        assert_eq!(stmts.len(), 1);
+        assert_eq!(
+            stmts[0].to_string(),
+            "CREATE TABLE iceberg.default.\"Employee\" AS\nSELECT \"catrust_id\", \"person_name\" AS \"emp_name\"\nFROM iceberg.default.\"Person\";"
+        );
+
+        // La cible suit la même politique d'identifiants que le FROM
+        let backend = SqlBackend::new(TrinoDialect::new("iceberg", "default"))
+            .with_identifier_policy(IdentifierPolicy::LowerUnquoted);
+        let sql = backend.generate_sigma(&mapping, &source, &target)[0].to_string();
+        assert!(sql.starts_with("CREATE TABLE employee AS") && sql.ends_with("FROM person;"), "{}", sql);
+    }
+
+    #[test]
+    fn test_trino_non_ctas_unqualified() {
+        // Seul le CTAS de Σ passe par full_table_name : DDL et INSERT
+        // gardent des noms de table non qualifiés
+        let schema = company_schema();
+        let trino = SqlBackend::new(TrinoDialect::new("iceberg", "default"));
+        let ddl: Vec<String> = trino.deploy_schema(&schema).iter().map(|s| s.to_string()).collect();
+        assert_eq!(ddl, vec![
+            "CREATE TABLE \"Department\" (\n  \"catrust_id\" BIGINT,\n  \"dept_name\" VARCHAR\n);",
+            "CREATE TABLE \"Employee\" (\n  \"catrust_id\" BIGINT,\n  \"emp_name\" VARCHAR,\n  \"salary\" BIGINT,\n  \"works_in\" BIGINT\n);",
+        ]);
+
+        let mut inst = Instance::new("Data", &schema);
+        inst.insert("Department", HashMap::from([("dept_name".into(), Value::String("R&D".into()))]), HashMap::new());
+        assert_eq!(
+            trino.export_instance(&schema, &inst)[0].to_string(),
+            "INSERT INTO \"Department\" (\"catrust_id\", \"dept_name\") VALUES (1, 'R&D');"
+        );
+        assert_eq!(
+            trino.export_instance_prepared(&schema, &inst)[0].0,
+            "INSERT INTO \"Department\" (\"catrust_id\", \"dept_name\") VALUES (?, ?);"
+        );
+    }
+
+    #[test]
+    fn test_prepared_insert() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        let d1 = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("R&D'); DROP TABLE x; --".into()))]),
+            HashMap::new(),
+        );
+        inst.insert("Employee",
+            HashMap::from([
+                ("emp_name".into(), Value::String("Alice".into())),
+                ("salary".into(), Value::Integer(80000)),
+            ]),
+            HashMap::from([("works_in".into(), d1)]),
+        );
+
+        let backend = SqlBackend::new(PostgresDialect);
+        let stmts = backend.export_instance_prepared(&schema, &inst);
+        let (sql, params) = stmts.iter().find(|(sql, _)| sql.contains("\"Employee\"")).unwrap();
+        assert_eq!(
+            sql,
+            "INSERT INTO \"Employee\" (\"catrust_id\", \"emp_name\", \"salary\", \"works_in\") VALUES ($1, $2, $3, $4);"
+        );
+        assert_eq!(params, &vec![
+            Value::Integer(1),
+            Value::String("Alice".into()),
+            Value::Integer(80000),
+            Value::Integer(d1 as i64),
+        ]);
+
+        // La valeur hostile reste un paramètre, jamais du SQL
+        let (sql, params) = stmts.iter().find(|(sql, _)| sql.contains("\"Department\"")).unwrap();
+        assert!(!sql.contains("DROP"));
+        assert_eq!(params[1], Value::String("R&D'); DROP TABLE x; --".into()));
+
+        // Department avant Employee, qui le référence
+        let tables: Vec<&str> = stmts.iter().map(|(sql, _)| sql.split(' ').nth(2).unwrap()).collect();
+        assert_eq!(tables, vec!["\"Department\"", "\"Employee\""]);
+
+        // Placeholders positionnels pour les autres dialectes
+        let trino = SqlBackend::new(TrinoDialect::new("iceberg", "default"));
+        let (sql, _) = &trino.export_instance_prepared(&schema, &inst)[0];
+        assert_eq!(sql, "INSERT INTO \"Department\" (\"catrust_id\", \"dept_name\") VALUES (?, ?);");
+    }
+
     #[test]
     fn test_trino_ddl() {
         let schema = company_schema();
@@ -368,6 +2347,26 @@ mod tests {
         println!("=== Trino DDL ===\n{}", sql);
     }
 
+    #[test]
+    fn test_dialect_capabilities() {
+        let trino = TrinoDialect::new("iceberg", "default");
+        assert!(!trino.supports_foreign_keys());
+        assert!(!trino.supports_autoincrement());
+        assert!(trino.supports_ctas());
+        assert!(PostgresDialect.supports_foreign_keys());
+        assert!(PostgresDialect.supports_autoincrement());
+        assert!(PostgresDialect.supports_upsert());
+        assert!(!PostgresDialect.supports_ctas());
+
+        // La FK works_in reste une colonne, sans clause REFERENCES
+        let schema = company_schema();
+        let trino_ddl = SqlBackend::new(trino).create_table_sql("Employee", &schema);
+        assert!(trino_ddl.contains("\"works_in\" BIGINT"), "{}", trino_ddl);
+        assert!(!trino_ddl.contains("REFERENCES"), "{}", trino_ddl);
+        let postgres_ddl = SqlBackend::new(PostgresDialect).create_table_sql("Employee", &schema);
+        assert!(postgres_ddl.contains("REFERENCES"), "{}", postgres_ddl);
+    }
+
     #[test]
     fn test_trino_insert() {
         let schema = company_schema();
@@ -31,14 +31,20 @@
 //
 // =============================================================================
 
+use std::collections::HashMap;
 use crate::core::schema::{Schema, Edge, Path};
-use crate::core::query::{CqlQuery, QueryBlock, WhereClause, AttributeBinding};
+use crate::core::query::{CqlQuery, QueryBlock, WhereClause, AttributeBinding, AttrExpr, JoinMode, Side};
 use crate::core::optimize::PathOptimizer;
-use crate::backend::sql::SqlDialect;
+use crate::core::instance::Instance;
+use crate::core::eval::eval_query_optimized;
+use crate::backend::sql::{IdentifierPolicy, SqlDialect, qualified_name, value_to_sql};
+use crate::backend::IdStrategy;
 
 /// Résultat de la planification SQL
 #[derive(Debug, Clone)]
 pub struct SqlPlan {
+    /// L'entité résultat du block planifié
+    pub target_entity: String,
     /// La requête SQL générée
     pub sql: String,
     /// Nombre de JOINs dans la requête
@@ -47,8 +53,16 @@ pub struct SqlPlan {
     pub joins_saved: usize,
     /// Explication de l'optimisation
     pub explanation: Vec<String>,
+    /// Estimation du travail : lignes produites par le FROM puis par chaque
+    /// JOIN, cumulées (d'après les cardinalités fournies au planner)
+    pub estimated_rows: usize,
+    /// Fan-out estimé de chaque JOIN : (alias, lignes produites par ligne d'entrée)
+    pub join_fanouts: Vec<(String, f64)>,
 }
 
+/// Cardinalité supposée d'une entité absente de `SqlPlanner::with_cardinalities`
+pub const DEFAULT_CARDINALITY: usize = 1000;
+
 impl std::fmt::Display for SqlPlan {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "{}", self.sql)?;
@@ -66,113 +80,491 @@ pub struct SqlPlanner<'a, D: SqlDialect> {
     dialect: &'a D,
     schema: &'a Schema,
     optimizer: PathOptimizer,
+    /// Colonne de clé technique utilisée dans les JOINs
+    id_column: String,
+    /// Schéma SQL qui qualifie les tables (voir `SqlBackend::target_schema`)
+    target_schema: Option<String>,
+    /// Nombre de lignes par entité, pour `SqlPlan::estimated_rows`
+    cardinalities: HashMap<String, usize>,
+    /// Part des lignes dont la FK est définie, pour `SqlPlan::join_fanouts`
+    fk_fill: HashMap<String, f64>,
+    /// Écriture des identifiants (voir `SqlBackend::identifier_policy`)
+    identifier_policy: IdentifierPolicy,
 }
 
 impl<'a, D: SqlDialect> SqlPlanner<'a, D> {
     pub fn new(dialect: &'a D, schema: &'a Schema) -> Self {
         let optimizer = PathOptimizer::from_schema(schema);
-        SqlPlanner { dialect, schema, optimizer }
+        SqlPlanner {
+            dialect,
+            schema,
+            optimizer,
+            id_column: IdStrategy::default().column,
+            target_schema: None,
+            cardinalities: HashMap::new(),
+            fk_fill: HashMap::new(),
+            identifier_policy: IdentifierPolicy::default(),
+        }
+    }
+
+    /// Utilise la même clé technique que le backend qui a créé les tables
+    pub fn with_id_strategy(mut self, id_strategy: &IdStrategy) -> Self {
+        self.id_column = id_strategy.column.clone();
+        self
+    }
+
+    /// Qualifie les tables par le même schéma SQL que le backend
+    pub fn with_target_schema(mut self, target_schema: &str) -> Self {
+        self.target_schema = Some(target_schema.to_string());
+        self
+    }
+
+    /// Écrit les identifiants comme le backend qui a créé les tables
+    pub fn with_identifier_policy(mut self, policy: IdentifierPolicy) -> Self {
+        self.identifier_policy = policy;
+        self
+    }
+
+    /// Cardinalités des entités (nombre de lignes), pour estimer le coût des plans
+    pub fn with_cardinalities(mut self, cardinalities: HashMap<String, usize>) -> Self {
+        self.cardinalities = cardinalities;
+        self
+    }
+
+    /// Statistiques lues dans une instance : cardinalités des entités et,
+    /// pour chaque FK, la part des lignes où elle est définie
+    pub fn with_statistics(mut self, instance: &Instance) -> Self {
+        self.cardinalities = instance.data.iter()
+            .map(|(entity, data)| (entity.clone(), data.len()))
+            .collect();
+        self.fk_fill = self.schema.edges.values()
+            .filter_map(|edge| match edge {
+                Edge::ForeignKey { name, source, .. } => {
+                    let data = instance.data.get(source)?;
+                    let rows = data.row_ids();
+                    let set = rows.iter().filter(|&&row| data.get_fk(row, name).is_some()).count();
+                    let fill = if rows.is_empty() { 1.0 } else { set as f64 / rows.len() as f64 };
+                    Some((name.clone(), fill))
+                }
+                Edge::Attribute { .. } => None,
+            })
+            .collect();
+        self
+    }
+
+    /// Nom d'une table, qualifié par `target_schema`
+    fn table_name(&self, entity: &str) -> String {
+        qualified_name(self.dialect, self.identifier_policy, self.target_schema.as_deref(), entity)
+    }
+
+    /// Identifiant (colonne, alias de résultat) selon `identifier_policy`
+    fn ident(&self, name: &str) -> String {
+        self.identifier_policy.render(self.dialect, name)
+    }
+
+    /// Planifie une requête CQL complète (tous les blocks). Erreur si un
+    /// block parcourt une FK à l'envers (`~fk`) : le planner n'a pas de
+    /// jointure inverse, seul l'évaluateur en mémoire sait l'éclater.
+    pub fn plan_query(&self, query: &CqlQuery) -> Result<Vec<SqlPlan>, String> {
+        query.blocks.iter().map(|block| self.plan_block(block, &[])).collect()
+    }
+
+    /// Planifie les blocks en UNE requête, chaque block devenant une
+    /// expression de table commune (CTE) nommée d'après son entité cible :
+    ///
+    /// ```sql
+    /// WITH "Result" AS (...), "Top" AS (SELECT ... FROM "Result" r ...)
+    /// SELECT * FROM "Top";
+    /// ```
+    ///
+    /// Un block peut ainsi lire le résultat d'un block PRÉCÉDENT en déclarant
+    /// une variable FROM sur son entité (`from r : Result`) ; ses chemins se
+    /// limitent alors aux colonnes projetées (`r.name`). Un tel block ne vient
+    /// pas du schéma source : il s'ajoute par `query.blocks.push`, que
+    /// `CqlQuery::add_block` refuserait. Comme Σ∘Δ, les
+    /// résultats intermédiaires alimentent les étapes suivantes sans être
+    /// matérialisés. Seul le résultat du DERNIER block est renvoyé par la
+    /// requête : les blocks précédents n'existent que comme CTE. Compteurs,
+    /// estimations et explications cumulent ceux de tous les blocks.
+    ///
+    /// Erreur si la requête n'a aucun block, si deux blocks ont la même
+    /// entité cible (la seconde CTE masquerait la première) ou si une entité
+    /// cible porte le nom d'une entité du schéma (la CTE masquerait la table).
+    pub fn plan_query_cte(&self, query: &CqlQuery) -> Result<SqlPlan, String> {
+        if query.blocks.is_empty() {
+            return Err(format!("Requête '{}' : aucun block à planifier", query.name));
+        }
+        let mut ctes: Vec<String> = Vec::new();
+        let mut parts: Vec<String> = Vec::new();
+        let mut plan = SqlPlan {
+            target_entity: String::new(),
+            sql: String::new(),
+            join_count: 0,
+            joins_saved: 0,
+            explanation: Vec::new(),
+            estimated_rows: 0,
+            join_fanouts: Vec::new(),
+        };
+        for block in &query.blocks {
+            if ctes.contains(&block.target_entity) {
+                return Err(format!(
+                    "Requête '{}' : deux blocks ont l'entité cible '{}'",
+                    query.name, block.target_entity
+                ));
+            }
+            if self.schema.nodes.contains_key(&block.target_entity) {
+                return Err(format!(
+                    "Requête '{}' : la CTE '{}' masquerait l'entité du schéma",
+                    query.name, block.target_entity
+                ));
+            }
+            let block_plan = self.plan_block(block, &ctes)?;
+            let body = block_plan.sql.trim_end_matches(';').replace('\n', "\n  ");
+            parts.push(format!("{} AS (\n  {}\n)", self.ident(&block.target_entity), body));
+            ctes.push(block.target_entity.clone());
+
+            plan.target_entity = block_plan.target_entity;
+            plan.join_count += block_plan.join_count;
+            plan.joins_saved += block_plan.joins_saved;
+            plan.explanation.extend(block_plan.explanation);
+            plan.estimated_rows += block_plan.estimated_rows;
+            plan.join_fanouts.extend(block_plan.join_fanouts);
+        }
+        plan.sql = format!("WITH {}\nSELECT * FROM {};", parts.join(", "), self.ident(&plan.target_entity));
+        Ok(plan)
     }
 
-    /// Planifie une requête CQL complète (tous les blocks)
-    pub fn plan_query(&self, query: &CqlQuery) -> Vec<SqlPlan> {
-        query.blocks.iter().map(|block| self.plan_block(block)).collect()
+    /// Table lue par une variable FROM : la CTE d'un block précédent
+    /// (`plan_query_cte`) ou la table de l'entité
+    fn source_table(&self, entity: &str, ctes: &[String]) -> String {
+        if ctes.iter().any(|cte| cte == entity) {
+            self.ident(entity)
+        } else {
+            self.table_name(entity)
+        }
     }
 
-    /// Planifie un seul block de requête
-    fn plan_block(&self, block: &QueryBlock) -> SqlPlan {
+    /// Planifie un seul block de requête ; `ctes` : entités des blocks
+    /// précédents, lisibles comme des tables
+    fn plan_block(&self, block: &QueryBlock, ctes: &[String]) -> Result<SqlPlan, String> {
+        reject_reverse_edges(block)?;
+        // `var.*` → un binding explicite par attribut
+        let block = &block.expand_select_all(self.schema);
         let mut joins: Vec<JoinClause> = Vec::new();
         let mut where_parts: Vec<String> = Vec::new();
         let mut select_parts: Vec<String> = Vec::new();
+        let mut group_parts: Vec<String> = Vec::new();
         let mut explanation: Vec<String> = Vec::new();
-        let mut alias_counter: usize = 0;
+        let mut aliases = JoinAliases::default();
         let mut joins_saved = 0;
 
-        // Table principale (première variable FROM)
-        let (main_var, main_entity) = block.from_vars.iter().next().unwrap();
+        // Variables FROM, par ordre alphabétique : la première est la table
+        // principale, les autres sont ajoutées en CROSS JOIN (les prédicats
+        // qui les relient viennent des clauses WHERE)
+        let mut from_vars: Vec<(&String, &String)> = block.from_vars.iter().collect();
+        from_vars.sort();
+        let (main_var, main_entity) = from_vars[0];
         let main_alias = main_var.clone();
 
-        // Collecter les SELECT
-        for (result_attr, binding) in &block.attribute_bindings {
-            let (alias, attr, extra_joins, saved) = self.resolve_attribute_binding(
-                binding, &main_alias, block, &mut alias_counter,
+        // Collecter les SELECT, dans l'ordre des noms : les alias jN sont
+        // numérotés à la première rencontre d'un chemin, l'ordre doit être stable
+        let mut bindings: Vec<_> = block.attribute_bindings.iter().collect();
+        bindings.sort_by_key(|(result_attr, _)| *result_attr);
+        for (result_attr, binding) in bindings {
+            let (column, extra_joins, saved) = self.attribute_binding_sql(
+                binding, &main_alias, block, &mut aliases,
             );
-            select_parts.push(format!("{}.{} AS {}",
-                alias,
-                self.dialect.quote_identifier(&attr),
-                self.dialect.quote_identifier(result_attr),
-            ));
+            select_parts.push(format!("{} AS {}", column, self.ident(result_attr)));
+            group_parts.push(column);
             joins.extend(extra_joins);
             joins_saved += saved;
         }
 
-        if select_parts.is_empty() {
+        if block.count_only {
+            select_parts = vec!["COUNT(*)".to_string()];
+        } else if select_parts.is_empty() {
             select_parts.push(format!("{}.*", main_alias));
         }
 
         // Collecter les WHERE
         for clause in &block.where_clauses {
-            match clause {
-                WhereClause::Comparison { var, path, op, value } => {
-                    let (resolved, extra_joins, saved) = self.resolve_path_to_sql(
-                        var, block, path, &mut alias_counter,
+            let (predicate, saved) = self.where_clause_sql(
+                clause, block, &mut aliases, &mut joins, &mut explanation,
+            );
+            where_parts.push(predicate);
+            joins_saved += saved;
+        }
+
+        // Collecter les HAVING (agrégats sur les groupes de la projection)
+        let mut having_parts: Vec<String> = Vec::new();
+        for clause in &block.having {
+            let argument = match &clause.argument {
+                Some(binding) => {
+                    let (argument, extra_joins, saved) = self.attribute_binding_sql(
+                        binding, &main_alias, block, &mut aliases,
                     );
-                    where_parts.push(format!("{} {} {}", resolved, op, value_to_sql_literal(value)));
                     joins.extend(extra_joins);
                     joins_saved += saved;
-
-                    if saved > 0 {
-                        explanation.push(format!(
-                            "Path equation raccourcit {}.{} → {} JOIN(s) éliminé(s)",
-                            var, path.join("."), saved
-                        ));
-                    }
+                    argument
                 }
-                WhereClause::PathEqual { var1, path1, var2, path2 } => {
-                    let (r1, j1, s1) = self.resolve_path_to_sql(var1, block, path1, &mut alias_counter);
-                    let (r2, j2, s2) = self.resolve_path_to_sql(var2, block, path2, &mut alias_counter);
-                    where_parts.push(format!("{} = {}", r1, r2));
-                    joins.extend(j1);
-                    joins.extend(j2);
-                    joins_saved += s1 + s2;
-                }
-            }
+                None => "*".to_string(),
+            };
+            having_parts.push(format!("{}({}) {} {}",
+                clause.func, argument, clause.op, value_to_sql(self.dialect, &clause.value),
+            ));
         }
 
-        // Dédupliquer les JOINs
-        joins.sort_by(|a, b| a.alias.cmp(&b.alias));
-        joins.dedup_by(|a, b| a.alias == b.alias);
+        // Dédupliquer les JOINs par chemin : deux chemins distincts gardent
+        // chacun leur JOIN, même s'ils empruntent la même FK. Un préfixe se
+        // trie avant ses prolongements, l'ordre des dépendances est préservé.
+        dedup_joins(&mut joins);
 
         // Assembler le SQL
-        let mut sql = format!("SELECT {}\nFROM {} {}",
+        let mut sql = format!("SELECT {}{}\nFROM {} {}",
+            if block.distinct { "DISTINCT " } else { "" },
             select_parts.join(", "),
-            self.dialect.quote_identifier(main_entity),
+            self.source_table(main_entity, ctes),
             main_alias,
         );
 
+        for (var, entity) in &from_vars[1..] {
+            sql.push_str(&format!("\nCROSS JOIN {} {}", self.source_table(entity, ctes), var));
+        }
+
         for join in &joins {
-            sql.push_str(&format!("\nJOIN {} {} ON {}.{} = {}.catrust_id",
-                self.dialect.quote_identifier(&join.table),
-                join.alias,
-                join.source_alias,
-                self.dialect.quote_identifier(&join.fk_column),
-                join.alias,
-            ));
+            sql.push('\n');
+            sql.push_str(&self.join_sql(join, block.join_mode));
         }
 
         if !where_parts.is_empty() {
             sql.push_str(&format!("\nWHERE {}", where_parts.join("\n  AND ")));
         }
 
+        if !having_parts.is_empty() {
+            if !group_parts.is_empty() {
+                sql.push_str(&format!("\nGROUP BY {}", group_parts.join(", ")));
+            }
+            sql.push_str(&format!("\nHAVING {}", having_parts.join("\n  AND ")));
+        }
+
+        if !block.order_by.is_empty() {
+            let keys: Vec<String> = block.order_by.iter()
+                .map(|k| format!("{}{}",
+                    self.ident(&k.attribute),
+                    if k.descending { " DESC" } else { "" },
+                ))
+                .collect();
+            sql.push_str(&format!("\nORDER BY {}", keys.join(", ")));
+        }
+
         sql.push(';');
 
         let join_count = joins.len();
+        let (estimated_rows, join_fanouts) = self.estimate(&from_vars, &joins, block.join_mode);
 
-        SqlPlan {
+        Ok(SqlPlan {
+            target_entity: block.target_entity.clone(),
             sql,
             join_count,
             joins_saved,
             explanation,
+            estimated_rows,
+            join_fanouts,
+        })
+    }
+
+    /// Estime les lignes produites par un plan : le produit des cardinalités
+    /// des variables FROM, puis la sortie de chaque JOIN. Un JOIN suit une FK
+    /// (N:1) : une ligne garde au plus une ligne cible. En INNER, seules
+    /// restent celles dont la FK est définie (fan-out = part des FK définies,
+    /// 0 si la table cible est vide) ; un LEFT JOIN garde toutes les lignes.
+    /// Chaque JOIN évité par l'optimisation retire une passe sur les lignes.
+    fn estimate(&self, from_vars: &[(&String, &String)], joins: &[JoinClause], mode: JoinMode) -> (usize, Vec<(String, f64)>) {
+        let cardinality = |entity: &str| *self.cardinalities.get(entity).unwrap_or(&DEFAULT_CARDINALITY);
+
+        let mut rows = from_vars.iter()
+            .map(|(_, entity)| cardinality(entity) as f64)
+            .product::<f64>();
+        let mut total = rows;
+        let mut fanouts = Vec::new();
+        for join in joins {
+            let fanout = if mode == JoinMode::Left || join.left {
+                1.0
+            } else if cardinality(&join.table) == 0 {
+                0.0
+            } else {
+                *self.fk_fill.get(&join.fk).unwrap_or(&1.0)
+            };
+            rows *= fanout;
+            total += rows;
+            fanouts.push((join.alias.clone(), fanout));
+        }
+        (total.round() as usize, fanouts)
+    }
+
+    /// Traduit une clause WHERE en prédicat SQL.
+    /// Les JOINs nécessaires sont ajoutés à `joins` ; retourne (prédicat, JOINs éliminés).
+    fn where_clause_sql(
+        &self,
+        clause: &WhereClause,
+        block: &QueryBlock,
+        aliases: &mut JoinAliases,
+        joins: &mut Vec<JoinClause>,
+        explanation: &mut Vec<String>,
+    ) -> (String, usize) {
+        match clause {
+            WhereClause::Comparison { var, path, op, value } => {
+                let (resolved, extra_joins, saved) = self.resolve_path_to_sql(
+                    var, block, path, aliases,
+                );
+                joins.extend(extra_joins);
+
+                if saved > 0 {
+                    explanation.push(format!(
+                        "Path equation raccourcit {}.{} → {} JOIN(s) éliminé(s)",
+                        var, path.join("."), saved
+                    ));
+                }
+                (format!("{} {} {}", resolved, op, value_to_sql(self.dialect, value)), saved)
+            }
+            WhereClause::FnComparison { func, var, path, op, value } => {
+                let (resolved, extra_joins, saved) = self.resolve_path_to_sql(
+                    var, block, path, aliases,
+                );
+                joins.extend(extra_joins);
+                (format!("{} {} {}",
+                    self.dialect.string_fn_sql(func, &resolved), op, value_to_sql(self.dialect, value),
+                ), saved)
+            }
+            WhereClause::Between { var, path, low, high } => {
+                let (resolved, extra_joins, saved) = self.resolve_path_to_sql(
+                    var, block, path, aliases,
+                );
+                joins.extend(extra_joins);
+
+                if saved > 0 {
+                    explanation.push(format!(
+                        "Path equation raccourcit {}.{} → {} JOIN(s) éliminé(s)",
+                        var, path.join("."), saved
+                    ));
+                }
+                (format!("{} BETWEEN {} AND {}",
+                    resolved, value_to_sql(self.dialect, low), value_to_sql(self.dialect, high),
+                ), saved)
+            }
+            WhereClause::IsNull { var, path } | WhereClause::IsNotNull { var, path } => {
+                let (resolved, extra_joins, saved) = self.resolve_path_to_sql(
+                    var, block, path, aliases,
+                );
+                // Comme l'évaluateur, un chemin interrompu par une FK non
+                // définie vaut NULL : la ligne doit survivre au JOIN
+                joins.extend(extra_joins.into_iter().map(|join| JoinClause { left: true, ..join }));
+                let test = if matches!(clause, WhereClause::IsNull { .. }) { "IS NULL" } else { "IS NOT NULL" };
+                (format!("{} {}", resolved, test), saved)
+            }
+            WhereClause::PathEqual { var1, path1, var2, path2 } => {
+                let (r1, j1, s1) = self.resolve_path_to_sql(var1, block, path1, aliases);
+                let (r2, j2, s2) = self.resolve_path_to_sql(var2, block, path2, aliases);
+                joins.extend(j1);
+                joins.extend(j2);
+                (format!("{} = {}", r1, r2), s1 + s2)
+            }
+            WhereClause::PathCompare { var1, path1, op, var2, path2 } => {
+                let (r1, j1, s1) = self.resolve_path_to_sql(var1, block, path1, aliases);
+                let (r2, j2, s2) = self.resolve_path_to_sql(var2, block, path2, aliases);
+                joins.extend(j1);
+                joins.extend(j2);
+                (format!("{} {} {}", r1, op, r2), s1 + s2)
+            }
+            WhereClause::Compare { lhs, op, rhs } => {
+                let mut saved = 0;
+                let mut operand = |side: &Side| match side {
+                    Side::Path { var, path } => {
+                        let (resolved, extra_joins, s) = self.resolve_path_to_sql(var, block, path, aliases);
+                        joins.extend(extra_joins);
+                        saved += s;
+                        resolved
+                    }
+                    Side::Literal(value) => value_to_sql(self.dialect, value),
+                    // Sous-requête scalaire, non corrélée
+                    Side::Aggregate(aggregate) => format!("(SELECT {}({}) FROM {})",
+                        aggregate.func,
+                        aggregate.attribute.as_deref().map(|a| self.ident(a)).unwrap_or_else(|| "*".to_string()),
+                        self.table_name(&aggregate.entity),
+                    ),
+                };
+                let lhs = operand(lhs);
+                let rhs = operand(rhs);
+                (format!("{} {} {}", lhs, op, rhs), saved)
+            }
+            WhereClause::AlwaysFalse => ("1 = 0".to_string(), 0),
+            WhereClause::Exists { inner } => {
+                // Sous-requête corrélée : les variables du block externe restent
+                // visibles (sauf si le block interne les redéclare)
+                let mut scope = (**inner).clone();
+                for (var, entity) in &block.from_vars {
+                    scope.from_vars.entry(var.clone()).or_insert_with(|| entity.clone());
+                }
+
+                let mut inner_vars: Vec<(&String, &String)> = inner.from_vars.iter().collect();
+                inner_vars.sort();
+
+                let mut inner_joins = Vec::new();
+                let mut inner_where = Vec::new();
+                let mut saved = 0;
+                for inner_clause in &inner.where_clauses {
+                    let (predicate, s) = self.where_clause_sql(
+                        inner_clause, &scope, aliases, &mut inner_joins, explanation,
+                    );
+                    inner_where.push(predicate);
+                    saved += s;
+                }
+                dedup_joins(&mut inner_joins);
+
+                let mut sub = String::from("EXISTS (SELECT 1");
+                for (k, (var, entity)) in inner_vars.iter().enumerate() {
+                    let keyword = if k == 0 { "FROM" } else { "CROSS JOIN" };
+                    sub.push_str(&format!(" {} {} {}", keyword, self.table_name(entity), var));
+                }
+                for join in &inner_joins {
+                    sub.push(' ');
+                    sub.push_str(&self.join_sql(join, inner.join_mode));
+                }
+                if !inner_where.is_empty() {
+                    sub.push_str(&format!(" WHERE {}", inner_where.join(" AND ")));
+                }
+                sub.push(')');
+                (sub, saved)
+            }
+        }
+    }
+
+    /// Rend un JOIN en SQL (LEFT JOIN en `JoinMode::Left`, ou si seul un
+    /// test IS [NOT] NULL l'utilise)
+    fn join_sql(&self, join: &JoinClause, mode: JoinMode) -> String {
+        let on: Vec<String> = join.on.iter()
+            .map(|(fk_column, key_column)| format!("{}.{} = {}.{}",
+                join.source_alias, fk_column, join.alias, key_column,
+            ))
+            .collect();
+        format!("{}JOIN {} {} ON {}",
+            if mode == JoinMode::Left || join.left { "LEFT " } else { "" },
+            self.table_name(&join.table),
+            join.alias,
+            on.join(" AND "),
+        )
+    }
+
+    /// Colonnes de jointure d'une FK : (colonne source, colonne cible).
+    /// FK simple : sa colonne contre la clé technique ; FK composite : chaque
+    /// colonne contre l'attribut de la clé métier qu'elle référence.
+    fn fk_join_columns(&self, fk_name: &str, columns: &[(String, String)]) -> Vec<(String, String)> {
+        if columns.is_empty() {
+            vec![(self.ident(fk_name), self.ident(&self.id_column))]
+        } else {
+            columns.iter()
+                .map(|(col, attr)| (self.ident(col), self.ident(attr)))
+                .collect()
         }
     }
 
@@ -184,34 +576,30 @@ impl<'a, D: SqlDialect> SqlPlanner<'a, D> {
         var: &str,
         block: &QueryBlock,
         path: &[String],
-        alias_counter: &mut usize,
+        aliases: &mut JoinAliases,
     ) -> (String, Vec<JoinClause>, usize) {
         let entity = block.from_vars.get(var).unwrap();
 
+        if path.is_empty() {
+            return (format!("{}.{}", var, self.ident(&self.id_column)), vec![], 0);
+        }
+
+        // Optimiser le chemin entier : une équation d'observation peut
+        // remplacer `fk.attr` par une colonne recopiée sur le nœud de départ
+        let optimized = self.optimizer.optimize(&Path {
+            start: entity.clone(),
+            edges: path.to_vec(),
+        }).optimized.edges;
+
         // Séparer le chemin en FK + attribut final
-        let (fk_path, final_attr) = if path.is_empty() {
-            return (format!("{}.catrust_id", var), vec![], 0);
-        } else {
-            // Le dernier élément est-il un attribut ou une FK ?
-            let last = path.last().unwrap();
-            if let Some(Edge::Attribute { .. }) = self.schema.edges.get(last) {
-                (&path[..path.len()-1], Some(last.as_str()))
-            } else {
-                (path, None)
-            }
+        let fk_len = |edges: &[String]| match edges.last().and_then(|last| self.schema.edges.get(last)) {
+            Some(Edge::Attribute { .. }) => edges.len() - 1,
+            _ => edges.len(),
         };
-
-        // Optimiser le chemin de FK
-        let original_len = fk_path.len();
-        let optimized_fk = if !fk_path.is_empty() {
-            let full = Path {
-                start: entity.clone(),
-                edges: fk_path.to_vec(),
-            };
-            let opt = self.optimizer.optimize(&full);
-            opt.optimized.edges
-        } else {
-            vec![]
+        let original_len = fk_len(path);
+        let (optimized_fk, final_attr) = match optimized.split_last() {
+            Some((last, rest)) if fk_len(&optimized) == rest.len() => (rest.to_vec(), Some(last.as_str())),
+            _ => (optimized.clone(), None),
         };
         let saved = if original_len > optimized_fk.len() {
             original_len - optimized_fk.len()
@@ -219,46 +607,116 @@ impl<'a, D: SqlDialect> SqlPlanner<'a, D> {
             0
         };
 
-        // Générer les JOINs pour le chemin optimisé
+        // Générer les JOINs pour le chemin optimisé.
+        // Si le chemin se termine par une FK simple, la dernière étape n'a pas
+        // besoin de JOIN : la colonne de FK contient déjà la clé de la ligne
+        // visée. Une FK composite ne stocke que la clé métier : on joint la
+        // cible pour lire sa clé technique.
         let mut joins = Vec::new();
         let mut current_alias = var.to_string();
+        let (joined_fks, last_fk) = match (final_attr, optimized_fk.split_last()) {
+            (None, Some((last, rest))) if !self.is_composite_fk(last) => (rest, Some(last)),
+            _ => (&optimized_fk[..], None),
+        };
 
-        for fk_name in &optimized_fk {
-            if let Some(Edge::ForeignKey { target, .. }) = self.schema.edges.get(fk_name) {
-                *alias_counter += 1;
-                let new_alias = format!("j{}", alias_counter);
+        let mut prefix = var.to_string();
+        for fk_name in joined_fks {
+            if let Some(Edge::ForeignKey { target, columns, .. }) = self.schema.edges.get(fk_name) {
+                prefix = format!("{}.{}", prefix, fk_name);
+                let new_alias = aliases.alias(&prefix);
                 joins.push(JoinClause {
                     table: target.clone(),
+                    fk: fk_name.clone(),
                     alias: new_alias.clone(),
                     source_alias: current_alias.clone(),
-                    fk_column: fk_name.clone(),
+                    on: self.fk_join_columns(fk_name, columns),
+                    prefix: prefix.clone(),
+                    left: false,
                 });
                 current_alias = new_alias;
             }
         }
 
         // Expression finale
-        let sql_expr = match final_attr {
-            Some(attr) => format!("{}.{}", current_alias, self.dialect.quote_identifier(attr)),
-            None => format!("{}.catrust_id", current_alias),
+        let sql_expr = match (final_attr, last_fk) {
+            (Some(attr), _) => format!("{}.{}", current_alias, self.ident(attr)),
+            (None, Some(fk)) => format!("{}.{}", current_alias, self.ident(fk)),
+            (None, None) => format!("{}.{}", current_alias, self.ident(&self.id_column)),
         };
 
         (sql_expr, joins, saved)
     }
 
+    /// `fk_name` est-elle une FK composite (stockée sur plusieurs colonnes) ?
+    fn is_composite_fk(&self, fk_name: &str) -> bool {
+        matches!(self.schema.edges.get(fk_name), Some(Edge::ForeignKey { columns, .. }) if !columns.is_empty())
+    }
+
+    /// Expression SQL d'un AttributeBinding : sa colonne, ou sa colonne
+    /// calculée. Retourne (expression, joins, joins_saved).
+    fn attribute_binding_sql(
+        &self,
+        binding: &AttributeBinding,
+        main_alias: &str,
+        block: &QueryBlock,
+        aliases: &mut JoinAliases,
+    ) -> (String, Vec<JoinClause>, usize) {
+        let (alias, attr, mut joins, mut saved) = self.resolve_attribute_binding(
+            binding, main_alias, block, aliases,
+        );
+        let column = format!("{}.{}", alias, self.ident(&attr));
+        match &binding.expr {
+            Some(expr) => {
+                let sql = self.attr_expr_sql(expr, &column, block, aliases, &mut joins, &mut saved);
+                (sql, joins, saved)
+            }
+            None => (column, joins, saved),
+        }
+    }
+
+    /// Rend une colonne calculée en SQL ; `bound` est la colonne du binding.
+    fn attr_expr_sql(
+        &self,
+        expr: &AttrExpr,
+        bound: &str,
+        block: &QueryBlock,
+        aliases: &mut JoinAliases,
+        joins: &mut Vec<JoinClause>,
+        saved: &mut usize,
+    ) -> String {
+        match expr {
+            AttrExpr::Bound => bound.to_string(),
+            AttrExpr::Path { var, path } => {
+                let (sql, extra_joins, s) = self.resolve_path_to_sql(var, block, path, aliases);
+                joins.extend(extra_joins);
+                *saved += s;
+                sql
+            }
+            AttrExpr::Const(value) => value_to_sql(self.dialect, value),
+            AttrExpr::Binary { op, lhs, rhs } => format!("({} {} {})",
+                self.attr_expr_sql(lhs, bound, block, aliases, joins, saved),
+                op,
+                self.attr_expr_sql(rhs, bound, block, aliases, joins, saved),
+            ),
+            AttrExpr::Func { func, arg } => {
+                let arg = self.attr_expr_sql(arg, bound, block, aliases, joins, saved);
+                self.dialect.string_fn_sql(func, &arg)
+            }
+        }
+    }
+
     /// Résout un AttributeBinding en (alias, attribut, joins, joins_saved)
     fn resolve_attribute_binding(
         &self,
         binding: &AttributeBinding,
         _main_alias: &str,
         block: &QueryBlock,
-        alias_counter: &mut usize,
+        aliases: &mut JoinAliases,
     ) -> (String, String, Vec<JoinClause>, usize) {
-        let mut full_path = binding.path.clone();
-        full_path.push(binding.attribute.clone());
+        let full_path = binding.full_path();
 
         let (sql_expr, joins, saved) = self.resolve_path_to_sql(
-            &binding.from_var, block, &full_path, alias_counter,
+            &binding.from_var, block, &full_path, aliases,
         );
 
         // Extraire alias et attribut de l'expression "alias.attr"
@@ -272,27 +730,190 @@ impl<'a, D: SqlDialect> SqlPlanner<'a, D> {
     }
 }
 
+/// Erreur si le block (ou l'un de ses EXISTS) parcourt une FK à l'envers
+fn reject_reverse_edges(block: &QueryBlock) -> Result<(), String> {
+    if block.has_reverse_edges() {
+        return Err(format!(
+            "Block '{}' : arête inverse (~fk) non traduite en SQL, à évaluer en mémoire",
+            block.target_entity
+        ));
+    }
+    block.where_clauses.iter()
+        .filter_map(|c| match c {
+            WhereClause::Exists { inner } => Some(inner),
+            _ => None,
+        })
+        .try_for_each(|inner| reject_reverse_edges(inner))
+}
+
+/// Recoupe un plan SQL avec l'évaluateur en mémoire, pour détecter une
+/// divergence entre les deux chemins d'exécution.
+///
+/// Vérifie que :
+/// - les JOINs éliminés par le planner correspondent au raccourcissement
+///   des chemins opéré par `CqlQuery::optimize` (utilisé par l'évaluateur) ;
+/// - les colonnes projetées par le SQL sont exactement les attributs du
+///   block, et que l'évaluation en mémoire ne produit pas d'autre attribut.
+pub fn verify_plan(
+    plan: &SqlPlan,
+    query: &CqlQuery,
+    inst: &Instance,
+    schema: &Schema,
+) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+
+    let index = match query.blocks.iter().position(|b| b.target_entity == plan.target_entity) {
+        Some(i) => i,
+        None => return Err(vec![format!("Aucun block '{}' dans la requête", plan.target_entity)]),
+    };
+    let block = &query.blocks[index];
+    reject_reverse_edges(block).map_err(|e| vec![e])?;
+    let optimized = &query.optimize(schema).blocks[index];
+
+    // 1. Économies de JOINs
+    let shortened = |before: &[String], after: &[String]| before.len().saturating_sub(after.len());
+    let mut expected_saved = 0;
+    for (clause, opt_clause) in block.where_clauses.iter().zip(&optimized.where_clauses) {
+        match (clause, opt_clause) {
+            (WhereClause::Comparison { path, .. }, WhereClause::Comparison { path: opt, .. })
+            | (WhereClause::FnComparison { path, .. }, WhereClause::FnComparison { path: opt, .. })
+            | (WhereClause::Between { path, .. }, WhereClause::Between { path: opt, .. })
+            | (WhereClause::IsNull { path, .. }, WhereClause::IsNull { path: opt, .. })
+            | (WhereClause::IsNotNull { path, .. }, WhereClause::IsNotNull { path: opt, .. }) => {
+                expected_saved += shortened(path, opt);
+            }
+            (
+                WhereClause::PathEqual { path1, path2, .. },
+                WhereClause::PathEqual { path1: opt1, path2: opt2, .. },
+            )
+            | (
+                WhereClause::PathCompare { path1, path2, .. },
+                WhereClause::PathCompare { path1: opt1, path2: opt2, .. },
+            ) => {
+                expected_saved += shortened(path1, opt1) + shortened(path2, opt2);
+            }
+            (
+                WhereClause::Compare { lhs, rhs, .. },
+                WhereClause::Compare { lhs: opt_lhs, rhs: opt_rhs, .. },
+            ) => {
+                for (side, opt) in [(lhs, opt_lhs), (rhs, opt_rhs)] {
+                    if let (Some((_, path)), Some((_, opt))) = (side.as_path(), opt.as_path()) {
+                        expected_saved += shortened(path, opt);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    for (name, binding) in &block.attribute_bindings {
+        let opt_binding = &optimized.attribute_bindings[name];
+        expected_saved += shortened(&binding.path, &opt_binding.path);
+        let leaves = binding.expr.iter().flat_map(|e| e.paths());
+        let opt_leaves = opt_binding.expr.iter().flat_map(|e| e.paths());
+        for ((_, path), (_, opt)) in leaves.zip(opt_leaves) {
+            expected_saved += shortened(path, opt);
+        }
+    }
+    if plan.joins_saved != expected_saved {
+        errors.push(format!(
+            "JOINs éliminés : le planner annonce {}, l'optimiseur de l'évaluateur {}",
+            plan.joins_saved, expected_saved
+        ));
+    }
+
+    // 2. Colonnes projetées
+    let select_list = plan.sql
+        .strip_prefix("SELECT ")
+        .and_then(|rest| rest.split("\nFROM").next())
+        .unwrap_or("");
+    let mut sql_columns: Vec<String> = select_list
+        .split(", ")
+        .filter_map(|item| item.rsplit_once(" AS "))
+        .map(|(_, alias)| alias.trim_matches(|c| c == '"' || c == '`').to_string())
+        .collect();
+    sql_columns.sort();
+    let mut expected_columns: Vec<String> = block.attribute_bindings.keys().cloned().collect();
+    expected_columns.sort();
+    if sql_columns != expected_columns {
+        errors.push(format!(
+            "Colonnes projetées : SQL {:?}, block {:?}",
+            sql_columns, expected_columns
+        ));
+    }
+
+    match eval_query_optimized(query, inst, schema) {
+        Ok(result) => {
+            if let Some(data) = result.instance.data.get(&block.target_entity) {
+                for row_id in data.row_ids() {
+                    for (attr, _) in data.row_attrs(row_id) {
+                        if !expected_columns.contains(attr) {
+                            errors.push(format!(
+                                "L'évaluateur produit l'attribut '{}' absent du SQL", attr
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        Err(e) => errors.push(format!("Évaluation en mémoire impossible : {}", e)),
+    }
+
+    errors.dedup();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
 /// Un JOIN à ajouter à la requête
 #[derive(Debug, Clone)]
 struct JoinClause {
     table: String,
+    /// FK suivie par le JOIN
+    fk: String,
     alias: String,
     source_alias: String,
-    fk_column: String,
+    /// Égalités du ON (déjà quotées) : (colonne source, colonne cible)
+    on: Vec<(String, String)>,
+    /// Chemin joint depuis la variable FROM (ex: "e.direct_mgr.manager")
+    prefix: String,
+    /// LEFT JOIN même en `JoinMode::Inner` : le JOIN ne sert qu'à des tests
+    /// IS [NOT] NULL, qui doivent voir les lignes sans cible
+    left: bool,
 }
 
-/// Convertit une Value en littéral SQL
-fn value_to_sql_literal(value: &crate::core::typeside::Value) -> String {
-    use crate::core::typeside::Value;
-    match value {
-        Value::String(s) => format!("'{}'", s.replace('\'', "''")),
-        Value::Integer(i) => format!("{}", i),
-        Value::Float(f) => format!("{}", f),
-        Value::Boolean(b) => if *b { "TRUE".into() } else { "FALSE".into() },
-        Value::Null => "NULL".into(),
+/// Trie les JOINs par chemin et n'en garde qu'un par préfixe. Un JOIN
+/// partagé reste interne dès qu'un de ses usages l'est.
+fn dedup_joins(joins: &mut Vec<JoinClause>) {
+    joins.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+    joins.dedup_by(|a, b| {
+        if a.prefix == b.prefix {
+            b.left &= a.left;
+            true
+        } else {
+            false
+        }
+    });
+}
+
+/// Alias des JOINs d'un block, un par préfixe de chemin : deux chemins qui
+/// commencent par `e.direct_mgr` partagent son JOIN, alors que `e.manager`
+/// et `e.direct_mgr.manager` ont chacun le leur. Les alias (`j1`, `j2`...)
+/// sont numérotés dans l'ordre où les préfixes apparaissent.
+#[derive(Default)]
+struct JoinAliases {
+    by_prefix: HashMap<String, String>,
+}
+
+impl JoinAliases {
+    fn alias(&mut self, prefix: &str) -> String {
+        let next = format!("j{}", self.by_prefix.len() + 1);
+        self.by_prefix.entry(prefix.to_string()).or_insert(next).clone()
     }
 }
 
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -300,8 +921,8 @@ fn value_to_sql_literal(value: &crate::core::typeside::Value) -> String {
 mod tests {
     use super::*;
     use crate::core::typeside::{BaseType, Value};
-    use crate::core::query::{CqlQuery, QueryBlock, WhereClause, CompOp, AttributeBinding};
-    use crate::backend::sql::PostgresDialect;
+    use crate::core::query::{CqlQuery, QueryBlock, WhereClause, CompOp, AttributeBinding, JoinMode, AggFunc, StringFn};
+    use crate::backend::sql::{PostgresDialect, SnowflakeDialect};
     use std::collections::HashMap;
 
     fn company_schema() -> Schema {
@@ -343,12 +964,19 @@ mod tests {
                     from_var: "e".into(),
                     path: vec![],
                     attribute: "emp_name".into(),
+                    expr: None,
                 }),
             ]),
             fk_bindings: HashMap::new(),
-        });
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
 
-        let plans = planner.plan_query(&query);
+        let plans = planner.plan_query(&query).unwrap();
         assert_eq!(plans.len(), 1);
         let plan = &plans[0];
 
@@ -382,17 +1010,25 @@ mod tests {
                     from_var: "e".into(),
                     path: vec![],
                     attribute: "emp_name".into(),
+                    expr: None,
                 }),
                 ("salary".into(), AttributeBinding {
                     from_var: "e".into(),
                     path: vec![],
                     attribute: "salary".into(),
+                    expr: None,
                 }),
             ]),
             fk_bindings: HashMap::new(),
-        });
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
 
-        let plans = planner.plan_query(&query);
+        let plans = planner.plan_query(&query).unwrap();
         let plan = &plans[0];
 
         println!("=== Requête SQL OPTIMISÉE ===\n{}", plan);
@@ -402,6 +1038,482 @@ mod tests {
         println!("JOINs : {}, JOINs éliminés : {}", plan.join_count, plan.joins_saved);
     }
 
+    #[test]
+    fn test_verify_plan_manager_shortcut() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        let d = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Eng".into()))]),
+            HashMap::new(),
+        );
+        let alice = inst.insert("Employee",
+            HashMap::from([
+                ("emp_name".into(), Value::String("Alice".into())),
+                ("salary".into(), Value::Integer(100)),
+            ]),
+            HashMap::from([("department".into(), d), ("direct_mgr".into(), 1)]),
+        );
+        inst.set_fk("Department", d, "manager", alice, &schema).unwrap();
+        inst.insert("Employee",
+            HashMap::from([
+                ("emp_name".into(), Value::String("Bob".into())),
+                ("salary".into(), Value::Integer(50)),
+            ]),
+            HashMap::from([("department".into(), d), ("direct_mgr".into(), alice)]),
+        );
+
+        let mut query = CqlQuery::new("ByManager", "Company");
+        query.add_block(QueryBlock {
+            target_entity: "Result".into(),
+            from_vars: HashMap::from([("e".into(), "Employee".into())]),
+            where_clauses: vec![
+                WhereClause::Comparison {
+                    var: "e".into(),
+                    path: vec!["department".into(), "manager".into(), "emp_name".into()],
+                    op: CompOp::Eq,
+                    value: Value::String("Alice".into()),
+                },
+            ],
+            attribute_bindings: HashMap::from([
+                ("name".into(), AttributeBinding {
+                    from_var: "e".into(),
+                    path: vec![],
+                    attribute: "emp_name".into(),
+                    expr: None,
+                }),
+                ("boss".into(), AttributeBinding {
+                    from_var: "e".into(),
+                    path: vec!["department".into(), "manager".into()],
+                    attribute: "emp_name".into(),
+                    expr: None,
+                }),
+            ]),
+            fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
+
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert_eq!(plan.joins_saved, 2);
+        assert_eq!(verify_plan(plan, &query, &inst, &schema), Ok(()));
+
+        // Un plan trafiqué est détecté
+        let mut drifted = plan.clone();
+        drifted.joins_saved = 0;
+        drifted.sql = drifted.sql.replace(" AS \"boss\"", " AS \"chef\"");
+        let errors = verify_plan(&drifted, &query, &inst, &schema).unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_multi_variable_from() {
+        let schema = company_schema();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        // Paires de collègues : e1.department = e2.department
+        let mut query = CqlQuery::new("Colleagues", "Company");
+        query.add_block(QueryBlock {
+            target_entity: "Pair".into(),
+            from_vars: HashMap::from([
+                ("e1".into(), "Employee".into()),
+                ("e2".into(), "Employee".into()),
+            ]),
+            where_clauses: vec![
+                WhereClause::PathEqual {
+                    var1: "e1".into(),
+                    path1: vec!["department".into()],
+                    var2: "e2".into(),
+                    path2: vec!["department".into()],
+                },
+            ],
+            attribute_bindings: HashMap::from([
+                ("left".into(), AttributeBinding {
+                    from_var: "e1".into(),
+                    path: vec![],
+                    attribute: "emp_name".into(),
+                    expr: None,
+                }),
+                ("right".into(), AttributeBinding {
+                    from_var: "e2".into(),
+                    path: vec![],
+                    attribute: "emp_name".into(),
+                    expr: None,
+                }),
+            ]),
+            fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
+
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        println!("=== Multi-variables ===\n{}", plan);
+        assert!(plan.sql.contains("FROM \"Employee\" e1\nCROSS JOIN \"Employee\" e2"));
+        assert!(plan.sql.contains("WHERE e1.\"department\" = e2.\"department\""));
+        assert!(plan.sql.contains("e1.\"emp_name\" AS \"left\""));
+        assert!(plan.sql.contains("e2.\"emp_name\" AS \"right\""));
+        assert_eq!(plan.join_count, 0);
+    }
+
+    #[test]
+    fn test_exists_subquery() {
+        let mut schema = company_schema();
+        schema.add_node("Manager")
+              .add_fk("manages", "Manager", "Department");
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        // Employés dont le département a au moins un manager
+        let mut query = CqlQuery::new("Managed", "Company");
+        query.add_block(QueryBlock {
+            target_entity: "Result".into(),
+            from_vars: HashMap::from([("e".into(), "Employee".into())]),
+            where_clauses: vec![
+                WhereClause::Exists {
+                    inner: Box::new(QueryBlock {
+                        target_entity: "_".into(),
+                        from_vars: HashMap::from([("m".into(), "Manager".into())]),
+                        where_clauses: vec![
+                            WhereClause::PathEqual {
+                                var1: "m".into(),
+                                path1: vec!["manages".into()],
+                                var2: "e".into(),
+                                path2: vec!["department".into()],
+                            },
+                        ],
+                        attribute_bindings: HashMap::new(),
+                        fk_bindings: HashMap::new(),
+                        distinct: false,
+                        join_mode: JoinMode::Inner,
+                        having: vec![],
+                        count_only: false,
+                        order_by: Vec::new(),
+                        select_all: Vec::new(),
+                    }),
+                },
+            ],
+            attribute_bindings: HashMap::from([
+                ("name".into(), AttributeBinding {
+                    from_var: "e".into(),
+                    path: vec![],
+                    attribute: "emp_name".into(),
+                    expr: None,
+                }),
+            ]),
+            fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
+
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        println!("=== EXISTS ===\n{}", plan);
+        assert!(plan.sql.contains(
+            "WHERE EXISTS (SELECT 1 FROM \"Manager\" m WHERE m.\"manages\" = e.\"department\")"
+        ));
+    }
+
+    #[test]
+    fn test_select_distinct() {
+        let schema = company_schema();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        let mut query = CqlQuery::new("Depts", "Company");
+        query.add_block(QueryBlock {
+            target_entity: "Depts".into(),
+            from_vars: HashMap::from([("e".into(), "Employee".into())]),
+            where_clauses: vec![],
+            attribute_bindings: HashMap::from([
+                ("dept".into(), AttributeBinding {
+                    from_var: "e".into(),
+                    path: vec!["department".into()],
+                    attribute: "dept_name".into(),
+                    expr: None,
+                }),
+            ]),
+            fk_bindings: HashMap::new(),
+            distinct: true,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
+
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(plan.sql.starts_with("SELECT DISTINCT j1.\"dept_name\" AS \"dept\""));
+    }
+
+    #[test]
+    fn test_having_group_by() {
+        let schema = company_schema();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        let mut query = CqlQuery::new("RichDepts", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .into_entity("RichDepts")
+                .from("e", "Employee")
+                .bind("dept", "e", &["department"], "dept_name")
+                .having(AggFunc::Sum, "e", &[], "salary", CompOp::Gt, Value::Integer(200000))
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(plan.sql.contains("GROUP BY j1.\"dept_name\""));
+        assert!(plan.sql.contains("HAVING SUM(e.\"salary\") > 200000"));
+    }
+
+    #[test]
+    fn test_estimated_rows_optimized_vs_unoptimized() {
+        // Même requête, avec et sans la path equation department.manager = direct_mgr
+        let schema = company_schema();
+        let mut plain = company_schema();
+        plain.path_equations.clear();
+        let cardinalities = HashMap::from([
+            ("Employee".to_string(), 10_000),
+            ("Department".to_string(), 50),
+        ]);
+
+        let mut query = CqlQuery::new("ByManager", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["department", "manager", "emp_name"], Value::String("Alice".into()))
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let optimized = &SqlPlanner::new(&PostgresDialect, &schema)
+            .with_cardinalities(cardinalities.clone())
+            .plan_query(&query).unwrap()[0];
+        let unoptimized = &SqlPlanner::new(&PostgresDialect, &plain)
+            .with_cardinalities(cardinalities)
+            .plan_query(&query).unwrap()[0];
+
+        // Scan + 1 JOIN contre scan + 2 JOINs, chacun de fan-out 1
+        assert_eq!(optimized.estimated_rows, 20_000);
+        assert_eq!(unoptimized.estimated_rows, 30_000);
+        assert_eq!(optimized.join_fanouts, vec![("j1".to_string(), 1.0)]);
+        assert_eq!(unoptimized.join_fanouts.len(), 2);
+
+        // Statistiques lues dans une instance : la moitié des employés n'a
+        // pas de département, le JOIN interne en écarte donc la moitié
+        let mut inst = Instance::new("Data", &schema);
+        let d = inst.insert("Department", crate::row! { dept_name: "R&D" }, HashMap::new());
+        for (i, name) in ["Alice", "Bob", "Carol", "Dan"].into_iter().enumerate() {
+            let fks = if i % 2 == 0 { HashMap::from([("department".to_string(), d)]) } else { HashMap::new() };
+            inst.insert("Employee", crate::row! { emp_name: name }, fks);
+        }
+        let mut by_dept = CqlQuery::new("ByDept", "Company");
+        by_dept.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["department", "dept_name"], Value::String("R&D".into()))
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema).with_statistics(&inst);
+        let plan = &planner.plan_query(&by_dept).unwrap()[0];
+        assert_eq!(plan.join_fanouts, vec![("j1".to_string(), 0.5)]);
+        assert_eq!(plan.estimated_rows, 4 + 2);
+
+        // En LEFT, le JOIN garde toutes les lignes
+        by_dept.blocks[0].join_mode = JoinMode::Left;
+        let plan = &planner.plan_query(&by_dept).unwrap()[0];
+        assert_eq!(plan.join_fanouts, vec![("j1".to_string(), 1.0)]);
+        assert_eq!(plan.estimated_rows, 4 + 4);
+    }
+
+    #[test]
+    fn test_between_sql() {
+        let schema = company_schema();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        let mut query = CqlQuery::new("MidSalaries", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_between("e", &["salary"], Value::Integer(70000), Value::Integer(90000))
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(plan.sql.contains("WHERE e.\"salary\" BETWEEN 70000 AND 90000"));
+    }
+
+    #[test]
+    fn test_literal_on_left_sql() {
+        let schema = company_schema();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        let mut query = CqlQuery::new("HighEarners", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_sides(Side::Literal(Value::Integer(80000)), CompOp::Lt, Side::path("e", &["salary"]))
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(plan.sql.contains("WHERE 80000 < e.\"salary\""), "{}", plan.sql);
+    }
+
+    #[test]
+    fn test_scalar_aggregate_sql() {
+        use crate::core::query::AggFunc;
+        let schema = company_schema();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        let mut query = CqlQuery::new("AboveAverage", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_sides(Side::path("e", &["salary"]), CompOp::Gt, Side::aggregate(AggFunc::Avg, "Employee", "salary"))
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(
+            plan.sql.contains("WHERE e.\"salary\" > (SELECT AVG(\"salary\") FROM \"Employee\")"),
+            "{}", plan.sql
+        );
+    }
+
+    #[test]
+    fn test_path_compare_sql() {
+        let schema = company_schema();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        let mut query = CqlQuery::new("OutEarners", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_path_cmp("e", &["salary"], CompOp::Gt, "e", &["direct_mgr", "salary"])
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(plan.sql.contains("JOIN \"Employee\" j1 ON e.\"direct_mgr\" = j1.\"catrust_id\""), "{}", plan.sql);
+        assert!(plan.sql.contains("WHERE e.\"salary\" > j1.\"salary\""), "{}", plan.sql);
+    }
+
+    #[test]
+    fn test_join_aliases_by_path_prefix() {
+        let schema = company_schema();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        // e.department et e.direct_mgr.department empruntent la même FK
+        // mais sont deux JOINs distincts ; e.direct_mgr est partagé
+        let mut query = CqlQuery::new("Managers", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["department", "dept_name"], Value::String("Engineering".into()))
+                .bind("boss_dept", "e", &["direct_mgr", "department"], "dept_name")
+                .bind("mgr", "e", &["direct_mgr"], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert_eq!(plan.sql.matches("JOIN ").count(), 3, "{}", plan.sql);
+        assert!(plan.sql.contains("JOIN \"Employee\" j1 ON e.\"direct_mgr\" = j1.\"catrust_id\""), "{}", plan.sql);
+        assert!(plan.sql.contains("JOIN \"Department\" j2 ON j1.\"department\" = j2.\"catrust_id\""), "{}", plan.sql);
+        assert!(plan.sql.contains("JOIN \"Department\" j3 ON e.\"department\" = j3.\"catrust_id\""), "{}", plan.sql);
+        assert!(plan.sql.contains("j2.\"dept_name\" AS \"boss_dept\""), "{}", plan.sql);
+        assert!(plan.sql.contains("j1.\"emp_name\" AS \"mgr\""), "{}", plan.sql);
+        assert!(plan.sql.contains("WHERE j3.\"dept_name\" = 'Engineering'"), "{}", plan.sql);
+    }
+
+    #[test]
+    fn test_observation_equation_reads_cached_column() {
+        let mut schema = company_schema();
+        schema.add_attribute("cached_dept_name", "Employee", BaseType::String)
+              .add_path_equation(
+                  Path::new("Employee", vec!["department", "dept_name"]),
+                  Path::new("Employee", vec!["cached_dept_name"]),
+              );
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        let mut query = CqlQuery::new("Engineers", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["department", "dept_name"], Value::String("Engineering".into()))
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(!plan.sql.contains("JOIN"), "{}", plan.sql);
+        assert!(plan.sql.contains("WHERE e.\"cached_dept_name\" = 'Engineering'"), "{}", plan.sql);
+        assert_eq!(plan.joins_saved, 1);
+    }
+
+    #[test]
+    fn test_string_fn_sql() {
+        let schema = company_schema();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        let mut query = CqlQuery::new("Alice", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_fn(StringFn::Lower, "e", &["emp_name"], CompOp::Eq, Value::String("alice".into()))
+                .bind_computed("initials", "e", &[], "emp_name", AttrExpr::func(
+                    StringFn::Substring { start: 1, length: Some(2) }, AttrExpr::Bound,
+                ))
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(plan.sql.contains("WHERE LOWER(e.\"emp_name\") = 'alice'"), "{}", plan.sql);
+        assert!(plan.sql.contains("SUBSTRING(e.\"emp_name\" FROM 1 FOR 2) AS \"initials\""), "{}", plan.sql);
+
+        // Snowflake n'a que SUBSTR
+        let plan = &SqlPlanner::new(&SnowflakeDialect, &schema).plan_query(&query).unwrap()[0];
+        assert!(plan.sql.contains("SUBSTR(e.\"emp_name\", 1, 2)"), "{}", plan.sql);
+
+        // Début 0 : SUBSTR le lirait comme 1, on garde la fenêtre standard
+        let sub = StringFn::Substring { start: 0, length: Some(3) };
+        assert_eq!(SnowflakeDialect.string_fn_sql(&sub, "x"), "SUBSTR(x, 1, 2)");
+        assert_eq!(PostgresDialect.string_fn_sql(&sub, "x"), "SUBSTRING(x FROM 0 FOR 3)");
+    }
+
     #[test]
     fn test_no_optimization_needed() {
         // Schéma SANS path equations
@@ -426,11 +1538,227 @@ mod tests {
             ],
             attribute_bindings: HashMap::new(),
             fk_bindings: HashMap::new(),
-        });
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
 
-        let plans = planner.plan_query(&query);
+        let plans = planner.plan_query(&query).unwrap();
         let plan = &plans[0];
         assert_eq!(plan.joins_saved, 0);
         println!("=== Sans optimisation ===\n{}", plan);
     }
+
+    #[test]
+    fn test_is_null_through_fk_left_join() {
+        // Eve n'a pas de département : pour l'évaluateur, e.department.dept_name
+        // est NULL. Le SQL doit la garder : LEFT JOIN, même en JoinMode::Inner
+        let schema = company_schema();
+        let mut inst = Instance::new("Data", &schema);
+        let d = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Eng".into()))]),
+            HashMap::new(),
+        );
+        inst.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String("Alice".into()))]),
+            HashMap::from([("department".into(), d)]),
+        );
+        inst.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String("Eve".into()))]),
+            HashMap::new(),
+        );
+
+        let mut query = CqlQuery::new("NoDept", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_null("e", &["department", "dept_name"])
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let result = eval_query_optimized(&query, &inst, &schema).unwrap();
+        let data = &result.instance.data["Result"];
+        let names: Vec<&Value> = data.row_ids().into_iter()
+            .map(|id| data.get_attr(id, "name").unwrap())
+            .collect();
+        assert_eq!(names, vec![&Value::String("Eve".into())]);
+
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        println!("{}", plan);
+        assert!(plan.sql.contains("LEFT JOIN \"Department\" j1 ON e.\"department\" = j1.\"catrust_id\""), "{}", plan.sql);
+        assert!(plan.sql.contains("WHERE j1.\"dept_name\" IS NULL"), "{}", plan.sql);
+        assert_eq!(verify_plan(plan, &query, &inst, &schema), Ok(()));
+
+        // Si la projection suit aussi la FK, le JOIN interne écarte Eve des
+        // deux côtés : l'évaluateur non plus ne la garde pas
+        let mut query = CqlQuery::new("Named", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_not_null("e", &["department", "dept_name"])
+                .bind("dept", "e", &["department"], "dept_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        assert_eq!(eval_query_optimized(&query, &inst, &schema).unwrap().rows_returned, 1);
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(plan.sql.contains("\nJOIN \"Department\" j1"), "{}", plan.sql);
+    }
+
+    #[test]
+    fn test_composite_fk_join() {
+        // Office.located_in référence Site par sa clé métier (country, code)
+        let mut schema = Schema::new("Offices");
+        schema.add_node("Site")
+              .add_node("Office")
+              .add_attribute("country", "Site", BaseType::String)
+              .add_attribute("code", "Site", BaseType::Integer)
+              .add_attribute("site_name", "Site", BaseType::String)
+              .add_attribute("label", "Office", BaseType::String)
+              .add_composite_fk("located_in", "Office", "Site",
+                  vec![("site_country", "country"), ("site_code", "code")]);
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        let mut query = CqlQuery::new("ParisOffices", "Offices");
+        query.add_block(
+            CqlQuery::select()
+                .into_entity("Result")
+                .from("o", "Office")
+                .where_eq("o", &["located_in", "site_name"], Value::String("Paris".into()))
+                .bind("label", "o", &[], "label")
+                .bind("site", "o", &["located_in"], "code")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        println!("{}", plan);
+        // Une égalité par colonne de la clé, jamais de colonne "located_in"
+        assert!(plan.sql.contains(
+            "JOIN \"Site\" j1 ON o.\"site_country\" = j1.\"country\" AND o.\"site_code\" = j1.\"code\""
+        ), "{}", plan.sql);
+        assert!(!plan.sql.contains("\"located_in\""), "{}", plan.sql);
+        assert_eq!(plan.join_count, 1);
+
+        // Un chemin qui finit sur la FK composite lit la clé technique de la cible
+        let mut query = CqlQuery::new("OfficeSites", "Offices");
+        query.add_block(
+            CqlQuery::select()
+                .into_entity("Result")
+                .from("o", "Office")
+                .from("s", "Site")
+                .where_path_eq("o", &["located_in"], "s", &[])
+                .bind("label", "o", &[], "label")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let plan = &planner.plan_query(&query).unwrap()[0];
+        assert!(plan.sql.contains("j1.\"catrust_id\" = s.\"catrust_id\""), "{}", plan.sql);
+    }
+
+    #[test]
+    fn test_plan_query_cte() {
+        let schema = company_schema();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        // Block 1 : les hauts salaires ; block 2 : ceux de l'ingénierie, lus
+        // depuis le résultat du block 1
+        let mut query = CqlQuery::new("EngHighEarners", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .into_entity("HighEarners")
+                .from("e", "Employee")
+                .where_cmp("e", &["salary"], CompOp::Gt, Value::Integer(70000))
+                .bind("name", "e", &[], "emp_name")
+                .bind("dept", "e", &["department"], "dept_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        // Ajouté tel quel : `HighEarners` n'est pas une entité du schéma
+        // source, add_block le refuserait
+        query.blocks.push(
+            CqlQuery::select()
+                .into_entity("Engineers")
+                .from("h", "HighEarners")
+                .where_eq("h", &["dept"], Value::String("Engineering".into()))
+                .bind("name", "h", &[], "name")
+                .build()
+                .unwrap(),
+        );
+
+        let plan = planner.plan_query_cte(&query).unwrap();
+        println!("{}", plan);
+        assert!(plan.sql.starts_with("WITH \"HighEarners\" AS (\n  SELECT "), "{}", plan.sql);
+        assert!(plan.sql.contains("), \"Engineers\" AS ("), "{}", plan.sql);
+        // Le block 2 lit la CTE du block 1, pas une table
+        assert!(plan.sql.contains("FROM \"HighEarners\" h"), "{}", plan.sql);
+        assert!(plan.sql.contains("h.\"dept\" = 'Engineering'"), "{}", plan.sql);
+        assert!(plan.sql.ends_with("SELECT * FROM \"Engineers\";"), "{}", plan.sql);
+        assert_eq!(plan.target_entity, "Engineers");
+        assert_eq!(plan.join_count, 1);
+
+        // Deux blocks "Result" : la seconde CTE masquerait la première
+        let mut duplicated = CqlQuery::new("Twice", "Company");
+        for _ in 0..2 {
+            duplicated.blocks.push(
+                CqlQuery::select().from("e", "Employee").bind("name", "e", &[], "emp_name").build().unwrap(),
+            );
+        }
+        let err = planner.plan_query_cte(&duplicated).unwrap_err();
+        assert!(err.contains("'Result'"), "{}", err);
+
+        // Une CTE "Employee" masquerait la table du schéma
+        let mut shadowing = CqlQuery::new("Shadow", "Company");
+        shadowing.blocks.push(
+            CqlQuery::select().into_entity("Employee").from("e", "Employee")
+                .bind("name", "e", &[], "emp_name").build().unwrap(),
+        );
+        let err = planner.plan_query_cte(&shadowing).unwrap_err();
+        assert!(err.contains("'Employee'"), "{}", err);
+
+        assert!(planner.plan_query_cte(&CqlQuery::new("Empty", "Company")).is_err());
+    }
+
+    #[test]
+    fn test_reverse_edge_rejected() {
+        let schema = company_schema();
+        let planner = SqlPlanner::new(&PostgresDialect, &schema);
+
+        // `d.~department` : les employés du département, que le SQL ne sait
+        // pas (encore) joindre à l'envers
+        let mut query = CqlQuery::new("Staff", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("d", "Department")
+                .bind("dept", "d", &[], "dept_name")
+                .bind("name", "d", &["~department"], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let err = planner.plan_query(&query).unwrap_err();
+        assert!(err.contains("~fk"), "{}", err);
+        assert!(planner.plan_query_cte(&query).is_err());
+
+        // verify_plan refuse de recouper un plan pour un tel block
+        let mut plain = CqlQuery::new("Depts", "Company");
+        plain.add_block(
+            CqlQuery::select().from("d", "Department").bind("dept", "d", &[], "dept_name").build().unwrap(),
+            &schema,
+        ).unwrap();
+        let plan = &planner.plan_query(&plain).unwrap()[0];
+        let inst = Instance::new("Data", &schema);
+        let errors = verify_plan(plan, &query, &inst, &schema).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("~fk"), "{:?}", errors);
+    }
 }
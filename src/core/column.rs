@@ -0,0 +1,313 @@
+// =============================================================================
+// COLUMN — Stockage en colonnes des lignes d'une entité
+// =============================================================================
+//
+// EntityData range les lignes par RowId :
+//   row_id → { attr → Value }        (une HashMap par ligne)
+//
+// ColumnStore range les mêmes données par colonne :
+//   ids     = [1, 2, 3, ...]
+//   salary  = [90000, 75000, 60000, ...]
+//   emp_name = ["Alice", "Bob", "Charlie", ...]
+//
+// Pour un filtre sur un attribut (e.salary > 80000), on parcourt un seul
+// Vec contigu au lieu de N HashMap : c'est le cas des scans analytiques.
+// C'est un des deux stockages d'EntityData, au choix : voir
+// `Instance::with_columns` pour l'activer sur une instance.
+//
+// =============================================================================
+
+use std::collections::HashMap;
+use super::instance::RowId;
+use super::typeside::Value;
+
+/// Lignes d'une entité rangées par colonne, derrière `EntityData`
+/// (cf. `EntityData::columnar`).
+///
+/// Les RowId sont croissants : une ligne se retrouve par dichotomie.
+/// Une cellule `None` est un attribut (ou une FK) absent de la ligne.
+#[derive(Debug, Clone)]
+pub struct ColumnStore {
+    next_id: RowId,
+    /// RowId de chaque position
+    ids: Vec<RowId>,
+    /// attribut → valeur à chaque position
+    columns: HashMap<String, Vec<Option<Value>>>,
+    /// FK → cible à chaque position
+    fk_columns: HashMap<String, Vec<Option<RowId>>>,
+}
+
+impl Default for ColumnStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ColumnStore {
+    pub fn new() -> Self {
+        ColumnStore {
+            next_id: 1,
+            ids: Vec::new(),
+            columns: HashMap::new(),
+            fk_columns: HashMap::new(),
+        }
+    }
+
+    /// Insère une nouvelle ligne ; retourne le RowId attribué.
+    pub fn insert(&mut self, attrs: HashMap<String, Value>, fks: HashMap<String, RowId>) -> RowId {
+        let id = self.next_id;
+        self.append(id, attrs, fks);
+        id
+    }
+
+    /// Insère une ligne avec un RowId donné, à sa place dans l'ordre des
+    /// RowId ; une ligne de même RowId est remplacée.
+    pub fn insert_with_id(&mut self, id: RowId, attrs: HashMap<String, Value>, fks: HashMap<String, RowId>) {
+        if id >= self.next_id {
+            self.next_id = id + 1;
+        }
+        let position = match self.ids.binary_search(&id) {
+            Ok(position) => {
+                for column in self.columns.values_mut() {
+                    column[position] = None;
+                }
+                for column in self.fk_columns.values_mut() {
+                    column[position] = None;
+                }
+                position
+            }
+            Err(position) => {
+                self.ids.insert(position, id);
+                for column in self.columns.values_mut() {
+                    column.insert(position, None);
+                }
+                for column in self.fk_columns.values_mut() {
+                    column.insert(position, None);
+                }
+                position
+            }
+        };
+        self.set_cells(position, attrs, fks);
+    }
+
+    /// Remplace les attributs et FK fournis d'une ligne existante, garde
+    /// les autres. Retourne `false` si la ligne n'existe pas.
+    pub fn update(&mut self, row_id: RowId, attrs: HashMap<String, Value>, fks: HashMap<String, RowId>) -> bool {
+        match self.position(row_id) {
+            Some(position) => {
+                self.set_cells(position, attrs, fks);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Supprime une ligne. Retourne `false` si elle n'existait pas.
+    pub fn remove(&mut self, row_id: RowId) -> bool {
+        let Some(position) = self.position(row_id) else { return false };
+        self.ids.remove(position);
+        for column in self.columns.values_mut() {
+            column.remove(position);
+        }
+        for column in self.fk_columns.values_mut() {
+            column.remove(position);
+        }
+        true
+    }
+
+    /// Ajoute une ligne en fin de colonnes (`id` plus grand que les autres)
+    fn append(&mut self, id: RowId, attrs: HashMap<String, Value>, fks: HashMap<String, RowId>) {
+        debug_assert!(self.ids.last().is_none_or(|&last| last < id), "RowId {} non croissant", id);
+        self.ids.push(id);
+        self.next_id = id + 1;
+        for column in self.columns.values_mut() {
+            column.push(None);
+        }
+        for column in self.fk_columns.values_mut() {
+            column.push(None);
+        }
+        self.set_cells(self.ids.len() - 1, attrs, fks);
+    }
+
+    /// Écrit les cellules d'une position ; une colonne nouvelle est créée
+    /// vide pour les autres lignes
+    fn set_cells(&mut self, position: usize, attrs: HashMap<String, Value>, fks: HashMap<String, RowId>) {
+        let rows = self.ids.len();
+        for (name, value) in attrs {
+            self.columns.entry(name).or_insert_with(|| vec![None; rows])[position] = Some(value);
+        }
+        for (name, target) in fks {
+            self.fk_columns.entry(name).or_insert_with(|| vec![None; rows])[position] = Some(target);
+        }
+    }
+
+    /// Prochain RowId attribué par `insert`
+    pub(crate) fn next_id(&self) -> RowId {
+        self.next_id
+    }
+
+    /// Force le prochain RowId attribué par `insert`
+    pub(crate) fn set_next_id(&mut self, next_id: RowId) {
+        self.next_id = next_id;
+    }
+
+    /// Nombre de lignes
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Aucune ligne ?
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Tous les RowId, croissants
+    pub fn row_ids(&self) -> Vec<RowId> {
+        self.ids.clone()
+    }
+
+    /// RowId de chaque position (même indexation que `column`)
+    pub fn ids(&self) -> &[RowId] {
+        &self.ids
+    }
+
+    /// Une colonne d'attribut entière (`None` si aucune ligne ne l'a)
+    pub fn column(&self, attr_name: &str) -> Option<&[Option<Value>]> {
+        self.columns.get(attr_name).map(Vec::as_slice)
+    }
+
+    fn position(&self, row_id: RowId) -> Option<usize> {
+        self.ids.binary_search(&row_id).ok()
+    }
+
+    /// Lit la valeur d'un attribut pour une ligne donnée
+    pub fn get_attr(&self, row_id: RowId, attr_name: &str) -> Option<&Value> {
+        let position = self.position(row_id)?;
+        self.columns.get(attr_name)?[position].as_ref()
+    }
+
+    /// Lit la cible d'une FK pour une ligne donnée
+    pub fn get_fk(&self, row_id: RowId, fk_name: &str) -> Option<RowId> {
+        let position = self.position(row_id)?;
+        self.fk_columns.get(fk_name)?[position]
+    }
+
+    /// La ligne existe-t-elle ?
+    pub fn contains(&self, row_id: RowId) -> bool {
+        self.position(row_id).is_some()
+    }
+
+    /// Attributs présents d'une ligne (vide si elle n'existe pas)
+    pub fn row_attrs(&self, row_id: RowId) -> Vec<(&String, &Value)> {
+        let Some(position) = self.position(row_id) else { return Vec::new() };
+        self.columns.iter()
+            .filter_map(|(name, column)| Some((name, column[position].as_ref()?)))
+            .collect()
+    }
+
+    /// FK définies d'une ligne (vide si elle n'existe pas)
+    pub fn row_fks(&self, row_id: RowId) -> Vec<(&String, RowId)> {
+        let Some(position) = self.position(row_id) else { return Vec::new() };
+        self.fk_columns.iter()
+            .filter_map(|(name, column)| Some((name, column[position]?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eval::eval_query;
+    use crate::core::instance::Instance;
+    use crate::core::query::{CompOp, CqlQuery};
+    use crate::core::schema::Schema;
+    use crate::core::typeside::BaseType;
+    use crate::{fks, row};
+
+    fn company() -> Schema {
+        let mut schema = Schema::new("Company");
+        schema.add_node("Department")
+              .add_node("Employee")
+              .add_attribute("dept_name", "Department", BaseType::String)
+              .add_attribute("emp_name", "Employee", BaseType::String)
+              .add_attribute("salary", "Employee", BaseType::Integer)
+              .add_fk("works_in", "Employee", "Department");
+        schema
+    }
+
+    /// Noms des employés d'Engineering payés plus de 70000, triés
+    fn well_paid(instance: &Instance, schema: &Schema) -> Vec<String> {
+        let mut query = CqlQuery::new("WellPaid", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_cmp("e", &["salary"], CompOp::Gt, Value::Integer(70000))
+                .where_eq("e", &["works_in", "dept_name"], Value::String("Engineering".into()))
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            schema,
+        ).unwrap();
+        let result = eval_query(&query, instance, schema).unwrap();
+        let data = &result.instance.data["Result"];
+        let mut names: Vec<String> = data.row_ids().iter()
+            .map(|&id| data.get_attr(id, "name").unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_columnar_matches_row_store() {
+        let schema = company();
+        let mut rows = Instance::new("Données", &schema);
+        let eng = rows.insert("Department", row! { dept_name: "Engineering" }, fks! {});
+        let mkt = rows.insert("Department", row! { dept_name: "Marketing" }, fks! {});
+        for (name, salary, dept) in [("Alice", 90000, eng), ("Bob", 75000, eng), ("Charlie", 60000, mkt), ("Diana", 85000, mkt)] {
+            rows.insert("Employee", row! { emp_name: name, salary: salary }, fks! { works_in: dept });
+        }
+        let columns = rows.clone().with_columns();
+
+        // Même API de lecture que le stockage par lignes, sans copie
+        let stored = &columns.data["Employee"];
+        let data = &rows.data["Employee"];
+        assert!(stored.column_store().is_some());
+        assert!(data.column_store().is_none());
+        let mut ids = data.row_ids();
+        ids.sort();
+        assert_eq!(stored.row_ids(), ids);
+        for &id in &ids {
+            assert_eq!(stored.get_attr(id, "salary"), data.get_attr(id, "salary"));
+            assert_eq!(stored.get_fk(id, "works_in"), data.get_fk(id, "works_in"));
+        }
+        assert_eq!(stored, data);
+
+        assert_eq!(well_paid(&columns, &schema), well_paid(&rows, &schema));
+        assert_eq!(well_paid(&columns, &schema), vec!["\"Alice\"", "\"Bob\""]);
+    }
+
+    #[test]
+    fn test_columnar_writes_are_visible() {
+        let schema = company();
+        let mut rows = Instance::new("Données", &schema);
+        let eng = rows.insert("Department", row! { dept_name: "Engineering" }, fks! {});
+        let mkt = rows.insert("Department", row! { dept_name: "Marketing" }, fks! {});
+        let alice = rows.insert("Employee", row! { emp_name: "Alice", salary: 90000 }, fks! { works_in: eng });
+        let bob = rows.insert("Employee", row! { emp_name: "Bob", salary: 75000 }, fks! { works_in: eng });
+        let charlie = rows.insert("Employee", row! { emp_name: "Charlie", salary: 60000 }, fks! { works_in: mkt });
+        let mut columns = rows.clone().with_columns();
+
+        // Les écritures vont dans les colonnes : aucune copie à rafraîchir
+        for instance in [&mut rows, &mut columns] {
+            instance.delete("Employee", bob).unwrap();
+            instance.set_attr("Employee", charlie, "salary", Value::Integer(95000), &schema).unwrap();
+            instance.set_fk("Employee", charlie, "works_in", eng, &schema).unwrap();
+            instance.set_attr("Employee", alice, "salary", Value::Integer(50000), &schema).unwrap();
+            instance.insert("Employee", row! { emp_name: "Eve", salary: 80000 }, fks! { works_in: eng });
+        }
+        assert!(columns.data["Employee"].column_store().is_some());
+        assert_eq!(columns.data["Employee"], rows.data["Employee"]);
+        assert_eq!(well_paid(&columns, &schema), well_paid(&rows, &schema));
+        assert_eq!(well_paid(&columns, &schema), vec!["\"Charlie\"", "\"Eve\""]);
+    }
+}
@@ -0,0 +1,174 @@
+// =============================================================================
+// ENTITY — Schémas déclarés par des structs Rust
+// =============================================================================
+//
+// Plutôt que d'appeler add_node / add_attribute / add_fk à la main, on
+// décrit chaque entité par une struct du domaine :
+//
+//   #[derive(CatrustEntity)]
+//   struct Department { dept_name: String }
+//
+//   #[derive(CatrustEntity)]
+//   struct Employee { emp_name: String, salary: i64, works_in: Fk<Department> }
+//
+//   let schema = Schema::from_entities::<(Employee, Department)>();
+//
+// Le derive (feature `derive`, crate catrust-derive) implémente
+// `CatrustEntity` ; les types des champs sont traduits en BaseType par
+// `AttributeType`, vérifié à la compilation.
+//
+// =============================================================================
+
+use std::marker::PhantomData;
+use super::instance::RowId;
+use super::schema::Schema;
+use super::typeside::BaseType;
+
+/// Une entité (nœud) du schéma, décrite par un type Rust.
+/// S'implémente avec `#[derive(CatrustEntity)]`.
+pub trait CatrustEntity {
+    /// Nom du nœud (le nom de la struct)
+    const NAME: &'static str;
+
+    /// Ajoute les attributs et FK de l'entité ; tous les nœuds visés
+    /// doivent déjà être dans `schema`.
+    fn register_edges(schema: &mut Schema);
+}
+
+/// Champ FK d'une entité : la ligne de `T` référencée.
+pub struct Fk<T> {
+    pub id: RowId,
+    target: PhantomData<T>,
+}
+
+impl<T> Fk<T> {
+    pub fn new(id: RowId) -> Self {
+        Fk { id, target: PhantomData }
+    }
+}
+
+// Pas de derive : il exigerait T: Clone, T: Debug...
+impl<T> Clone for Fk<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Fk<T> {}
+
+impl<T> PartialEq for Fk<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Fk<T> {}
+
+impl<T> std::fmt::Debug for Fk<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Fk({})", self.id)
+    }
+}
+
+/// Type Rust d'un champ attribut → type de base du schéma
+pub trait AttributeType {
+    fn base_type() -> BaseType;
+}
+
+impl AttributeType for String {
+    fn base_type() -> BaseType { BaseType::String }
+}
+
+impl AttributeType for i64 {
+    fn base_type() -> BaseType { BaseType::Integer }
+}
+
+impl AttributeType for i32 {
+    fn base_type() -> BaseType { BaseType::Integer }
+}
+
+impl AttributeType for f64 {
+    fn base_type() -> BaseType { BaseType::Float }
+}
+
+impl AttributeType for bool {
+    fn base_type() -> BaseType { BaseType::Boolean }
+}
+
+/// Un attribut optionnel a le type de sa valeur (l'absence est NULL)
+impl<T: AttributeType> AttributeType for Option<T> {
+    fn base_type() -> BaseType { T::base_type() }
+}
+
+impl<T: AttributeType> AttributeType for Vec<T> {
+    fn base_type() -> BaseType { BaseType::Array(Box::new(T::base_type())) }
+}
+
+/// Un ensemble d'entités, en tuple : `(Employee, Department)`.
+pub trait Entities {
+    /// Noms des nœuds, dans l'ordre du tuple
+    fn names() -> Vec<&'static str>;
+
+    /// Ajoute les arêtes de chaque entité
+    fn register_edges(schema: &mut Schema);
+}
+
+macro_rules! impl_entities {
+    ($($t:ident),+) => {
+        impl<$($t: CatrustEntity),+> Entities for ($($t,)+) {
+            fn names() -> Vec<&'static str> {
+                vec![$($t::NAME),+]
+            }
+
+            fn register_edges(schema: &mut Schema) {
+                $($t::register_edges(schema);)+
+            }
+        }
+    };
+}
+
+impl_entities!(A);
+impl_entities!(A, B);
+impl_entities!(A, B, C);
+impl_entities!(A, B, C, D);
+impl_entities!(A, B, C, D, E);
+impl_entities!(A, B, C, D, E, F);
+impl_entities!(A, B, C, D, E, F, G);
+impl_entities!(A, B, C, D, E, F, G, H);
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+    use crate::CatrustEntity;
+
+    #[derive(CatrustEntity)]
+    #[allow(dead_code)]
+    struct Department {
+        dept_name: String,
+    }
+
+    #[derive(CatrustEntity)]
+    #[allow(dead_code)]
+    struct Employee {
+        emp_name: String,
+        salary: i64,
+        works_in: Fk<Department>,
+    }
+
+    #[test]
+    fn test_schema_from_entities() {
+        let derived = Schema::from_entities::<(Employee, Department)>();
+
+        let mut built = Schema::new("Employee_Department");
+        built.add_node("Employee")
+             .add_node("Department")
+             .add_attribute("emp_name", "Employee", BaseType::String)
+             .add_attribute("salary", "Employee", BaseType::Integer)
+             .add_fk("works_in", "Employee", "Department")
+             .add_attribute("dept_name", "Department", BaseType::String);
+
+        assert_eq!(derived.name, built.name);
+        assert!(derived.diff(&built).is_empty());
+        assert_eq!(derived.node_names(), built.node_names());
+    }
+}
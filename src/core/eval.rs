@@ -35,11 +35,18 @@
 //
 // =============================================================================
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use super::schema::{Schema, Edge};
-use super::instance::{Instance, RowId, EntityData};
-use super::typeside::Value;
-use super::query::{CqlQuery, QueryBlock, WhereClause, CompOp, AttributeBinding, FkBinding};
+use super::instance::{next_version, Instance, IdPolicy, RowId, RowSnapshot, EntityData};
+use super::typeside::{OrdValue, Value};
+use crate::error::CatrustError;
+use super::query::{
+    CqlQuery, QueryBlock, WhereClause, CompOp, AttributeBinding, FkBinding, JoinMode,
+    HavingClause, AggFunc, AttrExpr, ArithOp, OrderKey, Side, ScalarAggregate, reverse_edge,
+};
 
 /// Résultat de l'évaluation d'une requête
 #[derive(Debug, Clone)]
@@ -52,27 +59,103 @@ pub struct EvalResult {
     pub rows_returned: usize,
     /// Temps d'évaluation (en microsecondes, si mesuré)
     pub eval_time_us: u128,
+    /// Détail par clause WHERE (vide sauf avec `eval_query_with(.., true)`)
+    pub trace: Vec<ClauseTrace>,
+    /// COUNT(*) d'un block `count_only` (le dernier s'il y en a plusieurs)
+    pub scalar: Option<Value>,
+    /// FK effectivement suivies pendant l'évaluation (WHERE, projections,
+    /// sous-requêtes EXISTS) : mesure le gain de `eval_query_optimized`
+    pub fk_hops: usize,
+}
+
+/// Erreur de résolution d'un chemin `var.fk1.fk2.attr` sur une instance.
+///
+/// Les variantes distinguent un schéma incohérent avec le chemin (arête
+/// inconnue, attribut au milieu du chemin) d'une donnée manquante (FK non
+/// définie, attribut absent de la ligne).
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathError {
+    /// Variable FROM absente de la ligne en cours
+    UnknownVariable { var: String },
+    /// Arête absente du schéma
+    UnknownEdge { edge: String },
+    /// Arête qui n'est pas une FK là où le chemin doit continuer
+    NotAnFk { edge: String },
+    /// FK non définie pour cette ligne (JOIN interne)
+    UnsetFk { fk: String, entity: String, row: RowId },
+    /// Attribut final absent de la ligne atteinte
+    MissingAttribute { attribute: String, entity: String, row: RowId },
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::UnknownVariable { var } => write!(f, "Variable FROM '{}' non trouvée", var),
+            PathError::UnknownEdge { edge } => write!(f, "Arête '{}' non trouvée dans le schéma", edge),
+            PathError::NotAnFk { edge } => write!(f, "'{}' est un attribut, pas une FK : il doit terminer le chemin", edge),
+            PathError::UnsetFk { fk, entity, row } => write!(f, "FK '{}' non définie pour {}[{}]", fk, entity, row),
+            PathError::MissingAttribute { attribute, entity, row } => {
+                write!(f, "Attribut '{}' non trouvé pour {}[{}]", attribute, entity, row)
+            }
+        }
+    }
+}
+
+impl From<PathError> for String {
+    fn from(e: PathError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Statistiques d'une clause WHERE, façon EXPLAIN ANALYZE.
+///
+/// Les clauses sont évaluées dans l'ordre et s'arrêtent au premier rejet :
+/// `evaluated` compte les tuples arrivés jusqu'à cette clause.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClauseTrace {
+    /// Entité résultat du block
+    pub block: String,
+    /// Position de la clause dans le block
+    pub clause_index: usize,
+    /// La clause, lisible (ex: "e.salary > 80000")
+    pub clause: String,
+    /// Tuples testés par cette clause
+    pub evaluated: usize,
+    /// Tuples rejetés par cette clause
+    pub rejected: usize,
+    /// FK suivies pour l'évaluer (hors sous-requêtes EXISTS)
+    pub fk_hops: usize,
+}
+
+impl std::fmt::Display for ClauseTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}#{} [{}] : {} testés, {} rejetés, {} FK suivies",
+               self.block, self.clause_index, self.clause,
+               self.evaluated, self.rejected, self.fk_hops)
+    }
 }
 
 impl std::fmt::Display for EvalResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Résultat : {} lignes retournées ({} scannées, {}µs)",
                  self.rows_returned, self.rows_scanned, self.eval_time_us)?;
+        if let Some(scalar) = &self.scalar {
+            writeln!(f, "  Valeur : {}", scalar)?;
+        }
+        for t in &self.trace {
+            writeln!(f, "  EXPLAIN {}", t)?;
+        }
         // Afficher les données
         for (entity, data) in &self.instance.data {
             if data.is_empty() { continue; }
             writeln!(f, "  {} ({} lignes) :", entity, data.len())?;
             for row_id in data.row_ids() {
                 write!(f, "    [{}]", row_id)?;
-                if let Some(attrs) = data.attribute_values.get(&row_id) {
-                    for (attr, val) in attrs {
-                        write!(f, " {}={},", attr, val)?;
-                    }
+                for (attr, val) in data.row_attrs(row_id) {
+                    write!(f, " {}={},", attr, val)?;
                 }
-                if let Some(fks) = data.fk_values.get(&row_id) {
-                    for (fk, target) in fks {
-                        write!(f, " {}→{},", fk, target)?;
-                    }
+                for (fk, target) in data.row_fks(row_id) {
+                    write!(f, " {}→{},", fk, target)?;
                 }
                 writeln!(f)?;
             }
@@ -96,18 +179,71 @@ pub fn eval_query(
     query: &CqlQuery,
     source: &Instance,
     schema: &Schema,
-) -> Result<EvalResult, String> {
+) -> Result<EvalResult, CatrustError> {
+    eval_query_with(query, source, schema, false)
+}
+
+/// Comme `eval_query`, avec `explain` : si vrai, `EvalResult::trace`
+/// détaille pour chaque clause WHERE les tuples rejetés et les FK suivies.
+pub fn eval_query_with(
+    query: &CqlQuery,
+    source: &Instance,
+    schema: &Schema,
+    explain: bool,
+) -> Result<EvalResult, CatrustError> {
+    eval_query_impl(query, source, schema, explain, None)
+}
+
+/// Comme `eval_query`, mais échoue (`CatrustError::TupleLimit`) dès qu'un
+/// block parcourt plus de `max_tuples` tuples : garde-fou contre les
+/// produits cartésiens de plusieurs FROM sur de grosses entités. Les tuples
+/// sont produits à la demande, jamais matérialisés en entier.
+pub fn eval_query_bounded(
+    query: &CqlQuery,
+    source: &Instance,
+    schema: &Schema,
+    max_tuples: usize,
+) -> Result<EvalResult, CatrustError> {
+    eval_query_impl(query, source, schema, false, Some(max_tuples))
+}
+
+fn eval_query_impl(
+    query: &CqlQuery,
+    source: &Instance,
+    schema: &Schema,
+    explain: bool,
+    max_tuples: Option<usize>,
+) -> Result<EvalResult, CatrustError> {
     let start = std::time::Instant::now();
+    // FK suivies par tous les blocks (cf. `EvalResult::fk_hops`)
+    let fk_hops = Cell::new(0);
     let mut result_instance = Instance {
         name: format!("{}_result", query.name),
         schema_name: query.result_schema.name.clone(),
         data: HashMap::new(),
+        journal: None,
+        id_policy: IdPolicy::Sequential,
+        version: next_version(),
     };
     let mut total_scanned = 0usize;
     let mut total_returned = 0usize;
+    let mut trace = Vec::new();
+    let mut scalar = None;
 
     for block in &query.blocks {
-        let (entity_data, scanned, returned) = eval_block(block, source, schema)?;
+        let block = &with_scalar_aggregates(block, source);
+        let mut block_trace = if explain { Some(Vec::new()) } else { None };
+        if block.count_only {
+            let (count, scanned) = count_block(block, source, schema, &mut block_trace, max_tuples, &fk_hops)?;
+            trace.extend(block_trace.unwrap_or_default());
+            result_instance.data.insert(block.target_entity.clone(), EntityData::new());
+            scalar = Some(Value::Integer(count as i64));
+            total_scanned += scanned;
+            total_returned += count;
+            continue;
+        }
+        let (entity_data, scanned, returned) = eval_block(block, source, schema, &mut block_trace, max_tuples, &fk_hops)?;
+        trace.extend(block_trace.unwrap_or_default());
         result_instance.data.insert(block.target_entity.clone(), entity_data);
         total_scanned += scanned;
         total_returned += returned;
@@ -120,122 +256,837 @@ pub fn eval_query(
         rows_scanned: total_scanned,
         rows_returned: total_returned,
         eval_time_us: elapsed,
+        trace,
+        scalar,
+        fk_hops: fk_hops.get(),
     })
 }
 
 /// Évalue un bloc de requête (un seul `entity ... { from ... where ... }`)
 ///
 /// Stratégie :
-///   1. Générer le produit cartésien de toutes les variables FROM
+///   1. Générer le produit cartésien de toutes les variables FROM, tuple
+///      par tuple (au plus `max_tuples`)
 ///   2. Pour chaque tuple, vérifier les clauses WHERE
 ///   3. Pour les tuples satisfaisants, projeter les attributs
+///   4. Si `having`, grouper par projection et filtrer les groupes
+///   5. Sinon, si `distinct`, écarter les lignes projetées déjà vues
 fn eval_block(
     block: &QueryBlock,
     source: &Instance,
     schema: &Schema,
-) -> Result<(EntityData, usize, usize), String> {
+    trace: &mut Option<Vec<ClauseTrace>>,
+    max_tuples: Option<usize>,
+    fk_hops: &Cell<usize>,
+) -> Result<(EntityData, usize, usize), CatrustError> {
     let mut result = EntityData::new();
-    let mut scanned = 0usize;
+    let block = &block.expand_select_all(schema);
 
-    // --- Étape 1 : collecter les RowId pour chaque variable FROM ---
-    let var_names: Vec<&String> = block.from_vars.keys().collect();
-    let var_entities: Vec<&String> = var_names.iter().map(|v| &block.from_vars[*v]).collect();
+    // Block insatisfiable (voir `CqlQuery::simplify`) : rien à parcourir
+    if block.where_clauses.iter().any(|c| matches!(c, WhereClause::AlwaysFalse)) {
+        return Ok((result, 0, 0));
+    }
 
-    // Collecter les RowId de chaque variable
-    let var_rows: Vec<Vec<RowId>> = var_entities.iter().map(|entity| {
-        source.data.get(*entity)
-            .map(|ed| ed.row_ids())
-            .unwrap_or_default()
-    }).collect();
+    // Arêtes inverses (`~fk`) → variables implicites
+    let (block, reverse_vars) = &expand_reverse_edges(block, schema)?;
+
+    // --- Étape 1 : collecter les RowId pour chaque variable FROM ---
+    let var_names: Vec<&String> = block.from_vars.keys()
+        .chain(reverse_vars.iter().map(|rv| &rv.name))
+        .collect();
+    let var_entities: Vec<&String> = block.from_vars.values()
+        .chain(reverse_vars.iter().map(|rv| &rv.entity))
+        .collect();
+    let vars = BlockVars { names: &var_names, entities: &var_entities };
+
+    // Collecter les RowId de chaque variable (filtrés sur les colonnes s'il
+    // y en a ; pas sous EXPLAIN, qui compte les rejets clause par clause)
+    let var_rows = scan_from_vars(block, source, schema, trace.is_none());
 
     // --- Étape 2 : produit cartésien des lignes ---
     // Pour un seul FROM (cas courant), c'est juste une itération simple.
-    // Pour N FROM, c'est le produit cartésien.
-    let tuples = cartesian_product(&var_rows);
+    // Pour N FROM, c'est le produit cartésien, éclaté ensuite par les
+    // variables implicites (une arête inverse mène à plusieurs lignes).
+    // Les tuples sont produits à la demande : seuls les tuples retenus
+    // occupent la mémoire.
+    let mut scanned = 0;
+    let tuples = fan_out_reverse(Box::new(cartesian_product(&var_rows).map(Ok)), &vars, reverse_vars, source, schema, fk_hops)?;
+    let tuples = limit_tuples(tuples, &block.target_entity, max_tuples, &mut scanned);
+
+    // --- Étapes 3 à 5 : filtrer et projeter chaque tuple ---
+    // (en parallèle avec la feature `rayon`, sauf si on trace : la trace
+    // est un état séquentiel)
+    let projected = match trace {
+        Some(trace) => project_tuples(block, &vars, tuples, source, schema, fk_hops, Some(trace))?,
+        None => {
+            #[cfg(feature = "rayon")]
+            { project_tuples_parallel(block, &vars, tuples, source, schema, fk_hops)? }
+            #[cfg(not(feature = "rayon"))]
+            { project_tuples(block, &vars, tuples, source, schema, fk_hops, None)? }
+        }
+    };
 
-    for tuple in &tuples {
-        scanned += 1;
+    // --- Étape 6 : HAVING (une ligne par groupe retenu) ---
+    let mut rows: Vec<(HashMap<String, Value>, HashMap<String, RowId>)> = if !block.having.is_empty() {
+        filter_groups(&block.having, projected)
+    } else {
+        // --- Étape 7 : DISTINCT (après projection, donc après filtrage) ---
+        // Lignes déjà projetées, décrites par attributs + FK triés
+        let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+        projected.into_iter()
+            .filter(|(attrs, fks, _)| !block.distinct || seen.insert(projected_row_key(attrs, fks)))
+            .map(|(attrs, fks, _)| (attrs, fks))
+            .collect()
+    };
 
-        // Construire le binding : var_name → (entity_name, row_id)
-        let binding: HashMap<&str, (&str, RowId)> = var_names.iter()
-            .enumerate()
-            .map(|(i, &vn)| (vn.as_str(), (var_entities[i].as_str(), tuple[i])))
-            .collect();
+    // --- Étape 8 : ORDER BY (les RowId résultat suivent l'ordre) ---
+    if !block.order_by.is_empty() {
+        rows.sort_by(|a, b| compare_order_keys(
+            &order_key(&block.order_by, &a.0, &a.1),
+            &order_key(&block.order_by, &b.0, &b.1),
+            &block.order_by,
+        ));
+    }
+    for (attrs, fks) in rows {
+        result.insert(attrs, fks);
+    }
 
-        // --- Étape 3 : vérifier les clauses WHERE ---
-        let passes = eval_where_clauses(&block.where_clauses, &binding, source, schema)?;
-        if !passes { continue; }
+    let returned = result.len();
+    Ok((result, scanned, returned))
+}
+
+/// Évalue un block `count_only` : compte les tuples qui passent le WHERE,
+/// sans projeter ni matérialiser de ligne. Retourne (compte, scannés).
+fn count_block(
+    block: &QueryBlock,
+    source: &Instance,
+    schema: &Schema,
+    trace: &mut Option<Vec<ClauseTrace>>,
+    max_tuples: Option<usize>,
+    fk_hops: &Cell<usize>,
+) -> Result<(usize, usize), CatrustError> {
+    let block = &block.expand_select_all(schema);
+    if !block.attribute_bindings.is_empty() || !block.fk_bindings.is_empty() {
+        return Err(format!(
+            "Block '{}' : count_only n'accepte pas de projection", block.target_entity
+        ).into());
+    }
+    if block.where_clauses.iter().any(|c| matches!(c, WhereClause::AlwaysFalse)) {
+        return Ok((0, 0));
+    }
 
-        // --- Étape 4 : projeter les attributs ---
-        let mut attrs = HashMap::new();
-        for (result_attr, ab) in &block.attribute_bindings {
-            let val = eval_attribute_binding(ab, &binding, source, schema)?;
-            attrs.insert(result_attr.clone(), val);
+    let (block, reverse_vars) = &expand_reverse_edges(block, schema)?;
+    let var_names: Vec<&String> = block.from_vars.keys()
+        .chain(reverse_vars.iter().map(|rv| &rv.name))
+        .collect();
+    let var_entities: Vec<&String> = block.from_vars.values()
+        .chain(reverse_vars.iter().map(|rv| &rv.entity))
+        .collect();
+    let vars = BlockVars { names: &var_names, entities: &var_entities };
+    let var_rows = scan_from_vars(block, source, schema, trace.is_none());
+    let mut scanned = 0;
+    let tuples = fan_out_reverse(Box::new(cartesian_product(&var_rows).map(Ok)), &vars, reverse_vars, source, schema, fk_hops)?;
+    let tuples = limit_tuples(tuples, &block.target_entity, max_tuples, &mut scanned);
+
+    let mut count = 0;
+    for tuple in tuples {
+        let tuple = tuple?;
+        let binding = tuple_binding(&vars, &tuple, fk_hops);
+        let passes = match trace {
+            None => eval_where_clauses(&block.where_clauses, block.join_mode, &binding, source, schema)?,
+            Some(trace) => eval_where_clauses_traced(block, &binding, source, schema, trace)?,
+        };
+        if passes {
+            count += 1;
         }
+    }
+    Ok((count, scanned))
+}
 
-        // --- Étape 5 : projeter les FK ---
-        let mut fks = HashMap::new();
-        for (result_fk, fb) in &block.fk_bindings {
-            let target_row = eval_fk_binding(fb, &binding, source, schema)?;
+/// Flux de tuples d'un block, produits un à un
+type TupleStream<'a> = Box<dyn Iterator<Item = Result<Vec<RowId>, CatrustError>> + 'a>;
+
+/// Compte dans `scanned` les tuples tirés du flux du block `entity` ; au-delà
+/// de `max_tuples`, le flux rend `CatrustError::TupleLimit`.
+fn limit_tuples<'a>(
+    tuples: TupleStream<'a>,
+    entity: &'a str,
+    max_tuples: Option<usize>,
+    scanned: &'a mut usize,
+) -> TupleStream<'a> {
+    Box::new(tuples.map(move |tuple| {
+        *scanned += 1;
+        match max_tuples {
+            Some(limit) if *scanned > limit => Err(CatrustError::TupleLimit { entity: entity.to_string(), limit }),
+            _ => tuple,
+        }
+    }))
+}
+
+/// RowId de chaque variable FROM, dans l'ordre de `from_vars`.
+///
+/// Si `use_columns` et que l'entité est stockée par colonnes
+/// (`Instance::with_columns`), les clauses WHERE de tête qui comparent un
+/// attribut direct d'une variable FROM sont évaluées sur les colonnes :
+/// seules les lignes qui les passent entrent dans le produit cartésien.
+/// Une ligne sans l'attribut est gardée, pour que l'évaluation par ligne
+/// signale l'erreur comme sans colonnes. `rows_scanned` compte les lignes
+/// après ce filtre.
+fn scan_from_vars(block: &QueryBlock, source: &Instance, schema: &Schema, use_columns: bool) -> Vec<Vec<RowId>> {
+    // Seules les clauses de tête : les suivantes ne sont évaluées (et ne
+    // peuvent échouer) qu'après celles qui les précèdent
+    let leading: Vec<(&WhereClause, &str, &str)> = block.where_clauses.iter()
+        .map_while(|clause| column_clause(clause, block, schema).map(|(var, attr)| (clause, var, attr)))
+        .collect();
+
+    block.from_vars.iter().map(|(var, entity)| {
+        let Some(entity_data) = source.data.get(entity) else { return Vec::new() };
+        let Some(store) = entity_data.column_store().filter(|_| use_columns) else {
+            return entity_data.row_ids();
+        };
+        let mut positions: Vec<usize> = (0..store.len()).collect();
+        for (clause, _, attr) in leading.iter().filter(|(_, v, _)| v == var) {
+            let Some(column) = store.column(attr) else { continue };
+            positions.retain(|&i| column[i].as_ref().is_none_or(|value| column_test(clause, value)));
+        }
+        positions.into_iter().map(|i| store.ids()[i]).collect()
+    }).collect()
+}
+
+/// (variable, attribut) si `clause` ne lit qu'un attribut direct d'une
+/// variable FROM : une comparaison ou un intervalle sur `var.attribut`.
+fn column_clause<'c>(clause: &'c WhereClause, block: &QueryBlock, schema: &Schema) -> Option<(&'c str, &'c str)> {
+    let (var, path) = match clause {
+        WhereClause::Comparison { var, path, .. } | WhereClause::Between { var, path, .. } => (var, path),
+        _ => return None,
+    };
+    let [attr] = path.as_slice() else { return None };
+    match schema.edges.get(attr) {
+        Some(Edge::Attribute { source, .. }) if block.from_vars.get(var) == Some(source) => {
+            Some((var.as_str(), attr.as_str()))
+        }
+        _ => None,
+    }
+}
+
+/// La valeur d'une cellule passe-t-elle une clause de `column_clause` ?
+fn column_test(clause: &WhereClause, value: &Value) -> bool {
+    match clause {
+        WhereClause::Comparison { op, value: constant, .. } => compare_values(value, op, constant),
+        WhereClause::Between { low, high, .. } => {
+            compare_values(value, &CompOp::Gte, low) && compare_values(value, &CompOp::Lte, high)
+        }
+        _ => true,
+    }
+}
+
+/// Variable implicite introduite par une arête inverse `var.path.~fk` :
+/// ses lignes sont celles de `entity` dont la FK `fk` pointe vers la ligne
+/// atteinte en suivant `path` (FK avant) depuis `base`.
+struct ReverseVar {
+    /// Nom de la variable, le préfixe du chemin (ex: "d.~works_in")
+    name: String,
+    /// Variable de départ (FROM ou implicite elle-même)
+    base: String,
+    /// FK avant suivies depuis `base` jusqu'à l'arête inverse
+    path: Vec<String>,
+    /// FK parcourue à l'envers
+    fk: String,
+    /// Source de `fk` : l'entité de la variable
+    entity: String,
+}
+
+/// Réécrit les chemins du block qui contiennent des arêtes inverses (`~fk`).
+///
+/// Chaque préfixe `var.….~fk` devient une variable implicite, partagée par
+/// tous les chemins de même préfixe (`d.~works_in.emp_name` et
+/// `d.~works_in.salary` lisent le même employé), et le chemin repart d'elle.
+/// Les sous-requêtes EXISTS ne sont pas réécrites. Sans arête inverse, le
+/// block est rendu tel quel, sans copie.
+fn expand_reverse_edges<'b>(
+    block: &'b QueryBlock,
+    schema: &Schema,
+) -> Result<(Cow<'b, QueryBlock>, Vec<ReverseVar>), CatrustError> {
+    let mut reverse_vars = Vec::new();
+    if !block.has_reverse_edges() {
+        return Ok((Cow::Borrowed(block), reverse_vars));
+    }
+    let mut block = block.clone();
+
+    for clause in &mut block.where_clauses {
+        match clause {
+            WhereClause::Comparison { var, path, .. }
+            | WhereClause::FnComparison { var, path, .. }
+            | WhereClause::Between { var, path, .. }
+            | WhereClause::IsNull { var, path }
+            | WhereClause::IsNotNull { var, path } => {
+                rewrite_reverse_path(var, path, &mut reverse_vars, schema)?;
+            }
+            WhereClause::PathEqual { var1, path1, var2, path2 }
+            | WhereClause::PathCompare { var1, path1, var2, path2, .. } => {
+                rewrite_reverse_path(var1, path1, &mut reverse_vars, schema)?;
+                rewrite_reverse_path(var2, path2, &mut reverse_vars, schema)?;
+            }
+            WhereClause::Compare { lhs, rhs, .. } => {
+                for (var, path) in [lhs, rhs].into_iter().filter_map(Side::as_path_mut) {
+                    rewrite_reverse_path(var, path, &mut reverse_vars, schema)?;
+                }
+            }
+            WhereClause::Exists { .. } | WhereClause::AlwaysFalse => {}
+        }
+    }
+    let bindings = block.attribute_bindings.values_mut()
+        .chain(block.having.iter_mut().filter_map(|h| h.argument.as_mut()));
+    for ab in bindings {
+        rewrite_reverse_path(&mut ab.from_var, &mut ab.path, &mut reverse_vars, schema)?;
+        for (var, path) in ab.expr.iter_mut().flat_map(|e| e.paths_mut()) {
+            rewrite_reverse_path(var, path, &mut reverse_vars, schema)?;
+        }
+    }
+    for fb in block.fk_bindings.values_mut() {
+        rewrite_reverse_path(&mut fb.from_var, &mut fb.path, &mut reverse_vars, schema)?;
+    }
+
+    Ok((Cow::Owned(block), reverse_vars))
+}
+
+/// Coupe `var.path` après chaque arête inverse : `var` devient la dernière
+/// variable implicite traversée (créée au besoin) et `path` le reste.
+fn rewrite_reverse_path(
+    var: &mut String,
+    path: &mut Vec<String>,
+    reverse_vars: &mut Vec<ReverseVar>,
+    schema: &Schema,
+) -> Result<(), CatrustError> {
+    while let Some(k) = path.iter().position(|e| reverse_edge(e).is_some()) {
+        let fk = reverse_edge(&path[k]).unwrap_or_default();
+        let entity = match schema.edges.get(fk) {
+            Some(Edge::ForeignKey { source, .. }) => source.clone(),
+            _ => return Err(format!("'{}' : '{}' n'est pas une FK", path[k], fk).into()),
+        };
+        let name = format!("{}.{}", var, path[..=k].join("."));
+        if !reverse_vars.iter().any(|rv| rv.name == name) {
+            reverse_vars.push(ReverseVar {
+                name: name.clone(),
+                base: var.clone(),
+                path: path[..k].to_vec(),
+                fk: fk.to_string(),
+                entity,
+            });
+        }
+        *var = name;
+        path.drain(..=k);
+    }
+    Ok(())
+}
+
+/// Éclate les tuples du produit cartésien selon les variables implicites,
+/// dans leur ordre de création (la base d'une variable précède la
+/// variable) : un tuple donne un tuple par ligne référençante, aucun s'il
+/// n'y en a pas.
+fn fan_out_reverse<'a>(
+    mut tuples: TupleStream<'a>,
+    vars: &'a BlockVars<'a>,
+    reverse_vars: &'a [ReverseVar],
+    source: &'a Instance,
+    schema: &'a Schema,
+    fk_hops: &'a Cell<usize>,
+) -> Result<TupleStream<'a>, CatrustError> {
+    for rv in reverse_vars {
+        let base = vars.names.iter().position(|v| **v == rv.base)
+            .ok_or_else(|| format!("Variable FROM '{}' non trouvée", rv.base))?;
+        let index = referencing_rows(source, &rv.entity, &rv.fk);
+        let base_entity = vars.entities[base];
+
+        tuples = Box::new(tuples.flat_map(move |tuple| -> Vec<Result<Vec<RowId>, CatrustError>> {
+            let tuple = match tuple {
+                Ok(tuple) => tuple,
+                Err(e) => return vec![Err(e)],
+            };
+            let target_row = match follow_fks(base_entity, tuple[base], &rv.path, JoinMode::Left, source, schema, fk_hops) {
+                Ok(Some((_, target_row))) => target_row,
+                Ok(None) => return vec![],
+                Err(e) => return vec![Err(e.into())],
+            };
+            fk_hops.set(fk_hops.get() + 1);
+            index.get(&target_row).into_iter().flatten()
+                .map(|&row| {
+                    let mut next = tuple.clone();
+                    next.push(row);
+                    Ok(next)
+                })
+                .collect()
+        }));
+    }
+    Ok(tuples)
+}
+
+/// Index inverse d'une FK : ligne cible → lignes de `entity` qui la
+/// référencent via `fk`, triées.
+fn referencing_rows(source: &Instance, entity: &str, fk: &str) -> HashMap<RowId, Vec<RowId>> {
+    let mut index: HashMap<RowId, Vec<RowId>> = HashMap::new();
+    if let Some(data) = source.data.get(entity) {
+        for row in data.row_ids() {
+            if let Some(target_row) = data.get_fk(row, fk) {
+                index.entry(target_row).or_default().push(row);
+            }
+        }
+    }
+    for rows in index.values_mut() {
+        rows.sort();
+    }
+    index
+}
+
+/// Lignes liées aux variables d'un tuple, et compteur des FK suivies pour
+/// l'évaluer : celui du block, cumulé dans `EvalResult::fk_hops`
+#[derive(Clone)]
+struct Binding<'a> {
+    /// var_name → (entity_name, row_id)
+    rows: HashMap<&'a str, (&'a str, RowId)>,
+    fk_hops: &'a Cell<usize>,
+}
+
+impl<'a> Binding<'a> {
+    fn get(&self, var: &str) -> Option<&(&'a str, RowId)> {
+        self.rows.get(var)
+    }
+}
+
+/// Binding d'un tuple : var_name → (entity_name, row_id)
+fn tuple_binding<'a>(vars: &BlockVars<'a>, tuple: &[RowId], fk_hops: &'a Cell<usize>) -> Binding<'a> {
+    let rows = vars.names.iter()
+        .enumerate()
+        .map(|(i, &vn)| (vn.as_str(), (vars.entities[i].as_str(), tuple[i])))
+        .collect();
+    Binding { rows, fk_hops }
+}
+
+/// Une ligne projetée : (attributs, FK, arguments des clauses HAVING)
+type ProjectedRow = (HashMap<String, Value>, HashMap<String, RowId>, Vec<Value>);
+
+/// Variables FROM d'un block, dans l'ordre des colonnes des tuples
+struct BlockVars<'a> {
+    names: &'a [&'a String],
+    entities: &'a [&'a String],
+}
+
+/// Filtre puis projette UN tuple. `None` si une clause WHERE le rejette.
+fn project_tuple(
+    block: &QueryBlock,
+    vars: &BlockVars,
+    tuple: &[RowId],
+    source: &Instance,
+    schema: &Schema,
+    fk_hops: &Cell<usize>,
+    trace: Option<&mut Vec<ClauseTrace>>,
+) -> Result<Option<ProjectedRow>, CatrustError> {
+    let binding = tuple_binding(vars, tuple, fk_hops);
+
+    // --- Étape 3 : vérifier les clauses WHERE ---
+    let passes = match trace {
+        None => eval_where_clauses(&block.where_clauses, block.join_mode, &binding, source, schema)?,
+        Some(trace) => eval_where_clauses_traced(block, &binding, source, schema, trace)?,
+    };
+    if !passes {
+        return Ok(None);
+    }
+
+    // --- Étape 4 : projeter les attributs ---
+    let mut attrs = HashMap::new();
+    for (result_attr, ab) in &block.attribute_bindings {
+        let val = eval_attribute_binding(ab, block.join_mode, &binding, source, schema)?;
+        attrs.insert(result_attr.clone(), val);
+    }
+
+    // --- Étape 5 : projeter les FK (une FK non résolue en LEFT reste non définie) ---
+    let mut fks = HashMap::new();
+    for (result_fk, fb) in &block.fk_bindings {
+        if let Some(target_row) = eval_fk_binding(fb, block.join_mode, &binding, source, schema)? {
             fks.insert(result_fk.clone(), target_row);
         }
+    }
 
-        result.insert(attrs, fks);
+    // --- Arguments des agrégats HAVING (NULL pour COUNT(*)) ---
+    let mut having_args = Vec::with_capacity(block.having.len());
+    for clause in &block.having {
+        having_args.push(match &clause.argument {
+            Some(ab) => eval_attribute_binding(ab, block.join_mode, &binding, source, schema)?,
+            None => Value::Null,
+        });
     }
 
-    let returned = result.len();
-    Ok((result, scanned, returned))
+    Ok(Some((attrs, fks, having_args)))
+}
+
+/// Groupe les lignes projetées par projection (ordre de première
+/// apparition) et ne garde que les groupes qui satisfont toutes les
+/// clauses HAVING.
+fn filter_groups(
+    having: &[HavingClause],
+    projected: Vec<ProjectedRow>,
+) -> Vec<(HashMap<String, Value>, HashMap<String, RowId>)> {
+    let mut groups: Vec<ProjectedRow> = Vec::new();
+    // Arguments de chaque groupe : un Vec par clause HAVING
+    let mut group_args: Vec<Vec<Vec<Value>>> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for (attrs, fks, args) in projected {
+        let key = projected_row_key(&attrs, &fks);
+        let i = *index.entry(key).or_insert_with(|| {
+            groups.push((attrs, fks, Vec::new()));
+            group_args.push(vec![Vec::new(); having.len()]);
+            groups.len() - 1
+        });
+        for (values, arg) in group_args[i].iter_mut().zip(args) {
+            values.push(arg);
+        }
+    }
+
+    groups.into_iter()
+        .zip(group_args)
+        .filter(|(_, args)| having.iter().zip(args).all(|(clause, values)| {
+            compare_values(&aggregate(clause.func, clause.argument.is_none(), values), &clause.op, &clause.value)
+        }))
+        .map(|((attrs, fks, _), _)| (attrs, fks))
+        .collect()
+}
+
+/// Calcule l'agrégat `func` sur les valeurs d'un groupe (`count_star` :
+/// COUNT(*)). Les NULL sont ignorés (sauf par COUNT(*)) ; un agrégat sans
+/// valeur est NULL.
+fn aggregate(func: AggFunc, count_star: bool, values: &[Value]) -> Value {
+    let present: Vec<&Value> = values.iter().filter(|v| **v != Value::Null).collect();
+    let numeric = |v: &&Value| match v {
+        Value::Integer(i) => Some(*i as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    };
+
+    match func {
+        AggFunc::Count if count_star => Value::Integer(values.len() as i64),
+        AggFunc::Count => Value::Integer(present.len() as i64),
+        _ if present.is_empty() => Value::Null,
+        AggFunc::Sum => {
+            if present.iter().all(|v| matches!(v, Value::Integer(_))) {
+                Value::Integer(present.iter().filter_map(|v| match v {
+                    Value::Integer(i) => Some(*i),
+                    _ => None,
+                }).sum())
+            } else {
+                Value::Float(present.iter().filter_map(numeric).sum())
+            }
+        }
+        AggFunc::Avg => {
+            Value::Float(present.iter().filter_map(numeric).sum::<f64>() / present.len() as f64)
+        }
+        AggFunc::Min => present.into_iter().min_by(|a, b| a.total_cmp(b)).cloned().unwrap_or(Value::Null),
+        AggFunc::Max => present.into_iter().max_by(|a, b| a.total_cmp(b)).cloned().unwrap_or(Value::Null),
+    }
+}
+
+/// Valeur d'une sous-requête scalaire : l'agrégat sur toutes les lignes
+/// de l'entité (attribut absent = NULL)
+fn scalar_aggregate(aggregate_of: &ScalarAggregate, source: &Instance) -> Value {
+    let values: Vec<Value> = source.data.get(&aggregate_of.entity)
+        .map(|data| data.row_ids().into_iter()
+            .map(|id| aggregate_of.attribute.as_ref()
+                .and_then(|attr| data.get_attr(id, attr))
+                .cloned()
+                .unwrap_or(Value::Null))
+            .collect())
+        .unwrap_or_default();
+    aggregate(aggregate_of.func, aggregate_of.attribute.is_none(), &values)
+}
+
+/// Le block où chaque sous-requête scalaire des clauses WHERE (EXISTS
+/// compris) est remplacée par sa valeur : l'agrégat est calculé une fois
+/// par évaluation, pas une fois par tuple.
+fn with_scalar_aggregates<'a>(block: &'a QueryBlock, source: &Instance) -> Cow<'a, QueryBlock> {
+    fn has_aggregate(clauses: &[WhereClause]) -> bool {
+        clauses.iter().any(|clause| match clause {
+            WhereClause::Compare { lhs, rhs, .. } => {
+                matches!(lhs, Side::Aggregate(_)) || matches!(rhs, Side::Aggregate(_))
+            }
+            WhereClause::Exists { inner } => has_aggregate(&inner.where_clauses),
+            _ => false,
+        })
+    }
+    fn resolve(clauses: &mut [WhereClause], source: &Instance) {
+        for clause in clauses {
+            match clause {
+                WhereClause::Compare { lhs, rhs, .. } => {
+                    for side in [lhs, rhs] {
+                        if let Side::Aggregate(aggregate_of) = side {
+                            *side = Side::Literal(scalar_aggregate(aggregate_of, source));
+                        }
+                    }
+                }
+                WhereClause::Exists { inner } => resolve(&mut inner.where_clauses, source),
+                _ => {}
+            }
+        }
+    }
+
+    if !has_aggregate(&block.where_clauses) {
+        return Cow::Borrowed(block);
+    }
+    let mut resolved = block.clone();
+    resolve(&mut resolved.where_clauses, source);
+    Cow::Owned(resolved)
+}
+
+/// Filtre et projette les tuples un par un, dans l'ordre.
+fn project_tuples(
+    block: &QueryBlock,
+    vars: &BlockVars,
+    tuples: TupleStream,
+    source: &Instance,
+    schema: &Schema,
+    fk_hops: &Cell<usize>,
+    mut trace: Option<&mut Vec<ClauseTrace>>,
+) -> Result<Vec<ProjectedRow>, CatrustError> {
+    let mut projected = Vec::new();
+    for tuple in tuples {
+        if let Some(row) = project_tuple(block, vars, &tuple?, source, schema, fk_hops, trace.as_deref_mut())? {
+            projected.push(row);
+        }
+    }
+    Ok(projected)
+}
+
+/// Tuples projetés ensemble par `project_tuples_parallel`
+#[cfg(feature = "rayon")]
+const PARALLEL_CHUNK: usize = 4096;
+
+/// Version parallèle de `project_tuples` (feature `rayon`).
+///
+/// Les tuples sont tirés du flux par paquets de `PARALLEL_CHUNK`. Chaque
+/// worker projette ses tuples indépendamment ; `collect` conserve l'ordre
+/// d'entrée, donc le résultat est identique à la version séquentielle.
+#[cfg(feature = "rayon")]
+fn project_tuples_parallel(
+    block: &QueryBlock,
+    vars: &BlockVars,
+    mut tuples: TupleStream,
+    source: &Instance,
+    schema: &Schema,
+    fk_hops: &Cell<usize>,
+) -> Result<Vec<ProjectedRow>, CatrustError> {
+    use rayon::prelude::*;
+
+    let mut projected = Vec::new();
+    loop {
+        let chunk = tuples.by_ref().take(PARALLEL_CHUNK).collect::<Result<Vec<_>, CatrustError>>()?;
+        if chunk.is_empty() {
+            return Ok(projected);
+        }
+
+        // Un compteur de FK par tuple (un `Cell` ne se partage pas entre
+        // workers), ajoutés ensuite à celui du block
+        let rows: Vec<(Option<ProjectedRow>, usize)> = chunk
+            .par_iter()
+            .map(|tuple| {
+                let hops = Cell::new(0);
+                let row = project_tuple(block, vars, tuple, source, schema, &hops, None);
+                row.map(|row| (row, hops.get()))
+            })
+            .collect::<Result<_, CatrustError>>()?;
+        fk_hops.set(fk_hops.get() + rows.iter().map(|(_, hops)| hops).sum::<usize>());
+        projected.extend(rows.into_iter().filter_map(|(row, _)| row));
+    }
+}
+
+/// Comme `eval_where_clauses`, mais clause par clause en alimentant `trace`
+/// (une entrée par clause, créée au premier appel).
+fn eval_where_clauses_traced(
+    block: &QueryBlock,
+    binding: &Binding,
+    source: &Instance,
+    schema: &Schema,
+    trace: &mut Vec<ClauseTrace>,
+) -> Result<bool, CatrustError> {
+    if trace.is_empty() {
+        for (i, clause) in block.where_clauses.iter().enumerate() {
+            trace.push(ClauseTrace {
+                block: block.target_entity.clone(),
+                clause_index: i,
+                clause: clause.to_string(),
+                evaluated: 0,
+                rejected: 0,
+                fk_hops: 0,
+            });
+        }
+    }
+
+    for (i, clause) in block.where_clauses.iter().enumerate() {
+        trace[i].evaluated += 1;
+        trace[i].fk_hops += clause_fk_hops(clause, schema);
+        if !eval_where_clauses(std::slice::from_ref(clause), block.join_mode, binding, source, schema)? {
+            trace[i].rejected += 1;
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Nombre de FK suivies pour évaluer une clause une fois.
+fn clause_fk_hops(clause: &WhereClause, schema: &Schema) -> usize {
+    let hops = |path: &[String]| path.iter()
+        .filter(|e| matches!(schema.edges.get(*e), Some(Edge::ForeignKey { .. })))
+        .count();
+    match clause {
+        WhereClause::Comparison { path, .. }
+        | WhereClause::FnComparison { path, .. }
+        | WhereClause::Between { path, .. }
+        | WhereClause::IsNull { path, .. }
+        | WhereClause::IsNotNull { path, .. } => hops(path),
+        WhereClause::PathEqual { path1, path2, .. }
+        | WhereClause::PathCompare { path1, path2, .. } => hops(path1) + hops(path2),
+        WhereClause::Compare { lhs, rhs, .. } => [lhs, rhs].into_iter()
+            .filter_map(Side::as_path)
+            .map(|(_, path)| hops(path))
+            .sum(),
+        WhereClause::Exists { .. } | WhereClause::AlwaysFalse => 0,
+    }
+}
+
+/// Clé d'une ligne projetée, indépendante de l'ordre des HashMap.
+fn projected_row_key(attrs: &HashMap<String, Value>, fks: &HashMap<String, RowId>) -> String {
+    let mut parts: Vec<String> = attrs.iter()
+        .map(|(k, v)| format!("{}={:?}", k, v))
+        .chain(fks.iter().map(|(k, v)| format!("{}->{}", k, v)))
+        .collect();
+    parts.sort();
+    parts.join(";")
 }
 
 /// Évalue toutes les clauses WHERE d'un binding. Retourne true si toutes passent.
 fn eval_where_clauses(
     clauses: &[WhereClause],
-    binding: &HashMap<&str, (&str, RowId)>,
+    join: JoinMode,
+    binding: &Binding,
     source: &Instance,
     schema: &Schema,
-) -> Result<bool, String> {
+) -> Result<bool, CatrustError> {
     for clause in clauses {
         match clause {
             WhereClause::Comparison { var, path, op, value } => {
-                let resolved = resolve_value(var, path, binding, source, schema)?;
+                let resolved = resolve_value(var, path, join, binding, source, schema)?;
+                if !compare_values(&resolved, op, value) {
+                    return Ok(false);
+                }
+            }
+            WhereClause::FnComparison { func, var, path, op, value } => {
+                let resolved = func.apply(&resolve_value(var, path, join, binding, source, schema)?)?;
                 if !compare_values(&resolved, op, value) {
                     return Ok(false);
                 }
             }
+            WhereClause::Between { var, path, low, high } => {
+                let resolved = resolve_value(var, path, join, binding, source, schema)?;
+                if !compare_values(&resolved, &CompOp::Gte, low) || !compare_values(&resolved, &CompOp::Lte, high) {
+                    return Ok(false);
+                }
+            }
+            WhereClause::IsNull { var, path } => {
+                if !is_null(var, path, binding, source, schema)? {
+                    return Ok(false);
+                }
+            }
+            WhereClause::IsNotNull { var, path } => {
+                if is_null(var, path, binding, source, schema)? {
+                    return Ok(false);
+                }
+            }
             WhereClause::PathEqual { var1, path1, var2, path2 } => {
-                let v1 = resolve_value(var1, path1, binding, source, schema)?;
-                let v2 = resolve_value(var2, path2, binding, source, schema)?;
-                if v1 != v2 {
+                let v1 = resolve_value(var1, path1, join, binding, source, schema)?;
+                let v2 = resolve_value(var2, path2, join, binding, source, schema)?;
+                // En LEFT, le NULL d'une FK manquante n'est égal à rien, pas
+                // même à NULL (sémantique SQL du LEFT JOIN) ; en INNER, deux
+                // attributs NULL restent égaux comme avant JoinMode
+                if (join == JoinMode::Left && v1 == Value::Null) || v1 != v2 {
+                    return Ok(false);
+                }
+            }
+            WhereClause::PathCompare { var1, path1, op, var2, path2 } => {
+                let v1 = resolve_value(var1, path1, join, binding, source, schema)?;
+                let v2 = resolve_value(var2, path2, join, binding, source, schema)?;
+                if !compare_values(&v1, op, &v2) {
+                    return Ok(false);
+                }
+            }
+            WhereClause::Compare { lhs, op, rhs } => {
+                let operand = |side: &Side| match side {
+                    Side::Path { var, path } => resolve_value(var, path, join, binding, source, schema),
+                    Side::Literal(value) => Ok(value.clone()),
+                    Side::Aggregate(aggregate_of) => Ok(scalar_aggregate(aggregate_of, source)),
+                };
+                if !compare_values(&operand(lhs)?, op, &operand(rhs)?) {
                     return Ok(false);
                 }
             }
+            WhereClause::Exists { inner } => {
+                if !eval_exists(inner, binding, source, schema)? {
+                    return Ok(false);
+                }
+            }
+            WhereClause::AlwaysFalse => return Ok(false),
         }
     }
     Ok(true)
 }
 
+/// Évalue une sous-requête EXISTS corrélée : cherche un tuple des variables
+/// internes qui, ajouté au binding externe, satisfait les clauses internes.
+fn eval_exists(
+    inner: &QueryBlock,
+    outer: &Binding,
+    source: &Instance,
+    schema: &Schema,
+) -> Result<bool, CatrustError> {
+    let var_names: Vec<&String> = inner.from_vars.keys().collect();
+    let var_rows: Vec<Vec<RowId>> = var_names.iter().map(|v| {
+        source.data.get(&inner.from_vars[*v])
+            .map(|ed| ed.row_ids())
+            .unwrap_or_default()
+    }).collect();
+
+    for tuple in cartesian_product(&var_rows) {
+        let mut binding = outer.clone();
+        for (i, vn) in var_names.iter().enumerate() {
+            binding.rows.insert(vn.as_str(), (inner.from_vars[*vn].as_str(), tuple[i]));
+        }
+        if eval_where_clauses(&inner.where_clauses, inner.join_mode, &binding, source, schema)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
 /// Résout un chemin (variable + arêtes) en une Value concrète.
 ///
 /// Le chemin est de la forme : [fk1, fk2, ..., attribut]
 /// On suit les FK puis on lit l'attribut final.
 ///
 /// C'est l'évaluation du foncteur Instance sur un morphisme composé.
+/// En `JoinMode::Left`, une FK non définie le long du chemin donne NULL.
+/// Le chemin vide (identité) projette le RowId de la ligne elle-même.
 fn resolve_value(
     var: &str,
     path: &[String],
-    binding: &HashMap<&str, (&str, RowId)>,
+    join: JoinMode,
+    binding: &Binding,
     source: &Instance,
     schema: &Schema,
-) -> Result<Value, String> {
+) -> Result<Value, PathError> {
     let (start_entity, start_row) = binding.get(var)
-        .ok_or_else(|| format!("Variable FROM '{}' non trouvée", var))?;
-
-    if path.is_empty() {
-        return Err(format!("Chemin vide pour la variable '{}'", var));
-    }
+        .ok_or_else(|| PathError::UnknownVariable { var: var.to_string() })?;
 
     // Séparer : les FK (tout sauf le dernier) et l'attribut (le dernier)
-    let last = &path[path.len() - 1];
+    let Some(last) = path.last() else {
+        return Ok(Value::Ref { entity: start_entity.to_string(), row: *start_row });
+    };
 
     // Déterminer si le dernier est un attribut ou une FK
     let is_last_attr = matches!(
@@ -246,171 +1097,236 @@ fn resolve_value(
     if is_last_attr {
         // Suivre les FK intermédiaires
         let fk_path = &path[..path.len() - 1];
-        let (current_entity, current_row) = follow_fks(
-            start_entity, *start_row, fk_path, source, schema
-        )?;
+        let Some((current_entity, current_row)) = follow_fks(
+            start_entity, *start_row, fk_path, join, source, schema, binding.fk_hops
+        )? else {
+            return Ok(Value::Null);
+        };
 
         // Lire l'attribut final
         source.data.get(&current_entity)
             .and_then(|ed| ed.get_attr(current_row, last))
             .cloned()
-            .ok_or_else(|| format!(
-                "Attribut '{}' non trouvé pour {}[{}]", last, current_entity, current_row
-            ))
+            .ok_or_else(|| PathError::MissingAttribute {
+                attribute: last.clone(),
+                entity: current_entity,
+                row: current_row,
+            })
     } else {
-        // Tout est FK — on résout le RowId final et on le retourne comme entier
-        // (utile pour les comparaisons de FK : e1.department = e2.department)
-        let (_entity, row) = follow_fks(start_entity, *start_row, path, source, schema)?;
-        Ok(Value::Integer(row as i64))
+        // Tout est FK — on résout la ligne finale et on la retourne comme
+        // référence (comparaisons de FK : e1.department = e2.department)
+        let resolved = follow_fks(start_entity, *start_row, path, join, source, schema, binding.fk_hops)?;
+        Ok(resolved.map_or(Value::Null, |(entity, row)| Value::Ref { entity, row }))
     }
 }
 
-/// Suit une séquence de FK et retourne (entité_finale, row_id_final)
+/// Vrai si le chemin mène à NULL : FK non définie en cours de route (quel
+/// que soit le mode de jointure du block), attribut absent ou NULL.
+fn is_null(
+    var: &str,
+    path: &[String],
+    binding: &Binding,
+    source: &Instance,
+    schema: &Schema,
+) -> Result<bool, CatrustError> {
+    let (start_entity, start_row) = binding.get(var)
+        .ok_or_else(|| format!("Variable FROM '{}' non trouvée", var))?;
+
+    let (fk_path, attribute) = match path.split_last() {
+        Some((last, fks)) if matches!(schema.edges.get(last), Some(Edge::Attribute { .. })) => (fks, Some(last)),
+        _ => (path, None),
+    };
+
+    let Some((entity, row)) = follow_fks(start_entity, *start_row, fk_path, JoinMode::Left, source, schema, binding.fk_hops)? else {
+        return Ok(true);
+    };
+    Ok(match attribute {
+        Some(attr) => matches!(
+            source.data.get(&entity).and_then(|ed| ed.get_attr(row, attr)),
+            None | Some(Value::Null)
+        ),
+        None => false,
+    })
+}
+
+/// Suit une séquence de FK et retourne (entité_finale, row_id_final).
+///
+/// Une FK non définie est une erreur en `JoinMode::Inner` et donne `None`
+/// en `JoinMode::Left`.
 fn follow_fks(
     start_entity: &str,
     start_row: RowId,
     fk_path: &[String],
+    join: JoinMode,
     source: &Instance,
     schema: &Schema,
-) -> Result<(String, RowId), String> {
+    fk_hops: &Cell<usize>,
+) -> Result<Option<(String, RowId)>, PathError> {
     let mut entity = start_entity.to_string();
     let mut row = start_row;
 
     for fk_name in fk_path {
         let edge = schema.edges.get(fk_name)
-            .ok_or_else(|| format!("FK '{}' non trouvée dans le schéma", fk_name))?;
+            .ok_or_else(|| PathError::UnknownEdge { edge: fk_name.clone() })?;
 
         match edge {
             Edge::ForeignKey { target, .. } => {
-                row = source.data.get(&entity)
-                    .and_then(|ed| ed.get_fk(row, fk_name))
-                    .ok_or_else(|| format!(
-                        "FK '{}' non définie pour {}[{}]", fk_name, entity, row
-                    ))?;
+                fk_hops.set(fk_hops.get() + 1);
+                match source.data.get(&entity).and_then(|ed| ed.get_fk(row, fk_name)) {
+                    Some(next) => row = next,
+                    None if join == JoinMode::Left => return Ok(None),
+                    None => return Err(PathError::UnsetFk { fk: fk_name.clone(), entity, row }),
+                }
                 entity = target.clone();
             }
-            _ => return Err(format!("'{}' n'est pas une FK", fk_name)),
+            Edge::Attribute { .. } => return Err(PathError::NotAnFk { edge: fk_name.clone() }),
         }
     }
 
-    Ok((entity, row))
+    Ok(Some((entity, row)))
 }
 
 /// Évalue un AttributeBinding → Value
 fn eval_attribute_binding(
     ab: &AttributeBinding,
-    binding: &HashMap<&str, (&str, RowId)>,
+    join: JoinMode,
+    binding: &Binding,
     source: &Instance,
     schema: &Schema,
-) -> Result<Value, String> {
-    let mut full_path = ab.path.clone();
-    full_path.push(ab.attribute.clone());
-    resolve_value(&ab.from_var, &full_path, binding, source, schema)
+) -> Result<Value, CatrustError> {
+    let value = projected(resolve_value(&ab.from_var, &ab.full_path(), join, binding, source, schema)?);
+    match &ab.expr {
+        Some(expr) => eval_attr_expr(expr, &value, join, binding, source, schema),
+        None => Ok(value),
+    }
 }
 
-/// Évalue un FkBinding → RowId cible
+/// Valeur projetée : une référence devient le catrust_id visé (attribut Int)
+fn projected(value: Value) -> Value {
+    match value {
+        Value::Ref { row, .. } => Value::Integer(row as i64),
+        value => value,
+    }
+}
+
+/// Évalue une colonne calculée ; `bound` est la valeur lue par le binding.
+fn eval_attr_expr(
+    expr: &AttrExpr,
+    bound: &Value,
+    join: JoinMode,
+    binding: &Binding,
+    source: &Instance,
+    schema: &Schema,
+) -> Result<Value, CatrustError> {
+    match expr {
+        AttrExpr::Bound => Ok(bound.clone()),
+        AttrExpr::Path { var, path } => Ok(projected(resolve_value(var, path, join, binding, source, schema)?)),
+        AttrExpr::Const(value) => Ok(value.clone()),
+        AttrExpr::Binary { op, lhs, rhs } => {
+            let lhs = eval_attr_expr(lhs, bound, join, binding, source, schema)?;
+            let rhs = eval_attr_expr(rhs, bound, join, binding, source, schema)?;
+            let value = match op {
+                ArithOp::Add => lhs.add(&rhs),
+                ArithOp::Sub => lhs.sub(&rhs),
+                ArithOp::Mul => lhs.mul(&rhs),
+                ArithOp::Div => lhs.div(&rhs),
+            };
+            Ok(value?)
+        }
+        AttrExpr::Func { func, arg } => Ok(func.apply(&eval_attr_expr(arg, bound, join, binding, source, schema)?)?),
+    }
+}
+
+/// Évalue un FkBinding → RowId cible (`None` : FK non définie en LEFT)
 fn eval_fk_binding(
     fb: &FkBinding,
-    binding: &HashMap<&str, (&str, RowId)>,
+    join: JoinMode,
+    binding: &Binding,
     source: &Instance,
     schema: &Schema,
-) -> Result<RowId, String> {
+) -> Result<Option<RowId>, CatrustError> {
     let (start_entity, start_row) = binding.get(fb.from_var.as_str())
         .ok_or_else(|| format!("Variable FROM '{}' non trouvée", fb.from_var))?;
 
-    let (_entity, row) = follow_fks(start_entity, *start_row, &fb.path, source, schema)?;
-    Ok(row)
+    let resolved = follow_fks(start_entity, *start_row, &fb.path, join, source, schema, binding.fk_hops)?;
+    Ok(resolved.map(|(_entity, row)| row))
 }
 
-/// Compare deux Values avec un opérateur.
+/// Compare deux Values avec un opérateur, selon l'ordre de `Value`.
+///
+/// Sémantique SQL : toute comparaison avec NULL est fausse, sauf `!=`.
+/// Booléens et listes n'admettent que `=` et `!=` ; des types
+/// incompatibles ne sont jamais comparables.
 fn compare_values(lhs: &Value, op: &CompOp, rhs: &Value) -> bool {
     match (lhs, rhs) {
-        (Value::Integer(a), Value::Integer(b)) => match op {
-            CompOp::Eq => a == b,
-            CompOp::Neq => a != b,
-            CompOp::Lt => a < b,
-            CompOp::Gt => a > b,
-            CompOp::Lte => a <= b,
-            CompOp::Gte => a >= b,
-        },
-        (Value::Float(a), Value::Float(b)) => match op {
-            CompOp::Eq => (a - b).abs() < f64::EPSILON,
-            CompOp::Neq => (a - b).abs() >= f64::EPSILON,
-            CompOp::Lt => a < b,
-            CompOp::Gt => a > b,
-            CompOp::Lte => a <= b,
-            CompOp::Gte => a >= b,
-        },
-        (Value::String(a), Value::String(b)) => match op {
-            CompOp::Eq => a == b,
-            CompOp::Neq => a != b,
-            CompOp::Lt => a < b,
-            CompOp::Gt => a > b,
-            CompOp::Lte => a <= b,
-            CompOp::Gte => a >= b,
-        },
-        (Value::Boolean(a), Value::Boolean(b)) => match op {
-            CompOp::Eq => a == b,
-            CompOp::Neq => a != b,
-            _ => false, // pas de < > pour les booléens
-        },
-        // Comparaisons croisées Int/Float
-        (Value::Integer(a), Value::Float(b)) => {
-            let af = *a as f64;
-            match op {
-                CompOp::Eq => (af - b).abs() < f64::EPSILON,
-                CompOp::Neq => (af - b).abs() >= f64::EPSILON,
-                CompOp::Lt => af < *b,
-                CompOp::Gt => af > *b,
-                CompOp::Lte => af <= *b,
-                CompOp::Gte => af >= *b,
-            }
-        },
-        (Value::Float(a), Value::Integer(b)) => {
-            let bf = *b as f64;
-            match op {
-                CompOp::Eq => (a - bf).abs() < f64::EPSILON,
-                CompOp::Neq => (a - bf).abs() >= f64::EPSILON,
-                CompOp::Lt => *a < bf,
-                CompOp::Gt => *a > bf,
-                CompOp::Lte => *a <= bf,
-                CompOp::Gte => *a >= bf,
-            }
-        },
         // NULL : rien n'est égal à NULL (sémantique SQL)
-        (Value::Null, _) | (_, Value::Null) => match op {
-            CompOp::Neq => true,
-            _ => false,
+        (Value::Null, _) | (_, Value::Null) => matches!(op, CompOp::Neq),
+        // Référence contre constante entière : le catrust_id de la ligne visée
+        (Value::Ref { row, .. }, Value::Integer(_)) => compare_values(&Value::Integer(*row as i64), op, rhs),
+        (Value::Integer(_), Value::Ref { row, .. }) => compare_values(lhs, op, &Value::Integer(*row as i64)),
+        // Deux références : même ligne de la même entité (un RowId seul
+        // ne dit rien d'une ligne d'une autre entité)
+        (Value::Ref { entity: e1, .. }, Value::Ref { entity: e2, .. }) if e1 != e2 => matches!(op, CompOp::Neq),
+        // NaN n'est égal ni comparable à rien, pas même à NaN (IEEE 754)
+        (Value::Float(f), _) | (_, Value::Float(f)) if f.is_nan() => matches!(op, CompOp::Neq),
+        (Value::Boolean(_), _) | (Value::Array(_), _)
+            if !matches!(op, CompOp::Eq | CompOp::Neq) => false,
+        _ => match lhs.partial_cmp(rhs) {
+            Some(ord) => match op {
+                CompOp::Eq => ord == Ordering::Equal,
+                CompOp::Neq => ord != Ordering::Equal,
+                CompOp::Lt => ord == Ordering::Less,
+                CompOp::Gt => ord == Ordering::Greater,
+                CompOp::Lte => ord != Ordering::Greater,
+                CompOp::Gte => ord != Ordering::Less,
+            },
+            // Types incompatibles → faux
+            None => false,
         },
-        // Types incompatibles → faux
-        _ => false,
     }
 }
 
-/// Produit cartésien de N vecteurs de RowId.
+/// Produit cartésien de N vecteurs de RowId, tuple par tuple : le
+/// produit n'est jamais matérialisé.
 ///
-/// Ex: [[1,2], [10,20]] → [[1,10], [1,20], [2,10], [2,20]]
-fn cartesian_product(sets: &[Vec<RowId>]) -> Vec<Vec<RowId>> {
-    if sets.is_empty() {
-        return vec![vec![]];
-    }
-    if sets.len() == 1 {
-        return sets[0].iter().map(|&r| vec![r]).collect();
+/// Ex: [[1,2], [10,20]] → [1,10], [1,20], [2,10], [2,20]
+fn cartesian_product(sets: &[Vec<RowId>]) -> CartesianProduct<'_> {
+    CartesianProduct {
+        sets,
+        indices: vec![0; sets.len()],
+        done: sets.iter().any(|set| set.is_empty()),
     }
+}
 
-    let first = &sets[0];
-    let rest = cartesian_product(&sets[1..]);
+/// Itérateur de `cartesian_product` : un compteur dont le chiffre `i`
+/// parcourt `sets[i]`, le dernier tournant le plus vite
+struct CartesianProduct<'a> {
+    sets: &'a [Vec<RowId>],
+    indices: Vec<usize>,
+    done: bool,
+}
+
+impl Iterator for CartesianProduct<'_> {
+    type Item = Vec<RowId>;
 
-    let mut result = Vec::with_capacity(first.len() * rest.len());
-    for &item in first {
-        for r in &rest {
-            let mut tuple = Vec::with_capacity(1 + r.len());
-            tuple.push(item);
-            tuple.extend(r);
-            result.push(tuple);
+    fn next(&mut self) -> Option<Vec<RowId>> {
+        if self.done {
+            return None;
         }
+        let tuple = self.indices.iter().enumerate().map(|(i, &k)| self.sets[i][k]).collect();
+
+        // Incrémente le compteur ; au débordement du premier chiffre, fini
+        self.done = true;
+        for i in (0..self.sets.len()).rev() {
+            self.indices[i] += 1;
+            if self.indices[i] < self.sets[i].len() {
+                self.done = false;
+                break;
+            }
+            self.indices[i] = 0;
+        }
+        Some(tuple)
     }
-    result
 }
 
 /// Version optimisée : évalue une requête après l'avoir optimisée
@@ -419,11 +1335,264 @@ pub fn eval_query_optimized(
     query: &CqlQuery,
     source: &Instance,
     schema: &Schema,
-) -> Result<EvalResult, String> {
+) -> Result<EvalResult, CatrustError> {
     let optimized = query.optimize(schema);
     eval_query(&optimized, source, schema)
 }
 
+/// Mémoïsation des résultats de `eval_query` (cf. `eval_query_cached`).
+///
+/// Une entrée par requête, valable pour une version d'instance : toute
+/// modification de l'instance (`Instance::version`) l'invalide. La clé est
+/// une forme canonique complète de la requête et du schéma (HashMap triées) :
+/// une requête reconstruite à l'identique retrouve son entrée, une requête
+/// ou un schéma différent n'en prend jamais une autre.
+#[derive(Debug, Clone, Default)]
+pub struct QueryCache {
+    /// forme canonique (requête, schéma) → (version de l'instance, résultat)
+    entries: HashMap<String, (u64, EvalResult)>,
+    hits: usize,
+    misses: usize,
+}
+
+impl QueryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appels servis depuis le cache
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Appels qui ont dû évaluer la requête
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Nombre de requêtes mémoïsées
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Oublie tous les résultats (les compteurs sont conservés)
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Clé d'une requête sur un schéma : leurs formes canoniques
+    fn key(query: &CqlQuery, schema: &Schema) -> String {
+        let mut key = format!(
+            "{}\n{} : {}\n{}",
+            canonical_schema(schema), query.name, query.source_schema_name,
+            canonical_schema(&query.result_schema),
+        );
+        for block in &query.blocks {
+            key.push('\n');
+            key.push_str(&canonical_block(block));
+        }
+        key
+    }
+}
+
+/// Forme `Debug` d'un schéma, HashMap triées
+fn canonical_schema(schema: &Schema) -> String {
+    let mut nodes: Vec<&String> = schema.nodes.keys().collect();
+    nodes.sort();
+    let edges: BTreeMap<&String, &Edge> = schema.edges.iter().collect();
+    let constants: BTreeMap<&String, _> = schema.typeside.constants.iter().collect();
+    format!(
+        "{:?}",
+        (&schema.name, nodes, edges, &schema.path_equations,
+         &schema.typeside.types, &schema.typeside.operations, constants),
+    )
+}
+
+/// Forme `Debug` d'un block, HashMap triées (y compris dans les EXISTS)
+fn canonical_block(block: &QueryBlock) -> String {
+    // Déstructuration complète : un nouveau champ doit être ajouté ici
+    let QueryBlock {
+        target_entity, from_vars, where_clauses, attribute_bindings, fk_bindings,
+        select_all, distinct, join_mode, having, count_only, order_by,
+    } = block;
+    let from_vars: BTreeMap<&String, &String> = from_vars.iter().collect();
+    let where_clauses: Vec<String> = where_clauses.iter()
+        .map(|clause| match clause {
+            WhereClause::Exists { inner } => format!("Exists({})", canonical_block(inner)),
+            clause => format!("{:?}", clause),
+        })
+        .collect();
+    let attribute_bindings: BTreeMap<&String, &AttributeBinding> = attribute_bindings.iter().collect();
+    let fk_bindings: BTreeMap<&String, &FkBinding> = fk_bindings.iter().collect();
+    format!(
+        "{:?}",
+        (target_entity, from_vars, where_clauses, attribute_bindings, fk_bindings,
+         select_all, distinct, join_mode, having, count_only, order_by),
+    )
+}
+
+/// Comme `eval_query`, mais réutilise le résultat mémoïsé dans `cache` si
+/// la même requête a déjà été évaluée sur cette version de l'instance.
+pub fn eval_query_cached(
+    cache: &mut QueryCache,
+    query: &CqlQuery,
+    source: &Instance,
+    schema: &Schema,
+) -> Result<EvalResult, CatrustError> {
+    let key = QueryCache::key(query, schema);
+    match cache.entries.get(&key) {
+        Some((version, result)) if *version == source.version => {
+            cache.hits += 1;
+            Ok(result.clone())
+        }
+        _ => {
+            cache.misses += 1;
+            let result = eval_query(query, source, schema)?;
+            cache.entries.insert(key, (source.version, result.clone()));
+            Ok(result)
+        }
+    }
+}
+
+/// Position dans un résultat paginé (cf. `eval_query_paged`).
+///
+/// Opaque : retient la clé de tri de la dernière ligne rendue, pas un
+/// décalage, si bien que la page suivante reste juste même si des lignes
+/// sont insérées entre deux appels. Les lignes identiques (sans DISTINCT)
+/// sont départagées par leur rang parmi celles de même clé.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    /// Entité résultat paginée
+    entity: String,
+    /// Clé de tri complète de la dernière ligne rendue
+    last_key: Vec<Value>,
+    /// Lignes de clé `last_key` déjà rendues
+    returned_with_key: usize,
+}
+
+/// Évalue une page de `page_size` lignes d'une requête à un seul block
+/// trié (ORDER BY), à partir de `cursor` (`None` = première page).
+///
+/// Retourne la page et le curseur de la suivante (`None` = dernière page).
+/// Les lignes de même clé ORDER BY sont départagées par les autres
+/// attributs puis les FK, par nom, et les lignes identiques par leur rang :
+/// aucune ligne n'est sautée ni répétée. Chaque page réévalue la requête.
+pub fn eval_query_paged(
+    query: &CqlQuery,
+    source: &Instance,
+    schema: &Schema,
+    cursor: Option<Cursor>,
+    page_size: usize,
+) -> Result<(EvalResult, Option<Cursor>), CatrustError> {
+    let [block] = query.blocks.as_slice() else {
+        return Err(format!("Pagination : la requête '{}' doit avoir un seul block", query.name).into());
+    };
+    if block.order_by.is_empty() || block.count_only {
+        return Err(format!("Pagination : le block '{}' doit avoir un ORDER BY", block.target_entity).into());
+    }
+    if page_size == 0 {
+        return Err("Pagination : page vide (page_size = 0)".into());
+    }
+    if let Some(c) = &cursor {
+        if c.entity != block.target_entity {
+            return Err(format!("Curseur de '{}' utilisé pour '{}'", c.entity, block.target_entity).into());
+        }
+    }
+
+    let mut result = eval_query(query, source, schema)?;
+    let data = result.instance.data.remove(&block.target_entity).unwrap_or_default();
+
+    // Les RowId résultat suivent déjà l'ORDER BY
+    let mut ids = data.row_ids();
+    ids.sort();
+    let mut skipped_with_key = 0;
+    let mut remaining = ids.into_iter()
+        .map(|id| {
+            let RowSnapshot { attrs, fks } = data.row(id).unwrap_or_default();
+            let key = order_key(&block.order_by, &attrs, &fks);
+            (key, attrs, fks)
+        })
+        .filter(|(key, _, _)| match &cursor {
+            Some(c) => match compare_order_keys(key, &c.last_key, &block.order_by) {
+                Ordering::Greater => true,
+                // Lignes identiques à la dernière rendue : sauter celles déjà rendues
+                Ordering::Equal => {
+                    skipped_with_key += 1;
+                    skipped_with_key > c.returned_with_key
+                }
+                Ordering::Less => false,
+            },
+            None => true,
+        })
+        .peekable();
+
+    let mut page = EntityData::new();
+    let mut last: Option<(Vec<Value>, usize)> = None;
+    for (key, attrs, fks) in remaining.by_ref().take(page_size) {
+        page.insert(attrs, fks);
+        last = match last {
+            Some((last_key, run)) if compare_order_keys(&key, &last_key, &block.order_by) == Ordering::Equal => {
+                Some((last_key, run + 1))
+            }
+            _ => Some((key, 1)),
+        };
+    }
+    let next = match (remaining.peek(), last) {
+        (Some(_), Some((last_key, mut returned_with_key))) => {
+            // La page n'a rendu que des lignes de la clé du curseur : cumuler
+            if let Some(c) = cursor.as_ref()
+                .filter(|c| compare_order_keys(&last_key, &c.last_key, &block.order_by) == Ordering::Equal)
+            {
+                returned_with_key += c.returned_with_key;
+            }
+            Some(Cursor { entity: block.target_entity.clone(), last_key, returned_with_key })
+        }
+        _ => None,
+    };
+
+    result.rows_returned = page.len();
+    result.instance.data.insert(block.target_entity.clone(), page);
+    Ok((result, next))
+}
+
+/// Clé de tri complète d'une ligne projetée : les attributs ORDER BY, puis
+/// les autres attributs et les FK par nom (départage déterministe).
+fn order_key(
+    order_by: &[OrderKey],
+    attrs: &HashMap<String, Value>,
+    fks: &HashMap<String, RowId>,
+) -> Vec<Value> {
+    let mut others: Vec<&String> = attrs.keys()
+        .filter(|a| !order_by.iter().any(|k| k.attribute == **a))
+        .collect();
+    others.sort();
+    let mut fk_names: Vec<&String> = fks.keys().collect();
+    fk_names.sort();
+
+    order_by.iter().map(|k| &k.attribute)
+        .chain(others)
+        .map(|a| attrs.get(a).cloned().unwrap_or(Value::Null))
+        .chain(fk_names.into_iter().map(|fk| Value::Integer(fks[fk] as i64)))
+        .collect()
+}
+
+/// Compare deux clés de `order_key` : sens ORDER BY sur les premières
+/// composantes, croissant sur le départage.
+fn compare_order_keys(a: &[Value], b: &[Value], order_by: &[OrderKey]) -> Ordering {
+    for (i, (x, y)) in a.iter().zip(b).enumerate() {
+        let ord = x.total_cmp(y);
+        let ord = if order_by.get(i).is_some_and(|k| k.descending) { ord.reverse() } else { ord };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
 // =============================================================================
 // Fonctions utilitaires d'agrégation (pour AoC et au-delà)
 // =============================================================================
@@ -454,48 +1623,38 @@ pub fn sum(result: &EvalResult, entity: &str, attr: &str) -> f64 {
         .unwrap_or(0.0)
 }
 
+/// Valeurs non NULL d'un attribut (MIN/MAX ignorent NULL, comme en SQL).
+fn present_values(result: &EvalResult, entity: &str, attr: &str) -> Vec<Value> {
+    result.instance.data.get(entity)
+        .map(|ed| {
+            ed.row_ids().iter().filter_map(|&rid| {
+                ed.get_attr(rid, attr).filter(|v| !matches!(v, Value::Null)).cloned()
+            }).collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Minimum d'un attribut numérique.
 pub fn min_val(result: &EvalResult, entity: &str, attr: &str) -> Option<Value> {
-    result.instance.data.get(entity).and_then(|ed| {
-        ed.row_ids().iter().filter_map(|&rid| {
-            ed.get_attr(rid, attr).cloned()
-        }).min_by(|a, b| cmp_values(a, b))
-    })
+    present_values(result, entity, attr).into_iter().min_by(Value::total_cmp)
 }
 
 /// Maximum d'un attribut numérique.
 pub fn max_val(result: &EvalResult, entity: &str, attr: &str) -> Option<Value> {
-    result.instance.data.get(entity).and_then(|ed| {
-        ed.row_ids().iter().filter_map(|&rid| {
-            ed.get_attr(rid, attr).cloned()
-        }).max_by(|a, b| cmp_values(a, b))
-    })
+    present_values(result, entity, attr).into_iter().max_by(Value::total_cmp)
 }
 
-/// Collecte les valeurs distinctes d'un attribut.
+/// Collecte les valeurs distinctes d'un attribut, triées (NULL en premier).
 pub fn distinct(result: &EvalResult, entity: &str, attr: &str) -> Vec<Value> {
-    let mut values: Vec<Value> = result.instance.data.get(entity)
+    let values: BTreeSet<OrdValue> = result.instance.data.get(entity)
         .map(|ed| {
             ed.row_ids().iter().filter_map(|&rid| {
-                ed.get_attr(rid, attr).cloned()
+                ed.get_attr(rid, attr).cloned().map(OrdValue)
             }).collect()
         })
         .unwrap_or_default();
 
-    values.sort_by(cmp_values);
-    values.dedup();
-    values
-}
-
-/// Comparaison ordonnée de deux Value (pour trier)
-fn cmp_values(a: &Value, b: &Value) -> std::cmp::Ordering {
-    match (a, b) {
-        (Value::Integer(x), Value::Integer(y)) => x.cmp(y),
-        (Value::Float(x), Value::Float(y)) => x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Equal),
-        (Value::String(x), Value::String(y)) => x.cmp(y),
-        (Value::Boolean(x), Value::Boolean(y)) => x.cmp(y),
-        _ => std::cmp::Ordering::Equal,
-    }
+    values.into_iter().map(|v| v.0).collect()
 }
 
 // =============================================================================
@@ -565,6 +1724,123 @@ mod tests {
         inst
     }
 
+    #[test]
+    fn test_resolve_value_path_errors() {
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+        let orphan = inst.insert("Employee", crate::row! { emp_name: "Eve" }, HashMap::new());
+        let fk_hops = Cell::new(0);
+        let binding = Binding { rows: HashMap::from([("e", ("Employee", orphan))]), fk_hops: &fk_hops };
+        let resolve = |var: &str, path: &[&str], join| {
+            let path: Vec<String> = path.iter().map(|s| s.to_string()).collect();
+            resolve_value(var, &path, join, &binding, &inst, &schema)
+        };
+
+        // Chemin vide : la ligne elle-même
+        assert_eq!(resolve("e", &[], JoinMode::Inner), Ok(Value::Ref { entity: "Employee".into(), row: orphan }));
+
+        assert_eq!(resolve("x", &["emp_name"], JoinMode::Inner), Err(PathError::UnknownVariable { var: "x".into() }));
+        assert_eq!(resolve("e", &["boss", "emp_name"], JoinMode::Inner), Err(PathError::UnknownEdge { edge: "boss".into() }));
+        assert_eq!(resolve("e", &["emp_name", "dept_name"], JoinMode::Inner), Err(PathError::NotAnFk { edge: "emp_name".into() }));
+        assert_eq!(
+            resolve("e", &["works_in", "dept_name"], JoinMode::Inner),
+            Err(PathError::UnsetFk { fk: "works_in".into(), entity: "Employee".into(), row: orphan }),
+        );
+        assert_eq!(resolve("e", &["works_in", "dept_name"], JoinMode::Left), Ok(Value::Null));
+        assert_eq!(
+            resolve("e", &["salary"], JoinMode::Inner),
+            Err(PathError::MissingAttribute { attribute: "salary".into(), entity: "Employee".into(), row: orphan }),
+        );
+
+        // Binding sans attribut : projette le RowId atteint
+        let mut query = CqlQuery::new("Ids", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["emp_name"], Value::String("Alice".into()))
+                .bind("id", "e", &[], "")
+                .bind("dept", "e", &["works_in"], "")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        query.validate(&schema).unwrap();
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        let data = &result.instance.data["Result"];
+        let row = data.row_ids()[0];
+        let alice = inst.data["Employee"].row_ids().into_iter()
+            .find(|&id| inst.data["Employee"].get_attr(id, "emp_name") == Some(&Value::String("Alice".into())))
+            .unwrap();
+        assert_eq!(data.get_attr(row, "id"), Some(&Value::Integer(alice as i64)));
+        let dept = inst.data["Employee"].get_fk(alice, "works_in").unwrap();
+        assert_eq!(data.get_attr(row, "dept"), Some(&Value::Integer(dept as i64)));
+    }
+
+    #[test]
+    fn test_eval_query_cached() {
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+        let mut query = CqlQuery::new("Names", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let mut cache = QueryCache::new();
+
+        let first = eval_query_cached(&mut cache, &query, &inst, &schema).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+        let second = eval_query_cached(&mut cache, &query, &inst, &schema).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+        // Le même résultat, pas une réévaluation
+        assert_eq!(second.instance.version, first.instance.version);
+        assert_eq!(second.instance.data["Result"], first.instance.data["Result"]);
+
+        // Toute modification de l'instance invalide l'entrée
+        inst.insert("Employee", crate::row! { emp_name: "Eve" }, HashMap::new());
+        let third = eval_query_cached(&mut cache, &query, &inst, &schema).unwrap();
+        assert_eq!(third.rows_returned, first.rows_returned + 1);
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_query_cache_key_is_canonical() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+        // Deux constructions indépendantes : leurs HashMap n'ont pas le même ordre
+        let build = || {
+            let mut query = CqlQuery::new("Names", "Company");
+            query.add_block(
+                CqlQuery::select()
+                    .from("e", "Employee")
+                    .from("d", "Department")
+                    .where_path_eq("e", &["works_in"], "d", &[])
+                    .bind("name", "e", &[], "emp_name")
+                    .bind("salary", "e", &[], "salary")
+                    .bind("dept", "d", &[], "dept_name")
+                    .build()
+                    .unwrap(),
+                &schema,
+            ).unwrap();
+            query
+        };
+        let mut cache = QueryCache::new();
+        eval_query_cached(&mut cache, &build(), &inst, &schema).unwrap();
+        eval_query_cached(&mut cache, &build(), &inst, &schema).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        // Même nom de schéma, contenu différent : pas de résultat partagé
+        let mut other = company_schema();
+        other.add_attribute("nickname", "Employee", BaseType::String);
+        eval_query_cached(&mut cache, &build(), &inst, &other).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+        assert_eq!(cache.len(), 2);
+    }
+
     #[test]
     fn test_eval_simple_select_all() {
         // SELECT * FROM Employee (pas de WHERE)
@@ -578,14 +1854,20 @@ mod tests {
             where_clauses: vec![],
             attribute_bindings: HashMap::from([
                 ("name".into(), AttributeBinding {
-                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(),
+                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
                 }),
                 ("salary".into(), AttributeBinding {
-                    from_var: "e".into(), path: vec![], attribute: "salary".into(),
+                    from_var: "e".into(), path: vec![], attribute: "salary".into(), expr: None,
                 }),
             ]),
             fk_bindings: HashMap::new(),
-        });
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
 
         let result = eval_query(&query, &inst, &schema).unwrap();
         println!("{}", result);
@@ -593,6 +1875,159 @@ mod tests {
         assert_eq!(count(&result, "Result"), 4);
     }
 
+    #[test]
+    fn test_eval_exists() {
+        // Employés dont le département a au moins un manager
+        let mut schema = company_schema();
+        schema.add_node("Manager").add_fk("manages", "Manager", "Department");
+        let mut inst = company_instance(&schema);
+
+        let engineering = inst.data["Department"].row_ids().into_iter()
+            .find(|&d| inst.data["Department"].get_attr(d, "dept_name")
+                == Some(&Value::String("Engineering".into())))
+            .unwrap();
+        inst.insert("Manager", HashMap::new(), HashMap::from([("manages".into(), engineering)]));
+
+        let mut query = CqlQuery::new("Managed", "Company");
+        query.add_block(QueryBlock {
+            target_entity: "Result".into(),
+            from_vars: HashMap::from([("e".into(), "Employee".into())]),
+            where_clauses: vec![
+                WhereClause::Exists {
+                    inner: Box::new(QueryBlock {
+                        target_entity: "_".into(),
+                        from_vars: HashMap::from([("m".into(), "Manager".into())]),
+                        where_clauses: vec![
+                            WhereClause::PathEqual {
+                                var1: "m".into(), path1: vec!["manages".into()],
+                                var2: "e".into(), path2: vec!["works_in".into()],
+                            },
+                        ],
+                        attribute_bindings: HashMap::new(),
+                        fk_bindings: HashMap::new(),
+                        distinct: false,
+                        join_mode: JoinMode::Inner,
+                        having: vec![],
+                        count_only: false,
+                        order_by: Vec::new(),
+                        select_all: Vec::new(),
+                    }),
+                },
+            ],
+            attribute_bindings: HashMap::from([
+                ("name".into(), AttributeBinding {
+                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
+                }),
+            ]),
+            fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
+
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        let mut names = distinct(&result, "Result", "name");
+        names.sort_by(Value::total_cmp);
+        assert_eq!(names, vec![Value::String("Alice".into()), Value::String("Bob".into())]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        // Produit cartésien Employee × Department filtré
+        let block = QueryBlock {
+            target_entity: "Pairs".into(),
+            from_vars: HashMap::from([
+                ("e".into(), "Employee".into()),
+                ("d".into(), "Department".into()),
+            ]),
+            where_clauses: vec![
+                WhereClause::Comparison {
+                    var: "e".into(), path: vec!["salary".into()],
+                    op: CompOp::Gt, value: Value::Integer(70000),
+                },
+            ],
+            attribute_bindings: HashMap::from([
+                ("emp".into(), AttributeBinding {
+                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
+                }),
+                ("dept".into(), AttributeBinding {
+                    from_var: "d".into(), path: vec![], attribute: "dept_name".into(), expr: None,
+                }),
+            ]),
+            fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        };
+
+        let var_names: Vec<&String> = block.from_vars.keys().collect();
+        let var_entities: Vec<&String> = var_names.iter().map(|v| &block.from_vars[*v]).collect();
+        let vars = BlockVars { names: &var_names, entities: &var_entities };
+        let var_rows: Vec<Vec<RowId>> = var_entities.iter()
+            .map(|e| inst.data[*e].row_ids())
+            .collect();
+        let tuples = || -> TupleStream { Box::new(cartesian_product(&var_rows).map(Ok)) };
+
+        let (sequential_hops, parallel_hops) = (Cell::new(0), Cell::new(0));
+        let sequential = project_tuples(&block, &vars, tuples(), &inst, &schema, &sequential_hops, None).unwrap();
+        let parallel = project_tuples_parallel(&block, &vars, tuples(), &inst, &schema, &parallel_hops).unwrap();
+        assert_eq!(sequential.len(), 6); // 3 salaires > 70000 × 2 départements
+        assert_eq!(sequential, parallel);
+        assert_eq!(sequential_hops.get(), parallel_hops.get());
+    }
+
+    #[test]
+    fn test_eval_distinct() {
+        // SELECT DISTINCT e.works_in.dept_name FROM Employee e
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let mut block = QueryBlock {
+            target_entity: "Depts".into(),
+            from_vars: HashMap::from([("e".into(), "Employee".into())]),
+            where_clauses: vec![],
+            attribute_bindings: HashMap::from([
+                ("dept".into(), AttributeBinding {
+                    from_var: "e".into(), path: vec!["works_in".into()], attribute: "dept_name".into(), expr: None,
+                }),
+            ]),
+            fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        };
+
+        let mut query = CqlQuery::new("Depts", "Company");
+        query.add_block(block.clone(), &schema).unwrap();
+        assert_eq!(eval_query(&query, &inst, &schema).unwrap().rows_returned, 4);
+
+        block.distinct = true;
+        let mut query = CqlQuery::new("Depts", "Company");
+        query.add_block(block, &schema).unwrap();
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        assert_eq!(result.rows_returned, 2);
+        assert_eq!(result.rows_scanned, 4);
+        let mut depts = distinct(&result, "Depts", "dept");
+        depts.sort_by(Value::total_cmp);
+        assert_eq!(depts, vec![
+            Value::String("Engineering".into()),
+            Value::String("Marketing".into()),
+        ]);
+    }
+
     #[test]
     fn test_eval_filter_by_salary() {
         // SELECT emp_name FROM Employee WHERE salary > 80000
@@ -613,11 +2048,17 @@ mod tests {
             ],
             attribute_bindings: HashMap::from([
                 ("name".into(), AttributeBinding {
-                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(),
+                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
                 }),
             ]),
             fk_bindings: HashMap::new(),
-        });
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
 
         let result = eval_query(&query, &inst, &schema).unwrap();
         println!("{}", result);
@@ -625,6 +2066,51 @@ mod tests {
         assert_eq!(result.rows_returned, 2);
     }
 
+    #[test]
+    fn test_eval_array_equality() {
+        // SELECT emp_name FROM Employee WHERE skills = ["rust", "sql"]
+        let mut schema = company_schema();
+        schema.add_attribute("skills", "Employee", BaseType::Array(Box::new(BaseType::String)));
+        let mut inst = company_instance(&schema);
+        let skills = |xs: &[&str]| Value::Array(xs.iter().map(|x| Value::String(x.to_string())).collect());
+        let mut ids = inst.data["Employee"].row_ids();
+        ids.sort();
+        inst.set_attr("Employee", ids[0], "skills", skills(&["rust", "sql"]), &schema).unwrap();
+        inst.set_attr("Employee", ids[1], "skills", skills(&["sql", "rust"]), &schema).unwrap();
+        inst.set_attr("Employee", ids[2], "skills", skills(&["rust", "sql"]), &schema).unwrap();
+        inst.set_attr("Employee", ids[3], "skills", skills(&[]), &schema).unwrap();
+
+        let mut query = CqlQuery::new("Rustaceans", "Company");
+        query.add_block(QueryBlock {
+            target_entity: "Result".into(),
+            from_vars: HashMap::from([("e".into(), "Employee".into())]),
+            where_clauses: vec![
+                WhereClause::Comparison {
+                    var: "e".into(),
+                    path: vec!["skills".into()],
+                    op: CompOp::Eq,
+                    value: skills(&["rust", "sql"]),
+                },
+            ],
+            attribute_bindings: HashMap::from([
+                ("name".into(), AttributeBinding {
+                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
+                }),
+            ]),
+            fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
+
+        // L'ordre compte : ["sql", "rust"] ≠ ["rust", "sql"]
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        assert_eq!(result.rows_returned, 2);
+    }
+
     #[test]
     fn test_eval_filter_by_fk_attribute() {
         // SELECT emp_name FROM Employee WHERE department.dept_name = "Engineering"
@@ -647,16 +2133,23 @@ mod tests {
             ],
             attribute_bindings: HashMap::from([
                 ("name".into(), AttributeBinding {
-                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(),
+                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
                 }),
                 ("dept".into(), AttributeBinding {
                     from_var: "e".into(),
                     path: vec!["works_in".into()],
                     attribute: "dept_name".into(),
+                    expr: None,
                 }),
             ]),
             fk_bindings: HashMap::new(),
-        });
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
 
         let result = eval_query(&query, &inst, &schema).unwrap();
         println!("{}", result);
@@ -691,11 +2184,17 @@ mod tests {
             ],
             attribute_bindings: HashMap::from([
                 ("name".into(), AttributeBinding {
-                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(),
+                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
                 }),
             ]),
             fk_bindings: HashMap::new(),
-        });
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
 
         let result = eval_query(&query, &inst, &schema).unwrap();
         println!("{}", result);
@@ -703,6 +2202,498 @@ mod tests {
         assert_eq!(result.rows_returned, 1);
     }
 
+    #[test]
+    fn test_eval_explain_trace() {
+        // Même requête que test_eval_combined_filters, avec EXPLAIN
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let mut query = CqlQuery::new("SeniorEngineers", "Company");
+        query.add_block(QueryBlock {
+            target_entity: "Result".into(),
+            from_vars: HashMap::from([("e".into(), "Employee".into())]),
+            where_clauses: vec![
+                WhereClause::Comparison {
+                    var: "e".into(),
+                    path: vec!["works_in".into(), "dept_name".into()],
+                    op: CompOp::Eq,
+                    value: Value::String("Engineering".into()),
+                },
+                WhereClause::Comparison {
+                    var: "e".into(),
+                    path: vec!["salary".into()],
+                    op: CompOp::Gt,
+                    value: Value::Integer(80000),
+                },
+            ],
+            attribute_bindings: HashMap::from([
+                ("name".into(), AttributeBinding {
+                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
+                }),
+            ]),
+            fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
+
+        // Sans explain : pas de trace
+        let plain = eval_query(&query, &inst, &schema).unwrap();
+        assert!(plain.trace.is_empty());
+
+        // EXPLAIN : Charlie et Diana tombent sur le département, Bob sur le salaire
+        let explained = eval_query_with(&query, &inst, &schema, true).unwrap();
+        println!("{}", explained);
+        assert_eq!(explained.rows_returned, plain.rows_returned);
+        assert_eq!(explained.trace.len(), 2);
+
+        let dept = &explained.trace[0];
+        assert_eq!((dept.block.as_str(), dept.clause_index), ("Result", 0));
+        assert_eq!(dept.clause, "e.works_in.dept_name = \"Engineering\"");
+        assert_eq!((dept.evaluated, dept.rejected, dept.fk_hops), (4, 2, 4));
+
+        // Le salaire n'est évalué que sur les lignes qui ont passé le département
+        let salary = &explained.trace[1];
+        assert_eq!(salary.clause_index, 1);
+        assert_eq!(salary.clause, "e.salary > 80000");
+        assert_eq!((salary.evaluated, salary.rejected, salary.fk_hops), (2, 1, 0));
+
+        assert!(explained.to_string().contains("EXPLAIN"));
+    }
+
+    #[test]
+    fn test_builder_matches_literal_query() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let mut literal = CqlQuery::new("SeniorEngineers", "Company");
+        literal.add_block(QueryBlock {
+            target_entity: "Result".into(),
+            from_vars: HashMap::from([("e".into(), "Employee".into())]),
+            where_clauses: vec![
+                WhereClause::Comparison {
+                    var: "e".into(),
+                    path: vec!["works_in".into(), "dept_name".into()],
+                    op: CompOp::Eq,
+                    value: Value::String("Engineering".into()),
+                },
+                WhereClause::Comparison {
+                    var: "e".into(),
+                    path: vec!["salary".into()],
+                    op: CompOp::Gt,
+                    value: Value::Integer(80000),
+                },
+            ],
+            attribute_bindings: HashMap::from([
+                ("name".into(), AttributeBinding {
+                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
+                }),
+                ("dept".into(), AttributeBinding {
+                    from_var: "e".into(), path: vec!["works_in".into()], attribute: "dept_name".into(), expr: None,
+                }),
+            ]),
+            fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
+
+        let mut built = CqlQuery::new("SeniorEngineers", "Company");
+        built.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["works_in", "dept_name"], Value::String("Engineering".into()))
+                .where_cmp("e", &["salary"], CompOp::Gt, Value::Integer(80000))
+                .bind("name", "e", &[], "emp_name")
+                .bind("dept", "e", &["works_in"], "dept_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let expected = eval_query(&literal, &inst, &schema).unwrap();
+        let actual = eval_query(&built, &inst, &schema).unwrap();
+        assert_eq!(actual.rows_returned, 1);
+        assert_eq!(actual.rows_scanned, expected.rows_scanned);
+        assert_eq!(actual.instance.data["Result"], expected.instance.data["Result"]);
+    }
+
+    #[test]
+    fn test_eval_count_only() {
+        // SELECT COUNT(*) FROM Employee e WHERE e.works_in.dept_name = 'Engineering'
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let mut query = CqlQuery::new("EngineerCount", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["works_in", "dept_name"], Value::String("Engineering".into()))
+                .count_only()
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        assert_eq!(result.scalar, Some(Value::Integer(2)));
+        assert_eq!(result.rows_scanned, 4);
+        assert_eq!(count(&result, "Result"), 0);
+    }
+
+    #[test]
+    fn test_eval_between() {
+        // 70000 <= salary <= 90000 : Alice (90000, borne incluse), Bob, Diana
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let between = |low: i64, high: i64| {
+            let mut query = CqlQuery::new("MidSalaries", "Company");
+            query.add_block(
+                CqlQuery::select()
+                    .from("e", "Employee")
+                    .where_between("e", &["salary"], Value::Integer(low), Value::Integer(high))
+                    .bind("name", "e", &[], "emp_name")
+                    .build()
+                    .unwrap(),
+                &schema,
+            ).unwrap();
+            eval_query(&query, &inst, &schema).unwrap()
+        };
+
+        let result = between(70000, 90000);
+        assert_eq!(distinct(&result, "Result", "name"), vec![
+            Value::String("Alice".into()),
+            Value::String("Bob".into()),
+            Value::String("Diana".into()),
+        ]);
+
+        // Intervalle vide : aucune ligne
+        assert_eq!(between(90000, 70000).rows_returned, 0);
+    }
+
+    #[test]
+    fn test_eval_literal_on_left() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let names = |clause: WhereClause| {
+            let mut block = CqlQuery::select()
+                .from("e", "Employee")
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap();
+            block.where_clauses.push(clause);
+            let mut query = CqlQuery::new("HighEarners", "Company");
+            query.add_block(block, &schema).unwrap();
+            distinct(&eval_query(&query, &inst, &schema).unwrap(), "Result", "name")
+        };
+
+        let path_left = names(WhereClause::Comparison {
+            var: "e".into(),
+            path: vec!["salary".into()],
+            op: CompOp::Gt,
+            value: Value::Integer(80000),
+        });
+        let literal_left = names(WhereClause::Compare {
+            lhs: Side::Literal(Value::Integer(80000)),
+            op: CompOp::Lt,
+            rhs: Side::path("e", &["salary"]),
+        });
+        assert!(!path_left.is_empty());
+        assert_eq!(literal_left, path_left);
+
+        // Deux constantes : la clause ne dépend d'aucune ligne
+        let never = names(WhereClause::Compare {
+            lhs: Side::Literal(Value::Integer(1)),
+            op: CompOp::Eq,
+            rhs: Side::Literal(Value::Integer(2)),
+        });
+        assert!(never.is_empty());
+    }
+
+    #[test]
+    fn test_where_above_average() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        // AVG(Employee.salary) = (90000 + 75000 + 60000 + 85000) / 4 = 77500
+        let mut query = CqlQuery::new("AboveAverage", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_sides(Side::path("e", &["salary"]), CompOp::Gt, Side::aggregate(AggFunc::Avg, "Employee", "salary"))
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        assert!(query.typecheck(&schema).is_ok());
+
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        assert_eq!(
+            distinct(&result, "Result", "name"),
+            vec![Value::String("Alice".into()), Value::String("Diana".into())],
+        );
+
+        // Attribut d'une autre entité : refusé avant l'évaluation
+        query.blocks[0].where_clauses[0] = WhereClause::Compare {
+            lhs: Side::path("e", &["salary"]),
+            op: CompOp::Gt,
+            rhs: Side::aggregate(AggFunc::Avg, "Employee", "dept_name"),
+        };
+        assert!(query.typecheck(&schema).is_err());
+    }
+
+    #[test]
+    fn test_compare_fks_as_refs() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+        let pairs = |path2: &[&str]| {
+            let block = CqlQuery::select()
+                .from("e1", "Employee")
+                .from("e2", "Employee")
+                .where_path_cmp("e1", &["emp_name"], CompOp::Lt, "e2", &["emp_name"])
+                .where_path_eq("e1", &["works_in"], "e2", path2)
+                .bind("a", "e1", &[], "emp_name")
+                .bind("b", "e2", &[], "emp_name")
+                .build()
+                .unwrap();
+            // Un block refusé par add_block est évalué quand même : l'évaluateur
+            // ne doit pas non plus confondre les références
+            let mut query = CqlQuery::new("Colleagues", "Company");
+            let accepted = query.add_block(block.clone(), &schema).is_ok();
+            if !accepted {
+                query.blocks.push(block);
+            }
+            let result = eval_query(&query, &inst, &schema).unwrap();
+            let data = &result.instance.data["Result"];
+            let mut pairs: Vec<String> = data.row_ids().into_iter()
+                .map(|id| format!("{}/{}", data.get_attr(id, "a").unwrap(), data.get_attr(id, "b").unwrap()))
+                .collect();
+            pairs.sort();
+            (accepted, pairs)
+        };
+
+        // Même département : Alice/Bob (Engineering), Charlie/Diana (Marketing)
+        let (accepted, same_dept) = pairs(&["works_in"]);
+        assert!(accepted);
+        assert_eq!(same_dept, vec!["\"Alice\"/\"Bob\"", "\"Charlie\"/\"Diana\""]);
+
+        // Un département contre un employé : jamais égaux, même quand les
+        // RowId coïncident (Engineering et Alice valent tous deux 1)
+        let (accepted, cross) = pairs(&[]);
+        assert!(!accepted);
+        assert!(cross.is_empty());
+        assert_ne!(
+            Value::Ref { entity: "Department".into(), row: 1 },
+            Value::Ref { entity: "Employee".into(), row: 1 },
+        );
+    }
+
+    #[test]
+    fn test_eval_nan_never_equal() {
+        let mut schema = Schema::new("Sensors");
+        schema.add_node("Measure").add_attribute("reading", "Measure", BaseType::Float);
+        let mut inst = Instance::new("Data", &schema);
+        for reading in [1.0, f64::NAN, 3.0] {
+            inst.insert("Measure", crate::row! { reading: reading }, HashMap::new());
+        }
+
+        let matching = |op: CompOp, value: f64| {
+            let mut query = CqlQuery::new("Q", "Sensors");
+            query.add_block(
+                CqlQuery::select()
+                    .from("m", "Measure")
+                    .where_cmp("m", &["reading"], op, Value::Float(value))
+                    .bind("reading", "m", &[], "reading")
+                    .build()
+                    .unwrap(),
+                &schema,
+            ).unwrap();
+            eval_query(&query, &inst, &schema).unwrap().rows_returned
+        };
+
+        // NaN n'est égal à rien, pas même à NaN, et n'est ni < ni > à rien
+        assert_eq!(matching(CompOp::Eq, f64::NAN), 0);
+        assert_eq!(matching(CompOp::Neq, f64::NAN), 3);
+        assert_eq!(matching(CompOp::Gte, 0.0), 2);
+        assert_eq!(matching(CompOp::Lte, 10.0), 2);
+        assert_eq!(matching(CompOp::Neq, 1.0), 2);
+    }
+
+    #[test]
+    fn test_eval_unsatisfiable_block_scans_nothing() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let mut query = CqlQuery::new("Impossible", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_cmp("e", &["salary"], CompOp::Gt, Value::Integer(80000))
+                .where_cmp("e", &["salary"], CompOp::Lt, Value::Integer(50000))
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let raw = eval_query(&query, &inst, &schema).unwrap();
+        assert_eq!((raw.rows_scanned, raw.rows_returned), (4, 0));
+
+        let simplified = eval_query(&query.simplify(), &inst, &schema).unwrap();
+        assert_eq!((simplified.rows_scanned, simplified.rows_returned), (0, 0));
+    }
+
+    #[test]
+    fn test_eval_having_sum_by_department() {
+        // SELECT d.dept_name FROM Employee e ... GROUP BY dept HAVING SUM(e.salary) > 150000
+        // Engineering : 90000 + 75000 = 165000 ; Marketing : 60000 + 85000 = 145000
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let mut query = CqlQuery::new("RichDepartments", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .bind("dept", "e", &["works_in"], "dept_name")
+                .having(AggFunc::Sum, "e", &[], "salary", CompOp::Gt, Value::Integer(150000))
+                .having_count(CompOp::Eq, Value::Integer(2))
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        assert_eq!(result.rows_scanned, 4);
+        assert_eq!(result.rows_returned, 1);
+        assert_eq!(distinct(&result, "Result", "dept"), vec![Value::String("Engineering".into())]);
+    }
+
+    #[test]
+    fn test_eval_is_null_unset_fk() {
+        // Eve n'a pas de département
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+        inst.insert("Employee",
+            HashMap::from([
+                ("emp_name".into(), Value::String("Eve".into())),
+                ("salary".into(), Value::Integer(50000)),
+            ]),
+            HashMap::new(),
+        );
+
+        let mut unassigned = CqlQuery::new("Unassigned", "Company");
+        unassigned.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_null("e", &["works_in"])
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let result = eval_query(&unassigned, &inst, &schema).unwrap();
+        assert_eq!(distinct(&result, "Result", "name"), vec![Value::String("Eve".into())]);
+
+        // IS NOT NULL à travers la FK : un chemin interrompu est NULL, pas une erreur
+        let mut assigned = CqlQuery::new("Assigned", "Company");
+        assigned.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_not_null("e", &["works_in", "dept_name"])
+                .bind("name", "e", &[], "emp_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        assert_eq!(eval_query(&assigned, &inst, &schema).unwrap().rows_returned, 4);
+    }
+
+    #[test]
+    fn test_eval_left_join_null_fk() {
+        // Eve n'a pas de département : LEFT la garde avec dept = NULL
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+        inst.insert("Employee",
+            HashMap::from([
+                ("emp_name".into(), Value::String("Eve".into())),
+                ("salary".into(), Value::Integer(50000)),
+            ]),
+            HashMap::new(),
+        );
+
+        let block = |left: bool| {
+            let mut builder = CqlQuery::select();
+            builder
+                .from("e", "Employee")
+                .bind("name", "e", &[], "emp_name")
+                .bind("dept", "e", &["works_in"], "dept_name");
+            if left {
+                builder.left_join();
+            }
+            builder.build().unwrap()
+        };
+
+        let mut left = CqlQuery::new("Staff", "Company");
+        left.add_block(block(true), &schema).unwrap();
+        let result = eval_query(&left, &inst, &schema).unwrap();
+        assert_eq!(result.rows_returned, 5);
+        let data = &result.instance.data["Result"];
+        let eve = data.row_ids().into_iter()
+            .find(|&r| data.get_attr(r, "name") == Some(&Value::String("Eve".into())))
+            .expect("Eve absente du résultat LEFT");
+        assert_eq!(data.get_attr(eve, "dept"), Some(&Value::Null));
+
+        // INNER : la FK non définie reste une erreur
+        let mut inner = CqlQuery::new("Staff", "Company");
+        inner.add_block(block(false), &schema).unwrap();
+        assert!(eval_query(&inner, &inst, &schema).is_err());
+    }
+
+    #[test]
+    fn test_path_equal_null_depends_on_join_mode() {
+        // Eve et Frank ont un salaire NULL : égaux en INNER, pas en LEFT
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+        for name in ["Eve", "Frank"] {
+            inst.insert("Employee",
+                HashMap::from([
+                    ("emp_name".into(), Value::String(name.into())),
+                    ("salary".into(), Value::Null),
+                ]),
+                HashMap::new(),
+            );
+        }
+
+        let rows = |left: bool| {
+            let mut builder = CqlQuery::select();
+            builder
+                .from("e1", "Employee")
+                .from("e2", "Employee")
+                .where_eq("e1", &["emp_name"], Value::String("Eve".into()))
+                .where_eq("e2", &["emp_name"], Value::String("Frank".into()))
+                .where_path_eq("e1", &["salary"], "e2", &["salary"])
+                .bind("name", "e1", &[], "emp_name");
+            if left {
+                builder.left_join();
+            }
+            let mut query = CqlQuery::new("SameSalary", "Company");
+            query.add_block(builder.build().unwrap(), &schema).unwrap();
+            eval_query(&query, &inst, &schema).unwrap().rows_returned
+        };
+
+        assert_eq!(rows(false), 1);
+        assert_eq!(rows(true), 0);
+    }
+
     #[test]
     fn test_aggregation_functions() {
         let schema = company_schema();
@@ -716,11 +2707,17 @@ mod tests {
             where_clauses: vec![],
             attribute_bindings: HashMap::from([
                 ("salary".into(), AttributeBinding {
-                    from_var: "e".into(), path: vec![], attribute: "salary".into(),
+                    from_var: "e".into(), path: vec![], attribute: "salary".into(), expr: None,
                 }),
             ]),
             fk_bindings: HashMap::new(),
-        });
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
 
         let result = eval_query(&query, &inst, &schema).unwrap();
 
@@ -773,11 +2770,9 @@ mod tests {
         );
 
         // Affecter le manager au département
-        inst.data.get_mut("Department").unwrap()
-            .fk_values.get_mut(&d1).unwrap()
-            .insert("manager".into(), mgr);
+        inst.set_fk("Department", d1, "manager", mgr, &schema).unwrap();
 
-        let e2 = inst.insert("Employee",
+        let _e2 = inst.insert("Employee",
             HashMap::from([("emp_name".into(), Value::String("Bob".into()))]),
             HashMap::from([
                 ("department".into(), d1),
@@ -801,17 +2796,307 @@ mod tests {
             ],
             attribute_bindings: HashMap::from([
                 ("name".into(), AttributeBinding {
-                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(),
+                    from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
                 }),
             ]),
             fk_bindings: HashMap::new(),
-        });
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
 
         // Évaluation optimisée
         let result = eval_query_optimized(&query, &inst, &schema).unwrap();
         println!("{}", result);
         // Alice (dept_mgr=elle-même) et Bob (dept_mgr=Alice) devraient passer
         assert!(result.rows_returned >= 1, "Au moins Bob devrait passer");
+
+        // department.manager (2 FK) devient direct_mgr (1 FK) pour chaque employé
+        let plain = eval_query(&query, &inst, &schema).unwrap();
+        assert_eq!(plain.rows_returned, result.rows_returned);
+        assert_eq!((plain.fk_hops, result.fk_hops), (4, 2));
+    }
+
+    #[test]
+    fn test_reverse_fk_fans_out() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        // Depuis Engineering, ses employés : une ligne par employé, nom et
+        // salaire lus sur le MÊME employé
+        let mut query = CqlQuery::new("EngStaff", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("d", "Department")
+                .where_eq("d", &["dept_name"], Value::String("Engineering".into()))
+                .bind("dept", "d", &[], "dept_name")
+                .bind("name", "d", &["~works_in"], "emp_name")
+                .bind("salary", "d", &["~works_in"], "salary")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        assert_eq!(result.rows_returned, 2);
+        let data = &result.instance.data["Result"];
+        let mut rows: Vec<(Value, Value)> = data.row_ids().into_iter()
+            .map(|id| (data.get_attr(id, "name").unwrap().clone(), data.get_attr(id, "salary").unwrap().clone()))
+            .collect();
+        rows.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(rows, vec![
+            (Value::String("Alice".into()), Value::Integer(90000)),
+            (Value::String("Bob".into()), Value::Integer(75000)),
+        ]);
+
+        // Aller-retour : les collègues (soi compris) de chaque employé
+        let mut query = CqlQuery::new("Colleagues", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["works_in", "~works_in", "emp_name"], Value::String("Diana".into()))
+                .count_only()
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        // Charlie et Diana ont Diana pour collègue
+        assert_eq!(result.scalar, Some(Value::Integer(2)));
+    }
+
+    #[test]
+    fn test_path_compare_same_variable() {
+        let mut schema = Schema::new("Hierarchy");
+        schema.add_node("Employee")
+              .add_fk("manager", "Employee", "Employee")
+              .add_attribute("emp_name", "Employee", BaseType::String)
+              .add_attribute("salary", "Employee", BaseType::Integer);
+
+        let mut inst = Instance::new("Data", &schema);
+        let employee = |name: &str, salary: i64| HashMap::from([
+            ("emp_name".to_string(), Value::String(name.into())),
+            ("salary".to_string(), Value::Integer(salary)),
+        ]);
+        let boss = inst.insert("Employee", employee("Boss", 100000), HashMap::new());
+        inst.insert("Employee", employee("Alice", 120000), HashMap::from([("manager".into(), boss)]));
+        inst.insert("Employee", employee("Bob", 80000), HashMap::from([("manager".into(), boss)]));
+
+        // e.salary > e.manager.salary (Boss, sans manager, est écarté en LEFT)
+        let mut query = CqlQuery::new("OutEarners", "Hierarchy");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_path_cmp("e", &["salary"], CompOp::Gt, "e", &["manager", "salary"])
+                .bind("name", "e", &[], "emp_name")
+                .left_join()
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        assert_eq!(
+            query.blocks[0].where_clauses[0].to_string(),
+            "e.salary > e.manager.salary"
+        );
+
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        assert_eq!(result.rows_returned, 1);
+        let data = &result.instance.data["Result"];
+        let names: Vec<&Value> = data.row_ids().into_iter().filter_map(|id| data.get_attr(id, "name")).collect();
+        assert_eq!(names, vec![&Value::String("Alice".into())]);
+    }
+
+    #[test]
+    fn test_computed_attribute() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let mut query = CqlQuery::new("Annual", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["emp_name"], Value::String("Alice".into()))
+                .bind_computed("annual", "e", &[], "salary", AttrExpr::binary(
+                    ArithOp::Mul, AttrExpr::Bound, AttrExpr::Const(Value::Integer(12)),
+                ))
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        let data = &result.instance.data["Result"];
+        let id = data.row_ids()[0];
+        assert_eq!(data.get_attr(id, "annual"), Some(&Value::Integer(90000 * 12)));
+
+        // Une chaîne dans l'arithmétique est une erreur, pas un NULL silencieux
+        query.blocks[0].attribute_bindings.get_mut("annual").unwrap().attribute = "emp_name".into();
+        assert!(eval_query(&query, &inst, &schema).is_err());
+    }
+
+    #[test]
+    fn test_eval_paged_by_salary() {
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+
+        let mut query = CqlQuery::new("BySalary", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .bind("name", "e", &[], "emp_name")
+                .bind("salary", "e", &[], "salary")
+                .order_by("salary")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let names = |result: &EvalResult| {
+            let data = &result.instance.data["Result"];
+            let mut ids = data.row_ids();
+            ids.sort();
+            ids.iter().map(|&id| data.get_attr(id, "name").unwrap().to_string()).collect::<Vec<_>>()
+        };
+
+        let (page1, cursor) = eval_query_paged(&query, &inst, &schema, None, 2).unwrap();
+        assert_eq!(names(&page1), vec!["\"Charlie\"", "\"Bob\""]);
+        assert_eq!(page1.rows_returned, 2);
+
+        // Une insertion avant le curseur ne décale pas la page suivante
+        inst.insert("Employee", crate::row! { emp_name: "Eve", salary: 50000 }, HashMap::new());
+
+        let (page2, cursor) = eval_query_paged(&query, &inst, &schema, cursor, 2).unwrap();
+        assert_eq!(names(&page2), vec!["\"Diana\"", "\"Alice\""]);
+        assert!(cursor.is_none());
+
+        // Sans ORDER BY, pas de pagination
+        query.blocks[0].order_by.clear();
+        assert!(eval_query_paged(&query, &inst, &schema, None, 2).is_err());
+    }
+
+    #[test]
+    fn test_eval_paged_duplicates_across_pages() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        // Sans DISTINCT : deux lignes "Engineering", deux "Marketing"
+        let mut query = CqlQuery::new("Depts", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .bind("dept", "e", &["works_in"], "dept_name")
+                .order_by("dept")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        for page_size in [1, 3] {
+            let mut depts = Vec::new();
+            let mut cursor = None;
+            loop {
+                let (page, next) = eval_query_paged(&query, &inst, &schema, cursor, page_size).unwrap();
+                let data = &page.instance.data["Result"];
+                let mut ids = data.row_ids();
+                ids.sort();
+                depts.extend(ids.iter().map(|&id| data.get_attr(id, "dept").unwrap().to_string()));
+                cursor = next;
+                if cursor.is_none() {
+                    break;
+                }
+            }
+            assert_eq!(depts, vec!["\"Engineering\"", "\"Engineering\"", "\"Marketing\"", "\"Marketing\""]);
+        }
+    }
+
+    #[test]
+    fn test_eval_select_all() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let mut query = CqlQuery::new("Everything", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .select_all("e")
+                .where_cmp("e", &["salary"], CompOp::Gt, Value::Integer(80000))
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        let data = &result.instance.data["Result"];
+        assert_eq!(data.len(), 2);
+        for id in data.row_ids() {
+            assert!(matches!(data.get_attr(id, "emp_name"), Some(Value::String(_))));
+            assert!(matches!(data.get_attr(id, "salary"), Some(Value::Integer(s)) if *s > 80000));
+        }
+    }
+
+    #[test]
+    fn test_eval_computed_path_to_fk_is_integer() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        // Une colonne calculée qui lit une FK rend le catrust_id visé, comme un binding
+        let mut query = CqlQuery::new("DeptIds", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["emp_name"], Value::String("Alice".into()))
+                .bind_computed("dept_id", "e", &[], "salary", AttrExpr::Path {
+                    var: "e".into(),
+                    path: vec!["works_in".into()],
+                })
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        assert_eq!(query.result_schema.edges["Result.dept_id"], Edge::attr("Result.dept_id", "Result", BaseType::Integer));
+
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        let data = &result.instance.data["Result"];
+        let employees = &inst.data["Employee"];
+        let alice = employees.row_ids().into_iter()
+            .find(|&id| employees.get_attr(id, "emp_name") == Some(&Value::String("Alice".into())))
+            .unwrap();
+        let dept = employees.get_fk(alice, "works_in").unwrap();
+        assert_eq!(data.get_attr(data.row_ids()[0], "dept_id"), Some(&Value::Integer(dept as i64)));
+    }
+
+    #[test]
+    fn test_eval_case_insensitive_name() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let mut query = CqlQuery::new("Alice", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_fn(StringFn::Lower, "e", &["emp_name"], CompOp::Eq, Value::String("alice".into()))
+                .bind_computed("shout", "e", &[], "emp_name", AttrExpr::func(StringFn::Upper, AttrExpr::Bound))
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        let result = eval_query(&query, &inst, &schema).unwrap();
+        let data = &result.instance.data["Result"];
+        assert_eq!(data.len(), 1);
+        assert_eq!(data.get_attr(data.row_ids()[0], "shout"), Some(&Value::String("ALICE".into())));
+
+        // Sur un entier, la fonction de chaîne est une erreur
+        query.blocks[0].where_clauses[0] = WhereClause::FnComparison {
+            func: StringFn::Trim,
+            var: "e".into(),
+            path: vec!["salary".into()],
+            op: CompOp::Eq,
+            value: Value::String("90000".into()),
+        };
+        assert!(eval_query(&query, &inst, &schema).is_err());
     }
 
     #[test]
@@ -830,10 +3115,17 @@ mod tests {
                     from_var: "e".into(),
                     path: vec!["works_in".into()],
                     attribute: "dept_name".into(),
+                    expr: None,
                 }),
             ]),
             fk_bindings: HashMap::new(),
-        });
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
+        }, &schema).unwrap();
 
         let result = eval_query(&query, &inst, &schema).unwrap();
         let depts = distinct(&result, "Result", "dept");
@@ -845,11 +3137,55 @@ mod tests {
     #[test]
     fn test_cartesian_product() {
         let sets = vec![vec![1, 2], vec![10, 20]];
-        let result = cartesian_product(&sets);
+        let result: Vec<Vec<RowId>> = cartesian_product(&sets).collect();
         assert_eq!(result.len(), 4);
         assert!(result.contains(&vec![1, 10]));
         assert!(result.contains(&vec![1, 20]));
         assert!(result.contains(&vec![2, 10]));
         assert!(result.contains(&vec![2, 20]));
     }
+
+    #[test]
+    fn test_streaming_cartesian_product() {
+        // 10^10 tuples : seuls ceux demandés sont construits
+        let sets: Vec<Vec<RowId>> = vec![(0..100_000).collect(), (0..100_000).collect()];
+        let first: Vec<Vec<RowId>> = cartesian_product(&sets).take(3).collect();
+        assert_eq!(first, vec![vec![0, 0], vec![0, 1], vec![0, 2]]);
+        assert_eq!(cartesian_product(&[vec![1], vec![]]).count(), 0);
+
+        // Employee × Department : 4 × 2 = 8 tuples parcourus
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+        let mut query = CqlQuery::new("Pairs", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .from("d", "Department")
+                .where_path_eq("e", &["works_in"], "d", &[])
+                .bind("emp", "e", &[], "emp_name")
+                .bind("dept", "d", &[], "dept_name")
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+        let rows = |result: &EvalResult| {
+            let data = &result.instance.data["Result"];
+            let mut rows: Vec<String> = data.row_ids().into_iter()
+                .map(|id| format!("{}/{}", data.get_attr(id, "emp").unwrap(), data.get_attr(id, "dept").unwrap()))
+                .collect();
+            rows.sort();
+            rows
+        };
+
+        let full = eval_query(&query, &inst, &schema).unwrap();
+        let bounded = eval_query_bounded(&query, &inst, &schema, 8).unwrap();
+        assert_eq!(bounded.rows_scanned, 8);
+        assert_eq!(rows(&bounded), rows(&full));
+        assert_eq!(full.rows_returned, 4);
+
+        assert_eq!(
+            eval_query_bounded(&query, &inst, &schema, 7).unwrap_err(),
+            CatrustError::TupleLimit { entity: "Result".into(), limit: 7 },
+        );
+    }
 }
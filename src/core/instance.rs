@@ -35,8 +35,38 @@
 // =============================================================================
 
 use std::collections::HashMap;
+use std::ops::Range;
 use super::typeside::Value;
-use super::schema::Schema;
+use super::schema::{Schema, Edge};
+use super::column::ColumnStore;
+use crate::error::CatrustError;
+
+/// Attributs d'une ligne, sans `HashMap::from` ni `Value::` :
+///
+/// ```ignore
+/// inst.insert("Employee", row!{emp_name: "Alice", salary: 90000}, fks!{works_in: d1});
+/// ```
+///
+/// Chaque valeur passe par `Value::from` : `&str`/`String` → String,
+/// entier → Integer, `f64` → Float, `bool` → Boolean, `None` → NULL.
+#[macro_export]
+macro_rules! row {
+    ($($attr:ident : $value:expr),* $(,)?) => {
+        ::std::collections::HashMap::<::std::string::String, $crate::core::typeside::Value>::from([
+            $((::std::string::String::from(stringify!($attr)), $crate::core::typeside::Value::from($value))),*
+        ])
+    };
+}
+
+/// FK d'une ligne : `fks!{works_in: d1}` (nom de FK → RowId cible).
+#[macro_export]
+macro_rules! fks {
+    ($($fk:ident : $target:expr),* $(,)?) => {
+        ::std::collections::HashMap::<::std::string::String, $crate::core::instance::RowId>::from([
+            $((::std::string::String::from(stringify!($fk)), $target)),*
+        ])
+    };
+}
 
 /// Identifiant unique d'une ligne dans une table.
 /// 
@@ -51,6 +81,12 @@ pub type RowId = u64;
 ///
 /// Le champ `fk_values` stocke les FK : pour chaque FK sortante,
 /// on sait vers quel RowId de l'entité cible cette ligne pointe.
+///
+/// Deux stockages, derrière la même API (`insert`, `get_attr`, `row_ids`...) :
+/// par lignes (défaut, dans `attribute_values` et `fk_values`) ou par
+/// colonnes (`EntityData::columnar`, cf. `ColumnStore`) pour les scans
+/// analytiques. Une entité stockée par colonnes laisse ces deux champs
+/// vides : l'API les lit quel que soit le stockage.
 #[derive(Debug, Clone)]
 pub struct EntityData {
     /// Compteur pour générer les RowId auto-incrémentés
@@ -59,17 +95,68 @@ pub struct EntityData {
     pub attribute_values: HashMap<RowId, HashMap<String, Value>>,
     /// Les valeurs de FK : row_id → (fk_name → RowId cible)
     pub fk_values: HashMap<RowId, HashMap<String, RowId>>,
+    /// Les lignes, si l'entité est stockée par colonnes
+    columns: Option<ColumnStore>,
+}
+
+impl Default for EntityData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Deux entités sont égales si elles ont les mêmes lignes, quel que soit
+/// leur stockage.
+impl PartialEq for EntityData {
+    fn eq(&self, other: &Self) -> bool {
+        let mut ids = self.row_ids();
+        ids.sort();
+        let mut other_ids = other.row_ids();
+        other_ids.sort();
+        ids == other_ids && ids.iter().all(|&id| self.row(id) == other.row(id))
+    }
 }
 
 impl EntityData {
+    /// Entité vide, stockée par lignes
     pub fn new() -> Self {
         EntityData {
             next_id: 1,
             attribute_values: HashMap::new(),
             fk_values: HashMap::new(),
+            columns: None,
         }
     }
 
+    /// Entité vide, stockée par colonnes
+    pub fn columnar() -> Self {
+        EntityData { columns: Some(ColumnStore::new()), ..EntityData::new() }
+    }
+
+    /// Passe au stockage par colonnes (sans effet s'il l'est déjà) : les
+    /// lignes sont déplacées, pas copiées.
+    pub fn to_columns(&mut self) {
+        if self.columns.is_some() {
+            return;
+        }
+        let mut store = ColumnStore::new();
+        let mut ids: Vec<RowId> = self.attribute_values.keys().copied().collect();
+        ids.sort();
+        for id in ids {
+            let attrs = self.attribute_values.remove(&id).unwrap_or_default();
+            let fks = self.fk_values.remove(&id).unwrap_or_default();
+            store.insert_with_id(id, attrs, fks);
+        }
+        self.fk_values.clear();
+        store.set_next_id(self.next_id);
+        self.columns = Some(store);
+    }
+
+    /// Les colonnes, si l'entité est stockée par colonnes
+    pub fn column_store(&self) -> Option<&ColumnStore> {
+        self.columns.as_ref()
+    }
+
     /// Insère une nouvelle ligne avec ses attributs et FK.
     /// Retourne le RowId attribué.
     ///
@@ -80,6 +167,9 @@ impl EntityData {
         attrs: HashMap<String, Value>,
         fks: HashMap<String, RowId>,
     ) -> RowId {
+        if let Some(store) = &mut self.columns {
+            return store.insert(attrs, fks);
+        }
         let id = self.next_id;
         self.next_id += 1;
         self.attribute_values.insert(id, attrs);
@@ -87,6 +177,26 @@ impl EntityData {
         id
     }
 
+    /// Insère un lot de lignes d'un coup : la place est réservée une seule
+    /// fois et les lignes reçoivent des RowId consécutifs, dans l'ordre.
+    ///
+    /// Retourne l'intervalle des RowId attribués, pour câbler les FK
+    /// des lignes qui référencent le lot.
+    pub fn bulk_insert(
+        &mut self,
+        rows: Vec<(HashMap<String, Value>, HashMap<String, RowId>)>,
+    ) -> Range<RowId> {
+        let start = self.next_id();
+        if self.columns.is_none() {
+            self.attribute_values.reserve(rows.len());
+            self.fk_values.reserve(rows.len());
+        }
+        for (attrs, fks) in rows {
+            self.insert(attrs, fks);
+        }
+        start..self.next_id()
+    }
+
     /// Insère une ligne avec un RowId spécifique (utile pour les migrations)
     pub fn insert_with_id(
         &mut self,
@@ -94,6 +204,9 @@ impl EntityData {
         attrs: HashMap<String, Value>,
         fks: HashMap<String, RowId>,
     ) {
+        if let Some(store) = &mut self.columns {
+            return store.insert_with_id(id, attrs, fks);
+        }
         if id >= self.next_id {
             self.next_id = id + 1;
         }
@@ -101,34 +214,331 @@ impl EntityData {
         self.fk_values.insert(id, fks);
     }
 
+    /// Remplace les attributs et FK fournis d'une ligne, garde les autres ;
+    /// la ligne est créée si elle n'existe pas.
+    pub fn update_row(
+        &mut self,
+        row_id: RowId,
+        attrs: HashMap<String, Value>,
+        fks: HashMap<String, RowId>,
+    ) {
+        if let Some(store) = &mut self.columns {
+            if !store.contains(row_id) {
+                store.insert_with_id(row_id, attrs, fks);
+            } else {
+                store.update(row_id, attrs, fks);
+            }
+            return;
+        }
+        if row_id >= self.next_id {
+            self.next_id = row_id + 1;
+        }
+        self.attribute_values.entry(row_id).or_default().extend(attrs);
+        self.fk_values.entry(row_id).or_default().extend(fks);
+    }
+
+    /// Supprime une ligne (attributs et FK sortantes).
+    /// Retourne `false` si la ligne n'existait pas.
+    ///
+    /// Les FK des AUTRES lignes qui pointaient vers celle-ci ne sont pas
+    /// touchées : voir `Instance::delete_cascade`.
+    pub fn remove(&mut self, row_id: RowId) -> bool {
+        if let Some(store) = &mut self.columns {
+            return store.remove(row_id);
+        }
+        self.fk_values.remove(&row_id);
+        self.attribute_values.remove(&row_id).is_some()
+    }
+
     /// Nombre de lignes dans cette entité
     pub fn len(&self) -> usize {
-        self.attribute_values.len()
+        match &self.columns {
+            Some(store) => store.len(),
+            None => self.attribute_values.len(),
+        }
     }
 
     /// L'entité est-elle vide ?
     pub fn is_empty(&self) -> bool {
-        self.attribute_values.is_empty()
+        self.len() == 0
     }
 
     /// Retourne tous les RowId
     pub fn row_ids(&self) -> Vec<RowId> {
-        self.attribute_values.keys().copied().collect()
+        match &self.columns {
+            Some(store) => store.row_ids(),
+            None => self.attribute_values.keys().copied().collect(),
+        }
+    }
+
+    /// La ligne existe-t-elle ?
+    pub fn contains(&self, row_id: RowId) -> bool {
+        match &self.columns {
+            Some(store) => store.contains(row_id),
+            None => self.attribute_values.contains_key(&row_id),
+        }
     }
 
     /// Lit la valeur d'un attribut pour une ligne donnée
     pub fn get_attr(&self, row_id: RowId, attr_name: &str) -> Option<&Value> {
-        self.attribute_values
-            .get(&row_id)
-            .and_then(|attrs| attrs.get(attr_name))
+        match &self.columns {
+            Some(store) => store.get_attr(row_id, attr_name),
+            None => self.attribute_values.get(&row_id).and_then(|attrs| attrs.get(attr_name)),
+        }
     }
 
     /// Lit la cible d'une FK pour une ligne donnée
     pub fn get_fk(&self, row_id: RowId, fk_name: &str) -> Option<RowId> {
-        self.fk_values
-            .get(&row_id)
-            .and_then(|fks| fks.get(fk_name))
-            .copied()
+        match &self.columns {
+            Some(store) => store.get_fk(row_id, fk_name),
+            None => self.fk_values.get(&row_id).and_then(|fks| fks.get(fk_name)).copied(),
+        }
+    }
+
+    /// Attributs présents d'une ligne, dans un ordre quelconque
+    /// (vide si elle n'existe pas)
+    pub fn row_attrs(&self, row_id: RowId) -> Vec<(&String, &Value)> {
+        match &self.columns {
+            Some(store) => store.row_attrs(row_id),
+            None => self.attribute_values.get(&row_id).into_iter().flatten().collect(),
+        }
+    }
+
+    /// FK définies d'une ligne, dans un ordre quelconque (vide si elle
+    /// n'existe pas)
+    pub fn row_fks(&self, row_id: RowId) -> Vec<(&String, RowId)> {
+        match &self.columns {
+            Some(store) => store.row_fks(row_id),
+            None => self.fk_values.get(&row_id).into_iter().flatten().map(|(fk, &target)| (fk, target)).collect(),
+        }
+    }
+
+    /// Copie d'une ligne : attributs et FK (`None` si elle n'existe pas)
+    pub fn row(&self, row_id: RowId) -> Option<RowSnapshot> {
+        if !self.contains(row_id) {
+            return None;
+        }
+        Some(RowSnapshot {
+            attrs: self.row_attrs(row_id).into_iter().map(|(a, v)| (a.clone(), v.clone())).collect(),
+            fks: self.row_fks(row_id).into_iter().map(|(fk, target)| (fk.clone(), target)).collect(),
+        })
+    }
+
+    /// Prochain RowId attribué par `insert`
+    pub(crate) fn next_id(&self) -> RowId {
+        match &self.columns {
+            Some(store) => store.next_id(),
+            None => self.next_id,
+        }
+    }
+
+    /// Force le prochain RowId attribué par `insert`
+    pub(crate) fn set_next_id(&mut self, id: RowId) {
+        match &mut self.columns {
+            Some(store) => store.set_next_id(id),
+            None => self.next_id = id,
+        }
+    }
+}
+
+/// Profil d'une instance : volumes et qualité des données (voir `Instance::stats`)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InstanceStats {
+    /// Nombre total de lignes
+    pub total_rows: usize,
+    /// Par entité du schéma
+    pub entities: HashMap<String, EntityStats>,
+    /// Par FK du schéma
+    pub foreign_keys: HashMap<String, FkStats>,
+}
+
+/// Statistiques d'une entité
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EntityStats {
+    pub rows: usize,
+    /// Par attribut de l'entité dans le schéma
+    pub attributes: HashMap<String, AttributeStats>,
+}
+
+/// Statistiques d'un attribut
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AttributeStats {
+    /// Lignes où l'attribut est absent ou NULL
+    pub nulls: usize,
+    /// Valeurs distinctes (hors NULL)
+    pub distinct: usize,
+}
+
+/// Statistiques d'une FK
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FkStats {
+    /// Lignes sources sans valeur pour la FK
+    pub unset: usize,
+    /// Références vers une ligne cible inexistante
+    pub dangling: usize,
+}
+
+impl InstanceStats {
+    /// Nombre de lignes par entité, à passer à `SqlPlanner::with_cardinalities`
+    pub fn cardinalities(&self) -> HashMap<String, usize> {
+        self.entities.iter().map(|(name, e)| (name.clone(), e.rows)).collect()
+    }
+}
+
+impl std::fmt::Display for InstanceStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} lignes", self.total_rows)?;
+        let mut entities: Vec<(&String, &EntityStats)> = self.entities.iter().collect();
+        entities.sort_by_key(|(name, _)| *name);
+        for (name, entity) in entities {
+            writeln!(f, "  {} : {} lignes", name, entity.rows)?;
+            let mut attrs: Vec<(&String, &AttributeStats)> = entity.attributes.iter().collect();
+            attrs.sort_by_key(|(name, _)| *name);
+            for (attr, stats) in attrs {
+                writeln!(f, "    {} : {} NULL, {} distinctes", attr, stats.nulls, stats.distinct)?;
+            }
+        }
+        let mut fks: Vec<(&String, &FkStats)> = self.foreign_keys.iter().collect();
+        fks.sort_by_key(|(name, _)| *name);
+        for (fk, stats) in fks {
+            writeln!(f, "  FK {} : {} non définies, {} pendantes", fk, stats.unset, stats.dangling)?;
+        }
+        Ok(())
+    }
+}
+
+/// Différences ligne à ligne entre deux instances (voir `Instance::diff`).
+///
+/// Les lignes sont comparées par RowId : c'est leur identité.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InstanceDiff {
+    /// Par entité, seulement celles qui diffèrent
+    pub entities: HashMap<String, EntityDiff>,
+}
+
+/// Différences d'une entité, lignes triées par RowId
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EntityDiff {
+    /// Lignes nouvelles, avec leur contenu
+    pub added: Vec<(RowId, RowSnapshot)>,
+    /// Lignes disparues
+    pub removed: Vec<RowId>,
+    /// Lignes présentes des deux côtés mais modifiées
+    pub changed: Vec<RowChange>,
+}
+
+/// Modifications d'une ligne : (avant, après) pour chaque attribut ou FK
+/// qui a changé. Un attribut absent vaut NULL, une FK absente `None`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RowChange {
+    pub row: RowId,
+    pub attrs: HashMap<String, (Value, Value)>,
+    pub fks: HashMap<String, (Option<RowId>, Option<RowId>)>,
+}
+
+impl InstanceDiff {
+    /// Les deux instances sont-elles identiques ?
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+/// Nature d'une modification enregistrée dans le journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// État d'une ligne à un instant donné : attributs et FK
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RowSnapshot {
+    pub attrs: HashMap<String, Value>,
+    pub fks: HashMap<String, RowId>,
+}
+
+/// Une entrée du journal : la ligne `entity[row]` avant et après la
+/// modification (`None` avant un insert, après un delete).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub entity: String,
+    pub row: RowId,
+    pub kind: ChangeKind,
+    pub before: Option<RowSnapshot>,
+    pub after: Option<RowSnapshot>,
+}
+
+/// Parcours des lignes d'une instance (cf. `Instance::accept`), sans
+/// dépendre du stockage (lignes ou colonnes).
+pub trait InstanceVisitor {
+    /// Une entité, avant ses lignes
+    fn visit_entity(&mut self, _entity: &str, _rows: usize) {}
+    /// Une ligne : ses attributs et ses FK
+    fn visit_row(&mut self, entity: &str, row: RowId, attrs: &HashMap<String, Value>, fks: &HashMap<String, RowId>);
+}
+
+/// Attribution des RowId par `Instance::insert`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum IdPolicy {
+    /// Compteur croissant par entité : 1, 2, 3... dans l'ordre d'insertion
+    #[default]
+    Sequential,
+    /// RowId dérivé d'un hash stable du contenu de la ligne : les mêmes
+    /// lignes insérées dans un autre ordre gardent les mêmes RowId.
+    ///
+    /// `keys` : entité → attributs clés hachés ; une entité absente est
+    /// hachée sur tous ses attributs et FK. Réinsérer une clé existante
+    /// remplace la ligne (upsert idempotent). Si le RowId haché est déjà
+    /// pris par une ligne d'une autre clé (collision), on sonde les RowId
+    /// suivants.
+    ContentHash { keys: HashMap<String, Vec<String>> },
+    /// Compteur croissant partagé par toutes les entités : un RowId est
+    /// unique dans toute l'instance, pas seulement dans son entité.
+    ///
+    /// Deux lignes d'entités différentes ne partagent jamais un identifiant
+    /// (ex: `catrust_id` côté Neo4j, même sans label). Conservé par Σ.
+    Global,
+}
+
+impl IdPolicy {
+    /// ContentHash sur tout le contenu de chaque ligne
+    pub fn content_hash() -> Self {
+        IdPolicy::ContentHash { keys: HashMap::new() }
+    }
+
+    /// RowId de la ligne selon la politique (`None` : compteur séquentiel),
+    /// avant résolution des collisions (cf. `Instance::insert`)
+    fn row_id(&self, entity: &str, attrs: &HashMap<String, Value>, fks: &HashMap<String, RowId>) -> Option<RowId> {
+        let parts = self.key_parts(entity, attrs, fks)?;
+
+        // FNV-1a 64 bits : stable d'une exécution et d'une version à l'autre,
+        // contrairement au hasher de la bibliothèque standard
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in std::iter::once(entity).chain(parts.iter().map(String::as_str))
+            .flat_map(|part| part.bytes().chain(std::iter::once(0)))
+        {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        // Tient dans un BIGINT SQL ; 0 n'est jamais un RowId
+        Some((hash & i64::MAX as u64).max(1))
+    }
+
+    /// Clé hachée d'une ligne en `ContentHash` : chaque (nom, valeur), trié
+    /// par nom. Deux lignes de même clé sont la même ligne.
+    fn key_parts(&self, entity: &str, attrs: &HashMap<String, Value>, fks: &HashMap<String, RowId>) -> Option<Vec<String>> {
+        let IdPolicy::ContentHash { keys } = self else { return None };
+        let mut parts: Vec<String> = match keys.get(entity) {
+            Some(key_attrs) => key_attrs.iter()
+                .map(|a| format!("{}={}", a, attrs.get(a).unwrap_or(&Value::Null)))
+                .collect(),
+            None => attrs.iter().map(|(a, v)| format!("{}={}", a, v))
+                .chain(fks.iter().map(|(fk, target)| format!("{}->{}", fk, target)))
+                .collect(),
+        };
+        parts.sort();
+        Some(parts)
     }
 }
 
@@ -145,6 +555,23 @@ pub struct Instance {
     pub schema_name: String,
     /// Données par entité : entity_name → EntityData
     pub data: HashMap<String, EntityData>,
+    /// Journal des modifications (`None` = désactivé, cf. `with_journal`)
+    pub journal: Option<Vec<Change>>,
+    /// Attribution des RowId par `insert` (cf. `with_id_policy`)
+    pub id_policy: IdPolicy,
+    /// Version des données : change à chaque `insert` / `update` / `delete`
+    /// / `merge` (cf. `QueryCache`). Les écritures directes dans `data` ne la
+    /// changent pas : appeler `touch`.
+    pub version: u64,
+}
+
+/// Source des versions d'instance : globale, pour que deux instances (ou
+/// deux clones qui ont divergé) n'aient jamais la même version.
+static NEXT_VERSION: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+/// Version fraîche, jamais attribuée auparavant
+pub(crate) fn next_version() -> u64 {
+    NEXT_VERSION.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
 }
 
 impl Instance {
@@ -158,21 +585,393 @@ impl Instance {
             name: name.to_string(),
             schema_name: schema.name.clone(),
             data,
+            journal: None,
+            id_policy: IdPolicy::Sequential,
+            version: next_version(),
+        }
+    }
+
+    /// Signale une modification faite hors des méthodes de l'instance
+    /// (écriture directe dans `data`) : invalide les `QueryCache`.
+    pub fn touch(&mut self) {
+        self.version = next_version();
+    }
+
+    /// Choisit l'attribution des RowId par `insert` (séquentielle par défaut).
+    /// Avec `IdPolicy::ContentHash`, la même donnée reçoit le même RowId quel
+    /// que soit l'ordre d'insertion : migrations et snapshots reproductibles.
+    pub fn with_id_policy(mut self, policy: IdPolicy) -> Self {
+        self.id_policy = policy;
+        self
+    }
+
+    /// Active le journal : chaque `insert` / `update` / `delete` (et leurs
+    /// raccourcis `set_attr`, `set_fk`, `delete_cascade`) y est enregistré,
+    /// dans l'ordre. Désactivé par défaut pour éviter le surcoût.
+    ///
+    /// Les écritures directes dans `data` ne sont pas journalisées ; `merge`
+    /// journalise ses insertions, pas les FK de cycle posées après.
+    pub fn with_journal(mut self) -> Self {
+        self.journal.get_or_insert_with(Vec::new);
+        self
+    }
+
+    /// Passe chaque entité au stockage par colonnes (`EntityData::to_columns`) :
+    /// l'évaluateur filtre alors les lignes sur les colonnes avant le produit
+    /// cartésien. Pour les grosses instances à scans analytiques ; les
+    /// écritures suivantes vont directement dans les colonnes.
+    pub fn with_columns(mut self) -> Self {
+        for entity_data in self.data.values_mut() {
+            entity_data.to_columns();
+        }
+        self
+    }
+
+    /// Présente les lignes à `visitor`, entité par entité dans l'ordre de
+    /// déclaration du schéma, puis par RowId croissant.
+    pub fn accept<V: InstanceVisitor + ?Sized>(&self, schema: &Schema, visitor: &mut V) {
+        for entity in schema.node_names() {
+            let Some(entity_data) = self.data.get(entity) else { continue };
+            visitor.visit_entity(entity, entity_data.len());
+            let mut row_ids = entity_data.row_ids();
+            row_ids.sort();
+            for row in row_ids {
+                let Some(snapshot) = entity_data.row(row) else { continue };
+                visitor.visit_row(entity, row, &snapshot.attrs, &snapshot.fks);
+            }
+        }
+    }
+
+    /// Modifications enregistrées depuis l'activation du journal
+    /// (vide si le journal est désactivé).
+    pub fn journal(&self) -> &[Change] {
+        self.journal.as_deref().unwrap_or(&[])
+    }
+
+    /// État courant d'une ligne, s'il faut le journaliser
+    fn snapshot(&self, entity: &str, row: RowId) -> Option<RowSnapshot> {
+        self.journal.as_ref()?;
+        self.data.get(entity)?.row(row)
+    }
+
+    /// Ajoute une entrée au journal s'il est actif
+    fn record(&mut self, entity: &str, row: RowId, kind: ChangeKind, before: Option<RowSnapshot>) {
+        if self.journal.is_none() {
+            return;
+        }
+        let after = self.snapshot(entity, row);
+        if let Some(journal) = &mut self.journal {
+            journal.push(Change { entity: entity.to_string(), row, kind, before, after });
         }
     }
 
     /// Insère une ligne dans une entité.
-    /// Retourne le RowId attribué.
+    /// Retourne le RowId attribué (cf. `with_id_policy`).
+    ///
+    /// En `IdPolicy::ContentHash`, une ligne de même clé est remplacée :
+    /// le journal l'enregistre alors comme une mise à jour. Une ligne d'une
+    /// autre clé au RowId haché n'est jamais écrasée : on prend le premier
+    /// RowId suivant libre (ou déjà porteur de la même clé).
     pub fn insert(
         &mut self,
         entity: &str,
         attrs: HashMap<String, Value>,
         fks: HashMap<String, RowId>,
     ) -> RowId {
-        self.data
+        let Some(mut hashed) = self.id_policy.row_id(entity, &attrs, &fks) else {
+            self.align_global_counter(entity);
+            let row = self.data
+                .get_mut(entity)
+                .unwrap_or_else(|| panic!("Entité '{}' n'existe pas dans l'instance", entity))
+                .insert(attrs, fks);
+            self.touch();
+            self.record(entity, row, ChangeKind::Insert, None);
+            return row;
+        };
+
+        let entity_data = self.data
+            .get(entity)
+            .unwrap_or_else(|| panic!("Entité '{}' n'existe pas dans l'instance", entity));
+        let key = self.id_policy.key_parts(entity, &attrs, &fks);
+        while let Some(stored) = entity_data.row(hashed) {
+            if self.id_policy.key_parts(entity, &stored.attrs, &stored.fks) == key {
+                break;
+            }
+            // Collision : sondage linéaire, en restant dans 1..=i64::MAX
+            hashed = if hashed >= i64::MAX as u64 { 1 } else { hashed + 1 };
+        }
+
+        let before = self.snapshot(entity, hashed);
+        let entity_data = self.data.get_mut(entity).unwrap();
+        let replaced = entity_data.contains(hashed);
+        entity_data.insert_with_id(hashed, attrs, fks);
+        self.touch();
+        let kind = if replaced { ChangeKind::Update } else { ChangeKind::Insert };
+        self.record(entity, hashed, kind, before);
+        hashed
+    }
+
+    /// En `IdPolicy::Global`, avance le compteur de `entity` au premier
+    /// RowId libre dans toute l'instance.
+    fn align_global_counter(&mut self, entity: &str) {
+        if self.id_policy != IdPolicy::Global {
+            return;
+        }
+        let next = self.data.values().map(EntityData::next_id).max().unwrap_or(1);
+        if let Some(entity_data) = self.data.get_mut(entity) {
+            entity_data.set_next_id(next);
+        }
+    }
+
+    /// Insère un lot de lignes dans une entité (cf. `EntityData::bulk_insert`).
+    /// Retourne l'intervalle des RowId attribués, consécutifs.
+    ///
+    /// Les RowId sont toujours séquentiels : erreur en
+    /// `IdPolicy::ContentHash`, où `insert` reste la seule voie, ou si
+    /// l'entité n'existe pas. Rien n'est inséré en cas d'erreur.
+    pub fn bulk_insert(
+        &mut self,
+        entity: &str,
+        rows: Vec<(HashMap<String, Value>, HashMap<String, RowId>)>,
+    ) -> Result<Range<RowId>, CatrustError> {
+        if matches!(self.id_policy, IdPolicy::ContentHash { .. }) {
+            return Err(CatrustError::Other(format!(
+                "bulk_insert dans '{}' : RowId consécutifs incompatibles avec IdPolicy::ContentHash, utiliser insert",
+                entity
+            )));
+        }
+        if !self.data.contains_key(entity) {
+            return Err(CatrustError::Other(format!("Entité '{}' n'existe pas dans l'instance", entity)));
+        }
+        self.align_global_counter(entity);
+        let ids = self.data.get_mut(entity).unwrap().bulk_insert(rows);
+        for row in ids.clone() {
+            self.record(entity, row, ChangeKind::Insert, None);
+        }
+        self.touch();
+        Ok(ids)
+    }
+
+    /// Met à jour une ligne existante : les attributs et FK fournis remplacent
+    /// les anciennes valeurs, les autres sont conservés.
+    ///
+    /// Tout est vérifié AVANT modification : les attributs et FK doivent
+    /// partir de `entity` dans le schéma, et les cibles des FK doivent exister.
+    pub fn update(
+        &mut self,
+        entity: &str,
+        row_id: RowId,
+        attrs: HashMap<String, Value>,
+        fks: HashMap<String, RowId>,
+        schema: &Schema,
+    ) -> Result<(), String> {
+        use super::schema::Edge;
+
+        match self.data.get(entity) {
+            None => return Err(format!("Entité '{}' n'existe pas dans l'instance", entity)),
+            Some(entity_data) if !entity_data.contains(row_id) => {
+                return Err(format!("{}[{}] n'existe pas", entity, row_id));
+            }
+            Some(_) => {}
+        }
+
+        for attr_name in attrs.keys() {
+            match schema.edges.get(attr_name) {
+                Some(Edge::Attribute { source, .. }) if source == entity => {}
+                _ => {
+                    return Err(format!(
+                        "L'attribut '{}' n'existe pas sur l'entité '{}'",
+                        attr_name, entity
+                    ));
+                }
+            }
+        }
+
+        for (fk_name, target_row) in &fks {
+            let target = match schema.edges.get(fk_name) {
+                Some(Edge::ForeignKey { source, target, .. }) if source == entity => target,
+                _ => {
+                    return Err(format!(
+                        "La FK '{}' n'existe pas sur l'entité '{}'",
+                        fk_name, entity
+                    ));
+                }
+            };
+            let exists = self.data
+                .get(target)
+                .is_some_and(|d| d.contains(*target_row));
+            if !exists {
+                return Err(format!(
+                    "FK '{}' : la cible {}[{}] n'existe pas",
+                    fk_name, target, target_row
+                ));
+            }
+        }
+
+        let before = self.snapshot(entity, row_id);
+        self.data.get_mut(entity).unwrap().update_row(row_id, attrs, fks);
+        self.touch();
+        self.record(entity, row_id, ChangeKind::Update, before);
+        Ok(())
+    }
+
+    /// Modifie un seul attribut d'une ligne (raccourci pour `update`).
+    pub fn set_attr(
+        &mut self,
+        entity: &str,
+        row_id: RowId,
+        attr_name: &str,
+        value: Value,
+        schema: &Schema,
+    ) -> Result<(), String> {
+        self.update(
+            entity, row_id,
+            HashMap::from([(attr_name.to_string(), value)]),
+            HashMap::new(),
+            schema,
+        )
+    }
+
+    /// Modifie une seule FK d'une ligne (raccourci pour `update`).
+    pub fn set_fk(
+        &mut self,
+        entity: &str,
+        row_id: RowId,
+        fk_name: &str,
+        target_row: RowId,
+        schema: &Schema,
+    ) -> Result<(), String> {
+        self.update(
+            entity, row_id,
+            HashMap::new(),
+            HashMap::from([(fk_name.to_string(), target_row)]),
+            schema,
+        )
+    }
+
+    /// Supprime une ligne d'une entité.
+    ///
+    /// Les lignes qui la référençaient gardent une FK pendante :
+    /// `validate_instance` la signalera.
+    pub fn delete(&mut self, entity: &str, row_id: RowId) -> Result<(), CatrustError> {
+        let before = self.snapshot(entity, row_id);
+        let entity_data = self.data
             .get_mut(entity)
-            .unwrap_or_else(|| panic!("Entité '{}' n'existe pas dans l'instance", entity))
-            .insert(attrs, fks)
+            .ok_or_else(|| format!("Entité '{}' n'existe pas dans l'instance", entity))?;
+        if entity_data.remove(row_id) {
+            self.touch();
+            self.record(entity, row_id, ChangeKind::Delete, before);
+            Ok(())
+        } else {
+            Err(format!("{}[{}] n'existe pas", entity, row_id).into())
+        }
+    }
+
+    /// Supprime une ligne ET, récursivement, toutes les lignes dont une FK
+    /// pointe vers une ligne supprimée (ON DELETE CASCADE).
+    ///
+    /// Retourne la liste des lignes supprimées (la ligne initiale en premier).
+    pub fn delete_cascade(
+        &mut self,
+        entity: &str,
+        row_id: RowId,
+        schema: &Schema,
+    ) -> Result<Vec<(String, RowId)>, CatrustError> {
+        self.delete(entity, row_id)?;
+
+        let index = self.reverse_fk_index(schema);
+        let mut deleted = vec![(entity.to_string(), row_id)];
+        let mut i = 0;
+        while i < deleted.len() {
+            let key = deleted[i].clone();
+            i += 1;
+            for (src_entity, src_row) in index.get(&key).into_iter().flatten() {
+                // La ligne a pu être supprimée via une autre FK (cycle, multi-FK)
+                if self.delete(src_entity, *src_row).is_ok() {
+                    deleted.push((src_entity.clone(), *src_row));
+                }
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    /// Index inverse des FK : (entité cible, ligne cible) → lignes qui la référencent.
+    pub fn reverse_fk_index(&self, schema: &Schema) -> HashMap<(String, RowId), Vec<(String, RowId)>> {
+        let mut index: HashMap<(String, RowId), Vec<(String, RowId)>> = HashMap::new();
+        for (entity_name, entity_data) in &self.data {
+            for row_id in entity_data.row_ids() {
+                for (fk_name, target_row) in entity_data.row_fks(row_id) {
+                    if let Some(super::schema::Edge::ForeignKey { target, .. }) = schema.edges.get(fk_name) {
+                        index
+                            .entry((target.clone(), target_row))
+                            .or_default()
+                            .push((entity_name.clone(), row_id));
+                    }
+                }
+            }
+        }
+        index
+    }
+
+    /// Union disjointe : ajoute toutes les lignes de `other` (même schéma)
+    /// avec de NOUVEAUX RowId, et réécrit ses FK vers ces nouveaux ID.
+    ///
+    /// Retourne la table de traduction (entité, ancien id) → nouvel id,
+    /// pour que l'appelant puisse corriger ses propres références.
+    /// Une FK de `other` qui pointait vers une ligne inexistante est
+    /// abandonnée (`validate_instance` la signalera comme manquante).
+    ///
+    /// Les lignes passent par `insert`, donc par `id_policy` de `self` :
+    /// RowId uniques dans toute l'instance en `IdPolicy::Global` ; en
+    /// `IdPolicy::ContentHash`, une ligne de même clé qu'une ligne existante
+    /// la remplace au lieu de s'ajouter. Les entités sont parcourues dans
+    /// l'ordre topologique pour que les FK comptent dans le hash ; seules
+    /// celles d'un cycle, vers une ligne pas encore insérée, sont posées après.
+    pub fn merge(&mut self, other: &Instance, schema: &Schema) -> HashMap<(String, RowId), RowId> {
+        self.touch();
+        let mut translation: HashMap<(String, RowId), RowId> = HashMap::new();
+
+        let mut entities = schema.topological_order();
+        let mut extra: Vec<String> = other.data.keys().filter(|e| !entities.contains(e)).cloned().collect();
+        extra.sort();
+        entities.extend(extra);
+
+        // Phase 1 : insérer les lignes, FK traduites quand leur cible l'est déjà
+        let mut deferred: Vec<(&String, RowId, &String, &String, RowId)> = Vec::new();
+        for entity_name in &entities {
+            let Some(other_data) = other.data.get(entity_name) else { continue };
+            let mut row_ids = other_data.row_ids();
+            row_ids.sort();
+
+            self.data.entry(entity_name.clone()).or_default();
+            for old_id in row_ids {
+                let attrs = other_data.row(old_id).map(|row| row.attrs).unwrap_or_default();
+                let mut fks = HashMap::new();
+                for (fk_name, old_target) in other_data.row_fks(old_id) {
+                    if let Some(super::schema::Edge::ForeignKey { target, .. }) = schema.edges.get(fk_name) {
+                        match translation.get(&(target.clone(), old_target)) {
+                            Some(new_target) => {
+                                fks.insert(fk_name.clone(), *new_target);
+                            }
+                            None => deferred.push((entity_name, old_id, fk_name, target, old_target)),
+                        }
+                    }
+                }
+                let new_id = self.insert(entity_name, attrs, fks);
+                translation.insert((entity_name.clone(), old_id), new_id);
+            }
+        }
+
+        // Phase 2 : FK d'un cycle, vers des lignes insérées depuis
+        for (entity_name, old_id, fk_name, target, old_target) in deferred {
+            let Some(new_target) = translation.get(&(target.clone(), old_target)) else { continue };
+            let new_id = translation[&(entity_name.clone(), old_id)];
+            self.data.get_mut(entity_name).unwrap()
+                .update_row(new_id, HashMap::new(), HashMap::from([(fk_name.clone(), *new_target)]));
+        }
+
+        translation
     }
 
     /// Évalue un chemin (séquence de FK) depuis un RowId de départ.
@@ -212,42 +1011,134 @@ impl Instance {
         self.data.values().map(|ed| ed.len()).sum()
     }
 
-    /// Affiche l'instance de manière lisible (pour le debug)
-    pub fn display(&self, schema: &Schema) -> String {
-        let mut out = format!("instance {} : {} = {{\n", self.name, self.schema_name);
+    /// Profil de l'instance : lignes par entité, NULL et valeurs distinctes
+    /// par attribut, FK non définies ou pendantes.
+    pub fn stats(&self, schema: &Schema) -> InstanceStats {
+        let mut stats = InstanceStats { total_rows: self.total_rows(), ..Default::default() };
 
-        for (entity_name, entity_data) in &self.data {
-            if entity_data.is_empty() {
-                continue;
+        for node in schema.node_names() {
+            let data = self.data.get(node);
+            let row_ids = data.map(|d| d.row_ids()).unwrap_or_default();
+            let mut entity = EntityStats { rows: row_ids.len(), ..Default::default() };
+
+            for edge in schema.edges_from(node) {
+                match edge {
+                    Edge::Attribute { name, .. } => {
+                        let mut attr = AttributeStats::default();
+                        let mut seen = std::collections::HashSet::new();
+                        for &row_id in &row_ids {
+                            match data.and_then(|d| d.get_attr(row_id, name)) {
+                                None | Some(Value::Null) => attr.nulls += 1,
+                                Some(value) => {
+                                    seen.insert(format!("{:?}", value));
+                                }
+                            }
+                        }
+                        attr.distinct = seen.len();
+                        entity.attributes.insert(name.clone(), attr);
+                    }
+                    Edge::ForeignKey { name, target, .. } => {
+                        let mut fk = FkStats::default();
+                        let targets = self.data.get(target);
+                        for &row_id in &row_ids {
+                            match data.and_then(|d| d.get_fk(row_id, name)) {
+                                None => fk.unset += 1,
+                                Some(target_id) => {
+                                    if !targets.is_some_and(|t| t.contains(target_id)) {
+                                        fk.dangling += 1;
+                                    }
+                                }
+                            }
+                        }
+                        stats.foreign_keys.insert(name.clone(), fk);
+                    }
+                }
             }
-            out.push_str(&format!("  {} ({} lignes):\n", entity_name, entity_data.len()));
+            stats.entities.insert(node.clone(), entity);
+        }
 
-            for row_id in entity_data.row_ids() {
-                out.push_str(&format!("    [{}]", row_id));
+        stats
+    }
 
-                // Attributs
-                if let Some(attrs) = entity_data.attribute_values.get(&row_id) {
-                    for (attr_name, value) in attrs {
-                        out.push_str(&format!(" {}: {},", attr_name, value));
+    /// Différences de `self` vers `other` : lignes ajoutées, supprimées, et
+    /// attributs / FK modifiés, pour chaque entité du schéma.
+    ///
+    /// Permet de ne pousser que ce qui a changé (cf. `SqlBackend::export_diff`).
+    pub fn diff(&self, other: &Instance, schema: &Schema) -> InstanceDiff {
+        let mut diff = InstanceDiff::default();
+        let empty = EntityData::new();
+
+        for node in schema.node_names() {
+            let old = self.data.get(node).unwrap_or(&empty);
+            let new = other.data.get(node).unwrap_or(&empty);
+            let mut entity = EntityDiff::default();
+
+            let mut old_ids = old.row_ids();
+            old_ids.sort();
+            let mut new_ids = new.row_ids();
+            new_ids.sort();
+
+            for &row in &new_ids {
+                if !old.contains(row) {
+                    if let Some(snapshot) = new.row(row) {
+                        entity.added.push((row, snapshot));
                     }
                 }
+            }
 
-                // FK
-                if let Some(fks) = entity_data.fk_values.get(&row_id) {
-                    for (fk_name, target_id) in fks {
-                        // Trouver le nom de l'entité cible
-                        let target_entity = if let Some(edge) = schema.edges.get(fk_name) {
-                            match edge {
-                                super::schema::Edge::ForeignKey { target, .. } => target.clone(),
-                                _ => "?".to_string(),
+            for &row in &old_ids {
+                if !new.contains(row) {
+                    entity.removed.push(row);
+                    continue;
+                }
+                let mut change = RowChange { row, ..Default::default() };
+                for edge in schema.edges_from(node) {
+                    match edge {
+                        Edge::Attribute { name, .. } => {
+                            let before = old.get_attr(row, name).cloned().unwrap_or(Value::Null);
+                            let after = new.get_attr(row, name).cloned().unwrap_or(Value::Null);
+                            if before != after {
+                                change.attrs.insert(name.clone(), (before, after));
+                            }
+                        }
+                        Edge::ForeignKey { name, .. } => {
+                            let before = old.get_fk(row, name);
+                            let after = new.get_fk(row, name);
+                            if before != after {
+                                change.fks.insert(name.clone(), (before, after));
                             }
-                        } else {
-                            "?".to_string()
-                        };
-                        out.push_str(&format!(" {} -> {}[{}],", fk_name, target_entity, target_id));
+                        }
                     }
                 }
+                if !change.attrs.is_empty() || !change.fks.is_empty() {
+                    entity.changed.push(change);
+                }
+            }
+
+            if entity != EntityDiff::default() {
+                diff.entities.insert(node.clone(), entity);
+            }
+        }
+
+        diff
+    }
 
+    /// Affiche l'instance de manière lisible (pour le debug).
+    ///
+    /// L'affichage est déterministe : entités par nom, lignes par RowId,
+    /// attributs et FK dans l'ordre de déclaration du schéma.
+    pub fn display(&self, schema: &Schema) -> String {
+        let mut out = format!("instance {} : {} = {{\n", self.name, self.schema_name);
+
+        for (entity_name, entity_data) in self.sorted_entities() {
+            out.push_str(&format!("  {} ({} lignes):\n", entity_name, entity_data.len()));
+            let mut row_ids = entity_data.row_ids();
+            row_ids.sort();
+            for row_id in row_ids {
+                out.push_str(&format!("    [{}]", row_id));
+                for field in row_fields(schema, entity_name, entity_data, row_id) {
+                    out.push_str(&format!(" {},", field));
+                }
                 out.push('\n');
             }
         }
@@ -255,6 +1146,268 @@ impl Instance {
         out.push_str("}\n");
         out
     }
+
+    /// Variante compacte et déterministe de `display` : une ligne par ligne
+    /// de données, ex: `Employee[3] emp_name: "Alice", works_in -> Department[1]`.
+    pub fn display_compact(&self, schema: &Schema) -> String {
+        let mut out = String::new();
+        for (entity_name, entity_data) in self.sorted_entities() {
+            let mut row_ids = entity_data.row_ids();
+            row_ids.sort();
+            for row_id in row_ids {
+                let fields = row_fields(schema, entity_name, entity_data, row_id);
+                out.push_str(&format!("{}[{}] {}\n", entity_name, row_id, fields.join(", ")));
+            }
+        }
+        out
+    }
+
+    /// Entités non vides, triées par nom
+    fn sorted_entities(&self) -> Vec<(&String, &EntityData)> {
+        let mut entities: Vec<(&String, &EntityData)> = self.data.iter()
+            .filter(|(_, ed)| !ed.is_empty())
+            .collect();
+        entities.sort_by_key(|(name, _)| *name);
+        entities
+    }
+}
+
+/// Champs affichés d'une ligne (`attr: valeur`, `fk -> Cible[id]`) : d'abord
+/// dans l'ordre de déclaration du schéma, puis ceux que le schéma ne connaît
+/// pas, par nom.
+fn row_fields(schema: &Schema, entity: &str, entity_data: &EntityData, row_id: RowId) -> Vec<String> {
+    let attrs = entity_data.row_attrs(row_id);
+    let fks = entity_data.row_fks(row_id);
+    let mut fields = Vec::new();
+
+    let declared = schema.edges_from(entity);
+    for edge in &declared {
+        match edge {
+            Edge::Attribute { name, .. } => {
+                if let Some(value) = entity_data.get_attr(row_id, name) {
+                    fields.push(format!("{}: {}", name, value));
+                }
+            }
+            Edge::ForeignKey { name, target, .. } => {
+                if let Some(target_id) = entity_data.get_fk(row_id, name) {
+                    fields.push(format!("{} -> {}[{}]", name, target, target_id));
+                }
+            }
+        }
+    }
+
+    let is_declared = |name: &str| declared.iter().any(|e| e.name() == name);
+    let mut extra_attrs: Vec<(&String, &Value)> = attrs.into_iter()
+        .filter(|(name, _)| !is_declared(name))
+        .collect();
+    extra_attrs.sort_by_key(|(name, _)| *name);
+    fields.extend(extra_attrs.into_iter().map(|(name, value)| format!("{}: {}", name, value)));
+
+    let mut extra_fks: Vec<(&String, RowId)> = fks.into_iter()
+        .filter(|(name, _)| !is_declared(name))
+        .collect();
+    extra_fks.sort();
+    fields.extend(extra_fks.into_iter().map(|(name, target_id)| format!("{} -> ?[{}]", name, target_id)));
+
+    fields
+}
+
+// =============================================================================
+// EXPORT ARROW (feature `arrow`)
+// =============================================================================
+//
+// Une entité = un RecordBatch : `catrust_id`, puis les attributs, puis les FK
+// (chacun par ordre alphabétique), une ligne par RowId croissant.
+// Value::Null (ou une valeur absente) → null Arrow.
+
+#[cfg(feature = "arrow")]
+impl Instance {
+    /// Exporte les lignes d'une entité en RecordBatch Arrow.
+    ///
+    /// Types : String/Custom/Enum → Utf8, Integer → Int64, Float → Float64,
+    /// Boolean → Boolean ; clé technique et FK → Int64.
+    pub fn to_record_batch(
+        &self,
+        schema: &Schema,
+        entity: &str,
+    ) -> Result<arrow::record_batch::RecordBatch, String> {
+        use std::sync::Arc;
+        use arrow::array::{ArrayRef, BooleanBuilder, Float64Builder, Int64Builder, StringBuilder};
+        use arrow::datatypes::{DataType, Field, Schema as ArrowSchema};
+        use super::schema::Edge;
+        use super::typeside::BaseType;
+
+        let entity_data = self.data.get(entity)
+            .ok_or_else(|| format!("Entité '{}' absente de l'instance", entity))?;
+        let mut row_ids = entity_data.row_ids();
+        row_ids.sort();
+
+        let mut fields = vec![Field::new("catrust_id", DataType::Int64, false)];
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(
+            row_ids.iter().map(|id| *id as i64).collect::<arrow::array::Int64Array>(),
+        )];
+
+        // Attributs
+        let mut attrs: Vec<(&String, &BaseType)> = schema.attributes_of(entity).into_iter()
+            .filter_map(|e| match e {
+                Edge::Attribute { name, target, .. } => Some((name, target)),
+                _ => None,
+            })
+            .collect();
+        attrs.sort_by_key(|(name, _)| *name);
+
+        for (attr, ty) in attrs {
+            let values = row_ids.iter().map(|id| entity_data.get_attr(*id, attr).unwrap_or(&Value::Null));
+            let mismatch = |v: &Value| format!(
+                "{}.{} : valeur {} incompatible avec le type {}", entity, attr, v, ty
+            );
+            let (data_type, column): (DataType, ArrayRef) = match ty {
+                BaseType::Integer => {
+                    let mut b = Int64Builder::new();
+                    for v in values {
+                        match v {
+                            Value::Integer(i) => b.append_value(*i),
+                            Value::Null => b.append_null(),
+                            other => return Err(mismatch(other)),
+                        }
+                    }
+                    (DataType::Int64, Arc::new(b.finish()))
+                }
+                BaseType::Float => {
+                    let mut b = Float64Builder::new();
+                    for v in values {
+                        match v {
+                            Value::Float(f) => b.append_value(*f),
+                            Value::Integer(i) => b.append_value(*i as f64),
+                            Value::Null => b.append_null(),
+                            other => return Err(mismatch(other)),
+                        }
+                    }
+                    (DataType::Float64, Arc::new(b.finish()))
+                }
+                BaseType::Boolean => {
+                    let mut b = BooleanBuilder::new();
+                    for v in values {
+                        match v {
+                            Value::Boolean(x) => b.append_value(*x),
+                            Value::Null => b.append_null(),
+                            other => return Err(mismatch(other)),
+                        }
+                    }
+                    (DataType::Boolean, Arc::new(b.finish()))
+                }
+                BaseType::Array(_) => {
+                    return Err(format!("{}.{} : type liste non supporté en Arrow", entity, attr));
+                }
+                BaseType::String | BaseType::Custom(_) | BaseType::Enum { .. } => {
+                    let mut b = StringBuilder::new();
+                    for v in values {
+                        match v {
+                            Value::String(x) => b.append_value(x),
+                            Value::Null => b.append_null(),
+                            other => b.append_value(other.to_string()),
+                        }
+                    }
+                    (DataType::Utf8, Arc::new(b.finish()))
+                }
+            };
+            fields.push(Field::new(attr.as_str(), data_type, true));
+            columns.push(column);
+        }
+
+        // FK : RowId cible, null si absente
+        let mut fks: Vec<&String> = schema.edges_from(entity).into_iter()
+            .filter_map(|e| match e {
+                Edge::ForeignKey { name, .. } => Some(name),
+                _ => None,
+            })
+            .collect();
+        fks.sort();
+
+        for fk in fks {
+            let column: arrow::array::Int64Array = row_ids.iter()
+                .map(|id| entity_data.get_fk(*id, fk).map(|t| t as i64))
+                .collect();
+            fields.push(Field::new(fk.as_str(), DataType::Int64, true));
+            columns.push(Arc::new(column));
+        }
+
+        arrow::record_batch::RecordBatch::try_new(Arc::new(ArrowSchema::new(fields)), columns)
+            .map_err(|e| format!("RecordBatch '{}' : {}", entity, e))
+    }
+}
+
+// =============================================================================
+// EXPORT JSON (feature `json`)
+// =============================================================================
+//
+// La vue logique des données, pour une API web : une entité = un tableau
+// d'objets `{ "catrust_id": 1, "emp_name": "Alice", "works_in": 2 }`, une
+// ligne par RowId croissant. Chaque attribut et FK du schéma est une clé ;
+// Value::Null (ou une valeur absente) → null.
+
+#[cfg(feature = "json")]
+impl Instance {
+    /// Exporte les lignes d'une entité en tableau d'objets JSON (tableau
+    /// vide si l'entité n'a pas de lignes).
+    ///
+    /// Les FK portent le `catrust_id` de la ligne visée ; un Float non fini
+    /// (NaN, ±∞) n'a pas d'équivalent JSON et devient null.
+    pub fn to_json(&self, schema: &Schema, entity: &str) -> serde_json::Value {
+        use super::schema::Edge;
+
+        let Some(entity_data) = self.data.get(entity) else {
+            return serde_json::Value::Array(Vec::new());
+        };
+        let mut row_ids = entity_data.row_ids();
+        row_ids.sort();
+
+        let edges = schema.edges_from(entity);
+        let rows = row_ids.into_iter()
+            .map(|id| {
+                let mut object = serde_json::Map::new();
+                object.insert("catrust_id".into(), id.into());
+                for edge in &edges {
+                    let value = match edge {
+                        Edge::Attribute { name, .. } => {
+                            entity_data.get_attr(id, name).map_or(serde_json::Value::Null, value_to_json)
+                        }
+                        Edge::ForeignKey { name, .. } => {
+                            entity_data.get_fk(id, name).map_or(serde_json::Value::Null, serde_json::Value::from)
+                        }
+                    };
+                    object.insert(edge.name().to_string(), value);
+                }
+                serde_json::Value::Object(object)
+            })
+            .collect();
+        serde_json::Value::Array(rows)
+    }
+
+    /// Exporte toutes les entités du schéma : objet JSON nom d'entité → `to_json`.
+    pub fn to_json_all(&self, schema: &Schema) -> serde_json::Value {
+        serde_json::Value::Object(
+            schema.node_names().into_iter()
+                .map(|node| (node.to_string(), self.to_json(schema, node)))
+                .collect(),
+        )
+    }
+}
+
+/// Une valeur en scalaire JSON (liste → tableau JSON)
+#[cfg(feature = "json")]
+fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::String(s) => s.clone().into(),
+        Value::Integer(i) => (*i).into(),
+        // NaN et ±∞ n'existent pas en JSON : null, comme JSON.stringify
+        Value::Float(f) if !f.is_finite() => serde_json::Value::Null,
+        Value::Float(f) => (*f).into(),
+        Value::Boolean(b) => (*b).into(),
+        Value::Array(values) => values.iter().map(value_to_json).collect(),
+        Value::Ref { row, .. } => (*row).into(),
+        Value::Null => serde_json::Value::Null,
+    }
 }
 
 // =============================================================================
@@ -277,6 +1430,28 @@ mod tests {
         s
     }
 
+    #[test]
+    fn test_row_macros() {
+        let schema = company_schema();
+        let mut inst = Instance::new("CompanyData", &schema);
+
+        let d1 = inst.insert("Department", row!{dept_name: "Engineering"}, fks!{});
+        let attrs = row!{emp_name: "Alice", salary: 90000, bonus: 0.5, active: true, nickname: None::<&str>};
+        assert_eq!(attrs, HashMap::from([
+            ("emp_name".to_string(), Value::String("Alice".into())),
+            ("salary".to_string(), Value::Integer(90000)),
+            ("bonus".to_string(), Value::Float(0.5)),
+            ("active".to_string(), Value::Boolean(true)),
+            ("nickname".to_string(), Value::Null),
+        ]));
+        assert!(matches!(attrs["salary"], Value::Integer(_)));
+        assert!(matches!(attrs["bonus"], Value::Float(_)));
+
+        let e1 = inst.insert("Employee", row!{emp_name: "Alice", salary: 90000}, fks!{works_in: d1});
+        assert_eq!(inst.data["Employee"].get_fk(e1, "works_in"), Some(d1));
+        assert_eq!(inst.data["Employee"].get_attr(e1, "emp_name"), Some(&Value::String("Alice".into())));
+    }
+
     fn company_instance(schema: &Schema) -> Instance {
         let mut inst = Instance::new("CompanyData", schema);
 
@@ -335,7 +1510,7 @@ mod tests {
         let emp_rows = inst.data["Employee"].row_ids();
         let first_emp = emp_rows[0];
         let dept_id = inst.data["Employee"].get_fk(first_emp, "works_in").unwrap();
-        assert!(inst.data["Department"].attribute_values.contains_key(&dept_id));
+        assert!(inst.data["Department"].contains(dept_id));
     }
 
     #[test]
@@ -355,6 +1530,371 @@ mod tests {
         assert!(result.is_some());
     }
 
+    #[test]
+    fn test_update_salary() {
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+
+        let emp = inst.data["Employee"].row_ids()[0];
+        let name = inst.data["Employee"].get_attr(emp, "emp_name").cloned();
+
+        inst.set_attr("Employee", emp, "salary", Value::Integer(100000), &schema).unwrap();
+        assert_eq!(inst.data["Employee"].get_attr(emp, "salary"), Some(&Value::Integer(100000)));
+        // Les autres attributs sont conservés
+        assert_eq!(inst.data["Employee"].get_attr(emp, "emp_name").cloned(), name);
+
+        // Attribut inconnu, attribut d'une autre entité, FK pendante
+        assert!(inst.set_attr("Employee", emp, "bonus", Value::Integer(1), &schema).is_err());
+        assert!(inst.set_attr("Employee", emp, "dept_name", Value::Null, &schema).is_err());
+        assert!(inst.set_fk("Employee", emp, "works_in", 999, &schema).is_err());
+        assert!(inst.set_attr("Employee", 999, "salary", Value::Integer(1), &schema).is_err());
+    }
+
+    #[test]
+    fn test_journal_records_insert_and_update() {
+        let schema = company_schema();
+        let mut inst = Instance::new("Audited", &schema).with_journal();
+
+        let emp = inst.insert("Employee",
+            HashMap::from([("salary".into(), Value::Integer(50000))]),
+            HashMap::new(),
+        );
+        inst.set_attr("Employee", emp, "salary", Value::Integer(60000), &schema).unwrap();
+
+        let journal = inst.journal();
+        assert_eq!(journal.len(), 2);
+        assert_eq!(journal[0].kind, ChangeKind::Insert);
+        assert_eq!(journal[0].before, None);
+        assert_eq!(
+            journal[0].after.as_ref().unwrap().attrs["salary"],
+            Value::Integer(50000)
+        );
+        assert_eq!(journal[1].kind, ChangeKind::Update);
+        assert_eq!((journal[1].entity.as_str(), journal[1].row), ("Employee", emp));
+        assert_eq!(
+            journal[1].before.as_ref().unwrap().attrs["salary"],
+            Value::Integer(50000)
+        );
+        assert_eq!(
+            journal[1].after.as_ref().unwrap().attrs["salary"],
+            Value::Integer(60000)
+        );
+
+        // Désactivé par défaut
+        assert!(company_instance(&schema).journal().is_empty());
+    }
+
+    #[test]
+    fn test_merge_instances() {
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+
+        let mut other = Instance::new("Other", &schema);
+        let sales = other.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Sales".into()))]),
+            HashMap::new(),
+        );
+        let dave = other.insert("Employee",
+            HashMap::from([
+                ("emp_name".into(), Value::String("Dave".into())),
+                ("salary".into(), Value::Integer(60000)),
+            ]),
+            HashMap::from([("works_in".into(), sales)]),
+        );
+
+        let translation = inst.merge(&other, &schema);
+        assert_eq!(inst.total_rows(), 7);
+        assert_eq!(translation.len(), 2);
+
+        // Dave doit toujours pointer vers SON département (Sales), pas vers
+        // le département qui avait le même RowId dans la première instance
+        let new_dave = translation[&("Employee".to_string(), dave)];
+        let dept = inst.data["Employee"].get_fk(new_dave, "works_in").unwrap();
+        assert_eq!(dept, translation[&("Department".to_string(), sales)]);
+        assert_eq!(
+            inst.data["Department"].get_attr(dept, "dept_name"),
+            Some(&Value::String("Sales".into()))
+        );
+        assert!(crate::core::validate::validate_instance(&inst, &schema).is_ok());
+    }
+
+    #[test]
+    fn test_merge_follows_id_policy() {
+        let schema = company_schema();
+
+        // Global : les lignes fusionnées gardent des RowId uniques dans
+        // toute l'instance
+        let mut inst = Instance::new("Data", &schema).with_id_policy(IdPolicy::Global);
+        let d = inst.insert("Department", crate::row! { dept_name: "R&D" }, HashMap::new());
+        inst.insert("Employee", crate::row! { emp_name: "Alice", salary: 80000 }, HashMap::from([("works_in".into(), d)]));
+        let other = company_instance(&schema);
+        let translation = inst.merge(&other, &schema);
+        let mut ids: Vec<RowId> = inst.data.values().flat_map(|data| data.row_ids()).collect();
+        let total = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), total);
+        assert_eq!(translation.len(), other.total_rows());
+        assert!(crate::core::validate::validate_instance(&inst, &schema).is_ok());
+
+        // ContentHash : fusionner deux fois les mêmes lignes ne les duplique
+        // pas, et les RowId sont ceux qu'aurait donnés un insert direct
+        let mut hashed = Instance::new("Hashed", &schema).with_id_policy(IdPolicy::content_hash());
+        hashed.merge(&other, &schema);
+        let rows = hashed.total_rows();
+        let again = hashed.merge(&other, &schema);
+        assert_eq!(hashed.total_rows(), rows);
+        let mut direct = Instance::new("Direct", &schema).with_id_policy(IdPolicy::content_hash());
+        let engineering = direct.insert("Department", crate::row! { dept_name: "Engineering" }, HashMap::new());
+        let engineering_in_other = other.data["Department"].row_ids().into_iter()
+            .find(|&id| other.data["Department"].get_attr(id, "dept_name") == Some(&Value::String("Engineering".into())))
+            .unwrap();
+        assert_eq!(again[&("Department".to_string(), engineering_in_other)], engineering);
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_to_record_batch() {
+        use arrow::datatypes::DataType;
+
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+        // Un salaire inconnu devient un null Arrow
+        let d = inst.data["Department"].row_ids()[0];
+        inst.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String("Dave".into())), ("salary".into(), Value::Null)]),
+            HashMap::from([("works_in".into(), d)]),
+        );
+
+        let batch = inst.to_record_batch(&schema, "Employee").unwrap();
+        assert_eq!(batch.num_rows(), 4);
+
+        let arrow_schema = batch.schema();
+        let columns: Vec<(&str, &DataType)> = arrow_schema.fields().iter()
+            .map(|f| (f.name().as_str(), f.data_type()))
+            .collect();
+        assert_eq!(columns, vec![
+            ("catrust_id", &DataType::Int64),
+            ("emp_name", &DataType::Utf8),
+            ("salary", &DataType::Int64),
+            ("works_in", &DataType::Int64),
+        ]);
+        assert_eq!(batch.column(2).null_count(), 1);
+        assert_eq!(batch.column(3).null_count(), 0);
+
+        assert!(inst.to_record_batch(&schema, "Unknown").is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_to_json() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        let all = inst.to_json_all(&schema);
+        let employees = all["Employee"].as_array().unwrap();
+        assert_eq!(employees.len(), 3);
+        assert_eq!(all["Department"].as_array().unwrap().len(), 2);
+
+        let mut keys: Vec<&str> = employees[0].as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["catrust_id", "emp_name", "salary", "works_in"]);
+
+        // La FK porte le catrust_id du département visé
+        for employee in employees {
+            let dept_id = employee["works_in"].as_u64().unwrap();
+            let dept = inst.data["Department"].get_attr(dept_id, "dept_name").unwrap();
+            let exported = all["Department"].as_array().unwrap().iter()
+                .find(|d| d["catrust_id"] == dept_id)
+                .unwrap();
+            assert_eq!(exported["dept_name"], value_to_json(dept));
+        }
+        let alice = employees.iter().find(|e| e["emp_name"] == "Alice").unwrap();
+        assert_eq!(alice["salary"], 80000);
+    }
+
+    #[test]
+    fn test_content_hash_ids_ignore_insertion_order() {
+        let schema = company_schema();
+        let load = |order: &[usize]| {
+            let mut inst = Instance::new("CompanyData", &schema).with_id_policy(IdPolicy::content_hash());
+            let eng = inst.insert("Department", row!{dept_name: "Engineering"}, fks!{});
+            let people = [("Alice", 80000), ("Bob", 75000), ("Charlie", 60000)];
+            let ids: HashMap<&str, RowId> = order.iter()
+                .map(|&i| {
+                    let (name, salary) = people[i];
+                    (name, inst.insert("Employee", row!{emp_name: name, salary: salary}, fks!{works_in: eng}))
+                })
+                .collect();
+            (inst, ids)
+        };
+
+        let (first, ids) = load(&[0, 1, 2]);
+        let (second, ids_reordered) = load(&[2, 0, 1]);
+        assert_eq!(ids, ids_reordered);
+        assert!(first.diff(&second, &schema).entities.is_empty());
+
+        // Clé métier : réinsérer Alice remplace sa ligne au lieu d'en ajouter une
+        let keys = HashMap::from([("Employee".to_string(), vec!["emp_name".to_string()])]);
+        let mut inst = Instance::new("CompanyData", &schema).with_id_policy(IdPolicy::ContentHash { keys });
+        let alice = inst.insert("Employee", row!{emp_name: "Alice", salary: 80000}, fks!{});
+        assert_eq!(inst.insert("Employee", row!{emp_name: "Alice", salary: 85000}, fks!{}), alice);
+        assert_eq!(inst.data["Employee"].len(), 1);
+        assert_eq!(inst.data["Employee"].get_attr(alice, "salary"), Some(&Value::Integer(85000)));
+    }
+
+    #[test]
+    fn test_content_hash_collision_probes() {
+        let schema = company_schema();
+        let mut inst = Instance::new("CompanyData", &schema).with_id_policy(IdPolicy::content_hash());
+
+        // Une autre ligne occupe déjà le RowId haché de Bob : collision forcée
+        let bob = row!{emp_name: "Bob", salary: 75000};
+        let hashed = inst.id_policy.row_id("Employee", &bob, &fks!{}).unwrap();
+        inst.data.get_mut("Employee").unwrap()
+            .insert_with_id(hashed, row!{emp_name: "Mallory", salary: 1}, fks!{});
+
+        let bob_id = inst.insert("Employee", bob.clone(), fks!{});
+        assert_ne!(bob_id, hashed);
+        assert_eq!(inst.data["Employee"].len(), 2);
+        assert_eq!(inst.data["Employee"].get_attr(hashed, "emp_name"), Some(&Value::String("Mallory".into())));
+        assert_eq!(inst.data["Employee"].get_attr(bob_id, "emp_name"), Some(&Value::String("Bob".into())));
+
+        // Réinsérer Bob retrouve sa ligne sondée au lieu d'en créer une autre
+        assert_eq!(inst.insert("Employee", bob, fks!{}), bob_id);
+        assert_eq!(inst.data["Employee"].len(), 2);
+    }
+
+    #[test]
+    fn test_counting_visitors() {
+        use crate::core::schema::{Node, Path, PathEquation, SchemaVisitor};
+
+        #[derive(Default)]
+        struct Counter {
+            nodes: Vec<String>,
+            fks: usize,
+            attrs: usize,
+            equations: usize,
+            entities: usize,
+            rows: usize,
+            fk_values: usize,
+        }
+        impl SchemaVisitor for Counter {
+            fn visit_node(&mut self, node: &Node) { self.nodes.push(node.name.clone()); }
+            fn visit_fk(&mut self, _: &str, _: &str, _: &str) { self.fks += 1; }
+            fn visit_attr(&mut self, _: &str, _: &str, _: &BaseType) { self.attrs += 1; }
+            fn visit_equation(&mut self, _: &PathEquation) { self.equations += 1; }
+        }
+        impl InstanceVisitor for Counter {
+            fn visit_entity(&mut self, _: &str, _: usize) { self.entities += 1; }
+            fn visit_row(&mut self, _: &str, _: RowId, _: &HashMap<String, Value>, fks: &HashMap<String, RowId>) {
+                self.rows += 1;
+                self.fk_values += fks.len();
+            }
+        }
+
+        let mut schema = company_schema();
+        schema.add_fk("manager", "Department", "Employee")
+              .add_path_equation(
+                  Path::new("Department", vec!["manager", "works_in"]),
+                  Path::new("Department", vec![]),
+              );
+        let mut inst = Instance::new("CompanyData", &schema);
+        let eng = inst.insert("Department", row!{dept_name: "Engineering"}, fks!{});
+        inst.insert("Employee", row!{emp_name: "Alice"}, fks!{works_in: eng});
+        inst.insert("Employee", row!{emp_name: "Bob"}, fks!{works_in: eng});
+
+        let mut counter = Counter::default();
+        schema.accept(&mut counter);
+        inst.accept(&schema, &mut counter);
+        assert_eq!(counter.nodes, vec!["Employee", "Department"]);
+        assert_eq!((counter.fks, counter.attrs, counter.equations), (2, 3, 1));
+        assert_eq!((counter.entities, counter.rows, counter.fk_values), (2, 3, 2));
+    }
+
+    #[test]
+    fn test_row_store_fields() {
+        // Stockage par lignes : les lignes restent lisibles dans les champs
+        let mut data = EntityData::new();
+        let id = data.insert(row!{emp_name: "Alice"}, fks!{works_in: 7});
+        assert_eq!(data.attribute_values[&id]["emp_name"], Value::String("Alice".into()));
+        assert_eq!(data.fk_values[&id]["works_in"], 7);
+
+        // Par colonnes : les champs se vident, l'API lit le ColumnStore
+        data.to_columns();
+        assert!(data.attribute_values.is_empty() && data.fk_values.is_empty());
+        assert_eq!(data.get_attr(id, "emp_name"), Some(&Value::String("Alice".into())));
+        assert_eq!(data.get_fk(id, "works_in"), Some(7));
+        assert_eq!(data.insert(row!{emp_name: "Bob"}, fks!{}), id + 1);
+    }
+
+    #[test]
+    fn test_bulk_insert() {
+        let schema = company_schema();
+        let mut inst = Instance::new("CompanyData", &schema).with_journal();
+        let eng = inst.insert("Department", row!{dept_name: "Engineering"}, fks!{});
+        inst.insert("Employee", row!{emp_name: "Alice"}, fks!{works_in: eng});
+
+        let rows = (0..1000)
+            .map(|i| (row!{emp_name: format!("E{}", i), salary: 1000 + i}, fks!{works_in: eng}))
+            .collect();
+        let ids = inst.bulk_insert("Employee", rows).unwrap();
+
+        // Intervalle consécutif, après la ligne déjà présente
+        assert_eq!(ids, 2..1002);
+        assert_eq!(inst.data["Employee"].len(), 1001);
+        for (i, id) in ids.clone().enumerate() {
+            let employee = &inst.data["Employee"];
+            assert_eq!(employee.get_attr(id, "emp_name"), Some(&Value::String(format!("E{}", i))));
+            assert_eq!(employee.get_fk(id, "works_in"), Some(eng));
+        }
+        assert_eq!(inst.journal().len(), 1002);
+
+        // Les insertions suivantes continuent après le lot
+        assert_eq!(inst.insert("Employee", row!{emp_name: "Zoe"}, fks!{}), ids.end);
+
+        // Entité inconnue, ou RowId dérivés du contenu : erreur, rien d'inséré
+        assert!(inst.bulk_insert("Project", vec![(row!{emp_name: "X"}, fks!{})]).is_err());
+        let mut hashed = Instance::new("Hashed", &schema).with_id_policy(IdPolicy::content_hash());
+        let err = hashed.bulk_insert("Department", vec![(row!{dept_name: "R&D"}, fks!{})]).unwrap_err();
+        assert!(err.to_string().contains("ContentHash"), "{}", err);
+        assert_eq!(hashed.data["Department"].len(), 0);
+    }
+
+    #[test]
+    fn test_delete_cascade() {
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+
+        let engineering = inst.data["Department"].row_ids().into_iter()
+            .find(|&id| inst.data["Department"].get_attr(id, "dept_name")
+                == Some(&Value::String("Engineering".into())))
+            .unwrap();
+
+        let deleted = inst.delete_cascade("Department", engineering, &schema).unwrap();
+        assert_eq!(deleted.len(), 3); // Engineering + Alice + Bob
+        assert_eq!(inst.data["Department"].len(), 1);
+        assert_eq!(inst.data["Employee"].len(), 1);
+
+        let remaining = inst.data["Employee"].row_ids()[0];
+        assert_eq!(
+            inst.data["Employee"].get_attr(remaining, "emp_name"),
+            Some(&Value::String("Charlie".into()))
+        );
+        assert!(crate::core::validate::validate_instance(&inst, &schema).is_ok());
+    }
+
+    #[test]
+    fn test_delete_leaves_dangling_fk() {
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+
+        let dept = inst.data["Department"].row_ids()[0];
+        inst.delete("Department", dept).unwrap();
+        assert!(inst.delete("Department", dept).is_err());
+        assert!(crate::core::validate::validate_instance(&inst, &schema).is_err());
+    }
+
     #[test]
     fn test_display() {
         let schema = company_schema();
@@ -363,4 +1903,81 @@ mod tests {
         assert!(display.contains("Employee"));
         assert!(display.contains("Department"));
     }
+
+    #[test]
+    fn test_stats() {
+        let schema = company_schema();
+        let mut inst = company_instance(&schema);
+        inst.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String("Dan".into()))]),
+            HashMap::new(),
+        );
+
+        let stats = inst.stats(&schema);
+        assert_eq!(stats.total_rows, 6);
+        assert_eq!(stats.entities["Employee"].rows, 4);
+        assert_eq!(stats.entities["Department"].rows, 2);
+        assert_eq!(stats.entities["Employee"].attributes["salary"], AttributeStats { nulls: 1, distinct: 3 });
+        assert_eq!(stats.foreign_keys["works_in"], FkStats { unset: 1, dangling: 0 });
+        assert_eq!(stats.cardinalities()["Employee"], 4);
+    }
+
+    #[test]
+    fn test_diff() {
+        let schema = company_schema();
+        let old = company_instance(&schema);
+        let mut new = old.clone();
+
+        let alice = old.data["Employee"].row_ids().into_iter()
+            .find(|&id| old.data["Employee"].get_attr(id, "emp_name") == Some(&Value::String("Alice".into())))
+            .unwrap();
+        new.set_attr("Employee", alice, "salary", Value::Integer(85000), &schema).unwrap();
+        let dan = new.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String("Dan".into()))]),
+            HashMap::new(),
+        );
+
+        let diff = old.diff(&new, &schema);
+        assert_eq!(diff.entities.len(), 1, "Department est inchangé");
+        let employees = &diff.entities["Employee"];
+        assert_eq!(employees.added.len(), 1);
+        assert_eq!(employees.added[0].0, dan);
+        assert_eq!(employees.added[0].1.attrs["emp_name"], Value::String("Dan".into()));
+        assert!(employees.removed.is_empty());
+        assert_eq!(employees.changed, vec![RowChange {
+            row: alice,
+            attrs: HashMap::from([(
+                "salary".to_string(),
+                (Value::Integer(80000), Value::Integer(85000)),
+            )]),
+            fks: HashMap::new(),
+        }]);
+
+        assert!(old.diff(&old, &schema).is_empty());
+        assert_eq!(new.diff(&old, &schema).entities["Employee"].removed, vec![dan]);
+    }
+
+    #[test]
+    fn test_display_is_deterministic() {
+        let schema = company_schema();
+        let inst = company_instance(&schema);
+
+        // Deux instances identiques ont des HashMap d'ordres différents
+        assert_eq!(inst.display(&schema), company_instance(&schema).display(&schema));
+        assert_eq!(inst.display(&schema), inst.display(&schema));
+
+        // Entités par nom, lignes par RowId, champs dans l'ordre déclaré
+        let compact = inst.display_compact(&schema);
+        let lines: Vec<&str> = compact.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("Department["));
+        assert!(lines[2].starts_with("Employee["));
+        assert!(lines[2].contains("works_in -> Department["), "{}", lines[2]);
+        let (works_in, name, salary) = (
+            lines[2].find("works_in").unwrap(),
+            lines[2].find("emp_name").unwrap(),
+            lines[2].find("salary").unwrap(),
+        );
+        assert!(works_in < name && name < salary, "{}", lines[2]);
+    }
 }
@@ -41,15 +41,20 @@
 //
 // =============================================================================
 
-use std::collections::HashMap;
-use super::schema::{Schema, Path, Edge};
+use std::collections::{HashMap, HashSet};
+use super::schema::{Schema, Path, Edge, SchemaDiff};
+use super::optimize::PathOptimizer;
+use super::typeside::{BaseType, Value};
+use crate::error::CatrustError;
 
 /// Correspondance pour une arête : vers quel chemin dans le schéma cible
 /// cette arête est-elle envoyée ?
 ///
 /// - Pour une FK : on mappe vers un chemin de FK dans T
 /// - Pour un attribut : on mappe vers un chemin se terminant par un attribut dans T
-#[derive(Debug, Clone)]
+/// - Les attributs de T sans équivalent dans S (constantes, dénormalisation)
+///   sont à part, dans `Mapping::filled_attrs`
+#[derive(Debug, Clone, PartialEq)]
 pub enum EdgeMapping {
     /// FK mappée vers un chemin de FK dans le schéma cible
     FkToPath(Path),
@@ -60,6 +65,45 @@ pub enum EdgeMapping {
         /// Nom de l'attribut final dans T
         attr_name: String,
     },
+    /// Relation plusieurs-à-plusieurs de S envoyée sur un nœud pont de T
+    /// (cf. `bridge_many_to_many`).
+    ///
+    /// La clé associée est un attribut de S de type `[Integer]` : la liste
+    /// des RowId de `related` liés à la ligne. Lors de Σ, chaque élément
+    /// devient une ligne du nœud pont avec ses deux FK.
+    Bridge(BridgeMapping),
+}
+
+/// Remplissage, lors de Σ, d'un attribut de T qui n'est l'image d'aucune
+/// arête de S (cf. `Mapping::filled_attrs`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttrFill {
+    /// Valeur constante : une nouvelle colonne ajoutée par l'évolution du
+    /// schéma (ex: `source_system = 'legacy'`)
+    Constant(Value),
+    /// Attribut de S lu, depuis la ligne source, au bout d'un chemin de FK
+    /// de S : c'est la dénormalisation construite par `flatten_fk`
+    /// (ex: `Employee.works_in_dept_name` lu à travers `works_in`).
+    SourcePath {
+        /// Chemin de FK à suivre dans S depuis le nœud source
+        fk_path: Vec<String>,
+        /// Nom de l'attribut de S lu au bout du chemin
+        attr_name: String,
+    },
+}
+
+/// Nœud pont d'une relation plusieurs-à-plusieurs (`EdgeMapping::Bridge`) :
+/// une ligne de `node` par paire (ligne source, ligne listée).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BridgeMapping {
+    /// Nœud de S dont la relation liste les RowId
+    pub related: String,
+    /// Nœud pont dans T
+    pub node: String,
+    /// FK du pont vers l'image de la ligne qui porte la relation
+    pub from: String,
+    /// FK du pont vers l'image de la ligne listée
+    pub to: String,
 }
 
 /// Un Mapping F : source_schema → target_schema.
@@ -79,6 +123,31 @@ pub struct Mapping {
     pub node_mapping: HashMap<String, String>,
     /// Correspondance des arêtes : edge_name_S → EdgeMapping dans T
     pub edge_mapping: HashMap<String, EdgeMapping>,
+    /// Attributs de T remplis par Σ sans être l'image d'une arête de S :
+    /// attr_T → remplissage. Clés séparées de `edge_mapping`, où une arête
+    /// de S peut porter le même nom.
+    pub filled_attrs: HashMap<String, AttrFill>,
+    /// Clés métier de nœuds de T : node_T → attributs de T (cf. `with_key`)
+    pub keys: HashMap<String, Vec<String>>,
+}
+
+/// Couverture du schéma cible par un mapping (`mapping.image_coverage(&target)`).
+///
+/// Un nœud de T qui n'est l'image d'aucun nœud de S restera vide après Σ ;
+/// une arête de T jamais atteinte restera NULL.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coverage {
+    /// Nœuds de T qui ne sont l'image d'aucun nœud de S (triés)
+    pub uncovered_nodes: Vec<String>,
+    /// Arêtes de T qui n'apparaissent dans aucune image (triées)
+    pub uncovered_edges: Vec<String>,
+}
+
+impl Coverage {
+    /// Vrai si tout le schéma cible est atteint par le mapping.
+    pub fn is_total(&self) -> bool {
+        self.uncovered_nodes.is_empty() && self.uncovered_edges.is_empty()
+    }
 }
 
 impl Mapping {
@@ -90,7 +159,253 @@ impl Mapping {
             target_schema_name: target.to_string(),
             node_mapping: HashMap::new(),
             edge_mapping: HashMap::new(),
+            filled_attrs: HashMap::new(),
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Construit le foncteur identité id_S : S → S.
+    ///
+    /// Chaque nœud est envoyé sur lui-même, chaque FK sur le chemin de
+    /// longueur 1 `[fk]` et chaque attribut sur lui-même.
+    pub fn identity(schema: &Schema) -> Self {
+        let mut m = Mapping::new(&format!("id_{}", schema.name), &schema.name, &schema.name);
+        for node_name in schema.nodes.keys() {
+            m.map_node(node_name, node_name);
+        }
+        for edge in schema.edges.values() {
+            match edge {
+                Edge::ForeignKey { name, source, .. } => {
+                    m.map_fk(name, Path::new(source, vec![name]));
+                }
+                Edge::Attribute { name, .. } => {
+                    m.map_attr_direct(name, name);
+                }
+            }
+        }
+        m
+    }
+
+    /// Propose un mapping partiel S → T par similarité de noms.
+    ///
+    /// Heuristique :
+    /// 1. Les nœuds sont appariés par similarité de noms (insensible à la casse,
+    ///    au snake_case/camelCase, abréviations `dept`/`department`, distance d'édition).
+    /// 2. La structure propage les correspondances : si `f : A → B` dans S et
+    ///    `g : A' → B'` dans T avec B ↔ B' déjà apparié, alors A et A' sont
+    ///    appariés s'ils sont l'unique candidat l'un de l'autre.
+    /// 3. Chaque FK de S est envoyée sur une FK de T entre les nœuds images
+    ///    (la seule candidate, ou la plus proche par le nom).
+    /// 4. Les attributs sont appariés au sein des nœuds images, en ignorant
+    ///    le nom du nœud dans celui de l'attribut (`age` ↔ `employee_age`).
+    ///
+    /// Seules les correspondances jugées fiables sont retenues : le mapping
+    /// retourné est à compléter par l'utilisateur.
+    pub fn suggest(source: &Schema, target: &Schema) -> Mapping {
+        let mut m = Mapping::new(
+            &format!("{}_to_{}", source.name, target.name),
+            &source.name,
+            &target.name,
+        );
+
+        // 1. Nœuds par le nom
+        let src_nodes: Vec<&String> = source.nodes.keys().collect();
+        let tgt_nodes: Vec<&String> = target.nodes.keys().collect();
+        for (src, tgt) in best_matches(&src_nodes, &tgt_nodes, name_similarity) {
+            m.map_node(src, tgt);
+        }
+
+        // 2. Propagation structurelle le long des FK (jusqu'au point fixe)
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for src_fk in source.foreign_keys() {
+                let Edge::ForeignKey { source: a, target: b, .. } = src_fk else { continue };
+                let (a_mapped, b_mapped) = (m.node_mapping.get(a).cloned(), m.node_mapping.get(b).cloned());
+                let mapped_tgts: Vec<&String> = m.node_mapping.values().collect();
+
+                let candidates: Vec<(String, String)> = target.foreign_keys().into_iter()
+                    .filter_map(|e| match e {
+                        Edge::ForeignKey { source: a2, target: b2, .. } => Some((a2.clone(), b2.clone())),
+                        _ => None,
+                    })
+                    .filter(|(a2, b2)| match (&a_mapped, &b_mapped) {
+                        (None, Some(b_img)) => b2 == b_img && !mapped_tgts.contains(&a2),
+                        (Some(a_img), None) => a2 == a_img && !mapped_tgts.contains(&b2),
+                        _ => false,
+                    })
+                    .collect();
+
+                if candidates.len() == 1 {
+                    let (a2, b2) = &candidates[0];
+                    if a_mapped.is_none() {
+                        m.map_node(a, a2);
+                    } else {
+                        m.map_node(b, b2);
+                    }
+                    changed = true;
+                    break;
+                }
+            }
+        }
+
+        // 3. FK entre nœuds images
+        for src_fk in source.foreign_keys() {
+            let Edge::ForeignKey { name, source: a, target: b, .. } = src_fk else { continue };
+            let (Some(a_img), Some(b_img)) = (m.node_mapping.get(a), m.node_mapping.get(b)) else { continue };
+
+            let candidates: Vec<&str> = target.edges_from(a_img).into_iter()
+                .filter_map(|e| match e {
+                    Edge::ForeignKey { name, target, .. } if target == b_img => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect();
+
+            let chosen = if candidates.len() == 1 {
+                Some(candidates[0])
+            } else {
+                candidates.iter()
+                    .map(|c| (*c, name_similarity(name, c)))
+                    .filter(|(_, score)| *score >= SUGGEST_THRESHOLD)
+                    .max_by(|x, y| x.1.partial_cmp(&y.1).unwrap_or(std::cmp::Ordering::Equal))
+                    .map(|(c, _)| c)
+            };
+            if let Some(fk) = chosen {
+                m.map_fk(name, Path::new(a_img, vec![fk]));
+            }
+        }
+
+        // 4. Attributs au sein des nœuds images
+        for (src_node, tgt_node) in m.node_mapping.clone() {
+            let src_attrs: Vec<String> = source.attributes_of(&src_node).iter()
+                .map(|e| e.name().to_string()).collect();
+            let tgt_attrs: Vec<String> = target.attributes_of(&tgt_node).iter()
+                .map(|e| e.name().to_string()).collect();
+            let src_refs: Vec<&String> = src_attrs.iter().collect();
+            let tgt_refs: Vec<&String> = tgt_attrs.iter().collect();
+
+            let matches = best_matches(&src_refs, &tgt_refs, |a, b| {
+                let raw = name_similarity(a, b);
+                let stripped = token_similarity(
+                    &strip_tokens(a, &src_node),
+                    &strip_tokens(b, &tgt_node),
+                );
+                raw.max(stripped)
+            });
+            for (src_attr, tgt_attr) in matches {
+                m.map_attr_direct(src_attr, tgt_attr);
+            }
+        }
+
+        m
+    }
+
+    /// Propose un mapping S → T à partir de `source.diff(target)`.
+    ///
+    /// - Nœuds et arêtes conservés (même nom) : envoyés sur eux-mêmes
+    /// - Renommages détectés par le diff : envoyés sur le nouveau nom
+    /// - Structure nouvelle de T : non mappée (à remplir par `map_attr_const`)
+    /// - Structure supprimée de S : non mappée, listée par `unmapped`
+    ///
+    /// Un attribut dont le type a changé est mappé tel quel : la conversion
+    /// reste à la charge de l'utilisateur.
+    pub fn from_diff(diff: &SchemaDiff, source: &Schema, target: &Schema) -> Mapping {
+        let mut m = Mapping::new(
+            &format!("{}_to_{}", source.name, target.name),
+            &source.name,
+            &target.name,
+        );
+
+        let renamed = |pairs: &[(String, String)], name: &str| -> Option<String> {
+            pairs.iter().find(|(old, _)| old == name).map(|(_, new)| new.clone())
+        };
+
+        for node in source.nodes.keys() {
+            if let Some(new) = renamed(&diff.renamed_nodes, node) {
+                m.map_node(node, &new);
+            } else if !diff.removed_nodes.contains(node) && target.nodes.contains_key(node) {
+                m.map_node(node, node);
+            }
+        }
+
+        for (name, edge) in &source.edges {
+            if diff.removed_edges.iter().any(|e| e.name() == name) {
+                continue;
+            }
+            let image = match renamed(&diff.renamed_edges, name) {
+                Some(new) => new,
+                None if target.edges.contains_key(name) => name.clone(),
+                None => continue,
+            };
+            match edge {
+                Edge::ForeignKey { source: src, .. } => {
+                    if let Some(src_img) = m.node_mapping.get(src).cloned() {
+                        m.map_fk(name, Path::new(&src_img, vec![&image]));
+                    }
+                }
+                Edge::Attribute { .. } => {
+                    m.map_attr_direct(name, &image);
+                }
+            }
+        }
+
+        m
+    }
+
+    /// Nœuds et arêtes de S sans image (triés) : vide si le mapping est complet.
+    pub fn unmapped(&self, source_schema: &Schema) -> Vec<String> {
+        let mut missing: Vec<String> = source_schema.nodes.keys()
+            .filter(|n| !self.node_mapping.contains_key(*n))
+            .chain(source_schema.edges.keys().filter(|e| !self.edge_mapping.contains_key(*e)))
+            .cloned()
+            .collect();
+        missing.sort();
+        missing
+    }
+
+    /// Nœuds et arêtes de T qui ne sont l'image de rien : pendant de
+    /// `unmapped` côté cible, pour repérer les correspondances oubliées.
+    ///
+    /// Une arête est couverte si elle apparaît dans un chemin image, ou si
+    /// c'est un attribut rempli par `filled_attrs`.
+    pub fn image_coverage(&self, target: &Schema) -> Coverage {
+        let mut covered_edges: HashSet<&str> = HashSet::new();
+        for em in self.edge_mapping.values() {
+            match em {
+                EdgeMapping::FkToPath(path) => {
+                    covered_edges.extend(path.edges.iter().map(String::as_str));
+                }
+                EdgeMapping::AttrToPath { fk_path, attr_name } => {
+                    covered_edges.extend(fk_path.iter().map(String::as_str));
+                    covered_edges.insert(attr_name);
+                }
+                EdgeMapping::Bridge(bridge) => {
+                    covered_edges.insert(&bridge.from);
+                    covered_edges.insert(&bridge.to);
+                }
+            }
         }
+        covered_edges.extend(self.filled_attrs.keys().map(String::as_str));
+        let covered_nodes: HashSet<&str> = self.node_mapping.values().map(String::as_str)
+            .chain(self.edge_mapping.values().filter_map(|em| match em {
+                EdgeMapping::Bridge(bridge) => Some(bridge.node.as_str()),
+                _ => None,
+            }))
+            .collect();
+
+        let mut coverage = Coverage {
+            uncovered_nodes: target.nodes.keys()
+                .filter(|n| !covered_nodes.contains(n.as_str()))
+                .cloned()
+                .collect(),
+            uncovered_edges: target.edges.keys()
+                .filter(|e| !covered_edges.contains(e.as_str()))
+                .cloned()
+                .collect(),
+        };
+        coverage.uncovered_nodes.sort();
+        coverage.uncovered_edges.sort();
+        coverage
     }
 
     /// Mappe un nœud source vers un nœud cible.
@@ -131,6 +446,56 @@ impl Mapping {
         self
     }
 
+    /// Remplit un attribut cible avec une constante lors de Σ.
+    /// T.target_attr = value, pour chaque ligne migrée vers le nœud de cet attribut.
+    pub fn map_attr_const(&mut self, target_attr: &str, value: Value) -> &mut Self {
+        self.filled_attrs.insert(target_attr.to_string(), AttrFill::Constant(value));
+        self
+    }
+
+    /// Remplit un attribut cible lors de Σ à partir d'un attribut de S,
+    /// lu à travers un chemin de FK de S.
+    /// T.target_attr = fk_path ∘ source_attr, évalué sur la ligne source.
+    pub fn map_attr_from_source(
+        &mut self,
+        target_attr: &str,
+        fk_path: Vec<&str>,
+        source_attr: &str,
+    ) -> &mut Self {
+        self.filled_attrs.insert(
+            target_attr.to_string(),
+            AttrFill::SourcePath {
+                fk_path: fk_path.into_iter().map(|s| s.to_string()).collect(),
+                attr_name: source_attr.to_string(),
+            },
+        );
+        self
+    }
+
+    /// Envoie une relation plusieurs-à-plusieurs de S sur un nœud pont de T.
+    /// `source_relation` liste les RowId de `related` (nœud de S) ; lors de Σ,
+    /// chaque élément devient une ligne de `bridge_node`, reliée par `from_fk`
+    /// à l'image de la ligne source et par `to_fk` à celle de la ligne listée.
+    pub fn map_relation_to_bridge(
+        &mut self,
+        source_relation: &str,
+        related: &str,
+        bridge_node: &str,
+        from_fk: &str,
+        to_fk: &str,
+    ) -> &mut Self {
+        self.edge_mapping.insert(
+            source_relation.to_string(),
+            EdgeMapping::Bridge(BridgeMapping {
+                related: related.to_string(),
+                node: bridge_node.to_string(),
+                from: from_fk.to_string(),
+                to: to_fk.to_string(),
+            }),
+        );
+        self
+    }
+
     /// Mappe un attribut source directement vers un attribut cible (cas simple, sans chemin FK).
     /// F(attr_source) = attr_target (dans le même nœud image)
     pub fn map_attr_direct(
@@ -148,6 +513,21 @@ impl Mapping {
         self
     }
 
+    /// Déclare une clé métier sur un nœud de T : lors de Σ, les lignes
+    /// source qui donnent les mêmes valeurs à `key_attrs` (attributs de T)
+    /// sont fusionnées en une seule ligne cible, et les FK qui visaient
+    /// l'une d'elles pointent vers la ligne fusionnée.
+    ///
+    /// Une ligne dont un attribut de la clé est NULL ou absent n'est jamais
+    /// fusionnée (comme une contrainte UNIQUE en SQL).
+    pub fn with_key(&mut self, target_node: &str, key_attrs: &[&str]) -> &mut Self {
+        self.keys.insert(
+            target_node.to_string(),
+            key_attrs.iter().map(|a| a.to_string()).collect(),
+        );
+        self
+    }
+
     /// Vérifie que le mapping est complet (chaque nœud et arête de S est mappé).
     pub fn is_complete(&self, source_schema: &Schema) -> bool {
         // Chaque nœud de S doit être mappé
@@ -169,7 +549,7 @@ impl Mapping {
     ///
     /// Pour chaque arête f: A → B dans S, on doit avoir :
     /// - Le chemin F(f) part de F(A) et arrive à F(B)
-    pub fn validate(&self, source: &Schema, target: &Schema) -> Result<(), String> {
+    pub fn validate(&self, source: &Schema, target: &Schema) -> Result<(), CatrustError> {
         // Vérifier la complétude
         if !self.is_complete(source) {
             return Err("Le mapping n'est pas complet : certains nœuds ou arêtes ne sont pas mappés".into());
@@ -181,12 +561,47 @@ impl Mapping {
                 return Err(format!(
                     "Le nœud cible '{}' (image de '{}') n'existe pas dans le schéma cible",
                     tgt_node, src_node
-                ));
+                ).into());
+            }
+        }
+
+        // Les clés métier portent sur des attributs des nœuds de T
+        for (node, key_attrs) in &self.keys {
+            if !target.nodes.contains_key(node) {
+                return Err(format!("Clé : le nœud '{}' n'existe pas dans le schéma cible", node).into());
+            }
+            for attr in key_attrs {
+                match target.edges.get(attr) {
+                    Some(Edge::Attribute { source, .. }) if source == node => {}
+                    _ => {
+                        return Err(format!(
+                            "Clé de '{}' : '{}' n'est pas un attribut de ce nœud",
+                            node, attr
+                        ).into());
+                    }
+                }
+            }
+        }
+
+        // Les remplissages portent sur un attribut de T
+        for (attr_name, fill) in &self.filled_attrs {
+            match fill {
+                AttrFill::Constant(value) => {
+                    validate_constant(attr_name, value, &self.node_mapping, target)?;
+                }
+                AttrFill::SourcePath { fk_path, attr_name: source_attr } => {
+                    validate_source_path(attr_name, fk_path, source_attr, &self.node_mapping, source, target)?;
+                }
             }
         }
 
         // Vérifier que chaque arête est mappée de façon cohérente
         for (edge_name, edge_mapping) in &self.edge_mapping {
+            if let EdgeMapping::Bridge(bridge) = edge_mapping {
+                validate_bridge(edge_name, bridge, self, source, target)?;
+                continue;
+            }
+
             let source_edge = source.edges.get(edge_name)
                 .ok_or_else(|| format!("Arête '{}' n'existe pas dans le schéma source", edge_name))?;
 
@@ -195,7 +610,7 @@ impl Mapping {
                     // F(fk: A→B) doit être un chemin de F(A) vers F(B) dans T
                     let mapped_src = self.node_mapping.get(src)
                         .ok_or_else(|| format!("Nœud source '{}' de FK '{}' non mappé", src, edge_name))?;
-                    let _mapped_tgt = self.node_mapping.get(tgt)
+                    let mapped_tgt = self.node_mapping.get(tgt)
                         .ok_or_else(|| format!("Nœud cible '{}' de FK '{}' non mappé", tgt, edge_name))?;
 
                     match edge_mapping {
@@ -204,22 +619,47 @@ impl Mapping {
                                 return Err(format!(
                                     "FK '{}': le chemin image commence à '{}' mais devrait commencer à '{}'",
                                     edge_name, path.start, mapped_src
-                                ));
+                                ).into());
+                            }
+                            let end = walk_fk_path(target, &path.start, &path.edges)
+                                .map_err(|e| format!("FK '{}': {}", edge_name, e))?;
+                            if end != *mapped_tgt {
+                                return Err(format!(
+                                    "FK '{}': le chemin image arrive à '{}' mais devrait arriver à '{}'",
+                                    edge_name, end, mapped_tgt
+                                ).into());
                             }
-                            // TODO: vérifier que le chemin arrive bien à mapped_tgt
                         }
-                        _ => return Err(format!("FK '{}' mappée comme attribut", edge_name)),
+                        _ => return Err(format!("FK '{}' mappée comme attribut", edge_name).into()),
                     }
                 }
                 Edge::Attribute { source: src, .. } => {
-                    let _mapped_src = self.node_mapping.get(src)
+                    let mapped_src = self.node_mapping.get(src)
                         .ok_or_else(|| format!("Nœud source '{}' de attribut '{}' non mappé", src, edge_name))?;
 
                     match edge_mapping {
-                        EdgeMapping::AttrToPath { .. } => {
-                            // OK, attribut bien mappé vers un chemin+attribut
+                        EdgeMapping::AttrToPath { fk_path, attr_name } => {
+                            // Le préfixe FK doit partir de F(src), et l'attribut final
+                            // doit appartenir au nœud où arrive ce préfixe
+                            let end = walk_fk_path(target, mapped_src, fk_path)
+                                .map_err(|e| format!("Attribut '{}': {}", edge_name, e))?;
+                            match target.edges.get(attr_name) {
+                                Some(Edge::Attribute { source: attr_src, .. }) if *attr_src == end => {}
+                                Some(Edge::Attribute { source: attr_src, .. }) => {
+                                    return Err(format!(
+                                        "Attribut '{}': l'attribut image '{}' appartient à '{}' mais le chemin arrive à '{}'",
+                                        edge_name, attr_name, attr_src, end
+                                    ).into());
+                                }
+                                _ => {
+                                    return Err(format!(
+                                        "Attribut '{}': l'attribut image '{}' n'existe pas dans le schéma cible",
+                                        edge_name, attr_name
+                                    ).into());
+                                }
+                            }
                         }
-                        _ => return Err(format!("Attribut '{}' mappé comme FK", edge_name)),
+                        _ => return Err(format!("Attribut '{}' mappé comme FK", edge_name).into()),
                     }
                 }
             }
@@ -227,6 +667,626 @@ impl Mapping {
 
         Ok(())
     }
+
+    /// Inverse un mapping bijectif (un simple renommage).
+    ///
+    /// Si F : S → T envoie chaque nœud sur un nœud distinct et chaque arête
+    /// sur une arête distincte (chemin de longueur 1), alors F est un
+    /// isomorphisme et F⁻¹ : T → S s'obtient en échangeant les correspondances.
+    ///
+    /// Échoue si F n'est pas inversible : deux nœuds fusionnés, une FK envoyée
+    /// sur un chemin de longueur ≠ 1, un attribut envoyé à travers une FK...
+    pub fn invert(&self) -> Result<Mapping, CatrustError> {
+        let mut inverse = Mapping::new(
+            &format!("{}⁻¹", self.name),
+            &self.target_schema_name,
+            &self.source_schema_name,
+        );
+
+        for (src, tgt) in &self.node_mapping {
+            if let Some(other) = inverse.node_mapping.get(tgt) {
+                return Err(format!(
+                    "Non inversible : les nœuds '{}' et '{}' sont tous deux envoyés sur '{}'",
+                    other, src, tgt
+                ).into());
+            }
+            inverse.map_node(tgt, src);
+        }
+
+        match self.filled_attrs.iter().next() {
+            Some((attr_name, AttrFill::Constant(value))) => {
+                return Err(format!(
+                    "Non inversible : l'attribut cible '{}' est rempli par la constante {}",
+                    attr_name, value
+                ).into());
+            }
+            Some((target_attr, AttrFill::SourcePath { fk_path, attr_name })) => {
+                return Err(format!(
+                    "Non inversible : l'attribut cible '{}' est lu à travers le chemin source '{}.{}'",
+                    target_attr, fk_path.join("."), attr_name
+                ).into());
+            }
+            None => {}
+        }
+
+        for (src_edge, edge_mapping) in &self.edge_mapping {
+            let (tgt_edge, inverse_mapping) = match edge_mapping {
+                EdgeMapping::FkToPath(path) => {
+                    if path.edges.len() != 1 {
+                        return Err(format!(
+                            "Non inversible : la FK '{}' est envoyée sur le chemin '{}' (longueur {} ≠ 1)",
+                            src_edge, path, path.edges.len()
+                        ).into());
+                    }
+                    let start = inverse.node_mapping.get(&path.start)
+                        .ok_or_else(|| format!(
+                            "Non inversible : le nœud '{}' (départ de l'image de '{}') n'est l'image d'aucun nœud",
+                            path.start, src_edge
+                        ))?;
+                    (
+                        path.edges[0].clone(),
+                        EdgeMapping::FkToPath(Path::new(start, vec![src_edge])),
+                    )
+                }
+                EdgeMapping::Bridge(bridge) => {
+                    return Err(format!(
+                        "Non inversible : la relation '{}' est envoyée sur le nœud pont '{}'",
+                        src_edge, bridge.node
+                    ).into());
+                }
+                EdgeMapping::AttrToPath { fk_path, attr_name } => {
+                    if !fk_path.is_empty() {
+                        return Err(format!(
+                            "Non inversible : l'attribut '{}' est envoyé à travers le chemin de FK '{}'",
+                            src_edge, fk_path.join(".")
+                        ).into());
+                    }
+                    (
+                        attr_name.clone(),
+                        EdgeMapping::AttrToPath { fk_path: vec![], attr_name: src_edge.clone() },
+                    )
+                }
+            };
+
+            if let Some(other) = inverse.edge_mapping.get(&tgt_edge) {
+                let other_name = match other {
+                    EdgeMapping::FkToPath(p) => p.edges.join("."),
+                    EdgeMapping::AttrToPath { attr_name, .. } => attr_name.clone(),
+                    EdgeMapping::Bridge(bridge) => bridge.node.clone(),
+                };
+                return Err(format!(
+                    "Non inversible : les arêtes '{}' et '{}' sont toutes deux envoyées sur '{}'",
+                    other_name, src_edge, tgt_edge
+                ).into());
+            }
+            inverse.edge_mapping.insert(tgt_edge, inverse_mapping);
+        }
+
+        Ok(inverse)
+    }
+
+    /// Calcule l'image F(p) d'un chemin de S dans T.
+    ///
+    /// Chaque arête du chemin est remplacée par son image :
+    /// - une FK par son chemin de FK image
+    /// - un attribut par son chemin de FK image suivi de l'attribut cible
+    ///
+    /// C'est l'action du foncteur sur les morphismes composés : F(g∘f) = F(g)∘F(f).
+    pub fn image_of_path(&self, path: &Path) -> Result<Path, CatrustError> {
+        let start = self.node_mapping.get(&path.start)
+            .ok_or_else(|| CatrustError::UnmappedNode {
+                node: path.start.clone(),
+                mapping: self.name.clone(),
+            })?;
+
+        let mut edges = Vec::new();
+        for edge_name in &path.edges {
+            match self.edge_mapping.get(edge_name) {
+                Some(EdgeMapping::FkToPath(image)) => {
+                    edges.extend(image.edges.clone());
+                }
+                Some(EdgeMapping::AttrToPath { fk_path, attr_name }) => {
+                    edges.extend(fk_path.clone());
+                    edges.push(attr_name.clone());
+                }
+                Some(EdgeMapping::Bridge(_))
+                | None => {
+                    return Err(CatrustError::UnmappedEdge {
+                        edge: edge_name.clone(),
+                        mapping: self.name.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(Path { start: start.clone(), edges })
+    }
+
+    /// Vérifie que le mapping respecte les équations de chemins du schéma source.
+    ///
+    /// Un foncteur doit envoyer deux chemins égaux sur deux chemins égaux :
+    /// pour chaque équation `p = q` de S, on doit avoir `F(p) = F(q)` dans T,
+    /// c'est-à-dire modulo les équations de T. On normalise les deux images
+    /// avec le PathOptimizer de T puis on compare les formes normales.
+    ///
+    /// Retourne la liste des équations violées.
+    pub fn preserves_equations(&self, source: &Schema, target: &Schema) -> Result<(), Vec<CatrustError>> {
+        let optimizer = PathOptimizer::from_schema(target);
+        let mut errors = Vec::new();
+
+        for eq in &source.path_equations {
+            let lhs = self.image_of_path(&eq.lhs);
+            let rhs = self.image_of_path(&eq.rhs);
+
+            match (lhs, rhs) {
+                (Ok(lhs), Ok(rhs)) => {
+                    let lhs_nf = optimizer.optimize_path(&lhs);
+                    let rhs_nf = optimizer.optimize_path(&rhs);
+                    if lhs_nf != rhs_nf {
+                        errors.push(format!(
+                            "Équation '{}' non préservée : {} ≠ {} dans '{}'",
+                            eq, lhs_nf, rhs_nf, target.name
+                        ).into());
+                    }
+                }
+                (Err(e), _) | (_, Err(e)) => {
+                    errors.push(format!("Équation '{}' : {}", eq, e).into());
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Égalité structurelle : mêmes noms (du mapping et de ses schémas),
+    /// mêmes images de nœuds et d'arêtes, mêmes clés, indépendamment de
+    /// l'ordre dans lequel les correspondances ont été déclarées.
+    pub fn structurally_eq(&self, other: &Mapping) -> bool {
+        self.name == other.name
+            && self.source_schema_name == other.source_schema_name
+            && self.target_schema_name == other.target_schema_name
+            && self.node_mapping == other.node_mapping
+            && self.edge_mapping == other.edge_mapping
+            && self.filled_attrs == other.filled_attrs
+            && self.keys == other.keys
+    }
+}
+
+/// Aplatit une FK : les attributs `attrs_to_embed` du nœud pointé par `fk`
+/// sont recopiés comme attributs directs du nœud source de la FK.
+///
+/// Retourne le schéma cible et le mapping S → T qui le réalise : identité
+/// sur tout S, et chaque copie est remplie lors de Σ en suivant `fk` dans S
+/// (`AttrFill::SourcePath`).
+///
+/// Les noms d'arêtes étant uniques dans un schéma, la copie de `attr` est
+/// nommée `{fk}_{attr}` ; l'original reste sur le nœud pointé.
+///
+/// Ex: `flatten_fk(&s, "works_in", &["dept_name"])` donne `Employee.works_in_dept_name`.
+pub fn flatten_fk(source: &Schema, fk: &str, attrs_to_embed: &[&str]) -> Result<(Schema, Mapping), CatrustError> {
+    let (fk_source, fk_target) = match source.edges.get(fk) {
+        Some(Edge::ForeignKey { source, target, .. }) => (source.clone(), target.clone()),
+        _ => return Err(format!("'{}' n'est pas une FK du schéma '{}'", fk, source.name).into()),
+    };
+    let mut copies = Vec::new();
+    for attr in attrs_to_embed {
+        let ty = match source.edges.get(*attr) {
+            Some(Edge::Attribute { source, target, .. }) if *source == fk_target => target.clone(),
+            _ => return Err(format!("'{}' n'est pas un attribut de '{}'", attr, fk_target).into()),
+        };
+        let copy = format!("{}_{}", fk, attr);
+        if source.edges.contains_key(&copy) || copies.iter().any(|(c, _, _)| *c == copy) {
+            return Err(format!("L'arête '{}' existe déjà dans le schéma '{}'", copy, source.name).into());
+        }
+        copies.push((copy, *attr, ty));
+    }
+
+    let mut target = source.clone();
+    target.name = format!("{}_flat", source.name);
+    for (copy, _, ty) in &copies {
+        target.add_attribute(copy, &fk_source, ty.clone());
+    }
+
+    let mut m = Mapping::new(&format!("flatten_{}", fk), &source.name, &target.name);
+    for node in source.node_names() {
+        m.map_node(node, node);
+    }
+    for edge in source.edges.values() {
+        match edge {
+            Edge::ForeignKey { name, source, .. } => {
+                m.map_fk(name, Path::new(source, vec![name]));
+            }
+            Edge::Attribute { name, .. } => {
+                m.map_attr_direct(name, name);
+            }
+        }
+    }
+    for (copy, attr, _) in &copies {
+        m.map_attr_from_source(copy, vec![fk], attr);
+    }
+
+    Ok((target, m))
+}
+
+/// Remplace une relation plusieurs-à-plusieurs par un nœud pont.
+///
+/// `relation` est un attribut `[Integer]` qui liste, pour chaque ligne de
+/// son nœud, des RowId de `related`. Dans le schéma cible, il disparaît au
+/// profit du nœud `bridge` et de ses deux FK : `from` vers le nœud qui
+/// portait la relation, `to` vers `related`. Σ par le mapping retourné
+/// remplit le pont (une ligne par élément des listes).
+///
+/// Ex: `bridge_many_to_many(&s, "friends", "Person", "Friendship", "from", "to")`.
+///
+/// Erreur si `relation` n'est pas un attribut `[Integer]`, si `related`
+/// n'est pas un nœud, ou si `bridge`, `from` ou `to` existent déjà.
+pub fn bridge_many_to_many(
+    source: &Schema,
+    relation: &str,
+    related: &str,
+    bridge: &str,
+    from: &str,
+    to: &str,
+) -> Result<(Schema, Mapping), CatrustError> {
+    let holder = match source.edges.get(relation) {
+        Some(Edge::Attribute { source, target: BaseType::Array(elem), .. }) if **elem == BaseType::Integer => {
+            source.clone()
+        }
+        _ => return Err(format!("'{}' n'est pas un attribut [Integer] du schéma '{}'", relation, source.name).into()),
+    };
+    if !source.nodes.contains_key(related) {
+        return Err(format!("'{}' n'est pas un nœud de '{}'", related, source.name).into());
+    }
+    if source.nodes.contains_key(bridge) {
+        return Err(format!("'{}' est déjà un nœud de '{}'", bridge, source.name).into());
+    }
+    if from == to {
+        return Err(format!("Les deux FK du pont '{}' s'appellent '{}'", bridge, from).into());
+    }
+    if let Some(fk) = [from, to].into_iter().find(|fk| source.edges.contains_key(*fk)) {
+        return Err(format!("L'arête '{}' existe déjà dans le schéma '{}'", fk, source.name).into());
+    }
+
+    let mut target = Schema::new(&format!("{}_bridged", source.name));
+    target.with_typeside(source.typeside.clone());
+    for node in source.node_names() {
+        target.add_node(node);
+    }
+    target.add_node(bridge);
+    for node in source.node_names() {
+        for edge in source.edges_from(node) {
+            match edge {
+                Edge::ForeignKey { name, target: fk_tgt, columns, .. } if !columns.is_empty() => {
+                    let columns = columns.iter().map(|(c, a)| (c.as_str(), a.as_str())).collect();
+                    target.add_composite_fk(name, node, fk_tgt, columns);
+                }
+                Edge::ForeignKey { name, target: fk_tgt, .. } => {
+                    target.add_fk(name, node, fk_tgt);
+                }
+                Edge::Attribute { name, .. } if name == relation => {}
+                Edge::Attribute { name, target: ty, .. } => {
+                    target.add_attribute(name, node, ty.clone());
+                }
+            }
+        }
+    }
+    target.add_fk(from, bridge, &holder)
+          .add_fk(to, bridge, related);
+    for eq in &source.path_equations {
+        let mentions_relation = eq.lhs.edges.iter().chain(&eq.rhs.edges).any(|e| e == relation);
+        if !mentions_relation {
+            target.path_equations.push(eq.clone());
+        }
+    }
+
+    let mut m = Mapping::new(&format!("bridge_{}", relation), &source.name, &target.name);
+    for node in source.node_names() {
+        m.map_node(node, node);
+    }
+    for edge in source.edges.values() {
+        match edge {
+            Edge::ForeignKey { name, source, .. } => {
+                m.map_fk(name, Path::new(source, vec![name]));
+            }
+            Edge::Attribute { name, .. } if name == relation => {
+                m.map_relation_to_bridge(name, related, bridge, from, to);
+            }
+            Edge::Attribute { name, .. } => {
+                m.map_attr_direct(name, name);
+            }
+        }
+    }
+
+    Ok((target, m))
+}
+
+// ─── Heuristiques de similarité de noms (pour Mapping::suggest) ─────────────
+
+/// Score minimal pour qu'une correspondance de noms soit proposée
+const SUGGEST_THRESHOLD: f64 = 0.5;
+
+/// Découpe un identifiant en mots minuscules (snake_case et camelCase).
+fn tokenize(name: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c == '_' || c == '-' || c == ' ' {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower && !current.is_empty() {
+            tokens.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Deux mots se correspondent s'ils sont égaux ou si l'un est une abréviation
+/// (d'au moins 3 lettres) de l'autre : même initiale et lettres dans l'ordre,
+/// comme `dept` ↔ `department` ou `emp` ↔ `employee`.
+fn tokens_match(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if short.len() < 3 || short.chars().next() != long.chars().next() {
+        return false;
+    }
+    let mut long_chars = long.chars();
+    short.chars().all(|c| long_chars.any(|l| l == c))
+}
+
+/// Proportion de mots communs entre deux listes de mots.
+fn token_similarity(a: &[String], b: &[String]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let common = a.iter().filter(|ta| b.iter().any(|tb| tokens_match(ta, tb))).count();
+    common as f64 / a.len().max(b.len()) as f64
+}
+
+/// Retire d'un nom d'attribut les mots du nom de son nœud
+/// (`employee_age` sur `Employee` → `age`).
+fn strip_tokens(attr: &str, node: &str) -> Vec<String> {
+    let node_tokens = tokenize(node);
+    tokenize(attr).into_iter()
+        .filter(|t| !node_tokens.iter().any(|n| tokens_match(t, n)))
+        .collect()
+}
+
+/// Distance d'édition de Levenshtein.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            row.push((prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// Similarité entre deux noms, dans [0, 1].
+fn name_similarity(a: &str, b: &str) -> f64 {
+    let ta = tokenize(a);
+    let tb = tokenize(b);
+    let (na, nb) = (ta.concat(), tb.concat());
+    let max_len = na.chars().count().max(nb.chars().count());
+    let edit = if max_len == 0 {
+        0.0
+    } else {
+        1.0 - levenshtein(&na, &nb) as f64 / max_len as f64
+    };
+    edit.max(token_similarity(&ta, &tb))
+}
+
+/// Appariement glouton : les paires de meilleur score d'abord,
+/// chaque élément n'étant utilisé qu'une fois.
+fn best_matches<'a, F>(sources: &[&'a String], targets: &[&'a String], score: F) -> Vec<(&'a String, &'a String)>
+where
+    F: Fn(&str, &str) -> f64,
+{
+    let mut candidates: Vec<(f64, &String, &String)> = Vec::new();
+    for src in sources {
+        for tgt in targets {
+            let sc = score(src, tgt);
+            if sc >= SUGGEST_THRESHOLD {
+                candidates.push((sc, src, tgt));
+            }
+        }
+    }
+    // Tri par score décroissant, puis par nom pour un résultat déterministe
+    candidates.sort_by(|x, y| {
+        y.0.partial_cmp(&x.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| x.1.cmp(y.1))
+            .then_with(|| x.2.cmp(y.2))
+    });
+
+    let mut used_src = Vec::new();
+    let mut used_tgt = Vec::new();
+    let mut result = Vec::new();
+    for (_, src, tgt) in candidates {
+        if !used_src.contains(&src) && !used_tgt.contains(&tgt) {
+            used_src.push(src);
+            used_tgt.push(tgt);
+            result.push((src, tgt));
+        }
+    }
+    result
+}
+
+/// Vérifie une constante : l'attribut cible existe, appartient à un nœud
+/// image, et la valeur est du bon type (NULL accepté, Int accepté pour Float).
+fn validate_constant(
+    attr_name: &str,
+    value: &Value,
+    node_mapping: &HashMap<String, String>,
+    target: &Schema,
+) -> Result<(), CatrustError> {
+    let (attr_src, attr_ty) = match target.edges.get(attr_name) {
+        Some(Edge::Attribute { source, target, .. }) => (source, target),
+        _ => {
+            return Err(format!(
+                "Constante : l'attribut cible '{}' n'existe pas dans le schéma cible",
+                attr_name
+            ).into());
+        }
+    };
+    if !node_mapping.values().any(|n| n == attr_src) {
+        return Err(format!(
+            "Constante : le nœud '{}' de l'attribut '{}' n'est l'image d'aucun nœud source",
+            attr_src, attr_name
+        ).into());
+    }
+    let compatible = match value {
+        Value::Null => true,
+        Value::Integer(_) => matches!(attr_ty, BaseType::Integer | BaseType::Float),
+        Value::Array(_) => attr_ty.accepts(value),
+        other => other.get_type() == *attr_ty,
+    };
+    if !compatible {
+        return Err(CatrustError::TypeMismatch {
+            context: format!("Constante (attribut '{}')", attr_name),
+            value: value.clone(),
+            expected: attr_ty.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Vérifie un attribut cible lu à travers un chemin de S : l'attribut existe
+/// dans T, chaque nœud source envoyé sur son nœud atteint l'attribut de S
+/// par le chemin, et les deux attributs ont le même type.
+fn validate_source_path(
+    target_attr: &str,
+    fk_path: &[String],
+    source_attr: &str,
+    node_mapping: &HashMap<String, String>,
+    source: &Schema,
+    target: &Schema,
+) -> Result<(), CatrustError> {
+    let (attr_node, attr_ty) = match target.edges.get(target_attr) {
+        Some(Edge::Attribute { source, target, .. }) => (source, target),
+        _ => {
+            return Err(format!(
+                "Chemin source : l'attribut cible '{}' n'existe pas dans le schéma cible",
+                target_attr
+            ).into());
+        }
+    };
+    let starts: Vec<&String> = node_mapping.iter()
+        .filter(|(_, tgt)| *tgt == attr_node)
+        .map(|(src, _)| src)
+        .collect();
+    if starts.is_empty() {
+        return Err(format!(
+            "Chemin source : le nœud '{}' de l'attribut '{}' n'est l'image d'aucun nœud source",
+            attr_node, target_attr
+        ).into());
+    }
+    for start in starts {
+        let end = walk_fk_path(source, start, fk_path)
+            .map_err(|e| format!("Attribut cible '{}': {}", target_attr, e))?;
+        match source.edges.get(source_attr) {
+            Some(Edge::Attribute { source: src, target: ty, .. }) if *src == end && ty == attr_ty => {}
+            _ => {
+                return Err(format!(
+                    "Attribut cible '{}': '{}' n'est pas un attribut {} de '{}' dans le schéma source",
+                    target_attr, source_attr, attr_ty, end
+                ).into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Vérifie une relation envoyée sur un nœud pont : `relation` est un
+/// attribut `[Integer]` de S, et les FK `from` / `to` du pont visent les
+/// images du nœud qui porte la relation et du nœud listé.
+fn validate_bridge(
+    relation: &str,
+    bridge: &BridgeMapping,
+    mapping: &Mapping,
+    source: &Schema,
+    target: &Schema,
+) -> Result<(), CatrustError> {
+    let holder = match source.edges.get(relation) {
+        Some(Edge::Attribute { source: src, target: BaseType::Array(elem), .. })
+            if **elem == BaseType::Integer => src,
+        _ => {
+            return Err(format!(
+                "Relation '{}' : ce n'est pas un attribut [Integer] du schéma source",
+                relation
+            ).into());
+        }
+    };
+    if !target.nodes.contains_key(&bridge.node) {
+        return Err(format!(
+            "Relation '{}' : le nœud pont '{}' n'existe pas dans le schéma cible",
+            relation, bridge.node
+        ).into());
+    }
+    for (fk, node) in [(&bridge.from, holder), (&bridge.to, &bridge.related)] {
+        let expected = mapping.node_mapping.get(node)
+            .ok_or_else(|| CatrustError::UnmappedNode { node: node.clone(), mapping: mapping.name.clone() })?;
+        match target.edges.get(fk) {
+            Some(Edge::ForeignKey { source: src, target: tgt, .. })
+                if *src == bridge.node && tgt == expected => {}
+            _ => {
+                return Err(format!(
+                    "Relation '{}' : '{}' n'est pas une FK de '{}' vers '{}'",
+                    relation, fk, bridge.node, expected
+                ).into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parcourt un chemin de FK dans un schéma et retourne le nœud d'arrivée.
+///
+/// Échoue si une arête n'existe pas, n'est pas une FK, ou ne part pas
+/// du nœud courant (chemin mal composé).
+fn walk_fk_path(schema: &Schema, start: &str, fk_path: &[String]) -> Result<String, String> {
+    let mut current = start.to_string();
+    for fk_name in fk_path {
+        match schema.edges.get(fk_name) {
+            Some(Edge::ForeignKey { source, target, .. }) => {
+                if *source != current {
+                    return Err(format!(
+                        "l'arête '{}' part de '{}' et non de '{}'",
+                        fk_name, source, current
+                    ));
+                }
+                current = target.clone();
+            }
+            Some(Edge::Attribute { .. }) => {
+                return Err(format!("'{}' n'est pas une FK", fk_name));
+            }
+            None => {
+                return Err(format!("l'arête '{}' n'existe pas dans le schéma '{}'", fk_name, schema.name));
+            }
+        }
+    }
+    Ok(current)
 }
 
 impl std::fmt::Display for Mapping {
@@ -238,6 +1298,13 @@ impl std::fmt::Display for Mapping {
             writeln!(f, "    {} -> {}", src, tgt)?;
         }
 
+        if !self.keys.is_empty() {
+            writeln!(f, "  keys")?;
+            for (node, attrs) in &self.keys {
+                writeln!(f, "    {} : {}", node, attrs.join(", "))?;
+            }
+        }
+
         writeln!(f, "  edges")?;
         for (src, mapping) in &self.edge_mapping {
             match mapping {
@@ -251,6 +1318,19 @@ impl std::fmt::Display for Mapping {
                         writeln!(f, "    {} -> {}.{}", src, fk_path.join("."), attr_name)?;
                     }
                 }
+                EdgeMapping::Bridge(bridge) => {
+                    writeln!(f, "    {} -> {}({}, {})", src, bridge.node, bridge.from, bridge.to)?;
+                }
+            }
+        }
+        for (attr, fill) in &self.filled_attrs {
+            match fill {
+                AttrFill::Constant(value) => {
+                    writeln!(f, "    {} := {}", attr, value)?;
+                }
+                AttrFill::SourcePath { fk_path, attr_name } => {
+                    writeln!(f, "    {} := {}.{}", attr, fk_path.join("."), attr_name)?;
+                }
             }
         }
 
@@ -286,6 +1366,64 @@ mod tests {
         s
     }
 
+    #[test]
+    fn test_mapping_structurally_eq() {
+        let mut a = Mapping::new("Rename", "OldCompany", "NewCompany");
+        a.map_node("Person", "Employee")
+         .map_node("Dept", "Department")
+         .map_fk("works_in", Path::new("Employee", vec!["department"]))
+         .map_attr_direct("person_name", "emp_name")
+         .map_attr_direct("dept_name", "dept_label");
+
+        let mut b = Mapping::new("Rename", "OldCompany", "NewCompany");
+        b.map_attr_direct("dept_name", "dept_label")
+         .map_node("Dept", "Department")
+         .map_attr_direct("person_name", "emp_name")
+         .map_fk("works_in", Path::new("Employee", vec!["department"]))
+         .map_node("Person", "Employee");
+        assert!(a.structurally_eq(&b));
+
+        b.map_attr_const("dept_label", Value::String("?".into()));
+        assert!(!a.structurally_eq(&b));
+    }
+
+    #[test]
+    fn test_image_coverage() {
+        let s_old = schema_old();
+        let mut s_new = schema_new();
+        let m = Mapping::from_diff(&s_old.diff(&s_new), &s_old, &s_new);
+        assert!(m.image_coverage(&s_new).is_total());
+
+        // Un nœud ajouté à T sans correspondance reste vide après Σ
+        s_new.add_node("Badge").add_attribute("badge_code", "Badge", BaseType::String);
+        let coverage = m.image_coverage(&s_new);
+        assert!(!coverage.is_total());
+        assert_eq!(coverage.uncovered_nodes, vec!["Badge"]);
+        assert_eq!(coverage.uncovered_edges, vec!["badge_code"]);
+    }
+
+    #[test]
+    fn test_from_diff_renames() {
+        let s_old = schema_old();
+        let s_new = schema_new();
+        let diff = s_old.diff(&s_new);
+
+        let m = Mapping::from_diff(&diff, &s_old, &s_new);
+        assert!(m.validate(&s_old, &s_new).is_ok(), "{:?}", m.validate(&s_old, &s_new));
+        assert!(m.unmapped(&s_old).is_empty());
+        assert_eq!(m.node_mapping["Person"], "Employee");
+        assert!(matches!(
+            &m.edge_mapping["works_in"],
+            EdgeMapping::FkToPath(p) if p.edges == vec!["department"]
+        ));
+
+        // Une suppression reste non mappée et signalée
+        let mut s_more = s_old.clone();
+        s_more.add_node("Badge").add_attribute("badge_code", "Badge", BaseType::String);
+        let m = Mapping::from_diff(&s_more.diff(&s_new), &s_more, &s_new);
+        assert_eq!(m.unmapped(&s_more), vec!["Badge", "badge_code"]);
+    }
+
     #[test]
     fn test_create_mapping() {
         let s_old = schema_old();
@@ -302,6 +1440,81 @@ mod tests {
         assert!(m.validate(&s_old, &s_new).is_ok());
     }
 
+    #[test]
+    fn test_identity_mapping() {
+        let s = schema_old();
+        let id = Mapping::identity(&s);
+        assert!(id.is_complete(&s));
+        assert!(id.validate(&s, &s).is_ok());
+        assert!(id.preserves_equations(&s, &s).is_ok());
+    }
+
+    #[test]
+    fn test_suggest_mapping() {
+        let mut s_old = Schema::new("OldCompany");
+        s_old.add_node("Person")
+             .add_node("Dept")
+             .add_fk("works_in", "Person", "Dept")
+             .add_attribute("person_name", "Person", BaseType::String)
+             .add_attribute("age", "Person", BaseType::Integer)
+             .add_attribute("dept_name", "Dept", BaseType::String)
+             .add_attribute("budget", "Dept", BaseType::Float);
+
+        let mut s_new = Schema::new("NewCompany");
+        s_new.add_node("Employee")
+             .add_node("Department")
+             .add_fk("department", "Employee", "Department")
+             .add_attribute("full_name", "Employee", BaseType::String)
+             .add_attribute("employee_age", "Employee", BaseType::Integer)
+             .add_attribute("dept_label", "Department", BaseType::String)
+             .add_attribute("dept_budget", "Department", BaseType::Float);
+
+        let m = Mapping::suggest(&s_old, &s_new);
+        println!("{}", m);
+
+        assert_eq!(m.node_mapping.get("Dept").map(String::as_str), Some("Department"));
+        assert_eq!(m.node_mapping.get("Person").map(String::as_str), Some("Employee"));
+        assert!(matches!(
+            m.edge_mapping.get("person_name"),
+            Some(EdgeMapping::AttrToPath { attr_name, .. }) if attr_name == "full_name"
+        ));
+        assert!(matches!(
+            m.edge_mapping.get("age"),
+            Some(EdgeMapping::AttrToPath { attr_name, .. }) if attr_name == "employee_age"
+        ));
+        assert!(matches!(
+            m.edge_mapping.get("works_in"),
+            Some(EdgeMapping::FkToPath(p)) if p.edges == vec!["department"]
+        ));
+    }
+
+    #[test]
+    fn test_constant_mapping_validation() {
+        let s_old = schema_old();
+        let mut s_new = schema_new();
+        s_new.add_attribute("source_system", "Employee", BaseType::String);
+
+        let mut m = Mapping::new("Rename", "OldCompany", "NewCompany");
+        m.map_node("Person", "Employee")
+         .map_node("Dept", "Department")
+         .map_fk("works_in", Path::new("Employee", vec!["department"]))
+         .map_attr_direct("person_name", "emp_name")
+         .map_attr_direct("dept_name", "dept_label")
+         .map_attr_const("source_system", Value::String("legacy".into()));
+        assert!(m.validate(&s_old, &s_new).is_ok());
+
+        // Mauvais type
+        m.map_attr_const("source_system", Value::Integer(1));
+        assert!(m.validate(&s_old, &s_new).is_err());
+
+        // Attribut inexistant, seul en cause
+        m.filled_attrs.remove("source_system");
+        assert!(m.validate(&s_old, &s_new).is_ok());
+        m.map_attr_const("unknown", Value::Null);
+        let err = m.validate(&s_old, &s_new).unwrap_err().to_string();
+        assert!(err.contains("'unknown' n'existe pas"), "{}", err);
+    }
+
     #[test]
     fn test_incomplete_mapping() {
         let s_old = schema_old();
@@ -312,4 +1525,106 @@ mod tests {
 
         assert!(!m.is_complete(&s_old));
     }
+
+    #[test]
+    fn test_fk_image_wrong_target() {
+        let s_old = schema_old();
+        let mut s_new = schema_new();
+        s_new.add_fk("buddy", "Employee", "Employee");
+
+        // works_in : Person → Dept, mais l'image arrive à Employee (≠ F(Dept))
+        let mut m = Mapping::new("Wrong", "OldCompany", "NewCompany");
+        m.map_node("Person", "Employee")
+         .map_node("Dept", "Department")
+         .map_fk("works_in", Path::new("Employee", vec!["buddy"]))
+         .map_attr_direct("person_name", "emp_name")
+         .map_attr_direct("dept_name", "dept_label");
+
+        let err = m.validate(&s_old, &s_new).unwrap_err().to_string();
+        assert!(err.contains("arrive à 'Employee'"), "{}", err);
+        assert!(err.contains("devrait arriver à 'Department'"), "{}", err);
+    }
+
+    #[test]
+    fn test_attr_image_wrong_node() {
+        let s_old = schema_old();
+        let s_new = schema_new();
+
+        // person_name envoyé sur dept_label sans suivre de FK : mauvais nœud
+        let mut m = Mapping::new("Wrong", "OldCompany", "NewCompany");
+        m.map_node("Person", "Employee")
+         .map_node("Dept", "Department")
+         .map_fk("works_in", Path::new("Employee", vec!["department"]))
+         .map_attr_direct("person_name", "dept_label")
+         .map_attr_direct("dept_name", "dept_label");
+
+        assert!(m.validate(&s_old, &s_new).is_err());
+
+        // Avec le préfixe FK department, c'est correct
+        m.map_attr("person_name", vec!["department"], "dept_label");
+        assert!(m.validate(&s_old, &s_new).is_ok());
+    }
+
+    #[test]
+    fn test_invert_rejects_non_bijective() {
+        let mut m = Mapping::new("Merge", "OldCompany", "NewCompany");
+        m.map_node("Person", "Employee")
+         .map_node("Dept", "Employee");
+        assert!(m.invert().is_err());
+
+        let mut m = Mapping::new("Through", "OldCompany", "NewCompany");
+        m.map_node("Person", "Employee")
+         .map_node("Dept", "Department")
+         .map_attr("person_name", vec!["department"], "dept_label");
+        let err = m.invert().unwrap_err().to_string();
+        assert!(err.contains("person_name"), "{}", err);
+    }
+
+    /// Schéma avec raccourci : department.manager = direct_mgr
+    fn schema_with_shortcut(name: &str) -> Schema {
+        let mut s = Schema::new(name);
+        s.add_node("Employee")
+         .add_node("Department")
+         .add_fk("department", "Employee", "Department")
+         .add_fk("manager", "Department", "Employee")
+         .add_fk("direct_mgr", "Employee", "Employee")
+         .add_fk("mentor", "Employee", "Employee")
+         .add_path_equation(
+             Path::new("Employee", vec!["department", "manager"]),
+             Path::new("Employee", vec!["direct_mgr"]),
+         );
+        s
+    }
+
+    fn shortcut_mapping(direct_mgr_image: &str) -> Mapping {
+        let mut m = Mapping::new("M", "S", "T");
+        m.map_node("Employee", "Employee")
+         .map_node("Department", "Department")
+         .map_fk("department", Path::new("Employee", vec!["department"]))
+         .map_fk("manager", Path::new("Department", vec!["manager"]))
+         .map_fk("direct_mgr", Path::new("Employee", vec![direct_mgr_image]))
+         .map_fk("mentor", Path::new("Employee", vec!["mentor"]));
+        m
+    }
+
+    #[test]
+    fn test_preserves_equations() {
+        let s = schema_with_shortcut("S");
+        let t = schema_with_shortcut("T");
+
+        let m = shortcut_mapping("direct_mgr");
+        assert!(m.preserves_equations(&s, &t).is_ok());
+    }
+
+    #[test]
+    fn test_broken_equation_detected() {
+        let s = schema_with_shortcut("S");
+        let t = schema_with_shortcut("T");
+
+        // direct_mgr envoyé sur mentor : department.manager ≠ mentor dans T
+        let m = shortcut_mapping("mentor");
+        let errors = m.preserves_equations(&s, &t).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("non préservée"));
+    }
 }
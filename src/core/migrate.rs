@@ -31,10 +31,13 @@
 //
 // =============================================================================
 
-use std::collections::HashMap;
-use super::schema::{Schema, Edge};
-use super::instance::{Instance, RowId};
-use super::mapping::{Mapping, EdgeMapping};
+use std::collections::{BTreeMap, HashMap};
+use super::schema::{Schema, Edge, Path};
+use super::instance::{IdPolicy, Instance, RowId};
+use super::mapping::{AttrFill, BridgeMapping, EdgeMapping, Mapping};
+use super::optimize::{compose_mappings, PathOptimizer};
+use super::typeside::{BaseType, OrdValue, Value};
+use crate::error::CatrustError;
 
 /// Effectue la migration Delta : Δ_F(instance_T) → instance_S
 ///
@@ -47,6 +50,10 @@ use super::mapping::{Mapping, EdgeMapping};
 ///   2. Pour chaque arête a: A → C dans S :
 ///      - F(a) = chemin dans T → on suit ce chemin pour retrouver les données
 ///
+/// Chaque chemin suivi est d'abord normalisé par les équations de T
+/// (`PathOptimizer`) : un raccourci déclaré évite des sauts, et une FK
+/// intermédiaire non renseignée sur le chemin long.
+///
 /// C'est comme faire un SELECT ... JOIN ... en SQL : on ne crée pas de données,
 /// on restructure les données existantes.
 ///
@@ -64,6 +71,7 @@ pub fn delta(
         &format!("delta_{}", mapping.name),
         source_schema,
     );
+    let optimizer = PathOptimizer::from_schema(target_schema);
 
     // Pour chaque nœud dans le schéma source S
     for (source_node, target_node) in &mapping.node_mapping {
@@ -86,7 +94,8 @@ pub fn delta(
                     match (source_edge, edge_mapping) {
                         // FK dans S → chemin de FK dans T
                         (Edge::ForeignKey { .. }, EdgeMapping::FkToPath(path)) => {
-                            // Suivre le chemin dans T pour trouver le RowId cible
+                            // Suivre le chemin (normalisé) dans T pour trouver le RowId cible
+                            let path = optimizer.optimize_path(path);
                             if let Some(target_row) = target_instance.follow_path(
                                 target_node, row_id,
                                 &path.edges,
@@ -97,13 +106,17 @@ pub fn delta(
                         }
                         // Attribut dans S → chemin + attribut dans T
                         (Edge::Attribute { .. }, EdgeMapping::AttrToPath { fk_path, attr_name }) => {
-                            // Suivre le chemin de FK dans T, puis lire l'attribut
+                            // Suivre le chemin de FK (normalisé) dans T, puis lire l'attribut
+                            let fk_path = optimizer.optimize_path(&Path {
+                                start: target_node.clone(),
+                                edges: fk_path.clone(),
+                            }).edges;
                             let resolved_row = if fk_path.is_empty() {
                                 Some(row_id)
                             } else {
                                 target_instance.follow_path(
                                     target_node, row_id,
-                                    &fk_path.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+                                    &fk_path,
                                     target_schema,
                                 )
                             };
@@ -111,7 +124,7 @@ pub fn delta(
                             if let Some(resolved) = resolved_row {
                                 // Trouver l'entité au bout du chemin FK
                                 let mut entity = target_node.clone();
-                                for fk in fk_path {
+                                for fk in &fk_path {
                                     if let Some(Edge::ForeignKey { target, .. }) = target_schema.edges.get(fk) {
                                         entity = target.clone();
                                     }
@@ -123,6 +136,18 @@ pub fn delta(
                                 }
                             }
                         }
+                        // Relation dans S → RowId visés par les lignes du pont
+                        (Edge::Attribute { .. }, EdgeMapping::Bridge(bridge)) => {
+                            let Some(bridge_data) = target_instance.data.get(&bridge.node) else { continue };
+                            let mut bridge_rows = bridge_data.row_ids();
+                            bridge_rows.sort();
+                            let listed = bridge_rows.into_iter()
+                                .filter(|&b| bridge_data.get_fk(b, &bridge.from) == Some(row_id))
+                                .filter_map(|b| bridge_data.get_fk(b, &bridge.to))
+                                .map(|to_row| Value::Integer(to_row as i64))
+                                .collect();
+                            new_attrs.insert(edge_name.clone(), Value::Array(listed));
+                        }
                         _ => {} // Mapping incohérent, on ignore
                     }
                 }
@@ -146,22 +171,34 @@ pub fn delta(
 ///   2. Toutes les lignes de ces A deviennent des lignes de B
 ///   3. Si une FK dans S envoie deux lignes au même endroit dans T,
 ///      elles sont identifiées (fusionnées = union-find / quotient)
+///   4. Si B a une clé métier (`Mapping::with_key`), les lignes de mêmes
+///      valeurs de clé ne forment qu'une ligne de B
+///   5. Une relation envoyée sur un nœud pont (`EdgeMapping::Bridge`)
+///      donne une ligne du pont par élément de chaque liste
 ///
 /// C'est comme faire des INSERT INTO ... SELECT ... UNION ALL en SQL.
 ///
 /// EXEMPLE SIMPLE (sans identifications) :
 ///   F(Person) = User → les lignes de Person deviennent des lignes de User
 ///   F(person_name) = username → la colonne person_name devient username
+///
+/// Erreur si une liste d'une relation pontée contient autre chose qu'un
+/// RowId de la ligne listée : valeur non entière, négative ou qui ne
+/// désigne aucune ligne.
 pub fn sigma(
     mapping: &Mapping,
     source_schema: &Schema,
     target_schema: &Schema,
     source_instance: &Instance,
-) -> Instance {
+) -> Result<Instance, CatrustError> {
     let mut result = Instance::new(
         &format!("sigma_{}", mapping.name),
         target_schema,
     );
+    // Des RowId uniques dans toute l'instance source le restent dans T
+    if source_instance.id_policy == IdPolicy::Global {
+        result.id_policy = IdPolicy::Global;
+    }
 
     // Construire le mapping inverse : pour chaque nœud de T, quels nœuds de S y sont envoyés ?
     let mut inverse_node_map: HashMap<String, Vec<String>> = HashMap::new();
@@ -175,11 +212,18 @@ pub fn sigma(
     // Table de traduction des RowId : (entity_source, old_row_id) → new_row_id dans T
     let mut id_translation: HashMap<(String, RowId), RowId> = HashMap::new();
 
+    // Lignes déjà créées par valeur de clé métier : node_T → clé → RowId
+    let mut by_key: HashMap<String, BTreeMap<Vec<OrdValue>, RowId>> = HashMap::new();
+
     // Phase 1 : Copier les lignes (avec nouveaux attributs)
     for (target_node, source_nodes) in &inverse_node_map {
-        for source_node in source_nodes {
+        let mut source_nodes = source_nodes.clone();
+        source_nodes.sort();
+        for source_node in &source_nodes {
             if let Some(source_data) = source_instance.data.get(source_node) {
-                for old_row_id in source_data.row_ids() {
+                let mut row_ids = source_data.row_ids();
+                row_ids.sort();
+                for old_row_id in row_ids {
                     let mut new_attrs = HashMap::new();
 
                     // Mapper les attributs
@@ -201,10 +245,87 @@ pub fn sigma(
                         }
                     }
 
-                    let new_row_id = result.data
-                        .get_mut(target_node)
-                        .unwrap()
-                        .insert(new_attrs, HashMap::new());
+                    // Attributs cibles remplis sans être l'image d'une arête de S
+                    for (attr_name, fill) in &mapping.filled_attrs {
+                        match target_schema.edges.get(attr_name) {
+                            Some(Edge::Attribute { source, .. }) if source == target_node => {}
+                            _ => continue,
+                        }
+                        let (fk_path, source_attr) = match fill {
+                            AttrFill::Constant(value) => {
+                                new_attrs.insert(attr_name.clone(), value.clone());
+                                continue;
+                            }
+                            AttrFill::SourcePath { fk_path, attr_name } => (fk_path, attr_name),
+                        };
+                        // Attribut de S lu à travers un chemin de FK de S
+                        let Some(resolved) = source_instance.follow_path(
+                            source_node, old_row_id, fk_path, source_schema,
+                        ) else {
+                            continue;
+                        };
+                        // Trouver l'entité au bout du chemin FK
+                        let mut entity = source_node.clone();
+                        for fk in fk_path {
+                            if let Some(Edge::ForeignKey { target, .. }) = source_schema.edges.get(fk) {
+                                entity = target.clone();
+                            }
+                        }
+                        if let Some(value) = source_instance.data.get(&entity)
+                            .and_then(|d| d.get_attr(resolved, source_attr))
+                        {
+                            new_attrs.insert(attr_name.clone(), value.clone());
+                        }
+                    }
+
+                    // Valeurs par défaut des attributs cibles qu'aucune arête
+                    // de ce nœud source ne remplit
+                    for edge in target_schema.attributes_of(target_node) {
+                        let Edge::Attribute { name, default: Some(default), .. } = edge else { continue };
+                        let mapped = mapping.filled_attrs.contains_key(name)
+                            || mapping.edge_mapping.iter().any(|(key, em)| match em {
+                                EdgeMapping::AttrToPath { attr_name, .. } => {
+                                    attr_name == name
+                                        && source_schema.edges.get(key).is_some_and(|e| e.source() == source_node)
+                                }
+                                EdgeMapping::FkToPath(_) | EdgeMapping::Bridge(_) => false,
+                            });
+                        if !mapped {
+                            new_attrs.entry(name.clone()).or_insert_with(|| default.0.clone());
+                        }
+                    }
+
+                    // Clé métier : une ligne existe déjà pour ces valeurs ?
+                    let key: Option<Vec<OrdValue>> = mapping.keys.get(target_node).and_then(|attrs| {
+                        attrs.iter()
+                            .map(|a| match new_attrs.get(a) {
+                                None | Some(Value::Null) => None,
+                                Some(v) => Some(OrdValue(v.clone())),
+                            })
+                            .collect()
+                    });
+                    let existing = key.as_ref()
+                        .and_then(|k| by_key.get(target_node).and_then(|rows| rows.get(k)))
+                        .copied();
+
+                    let new_row_id = match existing {
+                        Some(row_id) => {
+                            // Fusion : on complète les attributs encore absents
+                            let target_data = result.data.get_mut(target_node).unwrap();
+                            let missing = new_attrs.into_iter()
+                                .filter(|(name, _)| target_data.get_attr(row_id, name).is_none())
+                                .collect();
+                            target_data.update_row(row_id, missing, HashMap::new());
+                            row_id
+                        }
+                        None => {
+                            let row_id = result.insert(target_node, new_attrs, HashMap::new());
+                            if let Some(k) = key {
+                                by_key.entry(target_node.clone()).or_default().insert(k, row_id);
+                            }
+                            row_id
+                        }
+                    };
 
                     id_translation.insert(
                         (source_node.clone(), old_row_id),
@@ -250,8 +371,8 @@ pub fn sigma(
                 // Mettre à jour les FK de la ligne
                 if !new_fks.is_empty() {
                     if let Some(target_data) = result.data.get_mut(target_node) {
-                        if let Some(existing_fks) = target_data.fk_values.get_mut(&new_row_id) {
-                            existing_fks.extend(new_fks);
+                        if target_data.contains(new_row_id) {
+                            target_data.update_row(new_row_id, HashMap::new(), new_fks);
                         }
                     }
                 }
@@ -259,7 +380,202 @@ pub fn sigma(
         }
     }
 
-    result
+    // Phase 3 : Remplir les nœuds pont, une ligne par élément des relations
+    let mut relations: Vec<(&String, &BridgeMapping)> = mapping.edge_mapping.iter()
+        .filter_map(|(name, em)| match em {
+            EdgeMapping::Bridge(bridge) => Some((name, bridge)),
+            _ => None,
+        })
+        .collect();
+    relations.sort_by_key(|(name, _)| *name);
+    for (relation, bridge) in relations {
+        let Some(Edge::Attribute { source: holder, .. }) = source_schema.edges.get(relation) else {
+            continue;
+        };
+        let Some(source_data) = source_instance.data.get(holder) else { continue };
+        let mut row_ids = source_data.row_ids();
+        row_ids.sort();
+        for old_row_id in row_ids {
+            let Some(Value::Array(listed)) = source_data.get_attr(old_row_id, relation) else {
+                continue;
+            };
+            let Some(&from_row) = id_translation.get(&(holder.clone(), old_row_id)) else { continue };
+            let context = format!("Relation '{}' de {}[{}]", relation, holder, old_row_id);
+            for element in listed {
+                let listed_id = match element {
+                    Value::Integer(id) if *id >= 0 => *id as RowId,
+                    Value::Integer(id) => {
+                        return Err(format!("{} : RowId négatif {}", context, id).into());
+                    }
+                    _ => {
+                        return Err(CatrustError::TypeMismatch {
+                            context,
+                            value: element.clone(),
+                            expected: BaseType::Integer,
+                        });
+                    }
+                };
+                let Some(&to_row) = id_translation.get(&(bridge.related.clone(), listed_id)) else {
+                    return Err(format!(
+                        "{} : {} ne désigne aucune ligne de '{}'", context, listed_id, bridge.related
+                    ).into());
+                };
+                if result.data.contains_key(&bridge.node) {
+                    result.insert(
+                        &bridge.node,
+                        HashMap::new(),
+                        HashMap::from([(bridge.from.clone(), from_row), (bridge.to.clone(), to_row)]),
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Comme `sigma`, mais le résultat ne contient que les entités de T qui
+/// reçoivent au moins une ligne : ni les nœuds hors de l'image de F (aucun
+/// nœud de S n'y est envoyé), ni ceux dont les sources sont vides.
+///
+/// Retourne aussi ces nœuds laissés vides, triés, pour que l'appelant
+/// sache ce qui a été omis (`Instance::insert` sur l'un d'eux panique).
+pub fn sigma_pruned(
+    mapping: &Mapping,
+    source_schema: &Schema,
+    target_schema: &Schema,
+    source_instance: &Instance,
+) -> Result<(Instance, Vec<String>), CatrustError> {
+    let mut result = sigma(mapping, source_schema, target_schema, source_instance)?;
+    let mut empty: Vec<String> = result.data.iter()
+        .filter(|(_, data)| data.is_empty())
+        .map(|(name, _)| name.clone())
+        .collect();
+    empty.sort();
+    for name in &empty {
+        result.data.remove(name);
+    }
+    Ok((result, empty))
+}
+
+/// Vérifie qu'une chaîne de mappings `schemas[0] → schemas[1] → ...` est
+/// bien formée et retourne son composé (cf. `compose_mappings`).
+fn compose_chain(mappings: &[&Mapping], schemas: &[&Schema]) -> Result<Mapping, CatrustError> {
+    let Some((first, rest)) = mappings.split_first() else {
+        return Err("Chaîne de mappings vide".into());
+    };
+    if schemas.len() != mappings.len() + 1 {
+        return Err(format!(
+            "{} mappings demandent {} schémas, {} fournis",
+            mappings.len(), mappings.len() + 1, schemas.len()
+        ).into());
+    }
+    for (i, mapping) in mappings.iter().enumerate() {
+        if mapping.source_schema_name != schemas[i].name || mapping.target_schema_name != schemas[i + 1].name {
+            return Err(format!(
+                "Mapping '{}' : {} → {}, attendu {} → {}",
+                mapping.name, mapping.source_schema_name, mapping.target_schema_name,
+                schemas[i].name, schemas[i + 1].name
+            ).into());
+        }
+    }
+
+    let mut composed = (*first).clone();
+    for (i, next) in rest.iter().enumerate() {
+        composed = compose_mappings(&composed, next, schemas[i + 1])?;
+    }
+    Ok(composed)
+}
+
+/// Σ le long d'une chaîne S → T → U... en UNE passe : les mappings sont
+/// composés (`compose_mappings`) puis un seul Σ est exécuté, sans
+/// matérialiser les instances intermédiaires.
+///
+/// `schemas` contient les schémas de la chaîne dans l'ordre, source en
+/// premier : un de plus que de mappings. Même résultat que des Σ
+/// successifs, à renumérotation des RowId près.
+pub fn sigma_chain(
+    mappings: &[&Mapping],
+    schemas: &[&Schema],
+    source_instance: &Instance,
+) -> Result<Instance, CatrustError> {
+    let composed = compose_chain(mappings, schemas)?;
+    sigma(&composed, schemas[0], schemas[schemas.len() - 1], source_instance)
+}
+
+/// Δ le long d'une chaîne S → T → U... en une passe : Δ_{G∘F} = Δ_F ∘ Δ_G.
+///
+/// Mêmes conventions que `sigma_chain`, mais `target_instance` est une
+/// instance du DERNIER schéma, et le résultat une instance du premier.
+pub fn delta_chain(
+    mappings: &[&Mapping],
+    schemas: &[&Schema],
+    target_instance: &Instance,
+) -> Result<Instance, CatrustError> {
+    let composed = compose_chain(mappings, schemas)?;
+    Ok(delta(&composed, schemas[0], schemas[schemas.len() - 1], target_instance))
+}
+
+/// Vérifie l'aller-retour Δ_F(Σ_F(I)) ⊇ I (unité de l'adjonction Σ ⊣ Δ).
+///
+/// On pousse l'instance vers T avec Σ, on la relit avec Δ, puis on vérifie
+/// que chaque ligne d'origine est retrouvée avec les mêmes valeurs
+/// d'attributs (à renommage des RowId près). Δ peut produire PLUS de lignes
+/// (nœuds fusionnés), mais jamais moins.
+///
+/// Retourne la liste des lignes perdues ou altérées.
+pub fn check_roundtrip(
+    mapping: &Mapping,
+    source_schema: &Schema,
+    target_schema: &Schema,
+    instance: &Instance,
+) -> Result<(), Vec<String>> {
+    let pushed = sigma(mapping, source_schema, target_schema, instance).map_err(|e| vec![e.to_string()])?;
+    let recovered = delta(mapping, source_schema, target_schema, &pushed);
+
+    // Une ligne est identifiée par ses valeurs d'attributs (triées)
+    let describe = |attrs: Vec<(&String, &Value)>| -> String {
+        let mut parts: Vec<String> = attrs
+            .into_iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        parts.sort();
+        parts.join(", ")
+    };
+
+    let mut errors = Vec::new();
+    let mut entities: Vec<&String> = instance.data.keys().collect();
+    entities.sort();
+
+    for entity in entities {
+        // Multiset des lignes retrouvées
+        let mut available: HashMap<String, usize> = HashMap::new();
+        if let Some(data) = recovered.data.get(entity) {
+            for row_id in data.row_ids() {
+                *available.entry(describe(data.row_attrs(row_id))).or_insert(0) += 1;
+            }
+        }
+
+        let original = &instance.data[entity];
+        let mut row_ids = original.row_ids();
+        row_ids.sort();
+        for row_id in row_ids {
+            let key = describe(original.row_attrs(row_id));
+            match available.get_mut(&key) {
+                Some(count) if *count > 0 => *count -= 1,
+                _ => errors.push(format!(
+                    "{}[{}] perdue ou altérée par l'aller-retour : {{ {} }}",
+                    entity, row_id, key
+                )),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
 }
 
 // =============================================================================
@@ -328,6 +644,88 @@ mod tests {
         inst
     }
 
+    #[test]
+    fn test_roundtrip_rename_is_clean() {
+        let s_old = old_schema();
+        let s_new = new_schema();
+        let inst_old = old_instance(&s_old);
+        assert!(check_roundtrip(&rename_mapping(), &s_old, &s_new, &inst_old).is_ok());
+    }
+
+    #[test]
+    fn test_roundtrip_reports_loss() {
+        // Deux attributs source envoyés sur le même attribut cible :
+        // Σ ne peut en garder qu'un, Δ relit la même valeur pour les deux.
+        let mut s_old = old_schema();
+        s_old.add_attribute("nickname", "Person", BaseType::String);
+
+        let mut inst_old = Instance::new("OldData", &s_old);
+        let d = inst_old.insert("Dept",
+            HashMap::from([("dept_name".into(), Value::String("Engineering".into()))]),
+            HashMap::new(),
+        );
+        inst_old.insert("Person",
+            HashMap::from([
+                ("person_name".into(), Value::String("Robert".into())),
+                ("nickname".into(), Value::String("Bob".into())),
+            ]),
+            HashMap::from([("works_in".into(), d)]),
+        );
+
+        let mut m = rename_mapping();
+        m.map_attr_direct("nickname", "emp_name");
+
+        let errors = check_roundtrip(&m, &s_old, &new_schema(), &inst_old).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Person["));
+    }
+
+    /// Représentation canonique d'une instance, indépendante des RowId :
+    /// chaque ligne est décrite par ses attributs et par les attributs
+    /// des lignes que ses FK désignent.
+    fn canonical_rows(inst: &Instance, schema: &Schema) -> Vec<String> {
+        let describe = |entity: &str, row: RowId| -> String {
+            let mut attrs: Vec<String> = inst.data[entity].row_attrs(row)
+                .into_iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect();
+            attrs.sort();
+            attrs.join(",")
+        };
+
+        let mut rows = Vec::new();
+        for (entity, data) in &inst.data {
+            for row in data.row_ids() {
+                let mut fks: Vec<String> = data.row_fks(row).into_iter().map(|(fk, target)| {
+                    let target_entity = match &schema.edges[fk] {
+                        Edge::ForeignKey { target, .. } => target.clone(),
+                        _ => unreachable!(),
+                    };
+                    format!("{}->({})", fk, describe(&target_entity, target))
+                }).collect();
+                fks.sort();
+                rows.push(format!("{}[{}|{}]", entity, describe(entity, row), fks.join(",")));
+            }
+        }
+        rows.sort();
+        rows
+    }
+
+    #[test]
+    fn test_identity_migrations() {
+        let s = old_schema();
+        let inst = old_instance(&s);
+        let id = Mapping::identity(&s);
+
+        let pushed = sigma(&id, &s, &s, &inst).unwrap();
+        let pulled = delta(&id, &s, &s, &inst);
+
+        assert_eq!(pushed.total_rows(), inst.total_rows());
+        assert_eq!(pulled.total_rows(), inst.total_rows());
+        assert_eq!(canonical_rows(&pushed, &s), canonical_rows(&inst, &s));
+        assert_eq!(canonical_rows(&pulled, &s), canonical_rows(&inst, &s));
+    }
+
     #[test]
     fn test_sigma_simple_rename() {
         let s_old = old_schema();
@@ -336,7 +734,7 @@ mod tests {
         let inst_old = old_instance(&s_old);
 
         // Σ : pousser les données de Old vers New
-        let inst_new = sigma(&m, &s_old, &s_new, &inst_old);
+        let inst_new = sigma(&m, &s_old, &s_new, &inst_old).unwrap();
 
         // On doit avoir 2 Employee et 2 Department
         assert_eq!(inst_new.data["Employee"].len(), 2);
@@ -347,11 +745,350 @@ mod tests {
         let has_alice = emp_rows.iter().any(|&id| {
             inst_new.data["Employee"]
                 .get_attr(id, "emp_name")
-                .map_or(false, |v| *v == Value::String("Alice".into()))
+                .is_some_and(|v| *v == Value::String("Alice".into()))
         });
         assert!(has_alice, "Alice devrait exister dans Employee");
     }
 
+    #[test]
+    fn test_sigma_keeps_global_ids() {
+        let s_old = old_schema();
+        let s_new = new_schema();
+        let mut inst_old = Instance::new("OldData", &s_old).with_id_policy(IdPolicy::Global);
+        let d1 = inst_old.insert("Dept", HashMap::new(), HashMap::new());
+        let alice = inst_old.insert("Person", HashMap::new(), HashMap::from([("works_in".into(), d1)]));
+        assert_ne!(d1, alice);
+
+        let inst_new = sigma(&rename_mapping(), &s_old, &s_new, &inst_old).unwrap();
+        assert_eq!(inst_new.id_policy, IdPolicy::Global);
+        let dept = inst_new.data["Department"].row_ids()[0];
+        let emp = inst_new.data["Employee"].row_ids()[0];
+        assert_ne!(dept, emp, "Les RowId de T doivent rester uniques entre entités");
+        assert_eq!(inst_new.data["Employee"].get_fk(emp, "department"), Some(dept));
+    }
+
+    #[test]
+    fn test_sigma_pruned_reports_unmapped_nodes() {
+        let s_old = old_schema();
+        let mut s_new = new_schema();
+        s_new.add_node("Project")
+             .add_attribute("project_name", "Project", BaseType::String);
+        let m = rename_mapping();
+        let inst_old = old_instance(&s_old);
+
+        // Σ classique : Project existe, vide
+        assert!(sigma(&m, &s_old, &s_new, &inst_old).unwrap().data["Project"].is_empty());
+
+        let (inst_new, empty) = sigma_pruned(&m, &s_old, &s_new, &inst_old).unwrap();
+        assert_eq!(empty, vec!["Project".to_string()]);
+        assert!(!inst_new.data.contains_key("Project"));
+        assert_eq!(inst_new.data["Employee"].len(), 2);
+        assert_eq!(inst_new.data["Department"].len(), 2);
+    }
+
+    #[test]
+    fn test_sigma_inverse_roundtrip() {
+        let s_old = old_schema();
+        let s_new = new_schema();
+        let m = rename_mapping();
+        let inverse = m.invert().unwrap();
+        assert!(inverse.validate(&s_new, &s_old).is_ok());
+
+        let inst_old = old_instance(&s_old);
+        let inst_new = sigma(&m, &s_old, &s_new, &inst_old).unwrap();
+        let inst_back = sigma(&inverse, &s_new, &s_old, &inst_new).unwrap();
+
+        // Même contenu : (nom de la personne, nom de son département)
+        let people = |inst: &Instance| {
+            let persons = &inst.data["Person"];
+            let depts = &inst.data["Dept"];
+            let mut rows: Vec<(Value, Value)> = persons.row_ids().into_iter().map(|id| {
+                let dept = persons.get_fk(id, "works_in").unwrap();
+                (
+                    persons.get_attr(id, "person_name").unwrap().clone(),
+                    depts.get_attr(dept, "dept_name").unwrap().clone(),
+                )
+            }).collect();
+            rows.sort_by_key(|(name, _)| name.to_string());
+            rows
+        };
+
+        assert_eq!(inst_back.data["Dept"].len(), inst_old.data["Dept"].len());
+        assert_eq!(people(&inst_back), people(&inst_old));
+    }
+
+    #[test]
+    fn test_sigma_constant_attribute() {
+        let s_old = old_schema();
+        let mut s_new = new_schema();
+        s_new.add_attribute("source_system", "Employee", BaseType::String);
+
+        let mut m = rename_mapping();
+        m.map_attr_const("source_system", Value::String("legacy".into()));
+        assert!(m.validate(&s_old, &s_new).is_ok());
+
+        let inst_new = sigma(&m, &s_old, &s_new, &old_instance(&s_old)).unwrap();
+        let employees = &inst_new.data["Employee"];
+        assert_eq!(employees.len(), 2);
+        for id in employees.row_ids() {
+            assert_eq!(
+                employees.get_attr(id, "source_system"),
+                Some(&Value::String("legacy".into()))
+            );
+        }
+        // La constante ne concerne que le nœud de l'attribut
+        for id in inst_new.data["Department"].row_ids() {
+            assert!(inst_new.data["Department"].get_attr(id, "source_system").is_none());
+        }
+    }
+
+    #[test]
+    fn test_sigma_constant_named_like_source_edge() {
+        // La constante remplit Employee.dept_name, homonyme de l'attribut
+        // source Dept.dept_name, lui envoyé sur dept_label
+        let s_old = old_schema();
+        let mut s_new = new_schema();
+        s_new.add_attribute("dept_name", "Employee", BaseType::String);
+
+        let mut m = rename_mapping();
+        m.map_attr_const("dept_name", Value::String("n/a".into()));
+        assert!(m.is_complete(&s_old));
+        assert!(m.validate(&s_old, &s_new).is_ok());
+
+        let inst_new = sigma(&m, &s_old, &s_new, &old_instance(&s_old)).unwrap();
+        let departments = &inst_new.data["Department"];
+        let labels: Vec<&Value> = departments.row_ids().into_iter()
+            .filter_map(|id| departments.get_attr(id, "dept_label"))
+            .collect();
+        assert_eq!(labels.len(), 2);
+        let employees = &inst_new.data["Employee"];
+        for id in employees.row_ids() {
+            assert_eq!(employees.get_attr(id, "dept_name"), Some(&Value::String("n/a".into())));
+        }
+    }
+
+    #[test]
+    fn test_sigma_flatten_fk() {
+        let mut s = Schema::new("Company");
+        s.add_node("Employee")
+         .add_node("Department")
+         .add_fk("works_in", "Employee", "Department")
+         .add_attribute("emp_name", "Employee", BaseType::String)
+         .add_attribute("dept_name", "Department", BaseType::String);
+
+        let (t, m) = crate::core::mapping::flatten_fk(&s, "works_in", &["dept_name"]).unwrap();
+        assert!(matches!(
+            t.edges.get("works_in_dept_name"),
+            Some(Edge::Attribute { source, .. }) if source == "Employee"
+        ));
+        // L'attribut est recopié : Department et la FK restent intacts
+        assert!(matches!(
+            t.edges.get("dept_name"),
+            Some(Edge::Attribute { source, .. }) if source == "Department"
+        ));
+        assert!(t.edges.contains_key("works_in"));
+        m.validate(&s, &t).unwrap();
+        assert!(crate::core::mapping::flatten_fk(&s, "emp_name", &["dept_name"]).is_err());
+        assert!(crate::core::mapping::flatten_fk(&s, "works_in", &["emp_name"]).is_err());
+
+        let mut inst = Instance::new("Data", &s);
+        let eng = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Engineering".into()))]),
+            HashMap::new(),
+        );
+        let sales = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Sales".into()))]),
+            HashMap::new(),
+        );
+        for (name, dept) in [("Alice", eng), ("Bob", sales), ("Carol", eng)] {
+            inst.insert("Employee",
+                HashMap::from([("emp_name".into(), Value::String(name.into()))]),
+                HashMap::from([("works_in".into(), dept)]),
+            );
+        }
+
+        let flat = sigma(&m, &s, &t, &inst).unwrap();
+        let employees = &flat.data["Employee"];
+        let mut rows: Vec<(String, String)> = employees.row_ids().into_iter()
+            .map(|id| (
+                employees.get_attr(id, "emp_name").unwrap().to_string(),
+                employees.get_attr(id, "works_in_dept_name").unwrap().to_string(),
+            ))
+            .collect();
+        rows.sort();
+        assert_eq!(rows, vec![
+            ("\"Alice\"".to_string(), "\"Engineering\"".to_string()),
+            ("\"Bob\"".to_string(), "\"Sales\"".to_string()),
+            ("\"Carol\"".to_string(), "\"Engineering\"".to_string()),
+        ]);
+        let departments = &flat.data["Department"];
+        assert_eq!(departments.len(), 2);
+        assert!(departments.row_ids().into_iter().all(|id| departments.get_attr(id, "dept_name").is_some()));
+    }
+
+    #[test]
+    fn test_sigma_bridge_many_to_many() {
+        let mut s = Schema::new("Social");
+        s.add_node("Person")
+         .add_attribute("name", "Person", BaseType::String)
+         .add_attribute("friends", "Person", BaseType::Array(Box::new(BaseType::Integer)));
+
+        let (t, m) = crate::core::mapping::bridge_many_to_many(&s, "friends", "Person", "Friendship", "from", "to").unwrap();
+        assert!(!t.edges.contains_key("friends"));
+        assert!(matches!(
+            t.edges.get("to"),
+            Some(Edge::ForeignKey { source, target, .. }) if source == "Friendship" && target == "Person"
+        ));
+        m.validate(&s, &t).unwrap();
+
+        // Nœud listé sans image : erreur typée
+        let mut unmapped = m.clone();
+        if let Some(EdgeMapping::Bridge(bridge)) = unmapped.edge_mapping.get_mut("friends") {
+            bridge.related = "Nobody".into();
+        }
+        assert_eq!(
+            unmapped.validate(&s, &t),
+            Err(CatrustError::UnmappedNode { node: "Nobody".into(), mapping: "bridge_friends".into() })
+        );
+
+        let mut inst = Instance::new("Data", &s);
+        let ids: Vec<RowId> = ["Alice", "Bob", "Carol"].iter()
+            .map(|name| inst.insert("Person",
+                HashMap::from([("name".into(), Value::String(name.to_string()))]),
+                HashMap::new(),
+            ))
+            .collect();
+        let friends = |listed: &[RowId]| Value::Array(listed.iter().map(|&id| Value::Integer(id as i64)).collect());
+        inst.set_attr("Person", ids[0], "friends", friends(&[ids[1], ids[2]]), &s).unwrap();
+        inst.set_attr("Person", ids[1], "friends", friends(&[ids[2]]), &s).unwrap();
+
+        let bridged = sigma(&m, &s, &t, &inst).unwrap();
+        let people = &bridged.data["Person"];
+        let friendships = &bridged.data["Friendship"];
+        let name = |id: RowId| people.get_attr(id, "name").unwrap().to_string();
+        let mut pairs: Vec<(String, String)> = friendships.row_ids().into_iter()
+            .map(|id| (
+                name(friendships.get_fk(id, "from").unwrap()),
+                name(friendships.get_fk(id, "to").unwrap()),
+            ))
+            .collect();
+        pairs.sort();
+        assert_eq!(pairs, vec![
+            ("\"Alice\"".to_string(), "\"Bob\"".to_string()),
+            ("\"Alice\"".to_string(), "\"Carol\"".to_string()),
+            ("\"Bob\"".to_string(), "\"Carol\"".to_string()),
+        ]);
+
+        // Δ reconstruit les listes à partir du pont
+        let back = delta(&m, &s, &t, &bridged);
+        let alice = back.data["Person"].row_ids().into_iter()
+            .find(|&id| back.data["Person"].get_attr(id, "name") == Some(&Value::String("Alice".into())))
+            .unwrap();
+        let Some(Value::Array(listed)) = back.data["Person"].get_attr(alice, "friends") else {
+            panic!("friends absent après Δ");
+        };
+        let names: Vec<String> = listed.iter()
+            .map(|v| match v {
+                Value::Integer(id) => name(*id as RowId),
+                other => panic!("RowId attendu : {}", other),
+            })
+            .collect();
+        assert_eq!(names, vec!["\"Bob\"", "\"Carol\""]);
+
+        // Listes invalides : RowId sans ligne, négatif, non entier
+        for (listed, expected) in [
+            (Value::Array(vec![Value::Integer(99)]), "99 ne désigne aucune ligne de 'Person'"),
+            (Value::Array(vec![Value::Integer(-1)]), "RowId négatif -1"),
+            (Value::Array(vec![Value::String("Bob".into())]), "n'est pas du type"),
+        ] {
+            let mut bad = inst.clone();
+            bad.set_attr("Person", ids[2], "friends", listed, &s).unwrap();
+            let err = sigma(&m, &s, &t, &bad).unwrap_err().to_string();
+            assert!(err.starts_with(&format!("Relation 'friends' de Person[{}]", ids[2])), "{}", err);
+            assert!(err.contains(expected), "{}", err);
+        }
+
+        // Mauvais paramètres du pont : erreurs, pas de panique
+        use crate::core::mapping::bridge_many_to_many;
+        assert!(bridge_many_to_many(&s, "name", "Person", "Friendship", "from", "to").is_err());
+        assert!(bridge_many_to_many(&s, "friends", "Nobody", "Friendship", "from", "to").is_err());
+        assert!(bridge_many_to_many(&s, "friends", "Person", "Person", "from", "to").is_err());
+        assert!(bridge_many_to_many(&s, "friends", "Person", "Friendship", "name", "to").is_err());
+        assert!(bridge_many_to_many(&s, "friends", "Person", "Friendship", "to", "to").is_err());
+    }
+
+    #[test]
+    fn test_sigma_with_key_dedups() {
+        let s_old = old_schema();
+        let s_new = new_schema();
+        let mut m = rename_mapping();
+        m.with_key("Department", &["dept_label"]);
+        assert!(m.validate(&s_old, &s_new).is_ok());
+
+        // Données dénormalisées : un Dept par employé
+        let mut inst = Instance::new("Denormalized", &s_old);
+        for (person, dept) in [("Alice", "Engineering"), ("Bob", "Engineering"), ("Carol", "Sales")] {
+            let d = inst.insert("Dept",
+                HashMap::from([("dept_name".into(), Value::String(dept.into()))]),
+                HashMap::new(),
+            );
+            inst.insert("Person",
+                HashMap::from([("person_name".into(), Value::String(person.into()))]),
+                HashMap::from([("works_in".into(), d)]),
+            );
+        }
+
+        let out = sigma(&m, &s_old, &s_new, &inst).unwrap();
+        let departments = &out.data["Department"];
+        assert_eq!(departments.len(), 2);
+
+        let employees = &out.data["Employee"];
+        assert_eq!(employees.len(), 3);
+        let dept_of = |name: &str| -> Value {
+            let emp = employees.row_ids().into_iter()
+                .find(|&id| employees.get_attr(id, "emp_name") == Some(&Value::String(name.into())))
+                .unwrap();
+            let dept = employees.get_fk(emp, "department").unwrap();
+            departments.get_attr(dept, "dept_label").unwrap().clone()
+        };
+        assert_eq!(dept_of("Alice"), Value::String("Engineering".into()));
+        assert_eq!(dept_of("Bob"), Value::String("Engineering".into()));
+        assert_eq!(dept_of("Carol"), Value::String("Sales".into()));
+        assert_eq!(
+            employees.row_ids().into_iter()
+                .filter(|&id| employees.get_attr(id, "emp_name") != Some(&Value::String("Carol".into())))
+                .map(|id| employees.get_fk(id, "department"))
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            1,
+            "Alice et Bob pointent vers la même ligne"
+        );
+    }
+
+    #[test]
+    fn test_sigma_attribute_default() {
+        let s_old = old_schema();
+        let mut s_new = new_schema();
+        s_new.add_attribute_default("status", "Employee", BaseType::String, Value::String("active".into()));
+
+        let m = rename_mapping();
+        let inst_new = sigma(&m, &s_old, &s_new, &old_instance(&s_old)).unwrap();
+        let employees = &inst_new.data["Employee"];
+        assert_eq!(employees.len(), 2);
+        for id in employees.row_ids() {
+            assert_eq!(employees.get_attr(id, "status"), Some(&Value::String("active".into())));
+        }
+
+        use crate::backend::Backend;
+        let ddl = crate::backend::sql::SqlBackend::new(crate::backend::sql::PostgresDialect)
+            .deploy_schema(&s_new)
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(ddl.contains("\"status\" TEXT DEFAULT 'active'"), "{}", ddl);
+    }
+
     #[test]
     fn test_delta_simple_rename() {
         let s_old = old_schema();
@@ -380,8 +1117,135 @@ mod tests {
         let has_diana = person_rows.iter().any(|&id| {
             inst_old.data["Person"]
                 .get_attr(id, "person_name")
-                .map_or(false, |v| *v == Value::String("Diana".into()))
+                .is_some_and(|v| *v == Value::String("Diana".into()))
         });
         assert!(has_diana, "Diana devrait exister dans Person");
     }
+
+    #[test]
+    fn test_delta_follows_equation_shortcut() {
+        // T : Employee → Team → Department, avec le raccourci
+        // Employee.team.department = Employee.department
+        let mut s_new = Schema::new("New");
+        s_new.add_node("Employee")
+             .add_node("Team")
+             .add_node("Department")
+             .add_fk("team", "Employee", "Team")
+             .add_fk("department", "Team", "Department")
+             .add_fk("emp_department", "Employee", "Department")
+             .add_attribute("emp_name", "Employee", BaseType::String)
+             .add_attribute("dept_label", "Department", BaseType::String)
+             .add_path_equation(
+                 Path::new("Employee", vec!["team", "department"]),
+                 Path::new("Employee", vec!["emp_department"]),
+             );
+
+        let mut m = Mapping::new("F", "Old", "New");
+        m.map_node("Person", "Employee")
+         .map_node("Dept", "Department")
+         .map_fk("works_in", Path::new("Employee", vec!["team", "department"]))
+         .map_attr_direct("person_name", "emp_name")
+         .map_attr_direct("dept_name", "dept_label");
+
+        // L'équipe de Diana n'est pas renseignée : seul le raccourci l'est
+        let mut inst_new = Instance::new("NewData", &s_new);
+        let d1 = inst_new.insert("Department",
+            HashMap::from([("dept_label".into(), Value::String("R&D".into()))]),
+            HashMap::new(),
+        );
+        let diana = inst_new.insert("Employee",
+            HashMap::from([("emp_name".into(), Value::String("Diana".into()))]),
+            HashMap::from([("emp_department".into(), d1)]),
+        );
+
+        let inst_old = delta(&m, &old_schema(), &s_new, &inst_new);
+        assert_eq!(inst_old.data["Person"].get_fk(diana, "works_in"), Some(d1));
+    }
+
+    /// La chaîne S → T → U de `optimize::tests::test_compose_mappings`
+    fn chain() -> ([Schema; 3], Mapping, Mapping) {
+        let mut schema_s = Schema::new("S");
+        schema_s.add_node("Person").add_node("Dept")
+            .add_fk("works_in", "Person", "Dept")
+            .add_attribute("pname", "Person", BaseType::String)
+            .add_attribute("dname", "Dept", BaseType::String);
+
+        let mut schema_t = Schema::new("T");
+        schema_t.add_node("Employee").add_node("Department")
+            .add_fk("dept", "Employee", "Department")
+            .add_attribute("ename", "Employee", BaseType::String)
+            .add_attribute("dlabel", "Department", BaseType::String);
+
+        let mut schema_u = Schema::new("U");
+        schema_u.add_node("Worker").add_node("Unit")
+            .add_fk("unit", "Worker", "Unit")
+            .add_attribute("wname", "Worker", BaseType::String)
+            .add_attribute("uname", "Unit", BaseType::String);
+
+        let mut f = Mapping::new("F", "S", "T");
+        f.map_node("Person", "Employee")
+         .map_node("Dept", "Department")
+         .map_fk("works_in", Path::new("Employee", vec!["dept"]))
+         .map_attr_direct("pname", "ename")
+         .map_attr_direct("dname", "dlabel");
+
+        let mut g = Mapping::new("G", "T", "U");
+        g.map_node("Employee", "Worker")
+         .map_node("Department", "Unit")
+         .map_fk("dept", Path::new("Worker", vec!["unit"]))
+         .map_attr_direct("ename", "wname")
+         .map_attr_direct("dlabel", "uname");
+
+        ([schema_s, schema_t, schema_u], f, g)
+    }
+
+    /// Contenu d'une instance indépendant des RowId : chaque ligne de
+    /// `entity` décrite par son attribut et celui de la cible de sa FK
+    fn describe(inst: &Instance, entity: &str, attr: &str, fk: &str, target: &str, target_attr: &str) -> Vec<String> {
+        let data = &inst.data[entity];
+        let mut rows: Vec<String> = data.row_ids().into_iter()
+            .map(|id| {
+                let target_row = data.get_fk(id, fk).unwrap();
+                format!("{} -> {}", data.get_attr(id, attr).unwrap(),
+                        inst.data[target].get_attr(target_row, target_attr).unwrap())
+            })
+            .collect();
+        rows.sort();
+        rows
+    }
+
+    #[test]
+    fn test_sigma_chain_matches_two_steps() {
+        let ([s, t, u], f, g) = chain();
+        let mut inst = Instance::new("Data", &s);
+        let eng = inst.insert("Dept", crate::row! { dname: "Engineering" }, HashMap::new());
+        let ops = inst.insert("Dept", crate::row! { dname: "Ops" }, HashMap::new());
+        for (name, dept) in [("Alice", eng), ("Bob", eng), ("Carol", ops)] {
+            inst.insert("Person", crate::row! { pname: name }, HashMap::from([("works_in".into(), dept)]));
+        }
+
+        let one_pass = sigma_chain(&[&f, &g], &[&s, &t, &u], &inst).unwrap();
+        let two_pass = sigma(&g, &t, &u, &sigma(&f, &s, &t, &inst).unwrap()).unwrap();
+        assert_eq!(one_pass.data["Unit"].len(), 2);
+        assert_eq!(
+            describe(&one_pass, "Worker", "wname", "unit", "Unit", "uname"),
+            describe(&two_pass, "Worker", "wname", "unit", "Unit", "uname"),
+        );
+
+        // Δ le long de la chaîne ramène les données dans S
+        let back = delta_chain(&[&f, &g], &[&s, &t, &u], &one_pass).unwrap();
+        let back_two = delta(&f, &s, &t, &delta(&g, &t, &u, &one_pass));
+        assert_eq!(
+            describe(&back, "Person", "pname", "works_in", "Dept", "dname"),
+            describe(&inst, "Person", "pname", "works_in", "Dept", "dname"),
+        );
+        assert_eq!(
+            describe(&back, "Person", "pname", "works_in", "Dept", "dname"),
+            describe(&back_two, "Person", "pname", "works_in", "Dept", "dname"),
+        );
+
+        // Chaîne mal formée
+        assert!(sigma_chain(&[&g, &f], &[&s, &t, &u], &inst).is_err());
+        assert!(sigma_chain(&[&f, &g], &[&s, &u], &inst).is_err());
+    }
 }
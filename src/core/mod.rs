@@ -21,10 +21,12 @@
 
 pub mod typeside;
 pub mod schema;
+pub mod entity;
 pub mod optimize;
 pub mod query;
 pub mod eval;
 pub mod instance;
+pub mod column;
 pub mod mapping;
 pub mod migrate;
 pub mod validate;
@@ -30,6 +30,7 @@
 // =============================================================================
 
 use super::schema::{Schema, Path, Edge};
+use crate::error::CatrustError;
 
 /// Une règle de réécriture : on remplace `lhs` par `rhs` quand on trouve
 /// `lhs` comme sous-chemin.
@@ -98,45 +99,9 @@ impl PathOptimizer {
     /// - Si len(rhs) > len(lhs) : rhs → lhs (on raccourcit)
     /// - Si len(lhs) = len(rhs) : les deux directions (on choisit un ordre)
     pub fn from_schema(schema: &Schema) -> Self {
-        let mut rules = Vec::new();
-
-        for (i, eq) in schema.path_equations.iter().enumerate() {
-            let rule_name = format!("eq_{}", i);
-
-            if eq.lhs.len() > eq.rhs.len() {
-                // lhs est plus long → le réécrire vers rhs
-                rules.push(RewriteRule {
-                    lhs: eq.lhs.clone(),
-                    rhs: eq.rhs.clone(),
-                    name: rule_name,
-                });
-            } else if eq.rhs.len() > eq.lhs.len() {
-                // rhs est plus long → le réécrire vers lhs
-                rules.push(RewriteRule {
-                    lhs: eq.rhs.clone(),
-                    rhs: eq.lhs.clone(),
-                    name: rule_name,
-                });
-            } else {
-                // Même longueur : on choisit un ordre lexicographique
-                // (convention : on réécrit vers le "plus petit" lex.)
-                let lhs_str = format!("{}", eq.lhs);
-                let rhs_str = format!("{}", eq.rhs);
-                if lhs_str > rhs_str {
-                    rules.push(RewriteRule {
-                        lhs: eq.lhs.clone(),
-                        rhs: eq.rhs.clone(),
-                        name: rule_name,
-                    });
-                } else {
-                    rules.push(RewriteRule {
-                        lhs: eq.rhs.clone(),
-                        rhs: eq.lhs.clone(),
-                        name: rule_name,
-                    });
-                }
-            }
-        }
+        let mut rules: Vec<RewriteRule> = schema.path_equations.iter().enumerate()
+            .map(|(i, eq)| orient(&eq.lhs, &eq.rhs, format!("eq_{}", i)))
+            .collect();
 
         // Ajouter des règles dérivées par transitivité
         // Si A.f.g = A.h et A.h.k = A.m, alors A.f.g.k = A.m
@@ -147,6 +112,38 @@ impl PathOptimizer {
         PathOptimizer { rules }
     }
 
+    /// Complète le système de réécriture (Knuth-Bendix) pour le rendre
+    /// confluent : deux chemins égaux modulo les équations ont alors la
+    /// même forme normale.
+    ///
+    /// Pour chaque chevauchement entre deux membres gauches (paire critique),
+    /// on réécrit le mot commun des deux façons ; si les formes normales
+    /// diffèrent, leur égalité devient une nouvelle règle orientée. On
+    /// s'arrête au point fixe, ou après `MAX_COMPLETION_RULES` règles
+    /// ajoutées (la complétion peut ne pas terminer).
+    pub fn completed(mut self) -> Self {
+        let mut added = 0;
+        let mut changed = true;
+        while changed && added < MAX_COMPLETION_RULES {
+            changed = false;
+            'pairs: for i in 0..self.rules.len() {
+                for j in 0..self.rules.len() {
+                    for (via_i, via_j) in critical_pairs(&self.rules[i], &self.rules[j]) {
+                        let lhs = self.optimize_path(&via_i);
+                        let rhs = self.optimize_path(&via_j);
+                        if lhs != rhs {
+                            self.rules.push(orient(&lhs, &rhs, format!("completion_{}", added)));
+                            added += 1;
+                            changed = true;
+                            break 'pairs;
+                        }
+                    }
+                }
+            }
+        }
+        self
+    }
+
     /// Dérive des règles transitives.
     ///
     /// Si on a lhs1 → rhs1 et que rhs1 est un préfixe de lhs2,
@@ -308,6 +305,52 @@ impl PathOptimizer {
     }
 }
 
+/// Nombre maximal de règles ajoutées par `PathOptimizer::completed`
+const MAX_COMPLETION_RULES: usize = 64;
+
+/// Oriente l'égalité `a = b` en règle de réécriture : le chemin le plus
+/// long est réécrit vers le plus court ; à longueur égale, vers le plus
+/// petit dans l'ordre lexicographique.
+fn orient(a: &Path, b: &Path, name: String) -> RewriteRule {
+    let a_first = match a.len().cmp(&b.len()) {
+        std::cmp::Ordering::Equal => a.to_string() > b.to_string(),
+        ord => ord == std::cmp::Ordering::Greater,
+    };
+    let (lhs, rhs) = if a_first { (a, b) } else { (b, a) };
+    RewriteRule { lhs: lhs.clone(), rhs: rhs.clone(), name }
+}
+
+/// Paires critiques de deux règles de même départ : pour chaque mot où
+/// leurs membres gauches se chevauchent (suffixe de l'un = préfixe de
+/// l'autre, ou inclusion), ses deux réécrits en un pas.
+fn critical_pairs(r1: &RewriteRule, r2: &RewriteRule) -> Vec<(Path, Path)> {
+    let mut pairs = Vec::new();
+    if r1.lhs.start != r2.lhs.start || r1.lhs.edges.is_empty() || r2.lhs.edges.is_empty() {
+        return pairs;
+    }
+    let (l1, l2) = (&r1.lhs.edges, &r2.lhs.edges);
+    let path = |edges: Vec<String>| Path { start: r1.lhs.start.clone(), edges };
+
+    // Chevauchement : l1 = u.v, l2 = v.w  →  mot u.v.w
+    for k in 1..l1.len().min(l2.len()) {
+        if l1[l1.len() - k..] == l2[..k] {
+            let via_r1 = [&r1.rhs.edges[..], &l2[k..]].concat();
+            let via_r2 = [&l1[..l1.len() - k], &r2.rhs.edges[..]].concat();
+            pairs.push((path(via_r1), path(via_r2)));
+        }
+    }
+    // Inclusion : l1 = u.l2.w
+    if l2.len() < l1.len() {
+        for i in 0..=(l1.len() - l2.len()) {
+            if l1[i..i + l2.len()] == l2[..] {
+                let via_r2 = [&l1[..i], &r2.rhs.edges[..], &l1[i + l2.len()..]].concat();
+                pairs.push((r1.rhs.clone(), path(via_r2)));
+            }
+        }
+    }
+    pairs
+}
+
 /// Compose deux mappings pour éviter la matérialisation intermédiaire.
 ///
 /// Si F : S → T et G : T → U, alors compose(F, G) : S → U
@@ -322,15 +365,15 @@ pub fn compose_mappings(
     f: &super::mapping::Mapping,
     g: &super::mapping::Mapping,
     target_of_f: &Schema,  // = source de G
-) -> Result<super::mapping::Mapping, String> {
-    use super::mapping::{Mapping, EdgeMapping};
+) -> Result<super::mapping::Mapping, CatrustError> {
+    use super::mapping::{AttrFill, Mapping, EdgeMapping};
 
     // Vérifier que F.target = G.source
     if f.target_schema_name != g.source_schema_name {
-        return Err(format!(
-            "Incompatible : F cible '{}' ≠ G source '{}'",
-            f.target_schema_name, g.source_schema_name
-        ));
+        return Err(CatrustError::IncompatibleComposition {
+            f_target: f.target_schema_name.clone(),
+            g_source: g.source_schema_name.clone(),
+        });
     }
 
     let mut composed = Mapping::new(
@@ -346,10 +389,10 @@ pub fn compose_mappings(
                 composed.map_node(src_node, tgt_node);
             }
             None => {
-                return Err(format!(
-                    "Nœud intermédiaire '{}' (image de '{}' par F) non mappé par G",
-                    mid_node, src_node
-                ));
+                return Err(CatrustError::UnmappedNode {
+                    node: mid_node.clone(),
+                    mapping: g.name.clone(),
+                });
             }
         }
     }
@@ -366,17 +409,18 @@ pub fn compose_mappings(
                         Some(EdgeMapping::FkToPath(g_path)) => {
                             composed_edges.extend(g_path.edges.clone());
                         }
-                        Some(EdgeMapping::AttrToPath { .. }) => {
+                        Some(EdgeMapping::AttrToPath { .. })
+                        | Some(EdgeMapping::Bridge(_)) => {
                             return Err(format!(
                                 "FK '{}' dans F mène à l'arête '{}' qui est un attribut dans G",
                                 src_edge, mid_edge
-                            ));
+                            ).into());
                         }
                         None => {
-                            return Err(format!(
-                                "Arête '{}' (dans le chemin image de '{}') non mappée par G",
-                                mid_edge, src_edge
-                            ));
+                            return Err(CatrustError::UnmappedEdge {
+                                edge: mid_edge.clone(),
+                                mapping: g.name.clone(),
+                            });
                         }
                     }
                 }
@@ -420,7 +464,7 @@ pub fn compose_mappings(
                             return Err(format!(
                                 "FK '{}' dans le chemin de l'attribut '{}' non mappée par G",
                                 mid_fk, src_edge
-                            ));
+                            ).into());
                         }
                     }
                 }
@@ -439,10 +483,63 @@ pub fn compose_mappings(
                         return Err(format!(
                             "Attribut '{}' (image de '{}') non mappé comme attribut par G",
                             attr_name, src_edge
-                        ));
+                        ).into());
                     }
                 }
             }
+            EdgeMapping::Bridge(bridge) => {
+                // G doit renommer le pont et ses deux FK (chemins de longueur 1)
+                let fk_image = |fk: &str| match g.edge_mapping.get(fk) {
+                    Some(EdgeMapping::FkToPath(g_path)) if g_path.len() == 1 => Some(g_path.edges[0].clone()),
+                    _ => None,
+                };
+                match (g.node_mapping.get(&bridge.node), fk_image(&bridge.from), fk_image(&bridge.to)) {
+                    (Some(node), Some(from), Some(to)) => {
+                        composed.map_relation_to_bridge(src_edge, &bridge.related, node, &from, &to);
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Relation '{}' : G n'envoie pas le pont '{}' sur un nœud et deux FK",
+                            src_edge, bridge.node
+                        ).into());
+                    }
+                }
+            }
+        }
+    }
+
+    // Les clés métier de G portent sur des nœuds de U : elles sont conservées
+    composed.keys = g.keys.clone();
+
+    // F remplit l'attribut T.attr (constante ou chemin lu dans S) : G doit
+    // l'envoyer directement sur un attribut de U, qu'il suffit de renommer
+    for (attr, fill) in &f.filled_attrs {
+        match g.edge_mapping.get(attr) {
+            Some(EdgeMapping::AttrToPath { fk_path, attr_name }) if fk_path.is_empty() => {
+                composed.filled_attrs.insert(attr_name.clone(), fill.clone());
+            }
+            _ => {
+                return Err(format!(
+                    "Remplissage de '{}' : G ne l'envoie pas directement sur un attribut",
+                    attr
+                ).into());
+            }
+        }
+    }
+
+    // Les constantes de G portent sur des attributs de U : elles sont conservées ;
+    // les attributs lus par G à travers un chemin de T n'ont pas d'équivalent dans S
+    for (attr, fill) in &g.filled_attrs {
+        match fill {
+            AttrFill::Constant(value) => {
+                composed.map_attr_const(attr, value.clone());
+            }
+            AttrFill::SourcePath { .. } => {
+                return Err(format!(
+                    "Attribut '{}' lu par G à travers un chemin de T : non composable",
+                    attr
+                ).into());
+            }
         }
     }
 
@@ -451,6 +548,9 @@ pub fn compose_mappings(
 
 /// Énumère tous les chemins depuis un nœud donné, jusqu'à une profondeur max.
 /// Utile pour l'analyse d'optimisation.
+///
+/// Un chemin peut finir par un attribut (il s'arrête alors), pour que les
+/// équations d'observation soient aussi détectées.
 fn enumerate_paths(schema: &Schema, start: &str, max_depth: usize) -> Vec<Path> {
     let mut paths = Vec::new();
     let mut stack: Vec<(String, Vec<String>)> = vec![(start.to_string(), vec![])];
@@ -460,24 +560,25 @@ fn enumerate_paths(schema: &Schema, start: &str, max_depth: usize) -> Vec<Path>
             continue;
         }
 
-        // Trouver les FK sortantes du nœud courant
+        // Trouver les arêtes sortantes du nœud courant
         for edge in schema.edges.values() {
-            if let Edge::ForeignKey { name, source, target } = edge {
-                if source == &current_node {
-                    let mut new_edges = current_edges.clone();
-                    new_edges.push(name.clone());
-
-                    // Ajouter ce chemin s'il a au moins 2 arêtes
-                    if new_edges.len() >= 2 {
-                        paths.push(Path {
-                            start: start.to_string(),
-                            edges: new_edges.clone(),
-                        });
-                    }
+            if edge.source() != current_node {
+                continue;
+            }
+            let mut new_edges = current_edges.clone();
+            new_edges.push(edge.name().to_string());
+
+            // Ajouter ce chemin s'il a au moins 2 arêtes
+            if new_edges.len() >= 2 {
+                paths.push(Path {
+                    start: start.to_string(),
+                    edges: new_edges.clone(),
+                });
+            }
 
-                    // Continuer l'exploration
-                    stack.push((target.clone(), new_edges));
-                }
+            // Continuer l'exploration au-delà des FK
+            if let Edge::ForeignKey { target, .. } = edge {
+                stack.push((target.clone(), new_edges));
             }
         }
     }
@@ -638,4 +739,33 @@ mod tests {
         assert_eq!(composed.node_mapping.get("Dept").unwrap(), "Unit");
         println!("{}", composed);
     }
+
+    #[test]
+    fn test_compose_incompatible_error() {
+        use crate::core::mapping::Mapping;
+
+        let mut schema_t = Schema::new("T");
+        schema_t.add_node("Employee");
+
+        let mut f = Mapping::new("F", "S", "T");
+        f.map_node("Person", "Employee");
+        let mut g = Mapping::new("G", "V", "U");
+        g.map_node("Employee", "Worker");
+
+        // F arrive dans T, G part de V : la variante dit lesquels
+        match compose_mappings(&f, &g, &schema_t) {
+            Err(CatrustError::IncompatibleComposition { f_target, g_source }) => {
+                assert_eq!(f_target, "T");
+                assert_eq!(g_source, "V");
+            }
+            other => panic!("IncompatibleComposition attendu, obtenu {:?}", other),
+        }
+
+        // Schémas compatibles mais nœud intermédiaire oublié par G
+        let g = Mapping::new("G", "T", "U");
+        assert_eq!(
+            compose_mappings(&f, &g, &schema_t).unwrap_err(),
+            CatrustError::UnmappedNode { node: "Employee".into(), mapping: "G".into() },
+        );
+    }
 }
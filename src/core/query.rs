@@ -18,8 +18,9 @@
 // =============================================================================
 
 use std::collections::HashMap;
-use super::schema::{Schema, Path};
-use super::typeside::Value;
+use super::schema::{Schema, Path, Edge};
+use super::typeside::{BaseType, Value};
+use crate::error::CatrustError;
 
 /// Un bloc FROM d'une requête CQL : pour une entité cible,
 /// quelles entités source et quels chemins utiliser.
@@ -49,6 +50,110 @@ pub struct QueryBlock {
     pub attribute_bindings: HashMap<String, AttributeBinding>,
     /// Projection des FK : fk_résultat → chemin depuis une variable FROM
     pub fk_bindings: HashMap<String, FkBinding>,
+    /// `return var.*` : variables dont tous les attributs sont projetés,
+    /// sous leur propre nom (cf. `QueryBlock::expand_select_all`)
+    pub select_all: Vec<String>,
+    /// SELECT DISTINCT : supprime les lignes projetées identiques
+    pub distinct: bool,
+    /// Comportement face à une FK non définie le long d'un chemin
+    pub join_mode: JoinMode,
+    /// Filtres HAVING. S'il y en a, les lignes sont groupées par leur
+    /// projection (attributs + FK) : une ligne par groupe qui les satisfait.
+    pub having: Vec<HavingClause>,
+    /// SELECT COUNT(*) : le block ne projette rien et ne matérialise aucune
+    /// ligne ; seul le nombre de tuples retenus est retourné
+    /// (`EvalResult::scalar`). Exige des bindings vides.
+    pub count_only: bool,
+    /// ORDER BY sur des attributs résultat (vide = ordre non spécifié)
+    pub order_by: Vec<OrderKey>,
+}
+
+/// Clé de tri ORDER BY : un attribut résultat du block
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderKey {
+    /// Attribut résultat (clé de `attribute_bindings`)
+    pub attribute: String,
+    /// DESC (sinon ASC)
+    pub descending: bool,
+}
+
+impl QueryBlock {
+    /// Remplace chaque `var.*` de `select_all` par un binding par attribut
+    /// de l'entité de `var` (ordre de déclaration du schéma). Un binding
+    /// explicite du même nom l'emporte, de même que la première variable
+    /// quand deux entités ont un attribut homonyme.
+    pub fn expand_select_all(&self, schema: &Schema) -> QueryBlock {
+        let mut block = self.clone();
+        for var in std::mem::take(&mut block.select_all) {
+            let Some(entity) = block.from_vars.get(&var) else { continue };
+            for edge in schema.attributes_of(entity) {
+                block.attribute_bindings.entry(edge.name().to_string())
+                    .or_insert_with(|| AttributeBinding {
+                        from_var: var.clone(),
+                        path: Vec::new(),
+                        attribute: edge.name().to_string(),
+                        expr: None,
+                    });
+            }
+        }
+        block
+    }
+
+    /// Chemins du block (WHERE, projections, expressions, HAVING, FK
+    /// projetées), sans ceux des sous-requêtes EXISTS
+    pub fn paths(&self) -> Vec<(&String, &Vec<String>)> {
+        let mut paths = Vec::new();
+        for clause in &self.where_clauses {
+            match clause {
+                WhereClause::Comparison { var, path, .. }
+                | WhereClause::FnComparison { var, path, .. }
+                | WhereClause::Between { var, path, .. }
+                | WhereClause::IsNull { var, path }
+                | WhereClause::IsNotNull { var, path } => paths.push((var, path)),
+                WhereClause::PathEqual { var1, path1, var2, path2 }
+                | WhereClause::PathCompare { var1, path1, var2, path2, .. } => {
+                    paths.push((var1, path1));
+                    paths.push((var2, path2));
+                }
+                WhereClause::Compare { lhs, rhs, .. } => paths.extend([lhs, rhs].into_iter().filter_map(Side::as_path)),
+                WhereClause::Exists { .. } | WhereClause::AlwaysFalse => {}
+            }
+        }
+        let bindings = self.attribute_bindings.values()
+            .chain(self.having.iter().filter_map(|h| h.argument.as_ref()));
+        for ab in bindings {
+            paths.push((&ab.from_var, &ab.path));
+            paths.extend(ab.expr.iter().flat_map(|e| e.paths()));
+        }
+        paths.extend(self.fk_bindings.values().map(|fb| (&fb.from_var, &fb.path)));
+        paths
+    }
+
+    /// Le block parcourt-il une FK à l'envers (`~fk`, hors EXISTS) ?
+    pub fn has_reverse_edges(&self) -> bool {
+        self.paths().iter().any(|(_, path)| path.iter().any(|e| reverse_edge(e).is_some()))
+    }
+}
+
+/// Préfixe d'une FK parcourue à l'envers dans un chemin de requête :
+/// depuis un département `d`, `d.~works_in.emp_name` donne le nom de CHACUN
+/// des employés dont `works_in` pointe vers `d` (une ligne par employé).
+pub const REVERSE_EDGE_PREFIX: char = '~';
+
+/// Nom de la FK si `edge` est parcourue à l'envers (`~works_in` → `works_in`).
+pub fn reverse_edge(edge: &str) -> Option<&str> {
+    edge.strip_prefix(REVERSE_EDGE_PREFIX)
+}
+
+/// Sémantique d'une FK non définie lors de la navigation d'un chemin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JoinMode {
+    /// Jointure interne : une FK non définie est une erreur (défaut)
+    #[default]
+    Inner,
+    /// Jointure externe gauche : la valeur au bout du chemin est NULL et la
+    /// ligne est conservée (LEFT JOIN en SQL)
+    Left,
 }
 
 /// Une clause WHERE
@@ -61,6 +166,34 @@ pub enum WhereClause {
         op: CompOp,            // opérateur
         value: Value,          // valeur constante
     },
+    /// Comparaison d'une fonction de chaîne appliquée au chemin
+    /// (ex: lower(e.emp_name) = "alice")
+    FnComparison {
+        func: StringFn,
+        var: String,
+        path: Vec<String>,
+        op: CompOp,
+        value: Value,
+    },
+    /// Intervalle fermé : low <= chemin <= high (ex: e.salary BETWEEN 70000 AND 90000).
+    /// Un intervalle vide (low > high) n'est jamais satisfait.
+    Between {
+        var: String,
+        path: Vec<String>,
+        low: Value,
+        high: Value,
+    },
+    /// Le chemin mène à NULL : attribut absent ou NULL, ou FK non définie
+    /// en cours de route (ex: e.works_in IS NULL)
+    IsNull {
+        var: String,
+        path: Vec<String>,
+    },
+    /// Négation de `IsNull`
+    IsNotNull {
+        var: String,
+        path: Vec<String>,
+    },
     /// Égalité de chemins : deux chemins doivent mener au même endroit
     /// (ex: e1.department = e2.department)
     PathEqual {
@@ -69,6 +202,148 @@ pub enum WhereClause {
         var2: String,
         path2: Vec<String>,
     },
+    /// Comparaison de deux chemins, éventuellement sur la même variable
+    /// (ex: e.salary > e.manager.salary). Généralise `PathEqual` à tout
+    /// opérateur ; NULL suit la sémantique de `Comparison`.
+    PathCompare {
+        var1: String,
+        path1: Vec<String>,
+        op: CompOp,
+        var2: String,
+        path2: Vec<String>,
+    },
+    /// Comparaison dont chaque opérande est un chemin ou une constante
+    /// (ex: 80000 < e.salary, ou 1 = 1). NULL suit la sémantique de
+    /// `Comparison`.
+    Compare {
+        lhs: Side,
+        op: CompOp,
+        rhs: Side,
+    },
+    /// Sous-requête corrélée : vraie si le block interne a au moins un
+    /// résultat. Ses clauses peuvent référencer les variables du block
+    /// externe (ex: exists m : Manager where m.manages = e.department).
+    /// Seules `from_vars` et `where_clauses` du block interne sont utilisées.
+    Exists {
+        inner: Box<QueryBlock>,
+    },
+    /// Clause toujours fausse : produite par `CqlQuery::simplify` quand les
+    /// clauses d'un block sont contradictoires (ex: x > 80000 et x < 50000)
+    AlwaysFalse,
+}
+
+/// Opérande d'une clause `WhereClause::Compare`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Side {
+    /// `var.path` : chemin de FK puis (éventuellement) attribut
+    Path { var: String, path: Vec<String> },
+    /// Valeur constante
+    Literal(Value),
+    /// Sous-requête scalaire : agrégat sur toute une entité, calculé une
+    /// seule fois (ex: e.salary > AVG(Employee.salary))
+    Aggregate(ScalarAggregate),
+}
+
+/// Agrégat d'un attribut sur toutes les lignes d'une entité
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalarAggregate {
+    pub func: AggFunc,
+    pub entity: String,
+    /// Attribut agrégé ; `None` pour COUNT(*)
+    pub attribute: Option<String>,
+}
+
+impl std::fmt::Display for ScalarAggregate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.attribute {
+            Some(attribute) => write!(f, "{}({}.{})", self.func, self.entity, attribute),
+            None => write!(f, "{}({}.*)", self.func, self.entity),
+        }
+    }
+}
+
+impl Side {
+    /// Opérande `var.path`
+    pub fn path(var: &str, path: &[&str]) -> Self {
+        Side::Path { var: var.to_string(), path: to_strings(path) }
+    }
+
+    /// Opérande `func(entity.attribute)`, agrégé sur toute l'entité
+    pub fn aggregate(func: AggFunc, entity: &str, attribute: &str) -> Self {
+        Side::Aggregate(ScalarAggregate {
+            func,
+            entity: entity.to_string(),
+            attribute: Some(attribute.to_string()),
+        })
+    }
+
+    /// (variable, chemin) d'un opérande chemin
+    pub fn as_path(&self) -> Option<(&String, &Vec<String>)> {
+        match self {
+            Side::Path { var, path } => Some((var, path)),
+            Side::Literal(_) | Side::Aggregate(_) => None,
+        }
+    }
+
+    /// (variable, chemin) modifiables d'un opérande chemin
+    pub fn as_path_mut(&mut self) -> Option<(&mut String, &mut Vec<String>)> {
+        match self {
+            Side::Path { var, path } => Some((var, path)),
+            Side::Literal(_) | Side::Aggregate(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Path { var, path } => {
+                write!(f, "{}", std::iter::once(var).chain(path).cloned().collect::<Vec<_>>().join("."))
+            }
+            Side::Literal(value) => write!(f, "{}", value),
+            Side::Aggregate(aggregate) => write!(f, "{}", aggregate),
+        }
+    }
+}
+
+impl std::fmt::Display for WhereClause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dotted = |var: &str, path: &[String]| {
+            std::iter::once(var).chain(path.iter().map(|s| s.as_str())).collect::<Vec<_>>().join(".")
+        };
+        match self {
+            WhereClause::Comparison { var, path, op, value } => {
+                write!(f, "{} {} {}", dotted(var, path), op, value)
+            }
+            WhereClause::FnComparison { func, var, path, op, value } => {
+                write!(f, "{} {} {}", func.wrap(&dotted(var, path)), op, value)
+            }
+            WhereClause::Between { var, path, low, high } => {
+                write!(f, "{} between {} and {}", dotted(var, path), low, high)
+            }
+            WhereClause::IsNull { var, path } => write!(f, "{} is null", dotted(var, path)),
+            WhereClause::IsNotNull { var, path } => write!(f, "{} is not null", dotted(var, path)),
+            WhereClause::PathEqual { var1, path1, var2, path2 } => {
+                write!(f, "{} = {}", dotted(var1, path1), dotted(var2, path2))
+            }
+            WhereClause::PathCompare { var1, path1, op, var2, path2 } => {
+                write!(f, "{} {} {}", dotted(var1, path1), op, dotted(var2, path2))
+            }
+            WhereClause::Compare { lhs, op, rhs } => write!(f, "{} {} {}", lhs, op, rhs),
+            WhereClause::Exists { inner } => {
+                let mut vars: Vec<String> = inner.from_vars.iter()
+                    .map(|(v, e)| format!("{} : {}", v, e))
+                    .collect();
+                vars.sort();
+                write!(f, "exists {}", vars.join(", "))?;
+                for (i, clause) in inner.where_clauses.iter().enumerate() {
+                    write!(f, "{} {}", if i == 0 { " where" } else { " and" }, clause)?;
+                }
+                Ok(())
+            }
+            WhereClause::AlwaysFalse => write!(f, "false"),
+        }
+    }
 }
 
 /// Opérateur de comparaison
@@ -95,12 +370,200 @@ impl std::fmt::Display for CompOp {
     }
 }
 
+/// Fonction d'agrégat d'une clause HAVING ou d'une sous-requête scalaire
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggFunc {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl std::fmt::Display for AggFunc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AggFunc::Count => write!(f, "COUNT"),
+            AggFunc::Sum => write!(f, "SUM"),
+            AggFunc::Avg => write!(f, "AVG"),
+            AggFunc::Min => write!(f, "MIN"),
+            AggFunc::Max => write!(f, "MAX"),
+        }
+    }
+}
+
+/// Une clause HAVING : agrégat sur le groupe, comparé à une constante
+/// (ex: SUM(e.salary) > 200000)
+#[derive(Debug, Clone)]
+pub struct HavingClause {
+    pub func: AggFunc,
+    /// Valeur agrégée, lue depuis une variable FROM ; `None` pour COUNT(*)
+    pub argument: Option<AttributeBinding>,
+    pub op: CompOp,
+    pub value: Value,
+}
+
+impl std::fmt::Display for HavingClause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.argument {
+            Some(arg) => {
+                let dotted: Vec<&str> = std::iter::once(arg.from_var.as_str())
+                    .chain(arg.path.iter().map(|s| s.as_str()))
+                    .chain(std::iter::once(arg.attribute.as_str()))
+                    .collect();
+                write!(f, "{}({}) {} {}", self.func, dotted.join("."), self.op, self.value)
+            }
+            None => write!(f, "{}(*) {} {}", self.func, self.op, self.value),
+        }
+    }
+}
+
 /// Binding d'un attribut résultat vers un chemin source
 #[derive(Debug, Clone)]
 pub struct AttributeBinding {
     pub from_var: String,        // variable FROM
     pub path: Vec<String>,       // chemin de FK (peut être vide)
     pub attribute: String,       // attribut final
+    /// Colonne calculée : la valeur projetée est cette expression, où
+    /// `AttrExpr::Bound` désigne la valeur lue ci-dessus (None = valeur brute)
+    pub expr: Option<AttrExpr>,
+}
+
+impl AttributeBinding {
+    /// Chemin complet lu par le binding : les FK puis l'attribut. Un
+    /// attribut vide projette le RowId atteint par les FK (`e` seul : la
+    /// ligne elle-même).
+    pub fn full_path(&self) -> Vec<String> {
+        let mut full_path = self.path.clone();
+        if !self.attribute.is_empty() {
+            full_path.push(self.attribute.clone());
+        }
+        full_path
+    }
+}
+
+/// Opérateur arithmétique d'une colonne calculée
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArithOp {
+    Add,     // +
+    Sub,     // -
+    Mul,     // *
+    Div,     // /
+}
+
+impl std::fmt::Display for ArithOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArithOp::Add => write!(f, "+"),
+            ArithOp::Sub => write!(f, "-"),
+            ArithOp::Mul => write!(f, "*"),
+            ArithOp::Div => write!(f, "/"),
+        }
+    }
+}
+
+/// Fonction de chaîne, dans un WHERE ou une colonne calculée
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringFn {
+    Upper,
+    Lower,
+    /// Espaces retirés aux deux bouts, comme `TRIM` en SQL : les autres
+    /// blancs (tabulation, retour à la ligne) restent
+    Trim,
+    /// `length` caractères à partir de `start` (le premier est 1, comme en
+    /// SQL) ; `None` = jusqu'au bout. Comme `SUBSTRING ... FROM ... FOR`,
+    /// la fenêtre `[start, start + length)` est coupée avant le premier
+    /// caractère : `start: 0` donne `length - 1` caractères.
+    Substring { start: usize, length: Option<usize> },
+}
+
+impl StringFn {
+    /// Applique la fonction ; NULL donne NULL, un non-texte est une erreur.
+    pub fn apply(&self, value: &Value) -> Result<Value, String> {
+        let s = match value {
+            Value::Null => return Ok(Value::Null),
+            Value::String(s) => s,
+            other => return Err(format!("{} attend une chaîne, pas {}", self.wrap("_"), other)),
+        };
+        Ok(Value::String(match self {
+            StringFn::Upper => s.to_uppercase(),
+            StringFn::Lower => s.to_lowercase(),
+            StringFn::Trim => s.trim_matches(' ').to_string(),
+            StringFn::Substring { start, length } => {
+                let chars = s.chars().skip(start.saturating_sub(1));
+                match length {
+                    Some(n) => chars.take((start + n).saturating_sub((*start).max(1))).collect(),
+                    None => chars.collect(),
+                }
+            }
+        }))
+    }
+
+    /// Appel lisible sur `arg` (ex: "lower(e.emp_name)")
+    pub fn wrap(&self, arg: &str) -> String {
+        match self {
+            StringFn::Upper => format!("upper({})", arg),
+            StringFn::Lower => format!("lower({})", arg),
+            StringFn::Trim => format!("trim({})", arg),
+            StringFn::Substring { start, length: Some(n) } => format!("substring({}, {}, {})", arg, start, n),
+            StringFn::Substring { start, length: None } => format!("substring({}, {})", arg, start),
+        }
+    }
+}
+
+/// Expression d'une colonne calculée (ex: `e.salary * 12`)
+#[derive(Debug, Clone)]
+pub enum AttrExpr {
+    /// La valeur lue par le binding (`from_var.path.attribute`)
+    Bound,
+    /// La valeur au bout d'un autre chemin (FK* puis attribut)
+    Path { var: String, path: Vec<String> },
+    /// Une constante
+    Const(Value),
+    /// Opération arithmétique (cf. `Value::add` pour les types)
+    Binary { op: ArithOp, lhs: Box<AttrExpr>, rhs: Box<AttrExpr> },
+    /// Fonction de chaîne (ex: `upper(e.emp_name)`)
+    Func { func: StringFn, arg: Box<AttrExpr> },
+}
+
+impl AttrExpr {
+    /// `lhs op rhs`
+    pub fn binary(op: ArithOp, lhs: AttrExpr, rhs: AttrExpr) -> Self {
+        AttrExpr::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) }
+    }
+
+    /// `func(arg)`
+    pub fn func(func: StringFn, arg: AttrExpr) -> Self {
+        AttrExpr::Func { func, arg: Box::new(arg) }
+    }
+
+    /// Chemins des feuilles `Path`, mutables (optimisation, réécritures)
+    pub fn paths_mut(&mut self) -> Vec<(&mut String, &mut Vec<String>)> {
+        match self {
+            AttrExpr::Path { var, path } => vec![(var, path)],
+            AttrExpr::Binary { lhs, rhs, .. } => {
+                let mut paths = lhs.paths_mut();
+                paths.extend(rhs.paths_mut());
+                paths
+            }
+            AttrExpr::Func { arg, .. } => arg.paths_mut(),
+            AttrExpr::Bound | AttrExpr::Const(_) => vec![],
+        }
+    }
+
+    /// Chemins des feuilles `Path` : (variable, chemin)
+    pub fn paths(&self) -> Vec<(&String, &Vec<String>)> {
+        match self {
+            AttrExpr::Path { var, path } => vec![(var, path)],
+            AttrExpr::Binary { lhs, rhs, .. } => {
+                let mut paths = lhs.paths();
+                paths.extend(rhs.paths());
+                paths
+            }
+            AttrExpr::Func { arg, .. } => arg.paths(),
+            AttrExpr::Bound | AttrExpr::Const(_) => vec![],
+        }
+    }
 }
 
 /// Binding d'une FK résultat vers un chemin source
@@ -120,6 +583,19 @@ pub struct CqlQuery {
 }
 
 impl CqlQuery {
+    /// Démarre la construction fluide d'un block (entité cible "Result") :
+    ///
+    /// ```ignore
+    /// let block = CqlQuery::select()
+    ///     .from("e", "Employee")
+    ///     .where_eq("e", &["salary"], Value::Integer(80000))
+    ///     .bind("name", "e", &[], "emp_name")
+    ///     .build()?;
+    /// ```
+    pub fn select() -> QueryBlockBuilder {
+        QueryBlockBuilder::new("Result")
+    }
+
     pub fn new(name: &str, source_schema: &str) -> Self {
         CqlQuery {
             name: name.to_string(),
@@ -129,11 +605,108 @@ impl CqlQuery {
         }
     }
 
-    /// Ajoute un block de requête
-    pub fn add_block(&mut self, block: QueryBlock) {
-        // Ajouter l'entité au schéma résultat
-        self.result_schema.add_node(&block.target_entity);
+    /// Ajoute un block de requête, après l'avoir vérifié contre le schéma
+    /// source. Déclare dans `result_schema` l'entité cible, ses attributs
+    /// (typés comme l'attribut source au bout du chemin) et ses FK. Une FK
+    /// résultat doit viser une entité déjà présente dans `result_schema`.
+    ///
+    /// Les arêtes résultat sont nommées `entité.nom` (voir `result_edge`) :
+    /// deux blocks peuvent projeter chacun un attribut `name`.
+    pub fn add_block(&mut self, block: QueryBlock, source: &Schema) -> Result<(), Vec<String>> {
+        let block = block.expand_select_all(source);
+        let mut errors = Vec::new();
+        block_errors(&block, source, &HashMap::new(), &mut errors);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let target = block.target_entity.clone();
+        let mut attributes: Vec<(&String, BaseType)> = Vec::new();
+        let mut fks: Vec<(&String, String)> = Vec::new();
+        for (name, ab) in &block.attribute_bindings {
+            match binding_type(ab, &block.from_vars, source) {
+                Ok(ty) => match self.result_edge(&target, name) {
+                    // Même entité cible qu'un block précédent : même type
+                    Some(Edge::Attribute { target: declared, .. }) if *declared != ty => errors.push(format!(
+                        "Block '{}' : attribut '{}' : {} ici, {} dans un block précédent", target, name, ty, declared
+                    )),
+                    Some(Edge::ForeignKey { .. }) => errors.push(format!(
+                        "Block '{}' : '{}' est déjà une FK du résultat", target, name
+                    )),
+                    _ => attributes.push((name, ty)),
+                },
+                Err(e) => errors.push(format!("Block '{}' : attribut '{}' : {}", target, name, e)),
+            }
+        }
+        for (name, fb) in &block.fk_bindings {
+            let reached = walk_path(source, &block.from_vars[&fb.from_var], &fb.path)
+                .map(|(entity, _)| entity)
+                .unwrap_or_default();
+            if reached != target && !self.result_schema.nodes.contains_key(&reached) {
+                errors.push(format!(
+                    "Block '{}' : la FK '{}' vise '{}', absente du schéma résultat", target, name, reached
+                ));
+            } else if matches!(self.result_edge(&target, name), Some(Edge::Attribute { .. })) {
+                errors.push(format!("Block '{}' : '{}' est déjà un attribut du résultat", target, name));
+            } else {
+                fks.push((name, reached));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        self.result_schema.add_node(&target);
+        attributes.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, ty) in attributes {
+            if !self.result_schema.typeside.declares(&ty) {
+                self.result_schema.typeside.add_type(ty.clone());
+            }
+            self.result_schema.add_attribute(&result_edge_name(&target, name), &target, ty);
+        }
+        fks.sort();
+        for (name, fk_target) in fks {
+            self.result_schema.add_fk(&result_edge_name(&target, name), &target, &fk_target);
+        }
         self.blocks.push(block);
+        Ok(())
+    }
+
+    /// Arête de `result_schema` pour l'attribut ou la FK résultat `name` de
+    /// l'entité `entity`
+    pub fn result_edge(&self, entity: &str, name: &str) -> Option<&Edge> {
+        self.result_schema.edges.get(&result_edge_name(entity, name))
+    }
+
+    /// Vérifie chaque chemin de la requête (WHERE, projections, HAVING,
+    /// sous-requêtes EXISTS) contre le schéma source, avant toute
+    /// évaluation : variables déclarées, chaque arête intermédiaire est une
+    /// FK de l'entité atteinte, et le chemin finit par un attribut ou une FK
+    /// selon ce qu'attend la clause.
+    ///
+    /// Chaque erreur situe la clause fautive (ex: `Block 'Result' : WHERE #0 :
+    /// e.departement.dept_name : arête 'departement' inconnue`).
+    pub fn typecheck(&self, source: &Schema) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for block in &self.blocks {
+            block_errors(block, source, &HashMap::new(), &mut errors);
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Vérifie la requête contre le schéma source (`typecheck`), et la
+    /// présence de chaque entité cible dans `result_schema`.
+    pub fn validate(&self, source: &Schema) -> Result<(), Vec<String>> {
+        let mut errors = self.typecheck(source).err().unwrap_or_default();
+        for block in &self.blocks {
+            if !self.result_schema.nodes.contains_key(&block.target_entity) {
+                errors.push(format!(
+                    "Block '{}' : entité absente du schéma résultat '{}'",
+                    block.target_entity, self.result_schema.name
+                ));
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
     }
 
     /// Optimise les chemins de la requête en utilisant les path equations du schéma source.
@@ -147,7 +720,11 @@ impl CqlQuery {
             // Optimiser les WHERE
             for clause in &mut block.where_clauses {
                 match clause {
-                    WhereClause::Comparison { var, path, .. } => {
+                    WhereClause::Comparison { var, path, .. }
+                    | WhereClause::FnComparison { var, path, .. }
+                    | WhereClause::Between { var, path, .. }
+                    | WhereClause::IsNull { var, path }
+                    | WhereClause::IsNotNull { var, path } => {
                         if path.len() >= 2 {
                             // Construire un Path et l'optimiser
                             // On utilise la variable FROM pour trouver l'entité de départ
@@ -161,7 +738,8 @@ impl CqlQuery {
                             }
                         }
                     }
-                    WhereClause::PathEqual { var1, path1, var2, path2 } => {
+                    WhereClause::PathEqual { var1, path1, var2, path2 }
+                    | WhereClause::PathCompare { var1, path1, var2, path2, .. } => {
                         if let Some(entity1) = block.from_vars.get(var1) {
                             let full1 = Path { start: entity1.clone(), edges: path1.clone() };
                             let opt1 = optimizer.optimize_path(&full1);
@@ -173,11 +751,21 @@ impl CqlQuery {
                             *path2 = opt2.edges;
                         }
                     }
+                    WhereClause::Compare { lhs, rhs, .. } => {
+                        for (var, path) in [lhs, rhs].into_iter().filter_map(Side::as_path_mut) {
+                            if let Some(entity) = block.from_vars.get(var.as_str()) {
+                                let full_path = Path { start: entity.clone(), edges: path.clone() };
+                                *path = optimizer.optimize_path(&full_path).edges;
+                            }
+                        }
+                    }
+                    // Les sous-requêtes ne sont pas réécrites
+                    WhereClause::Exists { .. } | WhereClause::AlwaysFalse => {}
                 }
             }
 
             // Optimiser les bindings d'attributs
-            for (_attr, binding) in &mut block.attribute_bindings {
+            for binding in block.attribute_bindings.values_mut() {
                 if binding.path.len() >= 2 {
                     if let Some(entity) = block.from_vars.get(&binding.from_var) {
                         let full_path = Path {
@@ -188,11 +776,640 @@ impl CqlQuery {
                         binding.path = opt.edges;
                     }
                 }
+                // ... et les chemins de leurs colonnes calculées
+                for (var, path) in binding.expr.iter_mut().flat_map(|e| e.paths_mut()) {
+                    if let Some(entity) = block.from_vars.get(var.as_str()) {
+                        let full_path = Path { start: entity.clone(), edges: path.clone() };
+                        *path = optimizer.optimize_path(&full_path).edges;
+                    }
+                }
             }
         }
 
         optimized
     }
+
+    /// Simplifie les clauses WHERE de chaque block (y compris des EXISTS) :
+    /// - supprime les clauses exactement dupliquées ;
+    /// - si des comparaisons sur un même chemin sont contradictoires
+    ///   (`salary > 80000` et `salary < 50000`, deux `=` différents...),
+    ///   remplace toutes les clauses du block par `WhereClause::AlwaysFalse`.
+    pub fn simplify(&self) -> CqlQuery {
+        let mut simplified = self.clone();
+        for block in &mut simplified.blocks {
+            simplify_block(block);
+        }
+        simplified
+    }
+}
+
+fn simplify_block(block: &mut QueryBlock) {
+    // Doublons exacts (la première occurrence est gardée)
+    let mut seen = std::collections::HashSet::new();
+    block.where_clauses.retain(|clause| match clause {
+        WhereClause::Exists { .. } => true,
+        _ => seen.insert(format!("{:?}", clause)),
+    });
+
+    for clause in &mut block.where_clauses {
+        if let WhereClause::Exists { inner } = clause {
+            simplify_block(inner);
+        }
+    }
+
+    // Comparaisons regroupées par chemin
+    let mut ranges: Vec<((&String, &Vec<String>), Range)> = Vec::new();
+    let comparisons = block.where_clauses.iter().flat_map(|clause| match clause {
+        WhereClause::Comparison { var, path, op, value } => vec![(var, path, op.clone(), value)],
+        WhereClause::Between { var, path, low, high } => {
+            vec![(var, path, CompOp::Gte, low), (var, path, CompOp::Lte, high)]
+        }
+        _ => vec![],
+    });
+    for (var, path, op, value) in comparisons {
+        let key = (var, path);
+        let i = match ranges.iter().position(|(k, _)| *k == key) {
+            Some(i) => i,
+            None => {
+                ranges.push((key, Range::default()));
+                ranges.len() - 1
+            }
+        };
+        ranges[i].1.add(&op, value);
+    }
+
+    let contradictory = ranges.iter().any(|(_, range)| range.is_empty())
+        || block.where_clauses.iter().any(|c| matches!(c, WhereClause::AlwaysFalse));
+    if contradictory {
+        block.where_clauses = vec![WhereClause::AlwaysFalse];
+    }
+}
+
+/// Valeurs admises par les comparaisons portant sur un même chemin
+#[derive(Default)]
+struct Range<'a> {
+    /// Borne inférieure la plus serrée : (valeur, stricte)
+    lower: Option<(&'a Value, bool)>,
+    /// Borne supérieure la plus serrée : (valeur, stricte)
+    upper: Option<(&'a Value, bool)>,
+    eq: Vec<&'a Value>,
+    neq: Vec<&'a Value>,
+}
+
+impl<'a> Range<'a> {
+    fn add(&mut self, op: &CompOp, value: &'a Value) {
+        use std::cmp::Ordering;
+        // Remplace la borne si la nouvelle est plus serrée (dans le sens `tighter`)
+        let tighten = |bound: &mut Option<(&'a Value, bool)>, strict: bool, tighter: Ordering| {
+            let replace = match *bound {
+                None => true,
+                Some((current, current_strict)) => match value_order(value, current) {
+                    Some(Ordering::Equal) => strict && !current_strict,
+                    Some(order) => order == tighter,
+                    None => false,
+                },
+            };
+            if replace {
+                *bound = Some((value, strict));
+            }
+        };
+
+        match op {
+            CompOp::Eq => self.eq.push(value),
+            CompOp::Neq => self.neq.push(value),
+            CompOp::Gt => tighten(&mut self.lower, true, Ordering::Greater),
+            CompOp::Gte => tighten(&mut self.lower, false, Ordering::Greater),
+            CompOp::Lt => tighten(&mut self.upper, true, Ordering::Less),
+            CompOp::Lte => tighten(&mut self.upper, false, Ordering::Less),
+        }
+    }
+
+    /// Vrai si aucune valeur ne peut satisfaire toutes les comparaisons.
+    /// Des valeurs de types incomparables ne concluent jamais au vide.
+    fn is_empty(&self) -> bool {
+        use std::cmp::Ordering;
+        let outside = |v: &Value, bound: Option<(&Value, bool)>, outside: Ordering| match bound {
+            Some((b, strict)) => match value_order(v, b) {
+                Some(Ordering::Equal) => strict,
+                Some(order) => order == outside,
+                None => false,
+            },
+            None => false,
+        };
+
+        if let Some(v) = self.eq.first() {
+            let conflicting = self.eq.iter()
+                .any(|other| matches!(value_order(v, other), Some(o) if o != Ordering::Equal))
+                || self.neq.iter().any(|n| value_order(v, n) == Some(Ordering::Equal));
+            if conflicting || outside(v, self.lower, Ordering::Less) || outside(v, self.upper, Ordering::Greater) {
+                return true;
+            }
+        }
+        match (self.lower, self.upper) {
+            (Some((l, lower_strict)), Some((u, upper_strict))) => match value_order(l, u) {
+                Some(Ordering::Greater) => true,
+                Some(Ordering::Equal) => lower_strict || upper_strict,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Ordre entre deux constantes comparables en SQL (NULL ne l'est jamais)
+fn value_order(a: &Value, b: &Value) -> Option<std::cmp::Ordering> {
+    match (a, b) {
+        (Value::Null, _) | (_, Value::Null) => None,
+        _ => a.partial_cmp(b),
+    }
+}
+
+/// Construction fluide d'un `QueryBlock`, sans écrire les HashMap à la main.
+///
+/// `build` vérifie que chaque variable utilisée (WHERE, bindings) est
+/// déclarée par un `from`.
+#[derive(Debug, Clone)]
+pub struct QueryBlockBuilder {
+    block: QueryBlock,
+}
+
+impl QueryBlockBuilder {
+    /// Builder vide pour l'entité cible `target_entity`
+    pub fn new(target_entity: &str) -> Self {
+        QueryBlockBuilder {
+            block: QueryBlock {
+                target_entity: target_entity.to_string(),
+                from_vars: HashMap::new(),
+                where_clauses: Vec::new(),
+                attribute_bindings: HashMap::new(),
+                fk_bindings: HashMap::new(),
+                distinct: false,
+                join_mode: JoinMode::Inner,
+                having: Vec::new(),
+                count_only: false,
+                order_by: Vec::new(),
+                select_all: Vec::new(),
+            },
+        }
+    }
+
+    /// Change l'entité cible du block
+    pub fn into_entity(&mut self, target_entity: &str) -> &mut Self {
+        self.block.target_entity = target_entity.to_string();
+        self
+    }
+
+    /// Déclare une variable FROM : `from var : entity`
+    pub fn from(&mut self, var: &str, entity: &str) -> &mut Self {
+        self.block.from_vars.insert(var.to_string(), entity.to_string());
+        self
+    }
+
+    /// Ajoute `where var.path op value`
+    pub fn where_cmp(&mut self, var: &str, path: &[&str], op: CompOp, value: Value) -> &mut Self {
+        self.block.where_clauses.push(WhereClause::Comparison {
+            var: var.to_string(),
+            path: to_strings(path),
+            op,
+            value,
+        });
+        self
+    }
+
+    /// Ajoute `where var.path between low and high` (bornes incluses)
+    pub fn where_between(&mut self, var: &str, path: &[&str], low: Value, high: Value) -> &mut Self {
+        self.block.where_clauses.push(WhereClause::Between {
+            var: var.to_string(),
+            path: to_strings(path),
+            low,
+            high,
+        });
+        self
+    }
+
+    /// Ajoute `where var.path is null`
+    pub fn where_null(&mut self, var: &str, path: &[&str]) -> &mut Self {
+        self.block.where_clauses.push(WhereClause::IsNull { var: var.to_string(), path: to_strings(path) });
+        self
+    }
+
+    /// Ajoute `where var.path is not null`
+    pub fn where_not_null(&mut self, var: &str, path: &[&str]) -> &mut Self {
+        self.block.where_clauses.push(WhereClause::IsNotNull { var: var.to_string(), path: to_strings(path) });
+        self
+    }
+
+    /// Ajoute `where var.path = value`
+    pub fn where_eq(&mut self, var: &str, path: &[&str], value: Value) -> &mut Self {
+        self.where_cmp(var, path, CompOp::Eq, value)
+    }
+
+    /// Ajoute `where func(var.path) op value` (ex: lower(e.emp_name) = "alice")
+    pub fn where_fn(&mut self, func: StringFn, var: &str, path: &[&str], op: CompOp, value: Value) -> &mut Self {
+        self.block.where_clauses.push(WhereClause::FnComparison {
+            func,
+            var: var.to_string(),
+            path: to_strings(path),
+            op,
+            value,
+        });
+        self
+    }
+
+    /// Ajoute `where var1.path1 = var2.path2`
+    pub fn where_path_eq(&mut self, var1: &str, path1: &[&str], var2: &str, path2: &[&str]) -> &mut Self {
+        self.block.where_clauses.push(WhereClause::PathEqual {
+            var1: var1.to_string(),
+            path1: to_strings(path1),
+            var2: var2.to_string(),
+            path2: to_strings(path2),
+        });
+        self
+    }
+
+    /// Ajoute `where var1.path1 op var2.path2`
+    pub fn where_path_cmp(&mut self, var1: &str, path1: &[&str], op: CompOp, var2: &str, path2: &[&str]) -> &mut Self {
+        self.block.where_clauses.push(WhereClause::PathCompare {
+            var1: var1.to_string(),
+            path1: to_strings(path1),
+            op,
+            var2: var2.to_string(),
+            path2: to_strings(path2),
+        });
+        self
+    }
+
+    /// Ajoute `where lhs op rhs`, chaque côté étant un chemin ou une
+    /// constante (ex: `where_sides(Side::Literal(Value::Integer(80000)), CompOp::Lt, Side::path("e", &["salary"]))`)
+    pub fn where_sides(&mut self, lhs: Side, op: CompOp, rhs: Side) -> &mut Self {
+        self.block.where_clauses.push(WhereClause::Compare { lhs, op, rhs });
+        self
+    }
+
+    /// Projette `result_attr -> var.path.attribute`
+    pub fn bind(&mut self, result_attr: &str, var: &str, path: &[&str], attribute: &str) -> &mut Self {
+        self.block.attribute_bindings.insert(result_attr.to_string(), AttributeBinding {
+            from_var: var.to_string(),
+            path: to_strings(path),
+            attribute: attribute.to_string(),
+            expr: None,
+        });
+        self
+    }
+
+    /// Projette une colonne calculée `result_attr -> expr`, où
+    /// `AttrExpr::Bound` vaut `var.path.attribute`
+    /// (ex: `bind_computed("annual", "e", &[], "salary", AttrExpr::binary(ArithOp::Mul, AttrExpr::Bound, AttrExpr::Const(Value::Integer(12))))`)
+    pub fn bind_computed(&mut self, result_attr: &str, var: &str, path: &[&str], attribute: &str, expr: AttrExpr) -> &mut Self {
+        self.block.attribute_bindings.insert(result_attr.to_string(), AttributeBinding {
+            from_var: var.to_string(),
+            path: to_strings(path),
+            attribute: attribute.to_string(),
+            expr: Some(expr),
+        });
+        self
+    }
+
+    /// Projette la FK `result_fk -> var.path`
+    pub fn bind_fk(&mut self, result_fk: &str, var: &str, path: &[&str]) -> &mut Self {
+        self.block.fk_bindings.insert(result_fk.to_string(), FkBinding {
+            from_var: var.to_string(),
+            path: to_strings(path),
+        });
+        self
+    }
+
+    /// `return var.*` : projette tous les attributs de l'entité de `var`
+    pub fn select_all(&mut self, var: &str) -> &mut Self {
+        self.block.select_all.push(var.to_string());
+        self
+    }
+
+    /// SELECT DISTINCT
+    pub fn distinct(&mut self) -> &mut Self {
+        self.block.distinct = true;
+        self
+    }
+
+    /// SELECT COUNT(*) : compte les tuples retenus sans les projeter
+    pub fn count_only(&mut self) -> &mut Self {
+        self.block.count_only = true;
+        self
+    }
+
+    /// Ajoute `order by attribute` (croissant) ; les clés s'enchaînent
+    pub fn order_by(&mut self, attribute: &str) -> &mut Self {
+        self.block.order_by.push(OrderKey { attribute: attribute.to_string(), descending: false });
+        self
+    }
+
+    /// Ajoute `order by attribute desc`
+    pub fn order_by_desc(&mut self, attribute: &str) -> &mut Self {
+        self.block.order_by.push(OrderKey { attribute: attribute.to_string(), descending: true });
+        self
+    }
+
+    /// Jointures externes gauches (FK non définie → NULL)
+    pub fn left_join(&mut self) -> &mut Self {
+        self.block.join_mode = JoinMode::Left;
+        self
+    }
+
+    /// Ajoute `having func(var.path.attribute) op value` (groupe le block)
+    pub fn having(
+        &mut self,
+        func: AggFunc,
+        var: &str,
+        path: &[&str],
+        attribute: &str,
+        op: CompOp,
+        value: Value,
+    ) -> &mut Self {
+        self.block.having.push(HavingClause {
+            func,
+            argument: Some(AttributeBinding {
+                from_var: var.to_string(),
+                path: to_strings(path),
+                attribute: attribute.to_string(),
+                expr: None,
+            }),
+            op,
+            value,
+        });
+        self
+    }
+
+    /// Ajoute `having COUNT(*) op value` (groupe le block)
+    pub fn having_count(&mut self, op: CompOp, value: Value) -> &mut Self {
+        self.block.having.push(HavingClause { func: AggFunc::Count, argument: None, op, value });
+        self
+    }
+
+    /// Produit le `QueryBlock`, ou une erreur si une variable n'est pas déclarée.
+    pub fn build(&self) -> Result<QueryBlock, CatrustError> {
+        let declared = |var: &String| self.block.from_vars.contains_key(var);
+        let mut used: Vec<(&String, &str)> = Vec::new();
+
+        for clause in &self.block.where_clauses {
+            match clause {
+                WhereClause::Comparison { var, .. }
+                | WhereClause::FnComparison { var, .. }
+                | WhereClause::Between { var, .. }
+                | WhereClause::IsNull { var, .. }
+                | WhereClause::IsNotNull { var, .. } => used.push((var, "WHERE")),
+                WhereClause::PathEqual { var1, var2, .. }
+                | WhereClause::PathCompare { var1, var2, .. } => {
+                    used.push((var1, "WHERE"));
+                    used.push((var2, "WHERE"));
+                }
+                WhereClause::Compare { lhs, rhs, .. } => {
+                    used.extend([lhs, rhs].into_iter().filter_map(Side::as_path).map(|(var, _)| (var, "WHERE")));
+                }
+                // Le block interne a ses propres variables
+                WhereClause::Exists { .. } | WhereClause::AlwaysFalse => {}
+            }
+        }
+        used.extend(self.block.attribute_bindings.values().map(|b| (&b.from_var, "attribut")));
+        used.extend(self.block.attribute_bindings.values()
+            .filter_map(|b| b.expr.as_ref())
+            .flat_map(|e| e.paths())
+            .map(|(var, _)| (var, "attribut")));
+        used.extend(self.block.fk_bindings.values().map(|b| (&b.from_var, "FK")));
+        used.extend(self.block.select_all.iter().map(|var| (var, "select_all")));
+        used.extend(self.block.having.iter()
+            .filter_map(|h| h.argument.as_ref())
+            .map(|b| (&b.from_var, "HAVING")));
+
+        if let Some((var, usage)) = used.into_iter().find(|(var, _)| !declared(var)) {
+            let mut vars: Vec<&String> = self.block.from_vars.keys().collect();
+            vars.sort();
+            return Err(format!(
+                "Variable '{}' ({}) non déclarée par from (variables : {:?})",
+                var, usage, vars
+            ).into());
+        }
+        // Avec `var.*`, les attributs projetés ne sont connus qu'avec le schéma
+        if let Some(key) = self.block.order_by.iter()
+            .filter(|_| self.block.select_all.is_empty())
+            .find(|k| !self.block.attribute_bindings.contains_key(&k.attribute))
+        {
+            return Err(format!("ORDER BY '{}' : attribut résultat non projeté", key.attribute).into());
+        }
+        Ok(self.block.clone())
+    }
+}
+
+/// Suit `path` depuis `entity` dans `source` (arêtes inverses comprises) :
+/// retourne l'entité atteinte et le type de l'attribut final s'il y en a un.
+fn walk_path(source: &Schema, entity: &str, path: &[String]) -> Result<(String, Option<BaseType>), String> {
+    let mut current = entity.to_string();
+    for (i, edge_name) in path.iter().enumerate() {
+        if let Some(fk) = reverse_edge(edge_name) {
+            match source.edges.get(fk) {
+                Some(Edge::ForeignKey { source: from, target, .. }) if *target == current => {
+                    current = from.clone();
+                }
+                _ => return Err(format!("'{}' : aucune FK '{}' vers '{}'", edge_name, fk, current)),
+            }
+            continue;
+        }
+        match source.edges.get(edge_name) {
+            None => return Err(format!("arête '{}' inconnue", edge_name)),
+            Some(edge) if edge.source() != current => {
+                return Err(format!("'{}' ne part pas de '{}'", edge_name, current));
+            }
+            Some(Edge::ForeignKey { target, .. }) => current = target.clone(),
+            Some(Edge::Attribute { target, .. }) if i + 1 == path.len() => {
+                return Ok((current, Some(target.clone())));
+            }
+            Some(Edge::Attribute { .. }) => {
+                return Err(format!("l'attribut '{}' doit terminer le chemin", edge_name));
+            }
+        }
+    }
+    Ok((current, None))
+}
+
+/// Nom, dans `result_schema`, de l'arête résultat `name` de `entity` : les
+/// noms d'arêtes d'un schéma sont globaux, on les qualifie par l'entité.
+fn result_edge_name(entity: &str, name: &str) -> String {
+    format!("{}.{}", entity, name)
+}
+
+/// Erreurs d'un block (et de ses EXISTS) ; `outer` : variables visibles
+/// du block englobant.
+fn block_errors(block: &QueryBlock, source: &Schema, outer: &HashMap<String, String>, errors: &mut Vec<String>) {
+    let mut scope = outer.clone();
+    scope.extend(block.from_vars.iter().map(|(v, e)| (v.clone(), e.clone())));
+    let mut from_vars: Vec<(&String, &String)> = block.from_vars.iter().collect();
+    from_vars.sort();
+    for (var, entity) in from_vars {
+        if !source.nodes.contains_key(entity) {
+            errors.push(format!("Block '{}' : {} : entité '{}' inconnue", block.target_entity, var, entity));
+        }
+    }
+
+    let dotted = |var: &String, path: &[String]| {
+        std::iter::once(var.as_str()).chain(path.iter().map(String::as_str)).collect::<Vec<_>>().join(".")
+    };
+
+    // `end` : Some(true) = doit finir par un attribut, Some(false) = par une FK
+    // `at` : la clause, pour situer l'erreur
+    let mut check = |at: &str, var: &String, path: &[String], end: Option<bool>| {
+        let dotted = dotted(var, path);
+        let outcome = match scope.get(var) {
+            None => Err(format!("variable '{}' non déclarée", var)),
+            Some(entity) if !source.nodes.contains_key(entity) => return,
+            Some(entity) => walk_path(source, entity, path).and_then(|(_, attr)| match (end, attr) {
+                (Some(true), None) => Err("doit finir par un attribut".to_string()),
+                (Some(false), Some(_)) => Err("doit finir par une FK".to_string()),
+                _ => Ok(()),
+            }),
+        };
+        if let Err(e) = outcome {
+            errors.push(format!("Block '{}' : {} : {} : {}", block.target_entity, at, dotted, e));
+        }
+    };
+
+    // Entité visée par `var.path` s'il finit par une FK (une référence)
+    let ref_entity = |var: &String, path: &[String]| -> Option<String> {
+        let entity = scope.get(var)?;
+        match walk_path(source, entity, path) {
+            Ok((target, None)) => Some(target),
+            _ => None,
+        }
+    };
+    let mut ref_errors = Vec::new();
+    let mut aggregate_errors = Vec::new();
+    let mut inner_blocks = Vec::new();
+    for (i, clause) in block.where_clauses.iter().enumerate() {
+        let at = format!("WHERE #{}", i);
+        match clause {
+            WhereClause::Comparison { var, path, .. }
+            | WhereClause::Between { var, path, .. }
+            | WhereClause::IsNull { var, path }
+            | WhereClause::IsNotNull { var, path } => check(&at, var, path, None),
+            WhereClause::FnComparison { var, path, .. } => check(&at, var, path, Some(true)),
+            WhereClause::PathEqual { var1, path1, var2, path2 }
+            | WhereClause::PathCompare { var1, path1, var2, path2, .. } => {
+                check(&at, var1, path1, None);
+                check(&at, var2, path2, None);
+                if let (Some(e1), Some(e2)) = (ref_entity(var1, path1), ref_entity(var2, path2)) {
+                    if e1 != e2 {
+                        ref_errors.push(format!(
+                            "Block '{}' : {} : {} et {} désignent des lignes d'entités différentes ({}, {})",
+                            block.target_entity, at, dotted(var1, path1), dotted(var2, path2), e1, e2
+                        ));
+                    }
+                }
+            }
+            WhereClause::Compare { lhs, rhs, .. } => {
+                for side in [lhs, rhs] {
+                    match side {
+                        Side::Path { var, path } => check(&at, var, path, None),
+                        Side::Aggregate(aggregate) => {
+                            if let Err(e) = scalar_aggregate_check(aggregate, source) {
+                                aggregate_errors.push(format!(
+                                    "Block '{}' : {} : {} : {}", block.target_entity, at, aggregate, e
+                                ));
+                            }
+                        }
+                        Side::Literal(_) => {}
+                    }
+                }
+            }
+            WhereClause::Exists { inner } => inner_blocks.push(inner),
+            WhereClause::AlwaysFalse => {}
+        }
+    }
+    let mut bindings: Vec<(&String, &AttributeBinding)> = block.attribute_bindings.iter().collect();
+    bindings.sort_by(|a, b| a.0.cmp(b.0));
+    let arguments = block.having.iter()
+        .filter_map(|h| h.argument.as_ref())
+        .map(|ab| ("HAVING".to_string(), ab));
+    let bindings = bindings.into_iter().map(|(name, ab)| (format!("projection '{}'", name), ab));
+    for (at, ab) in bindings.chain(arguments) {
+        // Sans attribut, le binding projette un RowId : il finit par une FK
+        let end = !ab.attribute.is_empty();
+        check(&at, &ab.from_var, &ab.full_path(), if end { Some(true) } else { Some(false) });
+        // Un chemin d'expression finissant par une FK vaut l'id (Integer)
+        for (var, path) in ab.expr.iter().flat_map(|e| e.paths()) {
+            check(&at, var, path, None);
+        }
+    }
+    let mut fk_bindings: Vec<(&String, &FkBinding)> = block.fk_bindings.iter().collect();
+    fk_bindings.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, fb) in fk_bindings {
+        check(&format!("FK '{}'", name), &fb.from_var, &fb.path, Some(false));
+    }
+    errors.extend(ref_errors);
+    errors.extend(aggregate_errors);
+
+    for inner in inner_blocks {
+        block_errors(inner, source, &scope, errors);
+    }
+}
+
+/// Vérifie qu'un agrégat scalaire porte sur une entité du schéma et, sauf
+/// COUNT(*), sur un attribut de cette entité.
+fn scalar_aggregate_check(aggregate: &ScalarAggregate, source: &Schema) -> Result<(), String> {
+    if !source.nodes.contains_key(&aggregate.entity) {
+        return Err(format!("entité '{}' inconnue", aggregate.entity));
+    }
+    match &aggregate.attribute {
+        Some(attribute) => match source.edges.get(attribute) {
+            Some(Edge::Attribute { source: owner, .. }) if *owner == aggregate.entity => Ok(()),
+            _ => Err(format!("'{}' n'est pas un attribut de '{}'", attribute, aggregate.entity)),
+        },
+        None if aggregate.func == AggFunc::Count => Ok(()),
+        None => Err(format!("{} demande un attribut", aggregate.func)),
+    }
+}
+
+/// Type d'un attribut projeté : celui de l'attribut source lu ; une colonne
+/// calculée est Float dès qu'un de ses opérandes l'est.
+fn binding_type(ab: &AttributeBinding, from_vars: &HashMap<String, String>, source: &Schema) -> Result<BaseType, String> {
+    let bound = if ab.attribute.is_empty() {
+        BaseType::Integer
+    } else {
+        leaf_type(&ab.from_var, &ab.full_path(), from_vars, source)?
+    };
+    match &ab.expr {
+        Some(expr) => expr_type(expr, &bound, from_vars, source),
+        None => Ok(bound),
+    }
+}
+
+/// Type de l'attribut au bout de `var.path`
+fn leaf_type(var: &String, path: &[String], from_vars: &HashMap<String, String>, source: &Schema) -> Result<BaseType, String> {
+    let entity = from_vars.get(var).ok_or_else(|| format!("variable '{}' non déclarée", var))?;
+    walk_path(source, entity, path)?.1.ok_or_else(|| "doit finir par un attribut".to_string())
+}
+
+/// Type d'une colonne calculée ; `bound` : type de la valeur du binding
+fn expr_type(expr: &AttrExpr, bound: &BaseType, from_vars: &HashMap<String, String>, source: &Schema) -> Result<BaseType, String> {
+    Ok(match expr {
+        AttrExpr::Bound => bound.clone(),
+        AttrExpr::Path { var, path } => {
+            let entity = from_vars.get(var).ok_or_else(|| format!("variable '{}' non déclarée", var))?;
+            // Un chemin qui finit sur une ligne vaut son catrust_id, comme un binding sans attribut
+            walk_path(source, entity, path)?.1.unwrap_or(BaseType::Integer)
+        }
+        AttrExpr::Const(value) => value.get_type(),
+        AttrExpr::Binary { lhs, rhs, .. } => {
+            let lhs = expr_type(lhs, bound, from_vars, source)?;
+            let rhs = expr_type(rhs, bound, from_vars, source)?;
+            if lhs == BaseType::Float || rhs == BaseType::Float { BaseType::Float } else { lhs }
+        }
+        AttrExpr::Func { func, arg } => match expr_type(arg, bound, from_vars, source)? {
+            BaseType::String => BaseType::String,
+            other => return Err(format!("{} attend une chaîne, pas {}", func.wrap("_"), other)),
+        },
+    })
+}
+
+fn to_strings(path: &[&str]) -> Vec<String> {
+    path.iter().map(|s| s.to_string()).collect()
 }
 
 // =============================================================================
@@ -203,8 +1420,20 @@ mod tests {
     use super::*;
     use crate::core::typeside::BaseType;
 
+    fn company_schema() -> Schema {
+        let mut schema = Schema::new("Company");
+        schema.add_node("Employee")
+              .add_node("Department")
+              .add_fk("works_in", "Employee", "Department")
+              .add_attribute("emp_name", "Employee", BaseType::String)
+              .add_attribute("salary", "Employee", BaseType::Integer)
+              .add_attribute("dept_name", "Department", BaseType::String);
+        schema
+    }
+
     #[test]
     fn test_create_query() {
+        let schema = company_schema();
         let mut query = CqlQuery::new("FindEngineers", "Company");
 
         let block = QueryBlock {
@@ -213,7 +1442,7 @@ mod tests {
             where_clauses: vec![
                 WhereClause::Comparison {
                     var: "e".into(),
-                    path: vec!["department".into(), "dept_name".into()],
+                    path: vec!["works_in".into(), "dept_name".into()],
                     op: CompOp::Eq,
                     value: Value::String("Engineering".into()),
                 },
@@ -223,16 +1452,167 @@ mod tests {
                     from_var: "e".into(),
                     path: vec![],
                     attribute: "emp_name".into(),
+                    expr: None,
                 }),
             ]),
             fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
         };
 
-        query.add_block(block);
+        query.add_block(block, &schema).unwrap();
         assert_eq!(query.blocks.len(), 1);
         assert!(query.result_schema.nodes.contains_key("Engineer"));
     }
 
+    #[test]
+    fn test_builder_rejects_undeclared_variable() {
+        let err = CqlQuery::select()
+            .from("e", "Employee")
+            .where_eq("d", &["dept_name"], Value::String("Engineering".into()))
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("Variable 'd' (WHERE) non déclarée"), "{}", err);
+
+        let block = CqlQuery::select().into_entity("Engineer").from("e", "Employee").distinct().build().unwrap();
+        assert_eq!(block.target_entity, "Engineer");
+        assert!(block.distinct);
+    }
+
+    #[test]
+    fn test_simplify_dedups_and_detects_contradictions() {
+        let schema = company_schema();
+        let dept = |name: &str| Value::String(name.into());
+        let mut query = CqlQuery::new("Q", "Company");
+        query.add_block(
+            CqlQuery::select()
+                .from("e", "Employee")
+                .where_eq("e", &["works_in", "dept_name"], dept("X"))
+                .where_eq("e", &["works_in", "dept_name"], dept("X"))
+                .where_cmp("e", &["salary"], CompOp::Gte, Value::Integer(50000))
+                .where_cmp("e", &["salary"], CompOp::Lte, Value::Integer(50000))
+                .build()
+                .unwrap(),
+            &schema,
+        ).unwrap();
+
+        // Doublon retiré ; salary = 50000 reste satisfiable
+        let simplified = query.simplify();
+        let clauses: Vec<String> = simplified.blocks[0].where_clauses.iter().map(|c| c.to_string()).collect();
+        assert_eq!(clauses, vec!["e.works_in.dept_name = \"X\"", "e.salary >= 50000", "e.salary <= 50000"]);
+
+        // Deux égalités différentes sur le même chemin : insatisfiable
+        query.blocks[0].where_clauses.push(WhereClause::Comparison {
+            var: "e".into(),
+            path: vec!["works_in".into(), "dept_name".into()],
+            op: CompOp::Eq,
+            value: dept("Y"),
+        });
+        let simplified = query.simplify();
+        assert!(matches!(simplified.blocks[0].where_clauses[..], [WhereClause::AlwaysFalse]));
+    }
+
+    #[test]
+    fn test_typed_result_schema() {
+        let schema = company_schema();
+
+        let mut query = CqlQuery::new("SeniorEngineers", "Company");
+        let block = CqlQuery::select()
+            .from("e", "Employee")
+            .where_eq("e", &["works_in", "dept_name"], Value::String("Engineering".into()))
+            .where_cmp("e", &["salary"], CompOp::Gt, Value::Integer(80000))
+            .bind("name", "e", &[], "emp_name")
+            .bind("salary", "e", &[], "salary")
+            .build()
+            .unwrap();
+        query.add_block(block, &schema).unwrap();
+        assert!(query.validate(&schema).is_ok());
+
+        let attr_type = |query: &CqlQuery, entity: &str, name: &str| match query.result_edge(entity, name) {
+            Some(Edge::Attribute { source, target, .. }) if source == entity => target.clone(),
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(attr_type(&query, "Result", "name"), BaseType::String);
+        assert_eq!(attr_type(&query, "Result", "salary"), BaseType::Integer);
+
+        // Un autre block projette aussi `name`, depuis une autre entité
+        let departments = CqlQuery::select()
+            .into_entity("Departments")
+            .from("d", "Department")
+            .bind("name", "d", &[], "dept_name")
+            .build()
+            .unwrap();
+        query.add_block(departments, &schema).unwrap();
+        assert_eq!(attr_type(&query, "Result", "name"), BaseType::String);
+        assert_eq!(attr_type(&query, "Departments", "name"), BaseType::String);
+
+        // Même entité cible, même nom, autre type : refusé
+        let clash = CqlQuery::select()
+            .from("e", "Employee")
+            .bind("name", "e", &[], "salary")
+            .build()
+            .unwrap();
+        let errors = query.add_block(clash, &schema).unwrap_err();
+        assert!(errors[0].contains("attribut 'name'"), "{:?}", errors);
+        assert_eq!(query.blocks.len(), 2);
+
+        // Chemin invalide : l'arête n'existe pas / ne part pas de la bonne entité
+        let bad = CqlQuery::select()
+            .from("e", "Employee")
+            .where_eq("e", &["works_in", "emp_name"], Value::String("Alice".into()))
+            .bind("name", "e", &[], "dept_name")
+            .build()
+            .unwrap();
+        let errors = query.add_block(bad.clone(), &schema).unwrap_err();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        query.blocks.push(bad);
+        assert_eq!(query.validate(&schema).unwrap_err().len(), 2);
+    }
+
+    #[test]
+    fn test_typecheck_pinpoints_bad_edge() {
+        let mut schema = Schema::new("Company");
+        schema.add_node("Employee")
+              .add_node("Department")
+              .add_fk("department", "Employee", "Department")
+              .add_attribute("emp_name", "Employee", BaseType::String)
+              .add_attribute("dept_name", "Department", BaseType::String);
+
+        // Blocks ajoutés tels quels : add_block refuserait les chemins que
+        // typecheck doit situer
+        let query = |edge: &str| {
+            let mut query = CqlQuery::new("Engineers", "Company");
+            query.blocks.push(
+                CqlQuery::select()
+                    .from("e", "Employee")
+                    .where_eq("e", &["emp_name"], Value::String("Alice".into()))
+                    .where_eq("e", &[edge, "dept_name"], Value::String("Engineering".into()))
+                    .bind("name", "e", &[], "emp_name")
+                    .bind("dept", "e", &[edge], "dept_name")
+                    .build()
+                    .unwrap(),
+            );
+            query
+        };
+
+        assert!(query("department").typecheck(&schema).is_ok());
+
+        let errors = query("departement").typecheck(&schema).unwrap_err();
+        assert_eq!(errors, vec![
+            "Block 'Result' : WHERE #1 : e.departement.dept_name : arête 'departement' inconnue".to_string(),
+            "Block 'Result' : projection 'dept' : e.departement.dept_name : arête 'departement' inconnue".to_string(),
+        ]);
+
+        // Un attribut au milieu du chemin
+        let errors = query("emp_name").typecheck(&schema).unwrap_err();
+        assert!(errors[0].starts_with("Block 'Result' : WHERE #1 : e.emp_name.dept_name : "), "{:?}", errors);
+        assert!(errors[0].contains("'emp_name'"), "{:?}", errors);
+    }
+
     #[test]
     fn test_optimize_query() {
         // Schéma avec raccourci
@@ -267,11 +1647,18 @@ mod tests {
                     from_var: "e".into(),
                     path: vec![],
                     attribute: "emp_name".into(),
+                    expr: None,
                 }),
             ]),
             fk_bindings: HashMap::new(),
+            distinct: false,
+            join_mode: JoinMode::Inner,
+            having: vec![],
+            count_only: false,
+            order_by: Vec::new(),
+            select_all: Vec::new(),
         };
-        query.add_block(block);
+        query.add_block(block, &schema).unwrap();
 
         let optimized = query.optimize(&schema);
         let opt_where = &optimized.blocks[0].where_clauses[0];
@@ -286,4 +1673,19 @@ mod tests {
             println!("Après : {:?} ({} JOINs)", path, path.len());
         }
     }
+
+    #[test]
+    fn test_string_fn_matches_sql() {
+        let apply = |func: StringFn, s: &str| func.apply(&Value::String(s.into())).unwrap();
+
+        // TRIM ne retire que les espaces
+        assert_eq!(apply(StringFn::Trim, "  \tAlice\n "), Value::String("\tAlice\n".into()));
+
+        // SUBSTRING('Alice' FROM 0 FOR 3) = 'Al' : la position 0 compte
+        assert_eq!(apply(StringFn::Substring { start: 0, length: Some(3) }, "Alice"), Value::String("Al".into()));
+        assert_eq!(apply(StringFn::Substring { start: 0, length: Some(1) }, "Alice"), Value::String("".into()));
+        assert_eq!(apply(StringFn::Substring { start: 2, length: Some(3) }, "Alice"), Value::String("lic".into()));
+        assert_eq!(apply(StringFn::Substring { start: 0, length: None }, "Alice"), Value::String("Alice".into()));
+        assert_eq!(StringFn::Upper.apply(&Value::Null), Ok(Value::Null));
+    }
 }
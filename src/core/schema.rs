@@ -42,7 +42,9 @@
 // =============================================================================
 
 use std::collections::HashMap;
-use super::typeside::BaseType;
+use super::entity::Entities;
+use super::typeside::{BaseType, Typeside, Value};
+use crate::error::CatrustError;
 
 /// Un nœud dans la catégorie-schéma = une entité = une table.
 /// 
@@ -82,15 +84,87 @@ pub enum Edge {
         name: String,
         source: String,  // nom du Node source
         target: String,  // nom du Node cible
+        /// Colonnes physiques d'une FK composite : (colonne source, attribut
+        /// cible). Vide = une seule colonne qui référence la clé technique.
+        /// Dans l'instance, la FK reste un RowId : seul le stockage change.
+        columns: Vec<(String, String)>,
     },
     /// Attribut : entité → type de base
     Attribute {
         name: String,
         source: String,      // nom du Node source
         target: BaseType,    // type de la valeur
+        /// Valeur par défaut : SQL `DEFAULT`, et valeur donnée par Σ quand
+        /// aucun attribut source n'est envoyé sur celui-ci
+        default: Option<DefaultValue>,
     },
 }
 
+/// Valeur par défaut d'un attribut.
+///
+/// `Value` n'est ni `Eq` ni `Hash` (à cause des flottants) : ici l'égalité
+/// est structurelle, flottants comparés bit à bit, pour que `Edge` puisse
+/// rester une clé de `HashSet`/`HashMap`. `1` et `1.0` sont donc deux
+/// défauts différents, comme le seraient deux `DEFAULT` SQL.
+#[derive(Debug, Clone)]
+pub struct DefaultValue(pub Value);
+
+impl DefaultValue {
+    fn same(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::String(x), Value::String(y)) => x == y,
+            (Value::Integer(x), Value::Integer(y)) => x == y,
+            (Value::Float(x), Value::Float(y)) => x.to_bits() == y.to_bits(),
+            (Value::Boolean(x), Value::Boolean(y)) => x == y,
+            (Value::Array(xs), Value::Array(ys)) => {
+                xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| Self::same(x, y))
+            }
+            (Value::Ref { entity: e1, row: r1 }, Value::Ref { entity: e2, row: r2 }) => e1 == e2 && r1 == r2,
+            (Value::Null, Value::Null) => true,
+            _ => false,
+        }
+    }
+
+    fn hash_value<H: std::hash::Hasher>(value: &Value, state: &mut H) {
+        use std::hash::Hash;
+        std::mem::discriminant(value).hash(state);
+        match value {
+            Value::String(s) => s.hash(state),
+            Value::Integer(i) => i.hash(state),
+            Value::Float(f) => f.to_bits().hash(state),
+            Value::Boolean(b) => b.hash(state),
+            Value::Array(values) => {
+                values.len().hash(state);
+                for v in values {
+                    Self::hash_value(v, state);
+                }
+            }
+            Value::Ref { entity, row } => (entity, row).hash(state),
+            Value::Null => {}
+        }
+    }
+}
+
+impl PartialEq for DefaultValue {
+    fn eq(&self, other: &Self) -> bool {
+        Self::same(&self.0, &other.0)
+    }
+}
+
+impl Eq for DefaultValue {}
+
+impl std::hash::Hash for DefaultValue {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Self::hash_value(&self.0, state);
+    }
+}
+
+impl std::fmt::Display for DefaultValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl Edge {
     /// Crée une nouvelle Foreign Key
     pub fn fk(name: &str, source: &str, target: &str) -> Self {
@@ -98,6 +172,19 @@ impl Edge {
             name: name.to_string(),
             source: source.to_string(),
             target: target.to_string(),
+            columns: Vec::new(),
+        }
+    }
+
+    /// Crée une Foreign Key composite sur une clé métier de la cible
+    pub fn composite_fk(name: &str, source: &str, target: &str, columns: Vec<(&str, &str)>) -> Self {
+        Edge::ForeignKey {
+            name: name.to_string(),
+            source: source.to_string(),
+            target: target.to_string(),
+            columns: columns.into_iter()
+                .map(|(col, attr)| (col.to_string(), attr.to_string()))
+                .collect(),
         }
     }
 
@@ -107,6 +194,7 @@ impl Edge {
             name: name.to_string(),
             source: source.to_string(),
             target,
+            default: None,
         }
     }
 
@@ -177,6 +265,11 @@ impl Path {
         self.edges.len()
     }
 
+    /// Le chemin est-il vide (aucune arête) ?
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
     /// Le chemin est-il un chemin identité ?
     pub fn is_identity(&self) -> bool {
         self.edges.is_empty()
@@ -205,9 +298,15 @@ impl std::fmt::Display for Path {
 /// En SQL, ça se traduit par une contrainte CHECK ou un trigger.
 /// En Neo4j, ça se vérifie par une requête MATCH.
 ///
+/// ÉQUATION D'OBSERVATION : les deux chemins finissent par un attribut,
+/// l'égalité porte alors sur les VALEURS observées et non sur les lignes :
+///   `employee.department.dept_name = employee.cached_dept_name`
+/// signifie : "la colonne cached_dept_name est une copie fidèle du nom du
+/// département" (le planner peut lire la copie sans JOIN).
+///
 /// MATHÉMATIQUEMENT : c'est un quotient de la catégorie libre engendrée
 /// par le graphe sous-jacent, par les relations d'équivalence données.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PathEquation {
     pub lhs: Path,  // côté gauche
     pub rhs: Path,  // côté droit
@@ -217,6 +316,11 @@ impl PathEquation {
     pub fn new(lhs: Path, rhs: Path) -> Self {
         PathEquation { lhs, rhs }
     }
+
+    /// Équation d'observation : les deux côtés finissent par un attribut
+    pub fn is_observation(&self, schema: &Schema) -> bool {
+        schema.observed_attribute(&self.lhs).is_some() && schema.observed_attribute(&self.rhs).is_some()
+    }
 }
 
 impl std::fmt::Display for PathEquation {
@@ -225,6 +329,15 @@ impl std::fmt::Display for PathEquation {
     }
 }
 
+/// Clé de tri « ordre de déclaration » : position dans `order`, les noms
+/// absents venant ensuite, par ordre alphabétique.
+fn declaration_rank<'a>(order: &[String], name: &'a str) -> (bool, usize, &'a str) {
+    match order.iter().position(|n| n == name) {
+        Some(i) => (false, i, ""),
+        None => (true, 0, name),
+    }
+}
+
 /// Le Schema complet : une catégorie finiment présentée.
 ///
 /// C'est la structure centrale de CQL. Un Schema contient :
@@ -243,6 +356,13 @@ pub struct Schema {
     pub edges: HashMap<String, Edge>,
     /// Les équations de chemins (contraintes catégoriques)
     pub path_equations: Vec<PathEquation>,
+    /// Types de base admis pour les attributs (`Typeside::default_sql()`
+    /// par défaut), vérifiés par `validate_schema`
+    pub typeside: Typeside,
+    /// Noms des nœuds dans l'ordre de leur déclaration
+    node_order: Vec<String>,
+    /// Noms des arêtes dans l'ordre de leur déclaration
+    edge_order: Vec<String>,
 }
 
 impl Schema {
@@ -253,11 +373,62 @@ impl Schema {
             nodes: HashMap::new(),
             edges: HashMap::new(),
             path_equations: Vec::new(),
+            typeside: Typeside::default_sql(),
+            node_order: Vec::new(),
+            edge_order: Vec::new(),
         }
     }
 
+    /// Remplace le typeside du schéma
+    pub fn with_typeside(&mut self, typeside: Typeside) -> &mut Self {
+        self.typeside = typeside;
+        self
+    }
+
+    /// Schéma déclaré par des structs `#[derive(CatrustEntity)]`, en tuple :
+    /// `Schema::from_entities::<(Employee, Department)>()`. Les nœuds sont
+    /// déclarés dans l'ordre du tuple, puis les arêtes de chacun ; le schéma
+    /// est nommé d'après les entités (`Employee_Department`).
+    pub fn from_entities<E: Entities>() -> Schema {
+        let names = E::names();
+        let mut schema = Schema::new(&names.join("_"));
+        for name in names {
+            schema.add_node(name);
+        }
+        E::register_edges(&mut schema);
+        schema
+    }
+
+    /// Enregistre une arête (nouvelle ou redéclarée) en gardant sa position
+    /// de première déclaration
+    fn insert_edge(&mut self, edge: Edge) {
+        let name = edge.name().to_string();
+        if !self.edges.contains_key(&name) {
+            self.edge_order.push(name.clone());
+        }
+        self.edges.insert(name, edge);
+    }
+
+    /// Noms des nœuds dans l'ordre de déclaration (les nœuds insérés
+    /// directement dans `nodes` viennent ensuite, par nom)
+    pub fn node_names(&self) -> Vec<&String> {
+        let mut names: Vec<&String> = self.nodes.keys().collect();
+        names.sort_by_key(|n| declaration_rank(&self.node_order, n));
+        names
+    }
+
+    /// Trie des arêtes dans l'ordre de déclaration (les arêtes insérées
+    /// directement dans `edges` viennent ensuite, par nom)
+    fn in_declaration_order<'a>(&self, mut edges: Vec<&'a Edge>) -> Vec<&'a Edge> {
+        edges.sort_by_key(|e| declaration_rank(&self.edge_order, e.name()));
+        edges
+    }
+
     /// Ajoute un nœud (entité/table) au schéma
     pub fn add_node(&mut self, name: &str) -> &mut Self {
+        if !self.nodes.contains_key(name) {
+            self.node_order.push(name.to_string());
+        }
         self.nodes.insert(name.to_string(), Node::new(name));
         self
     }
@@ -268,7 +439,22 @@ impl Schema {
             "Nœud source '{}' n'existe pas dans le schéma", source);
         assert!(self.nodes.contains_key(target), 
             "Nœud cible '{}' n'existe pas dans le schéma", target);
-        self.edges.insert(name.to_string(), Edge::fk(name, source, target));
+        self.insert_edge(Edge::fk(name, source, target));
+        self
+    }
+
+    /// Ajoute une Foreign Key composite : `columns` associe chaque colonne
+    /// de la table source à un attribut de la cible (sa clé métier).
+    ///
+    /// Ex: `add_composite_fk("located_in", "Office", "Site", vec![("site_country", "country"), ("site_code", "code")])`
+    /// donne en SQL `FOREIGN KEY ("site_country", "site_code") REFERENCES "Site" ("country", "code")`.
+    pub fn add_composite_fk(&mut self, name: &str, source: &str, target: &str, columns: Vec<(&str, &str)>) -> &mut Self {
+        assert!(self.nodes.contains_key(source),
+            "Nœud source '{}' n'existe pas dans le schéma", source);
+        assert!(self.nodes.contains_key(target),
+            "Nœud cible '{}' n'existe pas dans le schéma", target);
+        assert!(!columns.is_empty(), "FK composite '{}' sans colonnes", name);
+        self.insert_edge(Edge::composite_fk(name, source, target, columns));
         self
     }
 
@@ -276,40 +462,130 @@ impl Schema {
     pub fn add_attribute(&mut self, name: &str, source: &str, ty: BaseType) -> &mut Self {
         assert!(self.nodes.contains_key(source),
             "Nœud source '{}' n'existe pas dans le schéma", source);
-        self.edges.insert(name.to_string(), Edge::attr(name, source, ty));
+        self.insert_edge(Edge::attr(name, source, ty));
         self
     }
 
-    /// Ajoute une équation de chemins (contrainte)
+    /// Ajoute un attribut avec une valeur par défaut (`DEFAULT` en SQL,
+    /// et valeur remplie par Σ quand le schéma source n'a pas la colonne)
+    pub fn add_attribute_default(&mut self, name: &str, source: &str, ty: BaseType, default: Value) -> &mut Self {
+        assert!(self.nodes.contains_key(source),
+            "Nœud source '{}' n'existe pas dans le schéma", source);
+        self.insert_edge(Edge::Attribute {
+            name: name.to_string(),
+            source: source.to_string(),
+            target: ty,
+            default: Some(DefaultValue(default)),
+        });
+        self
+    }
+
+    /// Ajoute une équation de chemins (contrainte). Les deux chemins
+    /// peuvent finir par un attribut : c'est une équation d'observation.
     pub fn add_path_equation(&mut self, lhs: Path, rhs: Path) -> &mut Self {
         self.path_equations.push(PathEquation::new(lhs, rhs));
         self
     }
 
-    /// Retourne toutes les Foreign Keys du schéma
+    /// Coproduit (union disjointe) de deux schémas : tous les nœuds, arêtes
+    /// et équations de `a` puis de `b`, sans rien identifier.
+    ///
+    /// Un nom de `b` déjà pris dans `a` (nœud ou arête) est renommé
+    /// `{prefix}_{nom}`, références comprises (FK, colonnes de FK composite,
+    /// équations). Sans préfixe, ou si le nom préfixé est déjà pris dans
+    /// `a` ou dans `b`, c'est une erreur.
+    pub fn coproduct(a: &Schema, b: &Schema, prefix: Option<&str>) -> Result<Schema, CatrustError> {
+        let rename = |kind: &str, name: &str, in_a: &dyn Fn(&str) -> bool, in_b: &dyn Fn(&str) -> bool| {
+            if !in_a(name) {
+                return Ok(name.to_string());
+            }
+            let Some(prefix) = prefix else {
+                return Err(CatrustError::Other(format!(
+                    "{} '{}' présent dans '{}' et '{}' : donner un préfixe", kind, name, a.name, b.name
+                )));
+            };
+            let renamed = format!("{}_{}", prefix, name);
+            for (schema, taken) in [(a, in_a), (b, in_b)] {
+                if taken(&renamed) {
+                    return Err(CatrustError::Other(format!(
+                        "{} '{}' : '{}' est déjà pris dans '{}'", kind, name, renamed, schema.name
+                    )));
+                }
+            }
+            Ok(renamed)
+        };
+
+        let mut node_names: HashMap<&str, String> = HashMap::new();
+        for node in b.node_names() {
+            let renamed = rename("Nœud", node, &|n| a.nodes.contains_key(n), &|n| b.nodes.contains_key(n))?;
+            node_names.insert(node, renamed);
+        }
+        let mut edge_names: HashMap<&str, String> = HashMap::new();
+        for edge in b.in_declaration_order(b.edges.values().collect()) {
+            let renamed = rename("Arête", edge.name(), &|e| a.edges.contains_key(e), &|e| b.edges.contains_key(e))?;
+            edge_names.insert(edge.name(), renamed);
+        }
+        let node = |n: &str| node_names.get(n).cloned().unwrap_or_else(|| n.to_string());
+        let edge = |e: &str| edge_names.get(e).cloned().unwrap_or_else(|| e.to_string());
+
+        let mut result = a.clone();
+        result.name = format!("{}_{}", a.name, b.name);
+        for ty in &b.typeside.types {
+            result.typeside.add_type(ty.clone());
+        }
+        for name in b.node_names() {
+            result.add_node(&node(name));
+        }
+        for e in b.in_declaration_order(b.edges.values().collect()) {
+            result.insert_edge(match e {
+                Edge::ForeignKey { name, source, target, columns } => Edge::ForeignKey {
+                    name: edge(name),
+                    source: node(source),
+                    target: node(target),
+                    columns: columns.iter().map(|(col, attr)| (col.clone(), edge(attr))).collect(),
+                },
+                Edge::Attribute { name, source, target, default } => Edge::Attribute {
+                    name: edge(name),
+                    source: node(source),
+                    target: target.clone(),
+                    default: default.clone(),
+                },
+            });
+        }
+        let path = |p: &Path| Path {
+            start: node(&p.start),
+            edges: p.edges.iter().map(|e| edge(e)).collect(),
+        };
+        for eq in &b.path_equations {
+            result.add_path_equation(path(&eq.lhs), path(&eq.rhs));
+        }
+        Ok(result)
+    }
+
+    /// Retourne toutes les Foreign Keys du schéma (ordre de déclaration)
     pub fn foreign_keys(&self) -> Vec<&Edge> {
-        self.edges.values()
+        self.in_declaration_order(self.edges.values()
             .filter(|e| matches!(e, Edge::ForeignKey { .. }))
-            .collect()
+            .collect())
     }
 
-    /// Retourne tous les attributs du schéma
+    /// Retourne tous les attributs du schéma (ordre de déclaration)
     pub fn attributes(&self) -> Vec<&Edge> {
-        self.edges.values()
+        self.in_declaration_order(self.edges.values()
             .filter(|e| matches!(e, Edge::Attribute { .. }))
-            .collect()
+            .collect())
     }
 
-    /// Retourne les arêtes sortant d'un nœud donné
+    /// Retourne les arêtes sortant d'un nœud donné (ordre de déclaration)
     pub fn edges_from(&self, node_name: &str) -> Vec<&Edge> {
-        self.edges.values()
+        self.in_declaration_order(self.edges.values()
             .filter(|e| e.source() == node_name)
-            .collect()
+            .collect())
     }
 
-    /// Retourne les FK pointant vers un nœud donné
+    /// Retourne les FK pointant vers un nœud donné (ordre de déclaration)
     pub fn fks_targeting(&self, node_name: &str) -> Vec<&Edge> {
-        self.edges.values()
+        self.in_declaration_order(self.edges.values()
             .filter(|e| {
                 if let Edge::ForeignKey { target, .. } = e {
                     target == node_name
@@ -317,12 +593,12 @@ impl Schema {
                     false
                 }
             })
-            .collect()
+            .collect())
     }
 
-    /// Retourne les attributs d'un nœud donné
+    /// Retourne les attributs d'un nœud donné (ordre de déclaration)
     pub fn attributes_of(&self, node_name: &str) -> Vec<&Edge> {
-        self.edges.values()
+        self.in_declaration_order(self.edges.values()
             .filter(|e| {
                 if let Edge::Attribute { source, .. } = e {
                     source == node_name
@@ -330,7 +606,445 @@ impl Schema {
                     false
                 }
             })
-            .collect()
+            .collect())
+    }
+
+    /// Attribut qui termine un chemin (`None` si le chemin finit sur un nœud)
+    pub fn observed_attribute(&self, path: &Path) -> Option<&Edge> {
+        let last = path.edges.last()?;
+        self.edges.get(last).filter(|e| matches!(e, Edge::Attribute { .. }))
+    }
+
+    /// Décide si deux chemins sont égaux modulo les équations du schéma :
+    /// les deux sont réduits en forme normale par le système de réécriture
+    /// complété (`PathOptimizer::completed`), puis comparés.
+    ///
+    /// Si la complétion n'a pas convergé, `false` peut signifier « non prouvé ».
+    pub fn paths_equal(&self, p1: &Path, p2: &Path) -> bool {
+        if p1.start != p2.start {
+            return false;
+        }
+        let optimizer = super::optimize::PathOptimizer::from_schema(self).completed();
+        optimizer.optimize_path(p1) == optimizer.optimize_path(p2)
+    }
+
+    /// Ordre topologique des nœuds selon les FK : un nœud apparaît APRÈS
+    /// tous les nœuds vers lesquels il pointe (ordre de création des tables).
+    ///
+    /// Les auto-références sont ignorées ; les nœuds pris dans un cycle de FK
+    /// sont ajoutés à la fin. Entre nœuds indépendants, l'ordre de
+    /// déclaration est conservé : l'ordre est déterministe.
+    pub fn topological_order(&self) -> Vec<String> {
+        let names = self.node_names();
+
+        let mut order: Vec<String> = Vec::new();
+        let mut placed: std::collections::HashSet<&str> = std::collections::HashSet::new();
+
+        loop {
+            // Un nœud est prêt quand toutes ses cibles de FK sont placées
+            let ready: Vec<&String> = names.iter()
+                .filter(|n| !placed.contains(n.as_str()))
+                .filter(|n| self.edges_from(n).iter().all(|e| match e {
+                    Edge::ForeignKey { target, .. } => target == **n || placed.contains(target.as_str()),
+                    Edge::Attribute { .. } => true,
+                }))
+                .copied()
+                .collect();
+            if ready.is_empty() {
+                break;
+            }
+            for n in ready {
+                placed.insert(n);
+                order.push(n.clone());
+            }
+        }
+
+        // Cycles
+        for n in names {
+            if !placed.contains(n.as_str()) {
+                order.push(n.clone());
+            }
+        }
+        order
+    }
+
+    /// Exporte le schéma en graphe GraphViz (DOT), à passer à `dot -Tpng`.
+    ///
+    /// - Entités : boîtes
+    /// - FK : flèches pleines étiquetées par le nom de la FK
+    /// - Attributs : flèches en tirets vers un nœud par type de base
+    /// - Équations de chemins : légende (nœud « note »)
+    pub fn to_dot(&self) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('"', "\\\""));
+        let mut out = format!("digraph {} {{\n", quote(&self.name));
+        out.push_str("  rankdir=LR;\n");
+
+        let mut names: Vec<&String> = self.nodes.keys().collect();
+        names.sort();
+        for name in names {
+            out.push_str(&format!("  {} [shape=box];\n", quote(name)));
+        }
+
+        let mut edges: Vec<&Edge> = self.edges.values().collect();
+        edges.sort_by_key(|e| e.name());
+
+        let mut types: Vec<String> = Vec::new();
+        for edge in &edges {
+            if let Edge::Attribute { target, .. } = edge {
+                let ty = format!("{}", target);
+                if !types.contains(&ty) {
+                    types.push(ty);
+                }
+            }
+        }
+        types.sort();
+        for ty in &types {
+            out.push_str(&format!(
+                "  {} [label={}, shape=ellipse, style=dashed];\n",
+                quote(&format!("type:{}", ty)), quote(ty)
+            ));
+        }
+
+        for edge in edges {
+            match edge {
+                Edge::ForeignKey { name, source, target, .. } => {
+                    out.push_str(&format!(
+                        "  {} -> {} [label={}];\n",
+                        quote(source), quote(target), quote(name)
+                    ));
+                }
+                Edge::Attribute { name, source, target, .. } => {
+                    out.push_str(&format!(
+                        "  {} -> {} [label={}, style=dashed];\n",
+                        quote(source), quote(&format!("type:{}", target)), quote(name)
+                    ));
+                }
+            }
+        }
+
+        if !self.path_equations.is_empty() {
+            // Une ligne par équation, alignée à gauche (\l)
+            let legend: String = self.path_equations.iter()
+                .map(|eq| format!("{}\\l", eq).replace('"', "\\\""))
+                .collect();
+            out.push_str(&format!("  equations [shape=note, label=\"{}\"];\n", legend));
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    /// Égalité structurelle : même nom, mêmes nœuds, mêmes arêtes (nom et
+    /// définition) et mêmes équations, comparés comme des ensembles.
+    ///
+    /// L'ordre de déclaration est ignoré, de même que le sens d'une
+    /// équation (`p = q` vaut `q = p`).
+    pub fn structurally_eq(&self, other: &Schema) -> bool {
+        let same_equation = |a: &PathEquation, b: &PathEquation| {
+            (a.lhs == b.lhs && a.rhs == b.rhs) || (a.lhs == b.rhs && a.rhs == b.lhs)
+        };
+        let covers = |xs: &[PathEquation], ys: &[PathEquation]| {
+            xs.iter().all(|x| ys.iter().any(|y| same_equation(x, y)))
+        };
+        self.name == other.name
+            && self.nodes == other.nodes
+            && self.edges == other.edges
+            && covers(&self.path_equations, &other.path_equations)
+            && covers(&other.path_equations, &self.path_equations)
+    }
+
+    /// Signale les traces de négligence d'un schéma : nœuds isolés, FK en
+    /// boucle sur leur propre nœud, et (dès que le schéma a des équations
+    /// d'observation) attributs qu'aucune équation ne mentionne.
+    ///
+    /// Ce sont des avertissements, pas des erreurs : `validate_schema`
+    /// accepte ces schémas. Ordre : nœuds, puis arêtes, par déclaration.
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        let fks = self.foreign_keys();
+
+        if self.nodes.len() > 1 {
+            for node in self.node_names() {
+                let linked = fks.iter().any(|fk| match fk {
+                    Edge::ForeignKey { source, target, .. } => source == node || target == node,
+                    Edge::Attribute { .. } => false,
+                });
+                if !linked {
+                    warnings.push(LintWarning::IsolatedNode { node: node.clone() });
+                }
+            }
+        }
+
+        for fk in &fks {
+            if let Edge::ForeignKey { name, source, target, .. } = fk {
+                if source == target {
+                    warnings.push(LintWarning::SelfLoop { fk: name.clone(), node: source.clone() });
+                }
+            }
+        }
+
+        // Sans équation d'observation, tous les attributs seraient signalés
+        if self.path_equations.iter().any(|eq| eq.is_observation(self)) {
+            let observed: Vec<&str> = self.path_equations.iter()
+                .flat_map(|eq| [&eq.lhs, &eq.rhs])
+                .flat_map(|path| path.edges.iter().map(String::as_str))
+                .collect();
+            for node in self.node_names() {
+                for attr in self.attributes_of(node) {
+                    if !observed.contains(&attr.name()) {
+                        warnings.push(LintWarning::UnusedAttribute {
+                            attribute: attr.name().to_string(),
+                            node: node.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        warnings
+    }
+}
+
+/// Avertissement de `Schema::lint`
+#[derive(Debug, Clone, PartialEq)]
+pub enum LintWarning {
+    /// Nœud qu'aucune FK ne relie au reste du schéma (îlot)
+    IsolatedNode { node: String },
+    /// Attribut absent de toutes les équations (informatif)
+    UnusedAttribute { attribute: String, node: String },
+    /// FK d'un nœud vers lui-même : hiérarchie voulue, ou erreur de cible ?
+    SelfLoop { fk: String, node: String },
+}
+
+impl LintWarning {
+    /// Simple information, plutôt qu'une anomalie probable
+    pub fn is_informational(&self) -> bool {
+        matches!(self, LintWarning::UnusedAttribute { .. })
+    }
+}
+
+impl std::fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LintWarning::IsolatedNode { node } => {
+                write!(f, "Nœud '{}' isolé : aucune FK ne le relie au schéma", node)
+            }
+            LintWarning::UnusedAttribute { attribute, node } => {
+                write!(f, "Attribut '{}.{}' absent de toutes les équations", node, attribute)
+            }
+            LintWarning::SelfLoop { fk, node } => {
+                write!(f, "FK '{}' boucle sur '{}'", fk, node)
+            }
+        }
+    }
+}
+
+/// Différence structurelle entre deux versions d'un schéma (`old.diff(&new)`).
+///
+/// Les renommages sont détectés par heuristique : un élément supprimé et un
+/// élément ajouté de même structure, chacun seul candidat de l'autre. Un
+/// élément renommé n'apparaît PAS dans les listes d'ajouts / suppressions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaDiff {
+    /// Nœuds présents seulement dans le nouveau schéma
+    pub added_nodes: Vec<String>,
+    /// Nœuds présents seulement dans l'ancien schéma
+    pub removed_nodes: Vec<String>,
+    /// Renommages probables de nœuds : (ancien nom, nouveau nom)
+    pub renamed_nodes: Vec<(String, String)>,
+    /// Arêtes présentes seulement dans le nouveau schéma (ou dont la source /
+    /// la cible a changé)
+    pub added_edges: Vec<Edge>,
+    /// Arêtes présentes seulement dans l'ancien schéma
+    pub removed_edges: Vec<Edge>,
+    /// Renommages probables d'arêtes : (ancien nom, nouveau nom)
+    pub renamed_edges: Vec<(String, String)>,
+    /// Attributs conservés dont le type a changé : (nom, ancien, nouveau)
+    pub retyped_attributes: Vec<(String, BaseType, BaseType)>,
+    /// Équations présentes seulement dans le nouveau schéma
+    pub added_equations: Vec<PathEquation>,
+    /// Équations présentes seulement dans l'ancien schéma
+    pub removed_equations: Vec<PathEquation>,
+}
+
+impl SchemaDiff {
+    /// Les deux schémas ont-ils la même structure ?
+    pub fn is_empty(&self) -> bool {
+        *self == SchemaDiff::default()
+    }
+}
+
+impl Schema {
+    /// Compare ce schéma (ancienne version) à `other` (nouvelle version).
+    /// Les listes sont triées par nom, pour un résultat déterministe.
+    pub fn diff(&self, other: &Schema) -> SchemaDiff {
+        let mut diff = SchemaDiff::default();
+
+        // --- Nœuds ---
+        let mut removed: Vec<&String> = self.nodes.keys().filter(|n| !other.nodes.contains_key(*n)).collect();
+        let mut added: Vec<&String> = other.nodes.keys().filter(|n| !self.nodes.contains_key(*n)).collect();
+        removed.sort();
+        added.sort();
+
+        let renamed_nodes = unique_matches(
+            &removed, &added,
+            |n| n.as_str(),
+            |old, new| self.node_signature(old) == other.node_signature(new),
+        );
+        let node_image = |n: &str| -> String {
+            renamed_nodes.iter()
+                .find(|(old, _)| old == n)
+                .map(|(_, new)| new.clone())
+                .unwrap_or_else(|| n.to_string())
+        };
+        diff.removed_nodes = removed.into_iter()
+            .filter(|n| !renamed_nodes.iter().any(|(old, _)| old == *n))
+            .cloned()
+            .collect();
+        diff.added_nodes = added.into_iter()
+            .filter(|n| !renamed_nodes.iter().any(|(_, new)| new == *n))
+            .cloned()
+            .collect();
+
+        // --- Arêtes ---
+        // Même nom mais source / cible différente = suppression + ajout
+        let mut removed: Vec<&Edge> = Vec::new();
+        let mut added: Vec<&Edge> = Vec::new();
+        for (name, old) in &self.edges {
+            match (old, other.edges.get(name)) {
+                (Edge::Attribute { source, target: old_ty, .. },
+                 Some(Edge::Attribute { source: new_source, target: new_ty, .. }))
+                    if node_image(source) == *new_source =>
+                {
+                    if old_ty != new_ty {
+                        diff.retyped_attributes.push((name.clone(), old_ty.clone(), new_ty.clone()));
+                    }
+                }
+                (Edge::ForeignKey { source, target, .. },
+                 Some(Edge::ForeignKey { source: new_source, target: new_target, .. }))
+                    if node_image(source) == *new_source && node_image(target) == *new_target => {}
+                (_, Some(new)) => {
+                    removed.push(old);
+                    added.push(new);
+                }
+                (_, None) => removed.push(old),
+            }
+        }
+        for (name, new) in &other.edges {
+            if !self.edges.contains_key(name) {
+                added.push(new);
+            }
+        }
+        removed.sort_by_key(|e| e.name());
+        added.sort_by_key(|e| e.name());
+
+        // Renommage : même nature, mêmes extrémités (au renommage de nœuds près)
+        let same_shape = |old: &&Edge, new: &&Edge| match (old, new) {
+            (Edge::ForeignKey { source, target, .. }, Edge::ForeignKey { source: ns, target: nt, .. }) => {
+                node_image(source) == *ns && node_image(target) == *nt
+            }
+            (Edge::Attribute { source, target, .. }, Edge::Attribute { source: ns, target: nt, .. }) => {
+                node_image(source) == *ns && target == nt
+            }
+            _ => false,
+        };
+        let renamed_edges = unique_matches(&removed, &added, |e| e.name(), |old, new| {
+            old.name() != new.name() && same_shape(old, new)
+        });
+        diff.removed_edges = removed.into_iter()
+            .filter(|e| !renamed_edges.iter().any(|(old, _)| old == e.name()))
+            .cloned()
+            .collect();
+        diff.added_edges = added.into_iter()
+            .filter(|e| !renamed_edges.iter().any(|(_, new)| new == e.name()))
+            .cloned()
+            .collect();
+        diff.renamed_nodes = renamed_nodes;
+        diff.renamed_edges = renamed_edges;
+        diff.retyped_attributes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // --- Équations (comparées telles quelles) ---
+        diff.removed_equations = self.path_equations.iter()
+            .filter(|eq| !other.path_equations.contains(eq))
+            .cloned()
+            .collect();
+        diff.added_equations = other.path_equations.iter()
+            .filter(|eq| !self.path_equations.contains(eq))
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    /// Signature structurelle d'un nœud, pour détecter les renommages :
+    /// types de ses attributs, nombre de FK sortantes et entrantes.
+    fn node_signature(&self, node: &str) -> (Vec<String>, usize, usize) {
+        let mut types: Vec<String> = self.attributes_of(node).into_iter()
+            .filter_map(|e| match e {
+                Edge::Attribute { target, .. } => Some(target.to_string()),
+                _ => None,
+            })
+            .collect();
+        types.sort();
+        let out = self.edges_from(node).into_iter()
+            .filter(|e| matches!(e, Edge::ForeignKey { .. }))
+            .count();
+        (types, out, self.fks_targeting(node).len())
+    }
+}
+
+/// Appariements sans ambiguïté : `old` et `new` sont appariés si chacun est
+/// le seul candidat de l'autre. Retourne des paires de noms.
+fn unique_matches<T>(
+    olds: &[T],
+    news: &[T],
+    name: impl Fn(&T) -> &str,
+    matches: impl Fn(&T, &T) -> bool,
+) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    for old in olds {
+        let candidates: Vec<&T> = news.iter().filter(|new| matches(old, new)).collect();
+        if let [new] = candidates[..] {
+            if olds.iter().filter(|o| matches(o, new)).count() == 1 {
+                pairs.push((name(old).to_string(), name(new).to_string()));
+            }
+        }
+    }
+    pairs
+}
+
+/// Parcours d'un schéma (cf. `Schema::accept`), pour les outils externes
+/// (linters, exporteurs...) qui ne veulent pas dépendre des HashMap internes.
+///
+/// Toutes les méthodes ne font rien par défaut : on ne redéfinit que
+/// celles dont on a besoin.
+pub trait SchemaVisitor {
+    /// Une entité, avant ses arêtes
+    fn visit_node(&mut self, _node: &Node) {}
+    /// Une FK `source → target`
+    fn visit_fk(&mut self, _name: &str, _source: &str, _target: &str) {}
+    /// Un attribut `source → ty`
+    fn visit_attr(&mut self, _name: &str, _source: &str, _ty: &BaseType) {}
+    /// Une équation de chemins, après tous les nœuds
+    fn visit_equation(&mut self, _equation: &PathEquation) {}
+}
+
+impl Schema {
+    /// Présente le schéma à `visitor` : chaque nœud suivi de ses arêtes
+    /// sortantes, dans l'ordre de déclaration, puis les équations.
+    pub fn accept<V: SchemaVisitor + ?Sized>(&self, visitor: &mut V) {
+        for name in self.node_names() {
+            visitor.visit_node(&self.nodes[name]);
+            for edge in self.edges_from(name) {
+                match edge {
+                    Edge::ForeignKey { name, source, target, .. } => visitor.visit_fk(name, source, target),
+                    Edge::Attribute { name, source, target, .. } => visitor.visit_attr(name, source, target),
+                }
+            }
+        }
+        for equation in &self.path_equations {
+            visitor.visit_equation(equation);
+        }
     }
 }
 
@@ -347,8 +1061,15 @@ impl std::fmt::Display for Schema {
         if !fks.is_empty() {
             writeln!(f, "  foreign_keys")?;
             for fk in fks {
-                if let Edge::ForeignKey { name, source, target } = fk {
-                    writeln!(f, "    {} : {} -> {}", name, source, target)?;
+                if let Edge::ForeignKey { name, source, target, columns } = fk {
+                    write!(f, "    {} : {} -> {}", name, source, target)?;
+                    if !columns.is_empty() {
+                        let cols: Vec<String> = columns.iter()
+                            .map(|(col, attr)| format!("{} = {}", col, attr))
+                            .collect();
+                        write!(f, " ({})", cols.join(", "))?;
+                    }
+                    writeln!(f)?;
                 }
             }
         }
@@ -357,8 +1078,11 @@ impl std::fmt::Display for Schema {
         if !attrs.is_empty() {
             writeln!(f, "  attributes")?;
             for attr in attrs {
-                if let Edge::Attribute { name, source, target } = attr {
-                    writeln!(f, "    {} : {} -> {}", name, source, target)?;
+                if let Edge::Attribute { name, source, target, default } = attr {
+                    match default {
+                        Some(value) => writeln!(f, "    {} : {} -> {} = {}", name, source, target, value)?,
+                        None => writeln!(f, "    {} : {} -> {}", name, source, target)?,
+                    }
                 }
             }
         }
@@ -393,6 +1117,83 @@ mod tests {
         s
     }
 
+    #[test]
+    fn test_lint() {
+        assert_eq!(company_schema().lint(), vec![]);
+
+        let mut s = company_schema();
+        s.add_node("Legacy")
+         .add_fk("manager", "Employee", "Employee")
+         .add_attribute("cached_dept_name", "Employee", BaseType::String)
+         .add_path_equation(
+             Path::new("Employee", vec!["works_in", "dept_name"]),
+             Path::new("Employee", vec!["cached_dept_name"]),
+         );
+        let warnings = s.lint();
+        assert_eq!(warnings[0], LintWarning::IsolatedNode { node: "Legacy".into() });
+        assert_eq!(warnings[1], LintWarning::SelfLoop { fk: "manager".into(), node: "Employee".into() });
+        let unused: Vec<String> = warnings.iter()
+            .filter(|w| w.is_informational())
+            .map(|w| w.to_string())
+            .collect();
+        assert_eq!(unused, vec![
+            "Attribut 'Employee.emp_name' absent de toutes les équations",
+            "Attribut 'Employee.salary' absent de toutes les équations",
+        ]);
+        assert_eq!(warnings.len(), 4);
+    }
+
+    #[test]
+    fn test_structurally_eq() {
+        let mut a = company_schema();
+        a.add_fk("manager", "Department", "Employee")
+         .add_path_equation(
+             Path::new("Department", vec!["manager", "works_in"]),
+             Path::new("Department", vec![]),
+         );
+
+        // Même schéma, déclaré dans un autre ordre et équation retournée
+        let mut b = Schema::new("Company");
+        b.add_node("Department")
+         .add_node("Employee")
+         .add_attribute("dept_name", "Department", BaseType::String)
+         .add_fk("manager", "Department", "Employee")
+         .add_attribute("salary", "Employee", BaseType::Integer)
+         .add_attribute("emp_name", "Employee", BaseType::String)
+         .add_fk("works_in", "Employee", "Department")
+         .add_path_equation(
+             Path::new("Department", vec![]),
+             Path::new("Department", vec!["manager", "works_in"]),
+         );
+        assert!(a.structurally_eq(&b));
+        assert!(b.structurally_eq(&a));
+
+        b.add_attribute("budget", "Department", BaseType::Integer);
+        assert!(!a.structurally_eq(&b));
+        assert!(!a.structurally_eq(&company_schema()));
+    }
+
+    #[test]
+    fn test_edges_with_defaults_are_hashable() {
+        use std::collections::HashSet;
+        let mut s = company_schema();
+        s.add_attribute_default("bonus", "Employee", BaseType::Float, Value::Float(f64::NAN))
+         .add_attribute_default("level", "Employee", BaseType::Integer, Value::Integer(1));
+        let mut edges: HashSet<Edge> = s.edges.values().cloned().collect();
+        assert_eq!(edges.len(), 6);
+        // Un défaut NaN reste égal à lui-même : pas de doublon
+        edges.insert(s.edges["bonus"].clone());
+        assert_eq!(edges.len(), 6);
+        // 1 et 1.0 sont deux défauts distincts
+        let float_level = Edge::Attribute {
+            name: "level".into(),
+            source: "Employee".into(),
+            target: BaseType::Integer,
+            default: Some(DefaultValue(Value::Float(1.0))),
+        };
+        assert!(edges.insert(float_level));
+    }
+
     #[test]
     fn test_create_schema() {
         let s = company_schema();
@@ -414,6 +1215,80 @@ mod tests {
         assert_eq!(emp_attrs.len(), 2); // emp_name, salary
     }
 
+    #[test]
+    fn test_topological_order() {
+        let mut s = company_schema();
+        s.add_node("Company")
+         .add_fk("belongs_to", "Department", "Company")
+         .add_fk("mentor", "Employee", "Employee");
+        assert_eq!(s.topological_order(), vec!["Company", "Department", "Employee"]);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut s = Schema::new("Company");
+        s.add_node("Employee")
+         .add_node("Department")
+         .add_fk("department", "Employee", "Department")
+         .add_fk("manager", "Department", "Employee")
+         .add_fk("direct_mgr", "Employee", "Employee")
+         .add_attribute("emp_name", "Employee", BaseType::String)
+         .add_path_equation(
+             Path::new("Employee", vec!["department", "manager"]),
+             Path::new("Employee", vec!["direct_mgr"]),
+         );
+
+        let dot = s.to_dot();
+        assert!(dot.starts_with("digraph \"Company\" {"));
+        assert!(dot.contains("\"Employee\" [shape=box];"));
+        assert!(dot.contains("\"Department\" [shape=box];"));
+        assert!(dot.contains("\"Employee\" -> \"Department\" [label=\"department\"];"));
+        assert!(dot.contains("\"Department\" -> \"Employee\" [label=\"manager\"];"));
+        assert!(dot.contains("\"Employee\" -> \"Employee\" [label=\"direct_mgr\"];"));
+        assert!(dot.contains("\"Employee\" -> \"type:String\" [label=\"emp_name\", style=dashed];"));
+        assert!(dot.contains("Employee.department.manager = Employee.direct_mgr\\l"));
+        println!("{}", dot);
+    }
+
+    #[test]
+    fn test_schema_diff() {
+        let mut old = Schema::new("OldCompany");
+        old.add_node("Person")
+           .add_node("Dept")
+           .add_node("Badge")
+           .add_fk("works_in", "Person", "Dept")
+           .add_attribute("person_name", "Person", BaseType::String)
+           .add_attribute("age", "Person", BaseType::Integer)
+           .add_attribute("dept_name", "Dept", BaseType::String)
+           .add_attribute("budget", "Dept", BaseType::Float);
+
+        let mut new = Schema::new("NewCompany");
+        new.add_node("Employee")
+           .add_node("Dept")
+           .add_node("Office")
+           .add_fk("works_in", "Employee", "Dept")
+           .add_fk("located_in", "Dept", "Office")
+           .add_attribute("full_name", "Employee", BaseType::String)
+           .add_attribute("age", "Employee", BaseType::Integer)
+           .add_attribute("dept_name", "Dept", BaseType::String)
+           .add_attribute("budget", "Dept", BaseType::Integer)
+           .add_attribute("city", "Office", BaseType::String);
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.renamed_nodes, vec![("Person".to_string(), "Employee".to_string())]);
+        assert_eq!(diff.removed_nodes, vec!["Badge"]);
+        assert_eq!(diff.added_nodes, vec!["Office"]);
+        assert_eq!(diff.renamed_edges, vec![("person_name".to_string(), "full_name".to_string())]);
+
+        // Attributs conservés : seul budget change de type
+        assert_eq!(diff.retyped_attributes, vec![("budget".to_string(), BaseType::Float, BaseType::Integer)]);
+        let added: Vec<&str> = diff.added_edges.iter().map(|e| e.name()).collect();
+        assert_eq!(added, vec!["city", "located_in"]);
+        assert!(diff.removed_edges.is_empty());
+
+        assert!(old.diff(&old).is_empty());
+    }
+
     #[test]
     fn test_path_display() {
         let p = Path::new("Employee", vec!["works_in", "dept_name"]);
@@ -428,6 +1303,74 @@ mod tests {
         assert_eq!(composed.edges, vec!["works_in", "dept_name"]);
     }
 
+    #[test]
+    fn test_paths_equal() {
+        let mut s = Schema::new("Org");
+        s.add_node("Employee")
+         .add_node("Department")
+         .add_fk("department", "Employee", "Department")
+         .add_fk("manager", "Department", "Employee")
+         .add_fk("direct_mgr", "Employee", "Employee")
+         .add_fk("mentor", "Employee", "Employee")
+         .add_path_equation(
+             Path::new("Employee", vec!["department", "manager"]),
+             Path::new("Employee", vec!["direct_mgr"]),
+         )
+         .add_path_equation(
+             Path::new("Employee", vec!["mentor", "mentor"]),
+             Path::new("Employee", vec!["mentor"]),
+         );
+
+        assert!(s.paths_equal(
+            &Path::new("Employee", vec!["department", "manager"]),
+            &Path::new("Employee", vec!["direct_mgr"]),
+        ));
+        assert!(s.paths_equal(
+            &Path::new("Employee", vec!["department", "manager", "mentor", "mentor"]),
+            &Path::new("Employee", vec!["direct_mgr", "mentor"]),
+        ));
+        // Chemins sans lien : non prouvés égaux
+        assert!(!s.paths_equal(
+            &Path::new("Employee", vec!["mentor"]),
+            &Path::new("Employee", vec!["direct_mgr"]),
+        ));
+        assert!(!s.paths_equal(
+            &Path::new("Employee", vec!["department"]),
+            &Path::new("Department", vec![]),
+        ));
+    }
+
+    #[test]
+    fn test_coproduct() {
+        let company = company_schema();
+        let mut project = Schema::new("Projects");
+        project.add_node("Project")
+               .add_node("Employee")
+               .add_fk("lead", "Project", "Employee")
+               .add_fk("staffed_by", "Project", "Employee")
+               .add_attribute("emp_name", "Employee", BaseType::String)
+               .add_path_equation(Path::new("Project", vec!["lead"]), Path::new("Project", vec!["staffed_by"]));
+
+        // Employee est dans les deux schémas : il faut un préfixe
+        assert!(Schema::coproduct(&company, &project, None).is_err());
+
+        let merged = Schema::coproduct(&company, &project, Some("proj")).unwrap();
+        assert_eq!(merged.nodes.len(), company.nodes.len() + project.nodes.len());
+        assert_eq!(merged.edges.len(), company.edges.len() + project.edges.len());
+        assert!(company.nodes.keys().all(|n| merged.nodes.contains_key(n)));
+        assert!(company.edges.keys().all(|e| merged.edges.contains_key(e)));
+        assert!(merged.nodes.contains_key("Project") && merged.nodes.contains_key("proj_Employee"));
+        assert_eq!(merged.edges["staffed_by"], Edge::fk("staffed_by", "Project", "proj_Employee"));
+        assert_eq!(merged.edges["proj_emp_name"], Edge::attr("proj_emp_name", "proj_Employee", BaseType::String));
+        assert_eq!(merged.path_equations, project.path_equations);
+
+        // Le nom préfixé ne doit pas non plus exister dans `b` : sinon
+        // proj_Employee renommé et proj_Employee d'origine se confondraient
+        project.add_node("proj_Employee");
+        let err = Schema::coproduct(&company, &project, Some("proj")).unwrap_err();
+        assert!(err.to_string().contains("'proj_Employee' est déjà pris dans 'Projects'"), "{}", err);
+    }
+
     #[test]
     fn test_schema_display() {
         let s = company_schema();
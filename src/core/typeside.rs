@@ -17,8 +17,10 @@
 //
 // =============================================================================
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
+use super::instance::RowId;
 
 /// Un type de base dans le système CQL.
 /// 
@@ -36,6 +38,35 @@ pub enum BaseType {
     Boolean,
     /// Type personnalisé défini par l'utilisateur
     Custom(std::string::String),
+    /// Liste de valeurs d'un même type (données dénormalisées)
+    /// (→ `elem[]` en PostgreSQL, ARRAY(elem) en Snowflake/Trino, liste en Neo4j)
+    Array(Box<BaseType>),
+    /// Chaîne restreinte à un ensemble de valeurs (ex: statut ∈ {active, inactive})
+    /// (→ ENUM en PostgreSQL, texte + CHECK ailleurs)
+    Enum {
+        name: std::string::String,
+        variants: Vec<std::string::String>,
+    },
+}
+
+impl BaseType {
+    /// Une valeur est-elle acceptable pour ce type ?
+    ///
+    /// NULL est accepté partout, Int et Float sont interchangeables, les
+    /// types Custom ne sont pas vérifiés (leur représentation est libre) et
+    /// un Enum n'accepte que ses variantes.
+    pub fn accepts(&self, value: &Value) -> bool {
+        match (self, value) {
+            (_, Value::Null) => true,
+            (BaseType::Custom(_), _) => true,
+            (BaseType::Enum { variants, .. }, Value::String(s)) => variants.contains(s),
+            (BaseType::Enum { .. }, _) => false,
+            (BaseType::Array(elem), Value::Array(values)) => values.iter().all(|v| elem.accepts(v)),
+            (BaseType::Array(_), _) => false,
+            (BaseType::Integer, Value::Float(_)) | (BaseType::Float, Value::Integer(_)) => true,
+            (ty, v) => v.get_type() == *ty,
+        }
+    }
 }
 
 impl fmt::Display for BaseType {
@@ -46,6 +77,8 @@ impl fmt::Display for BaseType {
             BaseType::Float => write!(f, "Float"),
             BaseType::Boolean => write!(f, "Bool"),
             BaseType::Custom(name) => write!(f, "{}", name),
+            BaseType::Enum { name, .. } => write!(f, "{}", name),
+            BaseType::Array(elem) => write!(f, "[{}]", elem),
         }
     }
 }
@@ -54,12 +87,23 @@ impl fmt::Display for BaseType {
 /// 
 /// Les valeurs sont les "éléments" des types. Quand on a des données
 /// dans une Instance, chaque cellule contient une Value.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Égalité et ordre suivent la sémantique des valeurs, pas des variantes :
+/// un entier et un flottant se comparent numériquement (`1 == 1.0`).
+/// NULL est placé AVANT toute autre valeur (comme `NULLS FIRST`) ;
+/// les comparaisons SQL, où NULL n'est jamais vrai, restent à la charge
+/// de l'évaluateur.
+#[derive(Debug, Clone)]
 pub enum Value {
     String(std::string::String),
     Integer(i64),
     Float(f64),
     Boolean(bool),
+    Array(Vec<Value>),
+    /// Référence à une ligne : la valeur d'un chemin qui finit par une FK.
+    /// Un RowId n'a de sens que dans son entité : deux références ne sont
+    /// égales que si elles désignent la même ligne de la même entité.
+    Ref { entity: std::string::String, row: RowId },
     Null,
 }
 
@@ -70,12 +114,187 @@ impl fmt::Display for Value {
             Value::Integer(i) => write!(f, "{}", i),
             Value::Float(fl) => write!(f, "{}", fl),
             Value::Boolean(b) => write!(f, "{}", b),
+            Value::Array(values) => {
+                let items: Vec<std::string::String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            Value::Ref { entity, row } => write!(f, "{}[{}]", entity, row),
             Value::Null => write!(f, "NULL"),
         }
     }
 }
 
+// Conversions depuis les types Rust (utilisées par la macro `row!`)
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<std::string::String> for Value {
+    fn from(s: std::string::String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Value::Integer(i)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(i: i32) -> Self {
+        Value::Integer(i as i64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Value::Float(f)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Boolean(b)
+    }
+}
+
+/// `None` donne NULL
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::Null, Into::into)
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Integer(a), Value::Float(b)) | (Value::Float(b), Value::Integer(a)) => *a as f64 == *b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Ref { entity: e1, row: r1 }, Value::Ref { entity: e2, row: r2 }) => e1 == e2 && r1 == r2,
+            (Value::Null, Value::Null) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Ordre partiel : NULL en premier, entiers et flottants mêlés, listes par
+/// ordre lexicographique, références d'une même entité par RowId. `None`
+/// entre types incompatibles, avec NaN et entre références d'entités
+/// différentes.
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Null, Value::Null) => Some(Ordering::Equal),
+            (Value::Null, _) => Some(Ordering::Less),
+            (_, Value::Null) => Some(Ordering::Greater),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+            (Value::Integer(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+            (Value::Float(a), Value::Integer(b)) => a.partial_cmp(&(*b as f64)),
+            (Value::Boolean(a), Value::Boolean(b)) => Some(a.cmp(b)),
+            (Value::Array(a), Value::Array(b)) => a.partial_cmp(b),
+            (Value::Ref { entity: e1, row: r1 }, Value::Ref { entity: e2, row: r2 }) if e1 == e2 => Some(r1.cmp(r2)),
+            _ => None,
+        }
+    }
+}
+
 impl Value {
+    /// Ordre total, pour trier n'importe quelles valeurs : celui de
+    /// `PartialOrd` quand il est défini, sinon par genre de valeur
+    /// (NULL < booléens < nombres < chaînes < listes < références, celles-ci
+    /// par entité puis RowId). NaN, quel que soit
+    /// son signe, se place après tous les autres nombres.
+    pub fn total_cmp(&self, other: &Value) -> Ordering {
+        if let Some(ord) = self.partial_cmp(other) {
+            return ord;
+        }
+        // Seul NaN rend deux nombres incomparables
+        let nan_last = |a: f64, b: f64| a.is_nan().cmp(&b.is_nan());
+        self.kind_rank().cmp(&other.kind_rank()).then_with(|| match (self, other) {
+            (Value::Float(a), Value::Float(b)) => nan_last(*a, *b),
+            (Value::Integer(a), Value::Float(b)) => nan_last(*a as f64, *b),
+            (Value::Float(a), Value::Integer(b)) => nan_last(*a, *b as f64),
+            (Value::Array(a), Value::Array(b)) => a.iter().zip(b)
+                .map(|(x, y)| x.total_cmp(y))
+                .find(|ord| *ord != Ordering::Equal)
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            (Value::Ref { entity: e1, row: r1 }, Value::Ref { entity: e2, row: r2 }) => (e1, r1).cmp(&(e2, r2)),
+            _ => Ordering::Equal,
+        })
+    }
+
+    /// Addition. Int + Int → Int, un Float rend le résultat Float ; NULL
+    /// absorbe l'opération (comme en SQL). Erreur sur un autre type ou en
+    /// cas de dépassement entier.
+    pub fn add(&self, other: &Value) -> Result<Value, std::string::String> {
+        self.arith(other, "+", i64::checked_add, |a, b| a + b)
+    }
+
+    /// Soustraction, mêmes règles que `add`.
+    pub fn sub(&self, other: &Value) -> Result<Value, std::string::String> {
+        self.arith(other, "-", i64::checked_sub, |a, b| a - b)
+    }
+
+    /// Multiplication, mêmes règles que `add`.
+    pub fn mul(&self, other: &Value) -> Result<Value, std::string::String> {
+        self.arith(other, "*", i64::checked_mul, |a, b| a * b)
+    }
+
+    /// Division, mêmes règles que `add` : Int / Int est une division
+    /// entière (tronquée, comme en SQL). Diviser par zéro est une erreur.
+    pub fn div(&self, other: &Value) -> Result<Value, std::string::String> {
+        if matches!(other, Value::Integer(0)) || matches!(other, Value::Float(f) if *f == 0.0) {
+            return Err(format!("Division par zéro : {} / {}", self, other));
+        }
+        self.arith(other, "/", i64::checked_div, |a, b| a / b)
+    }
+
+    /// Opération arithmétique avec promotion Int → Float
+    fn arith(
+        &self,
+        other: &Value,
+        symbol: &str,
+        int_op: fn(i64, i64) -> Option<i64>,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Result<Value, std::string::String> {
+        let as_f64 = |v: &Value| match v {
+            Value::Integer(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            _ => None,
+        };
+        match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (Value::Integer(a), Value::Integer(b)) => int_op(*a, *b)
+                .map(Value::Integer)
+                .ok_or_else(|| format!("Dépassement entier : {} {} {}", a, symbol, b)),
+            _ => match (as_f64(self), as_f64(other)) {
+                (Some(a), Some(b)) => Ok(Value::Float(float_op(a, b))),
+                _ => Err(format!("Opération {} impossible entre {} et {}", symbol, self, other)),
+            },
+        }
+    }
+
+    /// Rang du genre de valeur, pour départager les types incompatibles
+    fn kind_rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Boolean(_) => 1,
+            Value::Integer(_) | Value::Float(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Ref { .. } => 5,
+        }
+    }
+
     /// Retourne le BaseType correspondant à cette valeur
     pub fn get_type(&self) -> BaseType {
         match self {
@@ -83,9 +302,91 @@ impl Value {
             Value::Integer(_) => BaseType::Integer,
             Value::Float(_) => BaseType::Float,
             Value::Boolean(_) => BaseType::Boolean,
+            // Stockée comme un catrust_id
+            Value::Ref { .. } => BaseType::Integer,
+            // Type du premier élément non NULL (String pour une liste vide)
+            Value::Array(values) => BaseType::Array(Box::new(
+                values.iter()
+                    .find(|v| **v != Value::Null)
+                    .map(|v| v.get_type())
+                    .unwrap_or(BaseType::String),
+            )),
             Value::Null => BaseType::String, // Null est polymorphe, par défaut String
         }
     }
+
+    /// Lit une valeur texte (CSV, parseur) selon le type attendu.
+    ///
+    /// - Chaîne vide → NULL, quel que soit le type
+    /// - Integer / Float : nombre, sinon erreur (jamais de repli en String)
+    /// - Boolean : `true` / `false` (casse ignorée) ou `1` / `0`
+    /// - String / Custom : texte tel quel ; Enum : doit être une variante
+    /// - Array : `[a, b]` ou `a, b`, chaque élément lu avec le type des éléments
+    pub fn parse(s: &str, ty: &BaseType) -> Result<Value, std::string::String> {
+        if s.is_empty() {
+            return Ok(Value::Null);
+        }
+        match ty {
+            BaseType::String | BaseType::Custom(_) => Ok(Value::String(s.to_string())),
+            BaseType::Integer => s.trim().parse::<i64>()
+                .map(Value::Integer)
+                .map_err(|e| format!("'{}' n'est pas un entier valide ({})", s, e)),
+            BaseType::Float => s.trim().parse::<f64>()
+                .map(Value::Float)
+                .map_err(|e| format!("'{}' n'est pas un nombre à virgule valide ({})", s, e)),
+            BaseType::Boolean => match s.trim() {
+                b if b.eq_ignore_ascii_case("true") || b == "1" => Ok(Value::Boolean(true)),
+                b if b.eq_ignore_ascii_case("false") || b == "0" => Ok(Value::Boolean(false)),
+                _ => Err(format!("'{}' n'est pas un booléen (true/false/1/0)", s)),
+            },
+            BaseType::Enum { name, variants } => {
+                if variants.iter().any(|v| v == s) {
+                    Ok(Value::String(s.to_string()))
+                } else {
+                    Err(format!("'{}' n'est pas une variante de {} ({})", s, name, variants.join(", ")))
+                }
+            }
+            BaseType::Array(elem) => {
+                let inner = s.trim();
+                let inner = inner.strip_prefix('[')
+                    .and_then(|x| x.strip_suffix(']'))
+                    .unwrap_or(inner)
+                    .trim();
+                if inner.is_empty() {
+                    return Ok(Value::Array(Vec::new()));
+                }
+                inner.split(',')
+                    .map(|item| Value::parse(item.trim(), elem))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Value::Array)
+            }
+        }
+    }
+}
+
+/// Une Value munie de l'ordre total `Value::total_cmp` : utilisable comme
+/// clé de `BTreeMap` / `BTreeSet`.
+#[derive(Debug, Clone)]
+pub struct OrdValue(pub Value);
+
+impl PartialEq for OrdValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrdValue {}
+
+impl PartialOrd for OrdValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrdValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
 }
 
 /// Signature d'une opération sur les types.
@@ -158,6 +459,17 @@ impl Typeside {
         self.types.contains(ty)
     }
 
+    /// Un attribut de type `ty` est-il admis ? Un tableau l'est si son type
+    /// d'élément l'est ; une énumération porte ses propres valeurs et l'est
+    /// toujours.
+    pub fn declares(&self, ty: &BaseType) -> bool {
+        match ty {
+            BaseType::Array(elem) => self.declares(elem),
+            BaseType::Enum { .. } => true,
+            other => self.has_type(other),
+        }
+    }
+
     /// Ajoute un type au Typeside
     pub fn add_type(&mut self, ty: BaseType) {
         if !self.types.contains(&ty) {
@@ -178,6 +490,19 @@ impl Typeside {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_value_arithmetic() {
+        // Int * Int reste entier ; un Float promeut le résultat
+        assert!(matches!(Value::Integer(5).mul(&Value::Integer(12)), Ok(Value::Integer(60))));
+        assert!(matches!(Value::Integer(5).add(&Value::Float(0.5)), Ok(Value::Float(f)) if f == 5.5));
+        assert_eq!(Value::Integer(7).div(&Value::Integer(2)), Ok(Value::Integer(3)));
+        assert_eq!(Value::Integer(1).sub(&Value::Null), Ok(Value::Null));
+
+        assert!(Value::String("a".into()).add(&Value::Integer(1)).is_err());
+        assert!(Value::Integer(1).div(&Value::Integer(0)).is_err());
+        assert!(Value::Integer(i64::MAX).add(&Value::Integer(1)).is_err());
+    }
+
     #[test]
     fn test_default_typeside() {
         let ts = Typeside::default_sql();
@@ -197,6 +522,69 @@ mod tests {
         assert_eq!(v.get_type(), BaseType::Integer);
     }
 
+    #[test]
+    fn test_value_ordering() {
+        assert!(Value::Integer(1) < Value::Integer(2));
+        assert!(Value::Integer(1) < Value::Float(1.5));
+        assert!(Value::Float(2.5) > Value::Integer(2));
+        assert_eq!(Value::Integer(1), Value::Float(1.0));
+        assert_eq!(Value::Integer(1).partial_cmp(&Value::Float(1.0)), Some(Ordering::Equal));
+
+        // NULL avant toute valeur
+        assert!(Value::Null < Value::Integer(i64::MIN));
+        assert!(Value::Null < Value::String(String::new()));
+        assert_eq!(Value::Null.partial_cmp(&Value::Null), Some(Ordering::Equal));
+
+        // Types incompatibles : pas d'ordre partiel, mais un ordre total
+        assert_eq!(Value::Integer(1).partial_cmp(&Value::String("1".into())), None);
+        let mut values = [
+            OrdValue(Value::String("a".into())),
+            OrdValue(Value::Float(f64::NAN)),
+            OrdValue(Value::Integer(3)),
+            OrdValue(Value::Null),
+            OrdValue(Value::Boolean(true)),
+        ];
+        values.sort();
+        let sorted: Vec<String> = values.iter().map(|v| v.0.to_string()).collect();
+        assert_eq!(sorted, vec!["NULL", "true", "3", "NaN", "\"a\""]);
+
+        // NaN après tous les nombres, même négatif, même face à l'infini
+        let mut numbers = [
+            OrdValue(Value::Float(-f64::NAN)),
+            OrdValue(Value::Float(f64::INFINITY)),
+            OrdValue(Value::Integer(2)),
+            OrdValue(Value::Float(f64::NEG_INFINITY)),
+        ];
+        numbers.sort();
+        let sorted: Vec<String> = numbers.iter().map(|v| v.0.to_string()).collect();
+        assert_eq!(sorted, vec!["-inf", "2", "inf", "NaN"]);
+    }
+
+    #[test]
+    fn test_value_parse() {
+        assert_eq!(Value::parse("42", &BaseType::Integer), Ok(Value::Integer(42)));
+        assert_eq!(Value::parse("-3.5", &BaseType::Float), Ok(Value::Float(-3.5)));
+        assert_eq!(Value::parse("7", &BaseType::Float), Ok(Value::Float(7.0)));
+        assert_eq!(Value::parse("TRUE", &BaseType::Boolean), Ok(Value::Boolean(true)));
+        assert_eq!(Value::parse("0", &BaseType::Boolean), Ok(Value::Boolean(false)));
+        assert_eq!(Value::parse(" Alice ", &BaseType::String), Ok(Value::String(" Alice ".into())));
+        assert_eq!(Value::parse("", &BaseType::Integer), Ok(Value::Null));
+        assert_eq!(
+            Value::parse("[1, 2]", &BaseType::Array(Box::new(BaseType::Integer))),
+            Ok(Value::Array(vec![Value::Integer(1), Value::Integer(2)]))
+        );
+
+        // Un entier mal formé est une erreur, pas une chaîne
+        let err = Value::parse("x", &BaseType::Integer).unwrap_err();
+        assert!(err.contains("'x' n'est pas un entier"), "{}", err);
+        assert!(Value::parse("4.2", &BaseType::Integer).is_err());
+        assert!(Value::parse("abc", &BaseType::Float).is_err());
+        assert!(Value::parse("yes", &BaseType::Boolean).is_err());
+        let status = BaseType::Enum { name: "status".into(), variants: vec!["active".into()] };
+        assert!(Value::parse("retired", &status).is_err());
+        assert!(Value::parse("1, x", &BaseType::Array(Box::new(BaseType::Integer))).is_err());
+    }
+
     #[test]
     fn test_add_custom_type() {
         let mut ts = Typeside::default_sql();
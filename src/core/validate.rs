@@ -12,18 +12,174 @@
 //
 // =============================================================================
 
-use super::schema::{Schema, Edge};
-use super::instance::Instance;
+use super::schema::{Schema, Edge, Path, PathEquation};
+use super::instance::{Instance, RowId};
+use super::typeside::{BaseType, Value};
 
-/// Erreur de validation
+/// Erreur de validation.
+///
+/// Chaque variante porte les éléments fautifs, pour que l'appelant puisse
+/// traiter l'erreur sans analyser le message.
 #[derive(Debug, Clone)]
-pub struct ValidationError {
-    pub message: String,
+pub enum ValidationError {
+    /// Une entité de l'instance n'existe pas dans le schéma
+    MissingNode {
+        node: String,
+    },
+    /// Une arête du schéma référence un nœud inexistant
+    /// (`kind` = "FK" ou "Attribut", `role` = "source" ou "cible")
+    EdgeNodeMissing {
+        kind: &'static str,
+        edge: String,
+        role: &'static str,
+        node: String,
+    },
+    /// Une équation de chemins part d'un nœud inexistant (`side` = "lhs" ou "rhs")
+    EquationNodeMissing {
+        equation: usize,
+        side: &'static str,
+        node: String,
+    },
+    /// Une équation de chemins utilise une arête inexistante
+    EquationEdgeMissing {
+        equation: usize,
+        side: &'static str,
+        edge: String,
+    },
+    /// Une ligne n'a pas de valeur pour une FK (la FK doit être totale)
+    MissingFk {
+        entity: String,
+        row: RowId,
+        fk: String,
+    },
+    /// Une FK pointe vers une ligne qui n'existe pas
+    DanglingFk {
+        entity: String,
+        row: RowId,
+        fk: String,
+        target: String,
+        target_id: RowId,
+    },
+    /// Les deux côtés d'une équation de chemins mènent à des lignes différentes
+    EquationViolated {
+        equation: PathEquation,
+        row: RowId,
+        lhs: Option<RowId>,
+        rhs: Option<RowId>,
+    },
+    /// Les deux côtés d'une équation d'observation mènent à des valeurs différentes
+    ObservationViolated {
+        equation: PathEquation,
+        row: RowId,
+        lhs: Option<Value>,
+        rhs: Option<Value>,
+    },
+    /// Un seul côté d'une équation finit par un attribut, ou les deux
+    /// attributs n'ont pas le même type (`None` : le côté finit sur un nœud)
+    ObservationTypeMismatch {
+        equation: usize,
+        lhs: Option<BaseType>,
+        rhs: Option<BaseType>,
+    },
+    /// Une FK composite référence un attribut absent de l'entité cible
+    CompositeKeyAttributeMissing {
+        fk: String,
+        target: String,
+        attribute: String,
+    },
+    /// La ligne cible d'une FK composite n'a pas de valeur pour un attribut
+    /// de la clé : la FK ne peut pas être écrite colonne par colonne
+    CompositeKeyIncomplete {
+        entity: String,
+        row: RowId,
+        fk: String,
+        attribute: String,
+    },
+    /// Le type d'un attribut n'est pas déclaré dans le typeside du schéma
+    UndeclaredType {
+        attribute: String,
+        ty: BaseType,
+    },
+    /// La valeur d'un attribut n'a pas le type déclaré dans le schéma
+    TypeMismatch {
+        entity: String,
+        row: RowId,
+        attribute: String,
+        expected: BaseType,
+        found: BaseType,
+    },
 }
 
 impl std::fmt::Display for ValidationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Validation error: {}", self.message)
+        write!(f, "Validation error: ")?;
+        match self {
+            ValidationError::MissingNode { node } => {
+                write!(f, "L'entité '{}' n'existe pas dans le schéma", node)
+            }
+            ValidationError::EdgeNodeMissing { kind, edge, role, node } => {
+                write!(f, "{} '{}' : le nœud {} '{}' n'existe pas", kind, edge, role, node)
+            }
+            ValidationError::EquationNodeMissing { equation, side, node } => {
+                write!(f, "Équation {} : le nœud de départ '{}' ({}) n'existe pas", equation, node, side)
+            }
+            ValidationError::EquationEdgeMissing { equation, side, edge } => {
+                write!(f, "Équation {} : l'arête '{}' ({}) n'existe pas", equation, edge, side)
+            }
+            ValidationError::MissingFk { entity, row, fk } => {
+                write!(f, "{} row[{}] : FK '{}' manquante", entity, row, fk)
+            }
+            ValidationError::DanglingFk { entity, row, fk, target, target_id } => {
+                write!(
+                    f,
+                    "{} row[{}] : FK '{}' pointe vers {}[{}] qui n'existe pas",
+                    entity, row, fk, target, target_id
+                )
+            }
+            ValidationError::EquationViolated { equation, row, lhs, rhs } => {
+                write!(
+                    f,
+                    "Équation de chemins violée pour row[{}] : {} ≠ {} ({:?} vs {:?})",
+                    row, equation.lhs, equation.rhs, lhs, rhs
+                )
+            }
+            ValidationError::ObservationViolated { equation, row, lhs, rhs } => {
+                let show = |v: &Option<Value>| v.as_ref().map_or("absent".to_string(), |v| v.to_string());
+                write!(
+                    f,
+                    "Équation d'observation violée pour row[{}] : {} ≠ {} ({} vs {})",
+                    row, equation.lhs, equation.rhs, show(lhs), show(rhs)
+                )
+            }
+            ValidationError::ObservationTypeMismatch { equation, lhs, rhs } => {
+                let show = |t: &Option<BaseType>| t.as_ref().map_or("un nœud".to_string(), |t| t.to_string());
+                write!(
+                    f,
+                    "Équation {} : les deux côtés doivent finir par des attributs de même type ({} vs {})",
+                    equation, show(lhs), show(rhs)
+                )
+            }
+            ValidationError::CompositeKeyAttributeMissing { fk, target, attribute } => {
+                write!(f, "FK composite '{}' : l'attribut '{}' n'existe pas sur '{}'", fk, attribute, target)
+            }
+            ValidationError::CompositeKeyIncomplete { entity, row, fk, attribute } => {
+                write!(
+                    f,
+                    "{} row[{}] : FK composite '{}' sans valeur pour l'attribut de clé '{}'",
+                    entity, row, fk, attribute
+                )
+            }
+            ValidationError::UndeclaredType { attribute, ty } => {
+                write!(f, "Attribut '{}' : le type {} n'est pas dans le typeside", attribute, ty)
+            }
+            ValidationError::TypeMismatch { entity, row, attribute, expected, found } => {
+                write!(
+                    f,
+                    "{} row[{}] : l'attribut '{}' attend {} mais contient {}",
+                    entity, row, attribute, expected, found
+                )
+            }
+        }
     }
 }
 
@@ -31,38 +187,60 @@ impl std::fmt::Display for ValidationError {
 ///
 /// Conditions :
 /// - Toute arête FK référence des nœuds qui existent
+/// - Les colonnes d'une FK composite référencent des attributs de la cible
 /// - Tout attribut référence un nœud qui existe
 /// - Les équations de chemins sont sur des nœuds/arêtes qui existent
+/// - Une équation d'observation compare deux attributs de même type
 pub fn validate_schema(schema: &Schema) -> Result<(), Vec<ValidationError>> {
     let mut errors = Vec::new();
 
     for (edge_name, edge) in &schema.edges {
         match edge {
-            Edge::ForeignKey { source, target, .. } => {
+            Edge::ForeignKey { source, target, columns, .. } => {
+                // Une FK composite référence des attributs de la cible
+                for (_, attr) in columns {
+                    let is_target_attr = matches!(
+                        schema.edges.get(attr),
+                        Some(Edge::Attribute { source: attr_src, .. }) if attr_src == target
+                    );
+                    if !is_target_attr {
+                        errors.push(ValidationError::CompositeKeyAttributeMissing {
+                            fk: edge_name.clone(),
+                            target: target.clone(),
+                            attribute: attr.clone(),
+                        });
+                    }
+                }
                 if !schema.nodes.contains_key(source) {
-                    errors.push(ValidationError {
-                        message: format!(
-                            "FK '{}' : le nœud source '{}' n'existe pas",
-                            edge_name, source
-                        ),
+                    errors.push(ValidationError::EdgeNodeMissing {
+                        kind: "FK",
+                        edge: edge_name.clone(),
+                        role: "source",
+                        node: source.clone(),
                     });
                 }
                 if !schema.nodes.contains_key(target) {
-                    errors.push(ValidationError {
-                        message: format!(
-                            "FK '{}' : le nœud cible '{}' n'existe pas",
-                            edge_name, target
-                        ),
+                    errors.push(ValidationError::EdgeNodeMissing {
+                        kind: "FK",
+                        edge: edge_name.clone(),
+                        role: "cible",
+                        node: target.clone(),
                     });
                 }
             }
-            Edge::Attribute { source, .. } => {
+            Edge::Attribute { source, target, .. } => {
                 if !schema.nodes.contains_key(source) {
-                    errors.push(ValidationError {
-                        message: format!(
-                            "Attribut '{}' : le nœud source '{}' n'existe pas",
-                            edge_name, source
-                        ),
+                    errors.push(ValidationError::EdgeNodeMissing {
+                        kind: "Attribut",
+                        edge: edge_name.clone(),
+                        role: "source",
+                        node: source.clone(),
+                    });
+                }
+                if !schema.typeside.declares(target) {
+                    errors.push(ValidationError::UndeclaredType {
+                        attribute: edge_name.clone(),
+                        ty: target.clone(),
                     });
                 }
             }
@@ -73,43 +251,49 @@ pub fn validate_schema(schema: &Schema) -> Result<(), Vec<ValidationError>> {
     for (i, eq) in schema.path_equations.iter().enumerate() {
         // Vérifier que le nœud de départ de chaque côté existe
         if !schema.nodes.contains_key(&eq.lhs.start) {
-            errors.push(ValidationError {
-                message: format!(
-                    "Équation {} : le nœud de départ '{}' (lhs) n'existe pas",
-                    i, eq.lhs.start
-                ),
+            errors.push(ValidationError::EquationNodeMissing {
+                equation: i,
+                side: "lhs",
+                node: eq.lhs.start.clone(),
             });
         }
         if !schema.nodes.contains_key(&eq.rhs.start) {
-            errors.push(ValidationError {
-                message: format!(
-                    "Équation {} : le nœud de départ '{}' (rhs) n'existe pas",
-                    i, eq.rhs.start
-                ),
+            errors.push(ValidationError::EquationNodeMissing {
+                equation: i,
+                side: "rhs",
+                node: eq.rhs.start.clone(),
             });
         }
 
         // Vérifier que chaque arête du chemin existe
         for edge_name in &eq.lhs.edges {
             if !schema.edges.contains_key(edge_name) {
-                errors.push(ValidationError {
-                    message: format!(
-                        "Équation {} : l'arête '{}' (lhs) n'existe pas",
-                        i, edge_name
-                    ),
+                errors.push(ValidationError::EquationEdgeMissing {
+                    equation: i,
+                    side: "lhs",
+                    edge: edge_name.clone(),
                 });
             }
         }
         for edge_name in &eq.rhs.edges {
             if !schema.edges.contains_key(edge_name) {
-                errors.push(ValidationError {
-                    message: format!(
-                        "Équation {} : l'arête '{}' (rhs) n'existe pas",
-                        i, edge_name
-                    ),
+                errors.push(ValidationError::EquationEdgeMissing {
+                    equation: i,
+                    side: "rhs",
+                    edge: edge_name.clone(),
                 });
             }
         }
+
+        // Une ligne n'est jamais égale à une valeur
+        let observed = |path| match schema.observed_attribute(path) {
+            Some(Edge::Attribute { target, .. }) => Some(target.clone()),
+            _ => None,
+        };
+        let (lhs, rhs) = (observed(&eq.lhs), observed(&eq.rhs));
+        if lhs != rhs {
+            errors.push(ValidationError::ObservationTypeMismatch { equation: i, lhs, rhs });
+        }
     }
 
     if errors.is_empty() {
@@ -124,15 +308,18 @@ pub fn validate_schema(schema: &Schema) -> Result<(), Vec<ValidationError>> {
 /// Conditions :
 /// - Chaque FK est une fonction totale (chaque ligne a une FK bien définie)
 /// - Les FK pointent vers des lignes qui existent
+/// - La cible d'une FK composite a une valeur pour chaque attribut de la clé
+/// - Les valeurs d'attributs ont le type déclaré (Int ↔ Float et NULL tolérés)
 /// - Les équations de chemins sont satisfaites pour toutes les lignes
+///   (mêmes lignes atteintes, ou mêmes valeurs pour une équation d'observation)
 pub fn validate_instance(instance: &Instance, schema: &Schema) -> Result<(), Vec<ValidationError>> {
     let mut errors = Vec::new();
 
     for (entity_name, entity_data) in &instance.data {
         // Vérifier que l'entité existe dans le schéma
         if !schema.nodes.contains_key(entity_name) {
-            errors.push(ValidationError {
-                message: format!("L'entité '{}' n'existe pas dans le schéma", entity_name),
+            errors.push(ValidationError::MissingNode {
+                node: entity_name.clone(),
             });
             continue;
         }
@@ -148,28 +335,58 @@ pub fn validate_instance(instance: &Instance, schema: &Schema) -> Result<(), Vec
             })
             .collect();
 
+        // Vérifier le type des attributs
+        for row_id in entity_data.row_ids() {
+            for (attr_name, value) in entity_data.row_attrs(row_id) {
+                if let Some(Edge::Attribute { source, target, .. }) = schema.edges.get(attr_name) {
+                    if source == entity_name && !target.accepts(value) {
+                        errors.push(ValidationError::TypeMismatch {
+                            entity: entity_name.clone(),
+                            row: row_id,
+                            attribute: attr_name.clone(),
+                            expected: target.clone(),
+                            found: value.get_type(),
+                        });
+                    }
+                }
+            }
+        }
+
         for row_id in entity_data.row_ids() {
             for fk in &fks {
-                if let Edge::ForeignKey { name, target, .. } = fk {
+                if let Edge::ForeignKey { name, target, columns, .. } = fk {
                     match entity_data.get_fk(row_id, name) {
                         None => {
-                            errors.push(ValidationError {
-                                message: format!(
-                                    "{} row[{}] : FK '{}' manquante",
-                                    entity_name, row_id, name
-                                ),
+                            errors.push(ValidationError::MissingFk {
+                                entity: entity_name.clone(),
+                                row: row_id,
+                                fk: name.clone(),
                             });
                         }
                         Some(target_row_id) => {
                             // Vérifier que la ligne cible existe
                             if let Some(target_data) = instance.data.get(target) {
-                                if !target_data.attribute_values.contains_key(&target_row_id) {
-                                    errors.push(ValidationError {
-                                        message: format!(
-                                            "{} row[{}] : FK '{}' pointe vers {}[{}] qui n'existe pas",
-                                            entity_name, row_id, name, target, target_row_id
-                                        ),
+                                if !target_data.contains(target_row_id) {
+                                    errors.push(ValidationError::DanglingFk {
+                                        entity: entity_name.clone(),
+                                        row: row_id,
+                                        fk: name.clone(),
+                                        target: target.clone(),
+                                        target_id: target_row_id,
                                     });
+                                } else {
+                                    // FK composite : la clé métier de la cible doit être complète
+                                    for (_, attr) in columns {
+                                        let value = target_data.get_attr(target_row_id, attr);
+                                        if value.is_none_or(|v| *v == Value::Null) {
+                                            errors.push(ValidationError::CompositeKeyIncomplete {
+                                                entity: entity_name.clone(),
+                                                row: row_id,
+                                                fk: name.clone(),
+                                                attribute: attr.clone(),
+                                            });
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -181,6 +398,10 @@ pub fn validate_instance(instance: &Instance, schema: &Schema) -> Result<(), Vec
 
     // Vérifier les équations de chemins
     for eq in &schema.path_equations {
+        if eq.is_observation(schema) {
+            validate_observation(instance, schema, eq, &mut errors);
+            continue;
+        }
         if let Some(entity_data) = instance.data.get(&eq.lhs.start) {
             for row_id in entity_data.row_ids() {
                 let lhs_result = instance.follow_path(
@@ -193,11 +414,11 @@ pub fn validate_instance(instance: &Instance, schema: &Schema) -> Result<(), Vec
                 );
 
                 if lhs_result != rhs_result {
-                    errors.push(ValidationError {
-                        message: format!(
-                            "Équation de chemins violée pour row[{}] : {} ≠ {} ({:?} vs {:?})",
-                            row_id, eq.lhs, eq.rhs, lhs_result, rhs_result
-                        ),
+                    errors.push(ValidationError::EquationViolated {
+                        equation: eq.clone(),
+                        row: row_id,
+                        lhs: lhs_result,
+                        rhs: rhs_result,
                     });
                 }
             }
@@ -211,13 +432,39 @@ pub fn validate_instance(instance: &Instance, schema: &Schema) -> Result<(), Vec
     }
 }
 
+/// Vérifie une équation d'observation ligne par ligne : chaque côté suit
+/// ses FK puis lit l'attribut final, les deux valeurs doivent être égales.
+fn validate_observation(instance: &Instance, schema: &Schema, eq: &PathEquation, errors: &mut Vec<ValidationError>) {
+    let Some(entity_data) = instance.data.get(&eq.lhs.start) else { return };
+    let observe = |path: &Path, row_id: RowId| -> Option<Value> {
+        let (attr, fk_path) = path.edges.split_last()?;
+        let row = instance.follow_path(&path.start, row_id, fk_path, schema)?;
+        let Some(Edge::Attribute { source, .. }) = schema.edges.get(attr) else { return None };
+        instance.data.get(source)?.get_attr(row, attr).cloned()
+    };
+    let mut row_ids = entity_data.row_ids();
+    row_ids.sort();
+    for row_id in row_ids {
+        let lhs = observe(&eq.lhs, row_id);
+        let rhs = observe(&eq.rhs, row_id);
+        if lhs != rhs {
+            errors.push(ValidationError::ObservationViolated {
+                equation: eq.clone(),
+                row: row_id,
+                lhs,
+                rhs,
+            });
+        }
+    }
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::typeside::{BaseType, Value};
+    use crate::core::typeside::{BaseType, Typeside};
     use crate::core::schema::Schema;
     use std::collections::HashMap;
 
@@ -228,6 +475,23 @@ mod tests {
         assert!(validate_schema(&s).is_ok());
     }
 
+    #[test]
+    fn test_validate_schema_undeclared_type() {
+        let weird = BaseType::Custom("Weird".into());
+        let mut s = Schema::new("Test");
+        s.add_node("A")
+         .with_typeside(Typeside::empty())
+         .add_attribute("w", "A", weird.clone());
+
+        let errors = validate_schema(&s).unwrap_err();
+        assert!(matches!(&errors[..], [ValidationError::UndeclaredType { attribute, ty }]
+            if attribute == "w" && *ty == weird));
+
+        // Déclaré dans le typeside : accepté
+        s.typeside.add_type(weird);
+        assert!(validate_schema(&s).is_ok());
+    }
+
     #[test]
     fn test_validate_instance_ok() {
         let mut s = Schema::new("Test");
@@ -261,4 +525,134 @@ mod tests {
         let result = validate_instance(&inst, &s);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_validate_instance_type_mismatch() {
+        let mut s = Schema::new("Test");
+        s.add_node("Employee")
+         .add_attribute("salary", "Employee", BaseType::Integer)
+         .add_attribute("bonus", "Employee", BaseType::Float);
+
+        let mut inst = Instance::new("TestData", &s);
+        // Int dans une colonne Float et NULL : acceptés
+        inst.insert("Employee",
+            HashMap::from([
+                ("salary".into(), Value::Null),
+                ("bonus".into(), Value::Integer(100)),
+            ]),
+            HashMap::new(),
+        );
+        assert!(validate_instance(&inst, &s).is_ok());
+
+        inst.insert("Employee",
+            HashMap::from([("salary".into(), Value::String("beaucoup".into()))]),
+            HashMap::new(),
+        );
+        let errors = validate_instance(&inst, &s).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::TypeMismatch { attribute, expected: BaseType::Integer, found: BaseType::String, .. }
+                if attribute == "salary"
+        ));
+    }
+
+    #[test]
+    fn test_validate_observation_equation() {
+        let mut s = Schema::new("Company");
+        s.add_node("Employee")
+         .add_node("Department")
+         .add_fk("department", "Employee", "Department")
+         .add_attribute("dept_name", "Department", BaseType::String)
+         .add_attribute("cached_dept_name", "Employee", BaseType::String)
+         .add_path_equation(
+             Path::new("Employee", vec!["department", "dept_name"]),
+             Path::new("Employee", vec!["cached_dept_name"]),
+         );
+        assert!(s.path_equations[0].is_observation(&s));
+        assert!(validate_schema(&s).is_ok());
+
+        let mut inst = Instance::new("TestData", &s);
+        let eng = inst.insert("Department",
+            HashMap::from([("dept_name".into(), Value::String("Engineering".into()))]),
+            HashMap::new(),
+        );
+        let alice = inst.insert("Employee",
+            HashMap::from([("cached_dept_name".into(), Value::String("Engineering".into()))]),
+            HashMap::from([("department".into(), eng)]),
+        );
+        assert!(validate_instance(&inst, &s).is_ok());
+
+        // La copie n'a pas suivi le renommage du département
+        inst.set_attr("Department", eng, "dept_name", Value::String("R&D".into()), &s).unwrap();
+        let errors = validate_instance(&inst, &s).unwrap_err();
+        assert!(matches!(&errors[..], [ValidationError::ObservationViolated { row, lhs: Some(Value::String(lhs)), .. }]
+            if *row == alice && lhs == "R&D"));
+
+        // Une ligne n'est pas comparable à une valeur
+        s.add_path_equation(
+            Path::new("Employee", vec!["department"]),
+            Path::new("Employee", vec!["cached_dept_name"]),
+        );
+        let errors = validate_schema(&s).unwrap_err();
+        assert!(matches!(&errors[..], [ValidationError::ObservationTypeMismatch { equation: 1, lhs: None, .. }]));
+    }
+
+    #[test]
+    fn test_composite_fk_validation() {
+        let mut s = Schema::new("Test");
+        s.add_node("Site").add_node("Office")
+         .add_attribute("code", "Site", BaseType::Integer)
+         .add_composite_fk("located_in", "Office", "Site", vec![("site_code", "code"), ("site_zone", "zone")]);
+
+        let errors = validate_schema(&s).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ValidationError::CompositeKeyAttributeMissing { attribute, .. }] if attribute == "zone"
+        ));
+
+        // Clé métier incomplète sur la ligne cible
+        let mut s = Schema::new("Test");
+        s.add_node("Site").add_node("Office")
+         .add_attribute("code", "Site", BaseType::Integer)
+         .add_composite_fk("located_in", "Office", "Site", vec![("site_code", "code")]);
+        let mut inst = Instance::new("TestData", &s);
+        let site = inst.insert("Site", HashMap::new(), HashMap::new());
+        inst.insert("Office", HashMap::new(), HashMap::from([("located_in".into(), site)]));
+
+        let errors = validate_instance(&inst, &s).unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ValidationError::CompositeKeyIncomplete { fk, attribute, .. }] if fk == "located_in" && attribute == "code"
+        ));
+    }
+
+    #[test]
+    fn test_broken_fk_is_structured() {
+        let mut s = Schema::new("Test");
+        s.add_node("A").add_node("B").add_fk("f", "A", "B");
+
+        let mut inst = Instance::new("TestData", &s);
+        let a = inst.insert("A",
+            HashMap::new(),
+            HashMap::from([("f".into(), 999)]),
+        );
+
+        let errors = validate_instance(&inst, &s).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            ValidationError::DanglingFk { entity, row, fk, target, target_id } => {
+                assert_eq!(entity, "A");
+                assert_eq!(*row, a);
+                assert_eq!(fk, "f");
+                assert_eq!(target, "B");
+                assert_eq!(*target_id, 999);
+            }
+            other => panic!("Erreur inattendue : {}", other),
+        }
+        assert_eq!(
+            errors[0].to_string(),
+            "Validation error: A row[1] : FK 'f' pointe vers B[999] qui n'existe pas"
+        );
+    }
 }
@@ -0,0 +1,90 @@
+// =============================================================================
+// ERREURS — Le type d'erreur commun aux API publiques de Catrust
+// =============================================================================
+//
+// Les migrations (Σ, Δ), les mappings, leur composition et l'évaluateur
+// renvoient `CatrustError` : l'appelant peut réagir à la variante plutôt
+// qu'au texte du message. L'affichage garde les messages en français.
+//
+// =============================================================================
+
+use crate::core::eval::PathError;
+use crate::core::instance::RowId;
+use crate::core::typeside::{BaseType, Value};
+
+/// Erreur des API publiques de Catrust
+#[derive(Debug, Clone, PartialEq)]
+pub enum CatrustError {
+    /// Nœud sans image par le mapping `mapping`
+    UnmappedNode { node: String, mapping: String },
+    /// Arête sans image par le mapping `mapping`
+    UnmappedEdge { edge: String, mapping: String },
+    /// G ∘ F impossible : le schéma cible de F n'est pas la source de G
+    IncompatibleComposition { f_target: String, g_source: String },
+    /// FK non définie là où un chemin doit la suivre (JOIN interne)
+    MissingFk { fk: String, entity: String, row: RowId },
+    /// Chemin impossible à résoudre (variable, arête inconnue...)
+    PathResolution(PathError),
+    /// Le block `entity` parcourt plus de `limit` tuples (`eval_query_bounded`)
+    TupleLimit { entity: String, limit: usize },
+    /// Valeur d'un type autre que celui attendu par `context`
+    TypeMismatch { context: String, value: Value, expected: BaseType },
+    /// Toute autre erreur, décrite par son message
+    Other(String),
+}
+
+impl std::fmt::Display for CatrustError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatrustError::UnmappedNode { node, mapping } => {
+                write!(f, "Nœud '{}' non mappé par '{}'", node, mapping)
+            }
+            CatrustError::UnmappedEdge { edge, mapping } => {
+                write!(f, "Arête '{}' non mappée par '{}'", edge, mapping)
+            }
+            CatrustError::IncompatibleComposition { f_target, g_source } => {
+                write!(f, "Incompatible : F cible '{}' ≠ G source '{}'", f_target, g_source)
+            }
+            CatrustError::MissingFk { fk, entity, row } => {
+                write!(f, "FK '{}' non définie pour {}[{}]", fk, entity, row)
+            }
+            CatrustError::PathResolution(e) => write!(f, "{}", e),
+            CatrustError::TupleLimit { entity, limit } => {
+                write!(f, "Block '{}' : plus de {} tuples à parcourir", entity, limit)
+            }
+            CatrustError::TypeMismatch { context, value, expected } => {
+                write!(f, "{} : la valeur {} n'est pas du type {} attendu", context, value, expected)
+            }
+            CatrustError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CatrustError {}
+
+impl From<PathError> for CatrustError {
+    fn from(e: PathError) -> Self {
+        match e {
+            PathError::UnsetFk { fk, entity, row } => CatrustError::MissingFk { fk, entity, row },
+            e => CatrustError::PathResolution(e),
+        }
+    }
+}
+
+impl From<CatrustError> for String {
+    fn from(e: CatrustError) -> Self {
+        e.to_string()
+    }
+}
+
+impl From<String> for CatrustError {
+    fn from(message: String) -> Self {
+        CatrustError::Other(message)
+    }
+}
+
+impl From<&str> for CatrustError {
+    fn from(message: &str) -> Self {
+        CatrustError::Other(message.to_string())
+    }
+}
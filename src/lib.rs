@@ -9,6 +9,8 @@
 // Architecture :
 //   core/     → Le cœur catégorique pur (aucune dépendance externe)
 //   backend/  → Traduction vers les DB réelles (SQL, Cypher...)
+//   error     → CatrustError, l'erreur des API publiques
+//   repl      → Session interactive (`catrust --repl`, feature `cli`)
 //
 // Concepts fondamentaux :
 //   Schema   = une catégorie (nœuds + arêtes + équations de chemins)
@@ -18,5 +20,16 @@
 //
 // =============================================================================
 
+// Le code de #[derive(CatrustEntity)] vise `::catrust`, y compris ici
+extern crate self as catrust;
+
 pub mod core;
 pub mod backend;
+pub mod error;
+#[cfg(feature = "cli")]
+pub mod repl;
+
+pub use error::CatrustError;
+
+#[cfg(feature = "derive")]
+pub use catrust_derive::CatrustEntity;
@@ -2,6 +2,8 @@
 // CATRUST — Point d'entrée : démonstration du moteur CQL
 // =============================================================================
 //
+// `catrust --repl` (feature `cli`) ouvre une session interactive à la place.
+//
 // Ce main.rs montre un exemple complet :
 //   1. Définir deux schémas (ancien et nouveau format)
 //   2. Créer un mapping entre eux (foncteur)
@@ -23,6 +25,15 @@ use catrust::backend::sql::{SqlBackend, PostgresDialect, SnowflakeDialect, Trino
 use catrust::backend::graph::Neo4jBackend;
 
 fn main() {
+    #[cfg(feature = "cli")]
+    if std::env::args().any(|arg| arg == "--repl") {
+        let stdin = std::io::stdin();
+        if let Err(e) = catrust::repl::Repl::new().run(stdin.lock(), std::io::stdout()) {
+            eprintln!("Erreur d'entrée/sortie : {}", e);
+        }
+        return;
+    }
+
     println!("╔══════════════════════════════════════════════════╗");
     println!("║      CATRUST — Categorical Query Language        ║");
     println!("║      Moteur de migrations catégoriques           ║");
@@ -156,7 +167,7 @@ fn main() {
     // ═══════════════════════════════════════════════════════════
     println!("═══ ÉTAPE 5 : Migration Σ (pousser les données) ═══\n");
 
-    let instance_new = migrate::sigma(&mapping, &schema_old, &schema_new, &instance_old);
+    let instance_new = migrate::sigma(&mapping, &schema_old, &schema_new, &instance_old).unwrap();
     println!("{}", instance_new.display(&schema_new));
 
     // ═══════════════════════════════════════════════════════════
@@ -300,17 +311,25 @@ fn main() {
                 from_var: "e".into(),
                 path: vec![],
                 attribute: "emp_name".into(),
+                expr: None,
             }),
             ("salary".into(), AttributeBinding {
                 from_var: "e".into(),
                 path: vec![],
                 attribute: "salary".into(),
+                expr: None,
             }),
         ]),
         fk_bindings: HashMap::new(),
-    });
-
-    let plans = planner.plan_query(&query);
+        distinct: false,
+        join_mode: JoinMode::Inner,
+        having: vec![],
+        count_only: false,
+        order_by: Vec::new(),
+        select_all: Vec::new(),
+    }, &schema_optim).unwrap();
+
+    let plans = planner.plan_query(&query).unwrap();
     for plan in &plans {
         println!("{}", plan);
     }
@@ -408,17 +427,23 @@ fn main() {
         ],
         attribute_bindings: HashMap::from([
             ("name".into(), AttributeBinding {
-                from_var: "e".into(), path: vec![], attribute: "emp_name".into(),
+                from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
             }),
             ("salary".into(), AttributeBinding {
-                from_var: "e".into(), path: vec![], attribute: "salary".into(),
+                from_var: "e".into(), path: vec![], attribute: "salary".into(), expr: None,
             }),
             ("dept".into(), AttributeBinding {
-                from_var: "e".into(), path: vec!["works_in".into()], attribute: "dept_name".into(),
+                from_var: "e".into(), path: vec!["works_in".into()], attribute: "dept_name".into(), expr: None,
             }),
         ]),
         fk_bindings: HashMap::new(),
-    });
+        distinct: false,
+        join_mode: JoinMode::Inner,
+        having: vec![],
+        count_only: false,
+        order_by: Vec::new(),
+        select_all: Vec::new(),
+    }, &schema_eval).unwrap();
 
     println!("Requête : SELECT name, salary, dept FROM Employee");
     println!("          WHERE works_in.dept_name = 'Engineering' AND salary > 80000\n");
@@ -437,17 +462,23 @@ fn main() {
         where_clauses: vec![],
         attribute_bindings: HashMap::from([
             ("salary".into(), AttributeBinding {
-                from_var: "e".into(), path: vec![], attribute: "salary".into(),
+                from_var: "e".into(), path: vec![], attribute: "salary".into(), expr: None,
             }),
             ("name".into(), AttributeBinding {
-                from_var: "e".into(), path: vec![], attribute: "emp_name".into(),
+                from_var: "e".into(), path: vec![], attribute: "emp_name".into(), expr: None,
             }),
             ("dept".into(), AttributeBinding {
-                from_var: "e".into(), path: vec!["works_in".into()], attribute: "dept_name".into(),
+                from_var: "e".into(), path: vec!["works_in".into()], attribute: "dept_name".into(), expr: None,
             }),
         ]),
         fk_bindings: HashMap::new(),
-    });
+        distinct: false,
+        join_mode: JoinMode::Inner,
+        having: vec![],
+        count_only: false,
+        order_by: Vec::new(),
+        select_all: Vec::new(),
+    }, &schema_eval).unwrap();
 
     let all = eval::eval_query(&q_all, &inst_eval, &schema_eval).unwrap();
     println!("  COUNT(*) = {}", eval::count(&all, "R"));
@@ -0,0 +1,650 @@
+// =============================================================================
+// REPL — Exploration interactive d'un schéma et de ses données (feature `cli`)
+// =============================================================================
+//
+// Une ligne = une commande :
+//
+//   :node Employee                      déclare un nœud
+//   :fk works_in Employee Department    déclare une FK
+//   :attr salary Employee Integer       déclare un attribut
+//   :eq Employee.a.b = Employee.c       déclare une équation de chemins
+//   :source company.repl                exécute les commandes d'un fichier
+//   :source company.cql                 charge un `schema X = literal { ... }`
+//   :schema                             affiche le schéma
+//   :insert Employee emp_name='Alice' works_in=1
+//   :load Employee employees.csv        charge un CSV (en-tête = arêtes)
+//   :query from e:Employee where e.salary > 50000 select name=e.emp_name
+//   :optimize                           raccourcis offerts par les équations
+//   :quit
+//
+// =============================================================================
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use crate::core::schema::{Schema, Edge, Path};
+use crate::core::instance::{Instance, RowId};
+use crate::core::typeside::{BaseType, Value};
+use crate::core::query::{CqlQuery, CompOp, Side};
+use crate::core::optimize::PathOptimizer;
+use crate::core::eval::eval_query;
+
+/// Sections d'un `schema X = literal { ... }` lues par `:source x.cql`
+const CQL_SECTIONS: [&str; 4] = ["entities", "foreign_keys", "attributes", "path_equations"];
+
+/// Session interactive : un schéma et une instance construits commande
+/// par commande.
+#[derive(Debug, Clone)]
+pub struct Repl {
+    pub schema: Schema,
+    pub instance: Instance,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    /// Session vide (schéma "Repl" sans nœud)
+    pub fn new() -> Self {
+        let schema = Schema::new("Repl");
+        let instance = Instance::new("ReplData", &schema);
+        Repl { schema, instance }
+    }
+
+    /// Lit les commandes de `input` jusqu'à `:quit` ou la fin du flux, et
+    /// écrit les réponses (et les erreurs) dans `output`. Une erreur
+    /// n'interrompt pas la session.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> std::io::Result<()> {
+        write!(output, "catrust> ")?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            if matches!(line.trim(), ":quit" | ":q") {
+                break;
+            }
+            match self.execute(&line) {
+                Ok(reply) if reply.is_empty() => {}
+                Ok(reply) => writeln!(output, "{}", reply)?,
+                Err(e) => writeln!(output, "Erreur : {}", e)?,
+            }
+            write!(output, "catrust> ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Exécute une commande ; retourne le texte à afficher.
+    /// Les lignes vides et les commentaires (`#`, `//`) sont ignorés.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            return Ok(String::new());
+        }
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let args: Vec<&str> = rest.split_whitespace().collect();
+
+        match command {
+            ":node" => match args.as_slice() {
+                [name] => {
+                    self.schema.add_node(name);
+                    self.instance.data.entry(name.to_string()).or_default();
+                    Ok(String::new())
+                }
+                _ => Err("usage : :node <Nom>".into()),
+            },
+            ":fk" => match args.as_slice() {
+                [name, source, target] => {
+                    self.require_node(source)?;
+                    self.require_node(target)?;
+                    self.schema.add_fk(name, source, target);
+                    Ok(String::new())
+                }
+                _ => Err("usage : :fk <nom> <Source> <Cible>".into()),
+            },
+            ":attr" => match args.as_slice() {
+                [name, source, ty] => {
+                    self.require_node(source)?;
+                    let ty = parse_type(ty)?;
+                    self.schema.add_attribute(name, source, ty);
+                    Ok(String::new())
+                }
+                _ => Err("usage : :attr <nom> <Source> <Type>".into()),
+            },
+            ":eq" => {
+                let (lhs, rhs) = rest.split_once('=')
+                    .ok_or("usage : :eq <Nœud>.<chemin> = <Nœud>.<chemin>")?;
+                let lhs = self.parse_path(lhs.trim())?;
+                let rhs = self.parse_path(rhs.trim())?;
+                self.schema.add_path_equation(lhs, rhs);
+                Ok(String::new())
+            }
+            ":source" => match args.as_slice() {
+                [file] => {
+                    let text = std::fs::read_to_string(file)
+                        .map_err(|e| format!("Lecture de '{}' impossible : {}", file, e))?;
+                    if file.ends_with(".cql") {
+                        return self.load_cql(&text).map_err(|e| format!("{}:{}", file, e));
+                    }
+                    let mut replies = Vec::new();
+                    for (i, line) in text.lines().enumerate() {
+                        let reply = self.execute(line)
+                            .map_err(|e| format!("{}:{} : {}", file, i + 1, e))?;
+                        if !reply.is_empty() {
+                            replies.push(reply);
+                        }
+                    }
+                    Ok(replies.join("\n"))
+                }
+                _ => Err("usage : :source <fichier>".into()),
+            },
+            ":schema" => Ok(self.schema.to_string()),
+            ":insert" => match tokenize(rest)?.split_first() {
+                Some((entity, assignments)) => {
+                    self.require_node(entity)?;
+                    let mut cells = Vec::new();
+                    for assignment in assignments {
+                        let (name, raw) = assignment.split_once('=')
+                            .ok_or_else(|| format!("'{}' : attendu <arête>=<valeur>", assignment))?;
+                        cells.push((name.to_string(), unquote(raw).to_string()));
+                    }
+                    let row = self.insert_row(entity, cells)?;
+                    Ok(format!("{}[{}]", entity, row))
+                }
+                None => Err("usage : :insert <Entité> <arête>=<valeur>...".into()),
+            },
+            ":load" => {
+                let (entity, file) = match args.as_slice() {
+                    [entity, file] => (entity.to_string(), *file),
+                    // Entité déduite du nom du fichier : Employee.csv
+                    [file] => match std::path::Path::new(file).file_stem().and_then(|s| s.to_str()) {
+                        Some(stem) => (stem.to_string(), *file),
+                        None => return Err(format!("Nom de fichier invalide : '{}'", file)),
+                    },
+                    _ => return Err("usage : :load [<Entité>] <fichier.csv>".into()),
+                };
+                let text = std::fs::read_to_string(file)
+                    .map_err(|e| format!("Lecture de '{}' impossible : {}", file, e))?;
+                let count = self.load_csv(&entity, &text)?;
+                Ok(format!("{} lignes chargées dans {}", count, entity))
+            }
+            ":query" => self.query(rest),
+            ":optimize" => {
+                let mut shortcuts: Vec<String> = PathOptimizer::from_schema(&self.schema)
+                    .analyze_schema(&self.schema)
+                    .iter()
+                    .map(|r| r.to_string())
+                    .collect();
+                shortcuts.sort();
+                if shortcuts.is_empty() {
+                    Ok("Aucun raccourci : le schéma n'a pas d'équation exploitable".into())
+                } else {
+                    Ok(shortcuts.join("\n"))
+                }
+            }
+            ":help" => Ok(
+                ":node :fk :attr :eq :source :schema :insert :load :query :optimize :quit".into()
+            ),
+            _ => Err(format!("Commande inconnue '{}' (:help)", command)),
+        }
+    }
+
+    /// Erreur si `node` n'est pas un nœud du schéma
+    fn require_node(&self, node: &str) -> Result<(), String> {
+        if self.schema.nodes.contains_key(node) {
+            Ok(())
+        } else {
+            Err(format!("Nœud '{}' inconnu", node))
+        }
+    }
+
+    /// `Employee.works_in.manager` → chemin depuis Employee ;
+    /// `id_Employee` → chemin vide
+    fn parse_path(&self, text: &str) -> Result<Path, String> {
+        if let Some(node) = text.strip_prefix("id_").filter(|n| self.schema.nodes.contains_key(*n)) {
+            return Ok(Path::new(node, Vec::new()));
+        }
+        let mut parts = text.split('.');
+        let start = parts.next().unwrap_or_default();
+        self.require_node(start)?;
+        Ok(Path::new(start, parts.collect()))
+    }
+
+    /// Insère une ligne dont chaque cellule est lue selon l'arête qu'elle
+    /// remplit (attribut : `Value::parse` ; FK : RowId cible)
+    fn insert_row(&mut self, entity: &str, cells: Vec<(String, String)>) -> Result<RowId, String> {
+        let mut attrs = HashMap::new();
+        let mut fks = HashMap::new();
+        for (name, raw) in cells {
+            match self.schema.edges.get(&name) {
+                Some(Edge::Attribute { source, target, .. }) if source == entity => {
+                    attrs.insert(name, Value::parse(&raw, target)?);
+                }
+                Some(Edge::ForeignKey { source, .. }) if source == entity => {
+                    if raw.is_empty() {
+                        continue;
+                    }
+                    let row = raw.trim().parse::<RowId>()
+                        .map_err(|_| format!("FK '{}' : '{}' n'est pas un RowId", name, raw))?;
+                    fks.insert(name, row);
+                }
+                _ => return Err(format!("'{}' n'est pas une arête de '{}'", name, entity)),
+            }
+        }
+        Ok(self.instance.insert(entity, attrs, fks))
+    }
+
+    /// Charge un schéma CQL écrit comme l'affiche `:schema` :
+    ///
+    /// ```text
+    /// schema Company = literal {
+    ///   entities
+    ///     Employee Department
+    ///   foreign_keys
+    ///     works_in : Employee -> Department
+    ///   attributes
+    ///     salary : Employee -> Integer
+    ///   path_equations
+    ///     Employee.works_in.manager = Employee.boss
+    /// }
+    /// ```
+    ///
+    /// Les nœuds, arêtes et équations s'ajoutent au schéma de la session.
+    /// Les typesides, les FK composites et les valeurs par défaut ne sont
+    /// pas pris en charge. Une erreur est préfixée du numéro de ligne.
+    fn load_cql(&mut self, text: &str) -> Result<String, String> {
+        let mut section = "";
+        let mut closed = false;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split("//").next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            self.cql_line(line, &mut section, &mut closed)
+                .map_err(|e| format!("{} : {}", i + 1, e))?;
+        }
+        if !closed {
+            return Err("fin : '}' attendu".into());
+        }
+        Ok(String::new())
+    }
+
+    /// Une ligne non vide d'un schéma CQL ; `section` est la section en
+    /// cours (`entities`, `foreign_keys`...), vide avant l'en-tête.
+    fn cql_line(&mut self, line: &str, section: &mut &'static str, closed: &mut bool) -> Result<(), String> {
+        if *closed {
+            return Err(format!("'{}' après la fin du schéma", line));
+        }
+        if section.is_empty() {
+            return match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["schema", _, "=", "literal", "{"] => {
+                    *section = "literal";
+                    Ok(())
+                }
+                _ => Err("attendu : schema <Nom> = literal {".into()),
+            };
+        }
+        if line == "}" {
+            *closed = true;
+            return Ok(());
+        }
+        let (first, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let items = match CQL_SECTIONS.iter().find(|s| **s == first) {
+            Some(name) => {
+                *section = name;
+                rest.trim()
+            }
+            None => line,
+        };
+        if items.is_empty() {
+            return Ok(());
+        }
+        match *section {
+            "entities" => {
+                for node in items.split_whitespace() {
+                    self.execute(&format!(":node {}", node))?;
+                }
+            }
+            "foreign_keys" | "attributes" => {
+                let command = if *section == "foreign_keys" { ":fk" } else { ":attr" };
+                match items.split_whitespace().collect::<Vec<_>>().as_slice() {
+                    [name, ":", source, "->", target] => {
+                        self.execute(&format!("{} {} {} {}", command, name, source, target))?;
+                    }
+                    _ => return Err(format!("attendu : <nom> : <Source> -> <Cible>, trouvé '{}'", items)),
+                }
+            }
+            "path_equations" => {
+                self.execute(&format!(":eq {}", items))?;
+            }
+            _ => return Err(format!("'{}' hors section (entities, foreign_keys, attributes, path_equations)", items)),
+        }
+        Ok(())
+    }
+
+    /// Charge un CSV dont la première ligne nomme les arêtes de chaque
+    /// colonne ; retourne le nombre de lignes insérées.
+    fn load_csv(&mut self, entity: &str, text: &str) -> Result<usize, String> {
+        self.require_node(entity)?;
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        let header = split_csv_line(lines.next().ok_or("CSV vide")?);
+        let mut count = 0;
+        for (i, line) in lines.enumerate() {
+            let fields = split_csv_line(line);
+            if fields.len() != header.len() {
+                return Err(format!(
+                    "Ligne {} : {} champs, {} attendus", i + 2, fields.len(), header.len()
+                ));
+            }
+            let cells = header.iter().cloned().zip(fields).collect();
+            self.insert_row(entity, cells).map_err(|e| format!("Ligne {} : {}", i + 2, e))?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// `from v:Entité... [where <cond> [and <cond>]...] select <proj>...`
+    ///
+    /// - condition : `a op b`, chaque côté étant `v.chemin` ou un littéral
+    /// - projection : `nom=v.chemin.attribut`, `v.chemin.attribut`
+    ///   (nommée par l'attribut), `v.*` ou `count`
+    fn query(&self, text: &str) -> Result<String, String> {
+        let tokens = tokenize(text)?;
+        let mut builder = CqlQuery::select();
+        let mut section = "";
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = tokens[i].as_str();
+            match token {
+                "from" | "where" | "select" => {
+                    section = token;
+                    i += 1;
+                    continue;
+                }
+                "and" if section == "where" => {
+                    i += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            match section {
+                "from" => {
+                    let (var, entity) = token.split_once(':')
+                        .ok_or_else(|| format!("FROM : attendu v:Entité, trouvé '{}'", token))?;
+                    self.require_node(entity)?;
+                    builder.from(var, entity);
+                }
+                "where" => {
+                    let (op, rhs) = match (tokens.get(i + 1), tokens.get(i + 2)) {
+                        (Some(op), Some(rhs)) => (parse_op(op)?, rhs.as_str()),
+                        _ => return Err(format!("WHERE incomplet après '{}'", token)),
+                    };
+                    let side = |text: &str| -> Result<Side, String> {
+                        match parse_literal(text) {
+                            Some(value) => Ok(Side::Literal(value?)),
+                            None => {
+                                let (var, path) = split_var_path(text);
+                                Ok(Side::path(var, &path))
+                            }
+                        }
+                    };
+                    match (side(token)?, side(rhs)?) {
+                        (Side::Path { var, path }, Side::Literal(value)) => {
+                            let path: Vec<&str> = path.iter().map(String::as_str).collect();
+                            builder.where_cmp(&var, &path, op, value)
+                        }
+                        (lhs, rhs) => builder.where_sides(lhs, op, rhs),
+                    };
+                    i += 2;
+                }
+                "select" => {
+                    if token == "count" {
+                        builder.count_only();
+                    } else if let Some(var) = token.strip_suffix(".*") {
+                        builder.select_all(var);
+                    } else {
+                        let (name, expr) = token.split_once('=').unwrap_or(("", token));
+                        let (var, mut path) = split_var_path(expr);
+                        let attribute = path.pop()
+                            .ok_or_else(|| format!("SELECT : '{}' ne finit pas par un attribut", token))?;
+                        let name = if name.is_empty() { attribute } else { name };
+                        builder.bind(name, var, &path, attribute);
+                    }
+                }
+                _ => return Err(format!("Requête : attendu 'from', trouvé '{}'", token)),
+            }
+            i += 1;
+        }
+
+        let mut query = CqlQuery::new("repl", &self.schema.name);
+        query.add_block(builder.build()?, &self.schema)
+            .map_err(|errors| errors.join("\n"))?;
+        let result = eval_query(&query, &self.instance, &self.schema)?;
+        if let Some(scalar) = &result.scalar {
+            return Ok(format!("count = {}", scalar));
+        }
+        Ok(render_rows(&result.instance, "Result"))
+    }
+}
+
+/// Lignes d'une entité, colonnes et lignes triées : un affichage stable
+/// d'une évaluation à l'autre
+fn render_rows(instance: &Instance, entity: &str) -> String {
+    let Some(data) = instance.data.get(entity) else { return String::new() };
+    let row_ids = data.row_ids();
+    let mut lines: Vec<String> = row_ids.iter().map(|row| {
+        let mut cells = data.row_attrs(*row);
+        cells.sort_by(|a, b| a.0.cmp(b.0));
+        let cells: Vec<String> = cells.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        cells.join(", ")
+    }).collect();
+    lines.sort();
+    lines.push(format!("({} lignes)", row_ids.len()));
+    lines.join("\n")
+}
+
+/// Type de base par son nom (`[T]` : liste de T)
+fn parse_type(name: &str) -> Result<BaseType, String> {
+    if let Some(inner) = name.strip_prefix('[').and_then(|n| n.strip_suffix(']')) {
+        return Ok(BaseType::Array(Box::new(parse_type(inner)?)));
+    }
+    match name {
+        "String" => Ok(BaseType::String),
+        "Integer" | "Int" => Ok(BaseType::Integer),
+        "Float" => Ok(BaseType::Float),
+        "Boolean" | "Bool" => Ok(BaseType::Boolean),
+        _ => Err(format!("Type '{}' inconnu (String, Integer, Float, Boolean, [T])", name)),
+    }
+}
+
+fn parse_op(op: &str) -> Result<CompOp, String> {
+    match op {
+        "=" => Ok(CompOp::Eq),
+        "!=" | "<>" => Ok(CompOp::Neq),
+        "<" => Ok(CompOp::Lt),
+        ">" => Ok(CompOp::Gt),
+        "<=" => Ok(CompOp::Lte),
+        ">=" => Ok(CompOp::Gte),
+        _ => Err(format!("Opérateur '{}' inconnu", op)),
+    }
+}
+
+/// Littéral d'une condition, ou `None` si `text` est un chemin
+fn parse_literal(text: &str) -> Option<Result<Value, String>> {
+    if text.starts_with('\'') {
+        return Some(Ok(Value::String(unquote(text).to_string())));
+    }
+    match text {
+        "true" => return Some(Ok(Value::Boolean(true))),
+        "false" => return Some(Ok(Value::Boolean(false))),
+        "null" => return Some(Ok(Value::Null)),
+        _ => {}
+    }
+    if !text.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+        return None;
+    }
+    Some(text.parse::<i64>().map(Value::Integer)
+        .or_else(|_| text.parse::<f64>().map(Value::Float))
+        .map_err(|_| format!("Littéral '{}' invalide", text)))
+}
+
+/// `e.works_in.dept_name` → ("e", ["works_in", "dept_name"])
+fn split_var_path(text: &str) -> (&str, Vec<&str>) {
+    let mut parts = text.split('.');
+    let var = parts.next().unwrap_or_default();
+    (var, parts.collect())
+}
+
+/// Retire les apostrophes d'un littéral `'texte'`
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')).unwrap_or(text)
+}
+
+/// Découpe une requête sur les espaces, sauf entre apostrophes
+fn tokenize(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '\'' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if quoted {
+        return Err("Apostrophe non fermée".into());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+/// Champs d'une ligne CSV (séparateur `,`, champs entre guillemets avec `""`)
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_session() {
+        let dir = std::env::temp_dir().join(format!("catrust_repl_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("Employee.csv");
+        std::fs::write(&csv, "emp_name,salary,works_in\n\"Smith, Alice\",60000,1\nBob,40000,2\nCarol,75000,1\n").unwrap();
+
+        let script = format!(
+            ":node Employee\n\
+             :node Department\n\
+             :fk works_in Employee Department\n\
+             :attr emp_name Employee String\n\
+             :attr salary Employee Integer\n\
+             :attr dept_name Department String\n\
+             :insert Department dept_name='R&D'\n\
+             :insert Department dept_name=Sales\n\
+             :load {}\n\
+             :query from e:Employee where 50000 <= e.salary and e.works_in.dept_name = 'R&D' select name=e.emp_name e.salary\n\
+             :quit\n\
+             :query from e:Employee select count\n",
+            csv.display()
+        );
+        let mut output = Vec::new();
+        let mut repl = Repl::new();
+        repl.run(script.as_bytes(), &mut output).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("3 lignes chargées dans Employee"), "{}", output);
+        assert!(output.contains(
+            "name=\"Carol\", salary=75000\nname=\"Smith, Alice\", salary=60000\n(2 lignes)"
+        ), "{}", output);
+        // Rien n'est exécuté après :quit
+        assert!(!output.contains("count ="), "{}", output);
+
+        assert_eq!(repl.execute(":query from e:Employee select count").unwrap(), "count = 3");
+        assert!(repl.execute(":attr budget Team Float").unwrap_err().contains("'Team' inconnu"));
+        assert!(repl.execute(":query from e:Employee select e.bonus").is_err());
+    }
+
+    #[test]
+    fn test_insert_quoted_value_with_space() {
+        let mut repl = Repl::new();
+        repl.execute(":node Department").unwrap();
+        repl.execute(":attr dept_name Department String").unwrap();
+        assert_eq!(repl.execute(":insert Department dept_name='R&D Paris'").unwrap(), "Department[1]");
+        assert_eq!(
+            repl.execute(":query from d:Department where d.dept_name = 'R&D Paris' select count").unwrap(),
+            "count = 1"
+        );
+        assert!(repl.execute(":insert Department dept_name='R&D").unwrap_err().contains("Apostrophe non fermée"));
+    }
+
+    #[test]
+    fn test_source_cql_schema() {
+        let mut repl = Repl::new();
+        for command in [
+            ":node Employee",
+            ":node Department",
+            ":fk works_in Employee Department",
+            ":fk manager Department Employee",
+            ":fk boss Employee Employee",
+            ":attr salary Employee Integer",
+            ":attr tags Employee [String]",
+            ":eq Employee.works_in.manager = Employee.boss",
+            ":eq Department.manager.works_in = id_Department",
+        ] {
+            repl.execute(command).unwrap();
+        }
+
+        // Ce qu'affiche :schema se recharge tel quel
+        let dir = std::env::temp_dir().join(format!("catrust_repl_cql_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("company.cql");
+        std::fs::write(&file, format!("// Schéma exporté\n{}\n", repl.execute(":schema").unwrap())).unwrap();
+        let bad = dir.join("bad.cql");
+        std::fs::write(&bad, "schema Bad = literal {\n  foreign_keys\n    works_in Employee\n}\n").unwrap();
+
+        let mut loaded = Repl::new();
+        let reply = loaded.execute(&format!(":source {}", file.display()));
+        let err = loaded.execute(&format!(":source {}", bad.display())).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(reply.unwrap(), "");
+        assert_eq!(loaded.schema.nodes.len(), 2);
+        assert_eq!(loaded.schema.edges, repl.schema.edges);
+        assert_eq!(loaded.schema.path_equations, repl.schema.path_equations);
+        assert!(err.contains("bad.cql:3 : attendu : <nom> : <Source> -> <Cible>"), "{}", err);
+    }
+}